@@ -2,24 +2,29 @@
 //!
 //! The client maintains:
 //! - A reliable control stream (handshake + map loading + critical messages)
-//! - An unreliable datagram socket (snapshots, input, etc.)
+//! - An unreliable datagram transport (snapshots, input, etc.): UDP, or an
+//!   in-memory loopback channel to a listen server in the same process
 //! - Snapshot history for interpolation
-//! - Per-tick command generation (prediction stub)
+//! - Per-tick command generation and prediction
 //! - Console for user commands
+//! - Chat, sent and relayed back over the reliable stream
 //! - BSP map loading
 
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::PathBuf;
 
 use anyhow::Context;
 use engine_shared::{
-    bsp::BspMap,
+    bsp::{self, BspMap, LoadedMap},
+    chat::{ChatChannel, ChatMessage},
     config::EngineConfig,
-    console::{Console, CvarFlags, CvarValue},
+    console::{ConsoleRegistry, CvarFlags, CvarValue},
+    ecs::World,
     net::{
-        ClientId, EntitySpawn, MapInfo, NetMsg, PlayerCommand, ReliableConn, UnreliableConn,
-        PROTOCOL_VERSION,
+        ClientId, EntitySpawn, MapInfo, NetMsg, PlayerCommand, ReliableConn, Snapshot,
+        UnreliableConn, PROTOCOL_VERSION,
     },
+    transport::{LoopbackConnector, Transport},
 };
 use tokio::net::TcpStream;
 use tracing::{debug, info, warn};
@@ -27,6 +32,9 @@ use tracing::{debug, info, warn};
 use crate::{
     input::{build_command, InputState},
     interp::SnapshotBuffer,
+    prediction::{Correction, Prediction},
+    reconnect::{retry_with_backoff, ReconnectPolicy},
+    resolve::{connect_any, resolve, TokioResolver},
 };
 
 /// Client connection state.
@@ -42,18 +50,27 @@ pub enum ClientState {
     LoadingMap,
     /// Map loaded, ready to play.
     Ready,
+    /// Connection lost; retrying the handshake with backoff.
+    Reconnecting,
 }
 
+/// An established connection: our id and both channels.
+type Connection = (ClientId, ReliableConn, Box<dyn Transport>);
+
 /// High-level game client.
 pub struct GameClient {
     pub client_id: ClientId,
     pub state: ClientState,
-    pub console: Console,
+    pub console: ConsoleRegistry,
 
     reliable: ReliableConn,
-    pub unreliable: UnreliableConn,
+    pub unreliable: Box<dyn Transport>,
     pub snaps: SnapshotBuffer,
+    /// Replicated entities, mirroring the newest snapshot.
+    pub world: World,
     tick: u32,
+    /// Local player prediction, reconciled against snapshot acks.
+    pub prediction: Prediction,
 
     /// Currently loaded map.
     pub current_map: Option<BspMap>,
@@ -67,17 +84,119 @@ pub struct GameClient {
 
     /// Server messages to display.
     pub server_messages: Vec<String>,
+    /// Chat relayed by the server, oldest first.
+    pub chat_messages: Vec<ChatMessage>,
+
+    /// Config connected with, reused to reconnect.
+    cfg: EngineConfig,
+    pub reconnect_policy: ReconnectPolicy,
+    /// Set when the connection failed (as opposed to a clean disconnect).
+    connection_lost: bool,
+    /// Listen server we're connected to in-process, if any.
+    loopback: Option<LoopbackConnector>,
+    /// A map from the server's `MapInfo` loaded since the last `ClientReady`.
+    ready_pending: bool,
 }
 
 impl GameClient {
-    /// Connects to a server and performs handshake.
+    /// Connects to the server at `cfg.server_addr` and performs handshake.
+    ///
+    /// The address is a `host:port` string; a host name is resolved and
+    /// each of its addresses tried in turn.
     pub async fn connect(cfg: &EngineConfig) -> anyhow::Result<Self> {
-        let server_addr: SocketAddr = cfg.server_addr.parse().context("parse server_addr")?;
+        let conn = Self::handshake(cfg).await?;
+        Self::start(cfg, conn, None).await
+    }
+
+    /// Connects to a listen server in this process over loopback.
+    ///
+    /// The server accepts the connection on its next `step` (or in
+    /// `accept_local`), so it must be running concurrently.
+    pub async fn connect_loopback(
+        cfg: &EngineConfig,
+        connector: LoopbackConnector,
+    ) -> anyhow::Result<Self> {
+        let conn = Self::handshake_loopback(cfg, &connector).await?;
+        Self::start(cfg, conn, Some(connector)).await
+    }
+
+    async fn start(
+        cfg: &EngineConfig,
+        (client_id, reliable, unreliable): Connection,
+        loopback: Option<LoopbackConnector>,
+    ) -> anyhow::Result<Self> {
+        let mut console = ConsoleRegistry::new();
+        Self::register_cvars(&mut console);
+
+        let mut client = Self {
+            client_id,
+            state: ClientState::Connected,
+            console,
+            reliable,
+            unreliable,
+            snaps: SnapshotBuffer::new(32),
+            world: World::default(),
+            tick: 0,
+            prediction: Prediction::new(1.0 / cfg.tick_hz as f32),
+            current_map: None,
+            pending_map: None,
+            maps_dir: PathBuf::from(&cfg.maps_dir),
+            spawned_entities: Vec::new(),
+            server_messages: Vec::new(),
+            chat_messages: Vec::new(),
+            cfg: cfg.clone(),
+            reconnect_policy: ReconnectPolicy::from_config(cfg),
+            connection_lost: false,
+            loopback,
+            ready_pending: false,
+        };
+
+        // Check for immediate MapInfo.
+        client.poll_reliable().await?;
 
+        Ok(client)
+    }
+
+    /// Resolves `cfg.server_addr` and handshakes with the first address
+    /// that answers.
+    async fn handshake(cfg: &EngineConfig) -> anyhow::Result<Connection> {
+        let addrs = resolve(&TokioResolver, &cfg.server_addr).await?;
+        connect_any(&addrs, |addr| Self::handshake_with(addr, cfg))
+            .await
+            .with_context(|| format!("connect to {}", cfg.server_addr))
+    }
+
+    /// Opens in-process channels through `connector` and runs the
+    /// handshake over them.
+    async fn handshake_loopback(
+        cfg: &EngineConfig,
+        connector: &LoopbackConnector,
+    ) -> anyhow::Result<Connection> {
+        info!("Connecting to listen server over loopback");
+        let (mut reliable, transport) = connector.connect()?;
+        // There's no UDP port; the server uses the loopback channel.
+        let client_id = Self::hello(&mut reliable, cfg, 0).await?;
+        Ok((client_id, reliable, Box::new(transport)))
+    }
+
+    /// Opens both channels to `server_addr` and runs the handshake up to
+    /// `Welcome`.
+    async fn handshake_with(
+        server_addr: SocketAddr,
+        cfg: &EngineConfig,
+    ) -> anyhow::Result<Connection> {
         info!(server = %server_addr, "Connecting to server");
 
         // Bind UDP first so we can tell the server where to send snapshots.
-        let bind = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+        // Stay on loopback for a local server; otherwise any interface of
+        // the server's address family.
+        let local_ip = match (server_addr.ip(), server_addr.ip().is_loopback()) {
+            (IpAddr::V4(_), true) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            (IpAddr::V4(_), false) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            (IpAddr::V6(_), true) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            (IpAddr::V6(_), false) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        };
+        let bind = SocketAddr::new(local_ip, 0);
         let unreliable = UnreliableConn::connect(bind, server_addr).await?;
         let client_udp_port = unreliable.local_addr().context("udp local_addr")?.port();
 
@@ -86,9 +205,22 @@ impl GameClient {
             .context("tcp connect")?;
         let mut reliable = ReliableConn::new(stream);
 
+        let client_id = Self::hello(&mut reliable, cfg, client_udp_port).await?;
+        Ok((client_id, reliable, Box::new(unreliable)))
+    }
+
+    /// Sends `Hello` and `UdpHello` and waits for the server's answer.
+    async fn hello(
+        reliable: &mut ReliableConn,
+        cfg: &EngineConfig,
+        client_udp_port: u16,
+    ) -> anyhow::Result<ClientId> {
         reliable
             .send(&NetMsg::Hello {
                 protocol: PROTOCOL_VERSION,
+                steam_id: cfg.steam_id,
+                owned_dlc: cfg.owned_dlc.clone(),
+                player_name: cfg.player_name.clone(),
             })
             .await?;
 
@@ -97,36 +229,95 @@ impl GameClient {
         let welcome = reliable.recv().await?;
         let client_id = match welcome {
             NetMsg::Welcome { client_id } => client_id,
+            NetMsg::Reject { reason } => anyhow::bail!("server rejected connection: {reason}"),
             other => anyhow::bail!("expected Welcome, got {other:?}"),
         };
 
         info!(client_id = ?client_id, "Connected to server");
+        Ok(client_id)
+    }
 
-        let mut console = Console::new();
-        Self::register_cvars(&mut console);
+    /// Whether the connection failed rather than being closed on purpose,
+    /// so [`reconnect`](Self::reconnect) is worth trying.
+    pub fn connection_lost(&self) -> bool {
+        self.connection_lost
+    }
 
-        let mut client = Self {
-            client_id,
-            state: ClientState::Connected,
-            console,
-            reliable,
-            unreliable,
-            snaps: SnapshotBuffer::new(32),
-            tick: 0,
-            current_map: None,
-            pending_map: None,
-            maps_dir: PathBuf::from(&cfg.maps_dir),
-            spawned_entities: Vec::new(),
-            server_messages: Vec::new(),
+    /// Re-runs the handshake with the server, backing off per
+    /// `reconnect_policy` between attempts.
+    ///
+    /// On success the server's `MapInfo` is handled as on first connect,
+    /// and if that leaves the client `Ready` the server is told so. After
+    /// the last failed attempt the client stays `Disconnected`.
+    pub async fn reconnect(&mut self) -> anyhow::Result<()> {
+        self.state = ClientState::Reconnecting;
+        let cfg = self.cfg.clone();
+        let loopback = self.loopback.clone();
+        let result = retry_with_backoff(&self.reconnect_policy, tokio::time::sleep, |_| {
+            let (cfg, loopback) = (&cfg, &loopback);
+            async move {
+                match loopback {
+                    Some(connector) => Self::handshake_loopback(cfg, connector).await,
+                    None => Self::handshake(cfg).await,
+                }
+            }
+        })
+        .await;
+
+        let parts = match result {
+            Ok(parts) => parts,
+            Err(e) => {
+                self.state = ClientState::Disconnected;
+                return Err(e);
+            }
         };
+        self.adopt(parts).await
+    }
 
-        // Check for immediate MapInfo.
-        client.poll_reliable().await?;
+    /// Leaves the current server for the one at `addr` (`host:port`).
+    ///
+    /// The current connection is only dropped once the new handshake has
+    /// succeeded, so a bad address leaves the client where it was.
+    pub async fn connect_to(&mut self, addr: &str) -> anyhow::Result<()> {
+        let cfg = EngineConfig {
+            server_addr: addr.to_string(),
+            ..self.cfg.clone()
+        };
+        let parts = Self::handshake(&cfg).await?;
 
-        Ok(client)
+        if let Err(e) = self.reliable.close().await {
+            debug!(error = %e, "Closing previous connection");
+        }
+        self.cfg = cfg;
+        self.loopback = None;
+        self.current_map = None;
+        self.pending_map = None;
+        self.spawned_entities.clear();
+        self.adopt(parts).await
+    }
+
+    /// Switches to a freshly handshaken connection and resets per-session
+    /// state, then handles the server's `MapInfo` as on first connect and
+    /// tells it we're ready if that loaded the map.
+    async fn adopt(&mut self, (client_id, reliable, unreliable): Connection) -> anyhow::Result<()> {
+        self.client_id = client_id;
+        self.reliable = reliable;
+        self.unreliable = unreliable;
+        self.state = ClientState::Connected;
+        self.connection_lost = false;
+        self.tick = 0;
+        self.snaps = SnapshotBuffer::new(32);
+        self.world = World::default();
+        self.prediction.reset(Default::default());
+
+        self.poll_reliable().await?;
+        if self.state == ClientState::Ready {
+            self.send_ready().await?;
+        }
+        Ok(())
     }
 
-    fn register_cvars(console: &mut Console) {
+    fn register_cvars(console: &mut ConsoleRegistry) {
         console.register_cvar(
             "cl_interp",
             CvarValue::Float(0.1),
@@ -158,6 +349,7 @@ impl GameClient {
             Ok(Err(e)) => {
                 warn!(error = %e, "Reliable connection error");
                 self.state = ClientState::Disconnected;
+                self.connection_lost = true;
             }
             Err(_) => {
                 // Timeout, no message available.
@@ -173,8 +365,9 @@ impl GameClient {
                 self.pending_map = Some(info.clone());
                 self.state = ClientState::LoadingMap;
 
-                // Try to load the map.
-                self.load_map(&info.name)?;
+                // Try to load the map, refusing a different build.
+                self.load_verified_map(&info)?;
+                self.ready_pending = true;
             }
             NetMsg::EntitySpawn(spawn) => {
                 debug!(classname = %spawn.classname, "Entity spawn received");
@@ -184,6 +377,15 @@ impl GameClient {
                 info!(message = %message, "Server message");
                 self.server_messages.push(message);
             }
+            NetMsg::Chat(message) => {
+                info!(sender = %message.sender_name, content = %message.content, "Chat");
+                self.chat_messages.push(message);
+            }
+            NetMsg::CvarUpdate { name, value } => {
+                if let Err(e) = self.console.apply_replicated(&name, &value) {
+                    warn!(cvar = %name, error = %e, "Ignoring cvar update");
+                }
+            }
             NetMsg::Disconnect { reason } => {
                 info!(reason = %reason, "Disconnected from server");
                 self.state = ClientState::Disconnected;
@@ -195,13 +397,44 @@ impl GameClient {
         Ok(())
     }
 
+    /// Checks a local copy of a map against the server's advertised CRC.
+    ///
+    /// A CRC of 0 means the server didn't compute one, so any copy is accepted.
+    pub fn verify_map(info: &MapInfo, local: &LoadedMap) -> anyhow::Result<()> {
+        if info.crc != 0 && local.crc != info.crc {
+            anyhow::bail!(
+                "map {} differs from server (crc {:#010x}, server has {:#010x})",
+                info.name,
+                local.crc,
+                info.crc
+            );
+        }
+        Ok(())
+    }
+
+    /// Loads the map named in `info`, refusing a copy whose CRC differs.
+    pub fn load_verified_map(&mut self, info: &MapInfo) -> anyhow::Result<()> {
+        info!(map = %info.name, "Loading map");
+
+        let path = self.maps_dir.join(format!("{}.bsp", info.name));
+        let (local, bsp) =
+            bsp::read_map(&path).with_context(|| format!("load map {}", path.display()))?;
+        Self::verify_map(info, &local)?;
+        self.install_map(bsp);
+        Ok(())
+    }
+
     /// Loads a map by name.
     pub fn load_map(&mut self, map_name: &str) -> anyhow::Result<()> {
         info!(map = %map_name, "Loading map");
 
         let path = self.maps_dir.join(format!("{}.bsp", map_name));
         let bsp = BspMap::load(&path).with_context(|| format!("load map {}", path.display()))?;
+        self.install_map(bsp);
+        Ok(())
+    }
 
+    fn install_map(&mut self, bsp: BspMap) {
         info!(
             map = %bsp.name,
             entities = bsp.entities.len(),
@@ -212,9 +445,15 @@ impl GameClient {
         self.current_map = Some(bsp);
         self.spawned_entities.clear();
         self.snaps = SnapshotBuffer::new(32);
+        self.world = World::default();
+        self.prediction.reset(Default::default());
         self.state = ClientState::Ready;
+    }
 
-        Ok(())
+    /// Whether the server's map has loaded but the server hasn't been sent
+    /// [`send_ready`](Self::send_ready) since.
+    pub fn needs_ready(&self) -> bool {
+        self.ready_pending
     }
 
     /// Sends a "ready" signal to the server.
@@ -224,13 +463,40 @@ impl GameClient {
                 client_id: self.client_id,
             })
             .await?;
+        self.ready_pending = false;
         info!("Sent ready signal to server");
         Ok(())
     }
 
-    /// Advances one client tick: build input command and send.
+    /// Sends a chat message as the configured player.
+    ///
+    /// It shows up in [`chat_messages`](Self::chat_messages) once the server
+    /// relays it back.
+    pub async fn say(&mut self, channel: ChatChannel, text: &str) -> anyhow::Result<()> {
+        let message = ChatMessage::new(
+            self.cfg.steam_id,
+            &self.cfg.player_name,
+            channel,
+            text,
+            self.tick.into(),
+        );
+        self.reliable.send(&NetMsg::Chat(message)).await
+    }
+
+    /// Advances one client tick: build input command, predict it if
+    /// `cl_predict` is on, and send.
     pub async fn tick(&mut self, input: InputState) -> anyhow::Result<PlayerCommand> {
-        let cmd = build_command(self.client_id, self.tick, input);
+        let mut cmd = build_command(self.client_id, self.tick, input);
+        let predict = self
+            .console
+            .get_cvar("cl_predict")
+            .is_some_and(|v| v.as_bool());
+        if predict {
+            cmd.sequence = self
+                .prediction
+                .predict(self.tick, input, (0.0, 0.0))
+                .sequence;
+        }
         self.unreliable
             .send(&NetMsg::PlayerCommand(cmd.clone()))
             .await?;
@@ -238,6 +504,29 @@ impl GameClient {
         Ok(cmd)
     }
 
+    /// Buffers a snapshot and, if it's the newest, syncs the world to it.
+    fn push_snapshot(&mut self, snap: Snapshot) {
+        let newest = self
+            .snaps
+            .last_snapshot()
+            .is_none_or(|last| snap.tick > last.tick);
+        if newest {
+            self.world.apply_snapshot(&snap);
+            if let Some(ack) = &snap.ack {
+                match self.prediction.reconcile(ack) {
+                    Correction::None => {}
+                    Correction::Smoothed { error } => {
+                        debug!(error, sequence = ack.sequence, "Prediction corrected");
+                    }
+                    Correction::Snapped { error } => {
+                        debug!(error, sequence = ack.sequence, "Prediction snapped");
+                    }
+                }
+            }
+        }
+        self.snaps.push(snap);
+    }
+
     /// Receives messages over unreliable channel.
     pub async fn recv_snapshot(&mut self) -> anyhow::Result<()> {
         if let Some(msg) = self
@@ -247,8 +536,32 @@ impl GameClient {
         {
             match msg {
                 NetMsg::Snapshot(s) => {
-                    self.snaps.push(s);
+                    self.push_snapshot(s);
                 }
+                NetMsg::QuantizedSnapshot(q) => {
+                    self.push_snapshot(q.dequantize());
+                }
+                NetMsg::Ping { seq } => {
+                    self.unreliable
+                        .send(&NetMsg::Pong {
+                            client_id: self.client_id,
+                            seq,
+                        })
+                        .await?;
+                }
+                NetMsg::DeltaSnapshot(delta) => match self.snaps.get(delta.base_tick) {
+                    Some(base) => {
+                        let mut snap = base.clone();
+                        snap.apply_delta(&delta)?;
+                        self.push_snapshot(snap);
+                    }
+                    None => {
+                        debug!(
+                            base_tick = delta.base_tick,
+                            "Dropping delta with unknown base"
+                        );
+                    }
+                },
                 other => {
                     debug!(?other, "Unexpected UDP message");
                 }
@@ -270,7 +583,10 @@ impl GameClient {
                 if tokens.len() < 2 {
                     return Ok(vec!["Usage: connect <host:port>".to_string()]);
                 }
-                Ok(vec![format!("Would connect to {}", tokens[1])])
+                match self.connect_to(tokens[1]).await {
+                    Ok(()) => Ok(vec![format!("Connected to {}", tokens[1])]),
+                    Err(e) => Ok(vec![format!("Failed to connect: {:#}", e)]),
+                }
             }
             "disconnect" => {
                 self.state = ClientState::Disconnected;
@@ -298,11 +614,7 @@ impl GameClient {
             }
             "say" => {
                 let msg = tokens[1..].join(" ");
-                self.unreliable
-                    .send(&NetMsg::ClientCommand {
-                        command: format!("say {}", msg),
-                    })
-                    .await?;
+                self.say(ChatChannel::Global, &msg).await?;
                 Ok(vec![])
             }
             "quit" | "exit" => {
@@ -310,7 +622,7 @@ impl GameClient {
             }
             _ => {
                 // Delegate to console system.
-                self.console.exec(line)
+                Ok(self.console.exec(line)?)
             }
         }
     }
@@ -4,30 +4,19 @@
 //! action bindings, and per-frame sampling. This scaffold focuses on producing
 //! deterministic per-tick `PlayerCommand` messages.
 
-use engine_shared::{
-    math::Vec3,
-    net::{ClientId, PlayerCommand},
-};
+use engine_shared::net::{ClientId, PlayerCommand};
 
-/// User input state at a moment in time.
-#[derive(Debug, Clone, Copy, Default)]
-pub struct InputState {
-    pub forward: f32,
-    pub right: f32,
-    pub up: f32,
-}
-
-impl InputState {
-    pub fn wish_vector(self) -> Vec3 {
-        Vec3::new(self.forward, self.right, self.up)
-    }
-}
+pub use engine_shared::input::{Bindings, InButtons, InputState};
 
 /// Turns sampled input into a `PlayerCommand` for a tick.
+///
+/// The command is unsequenced; a predicting client sets `sequence` from
+/// the `UserCmd` it predicted with.
 pub fn build_command(client_id: ClientId, tick: u32, input: InputState) -> PlayerCommand {
     PlayerCommand {
         client_id,
         tick,
         wish: input.wish_vector(),
+        sequence: 0,
     }
 }
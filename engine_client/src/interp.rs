@@ -4,9 +4,10 @@
 //! The client renders at its own rate and interpolates entity states.
 
 use std::collections::VecDeque;
+use std::time::Duration;
 
 use engine_shared::{
-    math::Vec3,
+    math::{lerp_angle, Vec3},
     net::{EntityState, Snapshot},
 };
 
@@ -68,6 +69,11 @@ impl SnapshotBuffer {
         }
     }
 
+    /// Finds a buffered snapshot by tick.
+    pub fn get(&self, tick: u32) -> Option<&Snapshot> {
+        self.history.iter().find(|s| s.tick == tick)
+    }
+
     pub fn last_snapshot(&self) -> Option<&Snapshot> {
         self.history.back()
     }
@@ -77,3 +83,145 @@ impl SnapshotBuffer {
 pub fn find_entity(snap: &Snapshot, id: engine_shared::ecs::EntityId) -> Option<&EntityState> {
     snap.entities.iter().find(|e| e.id == id)
 }
+
+/// Default render delay behind the newest snapshot (Source's `cl_interp`).
+pub const DEFAULT_INTERP_DELAY: Duration = Duration::from_millis(100);
+
+/// Default cap on how far past the newest sample to extrapolate.
+pub const DEFAULT_MAX_EXTRAPOLATION: Duration = Duration::from_millis(250);
+
+/// An interpolated entity pose.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InterpSample {
+    pub position: Vec3,
+    pub angles: Vec3,
+    /// The render time was past the newest sample.
+    pub extrapolated: bool,
+}
+
+/// Recent states of one entity, sampled at a delayed render time.
+///
+/// Times are in seconds of server time, where tick `n` is at `n / tick_hz`.
+#[derive(Debug, Clone)]
+pub struct InterpBuffer {
+    samples: VecDeque<(u32, EntityState)>,
+    tick_hz: u32,
+    max_samples: usize,
+    interp_delay: Duration,
+    max_extrapolation: Duration,
+}
+
+impl InterpBuffer {
+    pub fn new(tick_hz: u32, max_samples: usize) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            tick_hz: tick_hz.max(1),
+            max_samples: max_samples.max(2),
+            interp_delay: DEFAULT_INTERP_DELAY,
+            max_extrapolation: DEFAULT_MAX_EXTRAPOLATION,
+        }
+    }
+
+    /// Sets how far behind the server clock to render.
+    pub fn with_interp_delay(mut self, delay: Duration) -> Self {
+        self.interp_delay = delay;
+        self
+    }
+
+    /// Sets the extrapolation cap; zero clamps to the newest sample.
+    pub fn with_max_extrapolation(mut self, limit: Duration) -> Self {
+        self.max_extrapolation = limit;
+        self
+    }
+
+    pub fn interp_delay(&self) -> Duration {
+        self.interp_delay
+    }
+
+    /// Records a state. Samples older than the newest are ignored, and a
+    /// repeated tick replaces the previous sample.
+    pub fn push(&mut self, tick: u32, state: EntityState) {
+        match self.samples.back_mut() {
+            Some((last, _)) if tick < *last => return,
+            Some((last, existing)) if tick == *last => {
+                *existing = state;
+                return;
+            }
+            _ => {}
+        }
+        self.samples.push_back((tick, state));
+        while self.samples.len() > self.max_samples {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Newest recorded tick.
+    pub fn latest_tick(&self) -> Option<u32> {
+        self.samples.back().map(|(tick, _)| *tick)
+    }
+
+    /// Samples at `server_time` minus the interp delay.
+    pub fn sample(&self, server_time: f32) -> Option<InterpSample> {
+        self.sample_at(server_time - self.interp_delay.as_secs_f32())
+    }
+
+    /// Samples at an explicit render time.
+    ///
+    /// Before the oldest sample this clamps to it. Past the newest it
+    /// extrapolates from the last two samples for up to the extrapolation
+    /// cap, then holds.
+    pub fn sample_at(&self, render_time: f32) -> Option<InterpSample> {
+        let time = |tick: u32| tick as f32 / self.tick_hz as f32;
+        let (first_tick, first) = self.samples.front()?;
+        if render_time <= time(*first_tick) || self.samples.len() == 1 {
+            return Some(Self::pose(first, first, 0.0, false));
+        }
+
+        // Bracketing pair: the last sample at or before render_time and the
+        // one after it.
+        let newer = self
+            .samples
+            .iter()
+            .position(|(tick, _)| time(*tick) > render_time);
+        let (i, extrapolated) = match newer {
+            Some(i) => (i, false),
+            None => (self.samples.len() - 1, true),
+        };
+        let (ta, a) = &self.samples[i - 1];
+        let (tb, b) = &self.samples[i];
+        let (ta, tb) = (time(*ta), time(*tb));
+
+        let target = if extrapolated {
+            render_time.min(tb + self.max_extrapolation.as_secs_f32())
+        } else {
+            render_time
+        };
+        let t = (target - ta) / (tb - ta);
+        Some(Self::pose(a, b, t, extrapolated))
+    }
+
+    fn pose(a: &EntityState, b: &EntityState, t: f32, extrapolated: bool) -> InterpSample {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        InterpSample {
+            position: Vec3::new(
+                lerp(a.position.x, b.position.x),
+                lerp(a.position.y, b.position.y),
+                lerp(a.position.z, b.position.z),
+            ),
+            angles: Vec3::new(
+                lerp_angle(a.angles.x, b.angles.x, t),
+                lerp_angle(a.angles.y, b.angles.y, t),
+                lerp_angle(a.angles.z, b.angles.z, t),
+            ),
+            extrapolated,
+        }
+    }
+}
@@ -1,14 +1,18 @@
 //! `engine_client`
 //!
 //! Client-side systems:
-//! - Connection management (reliable + unreliable channels)
+//! - Connection management (reliable + unreliable channels, host name
+//!   resolution, reconnect)
 //! - Input capture and command generation
-//! - Prediction and reconciliation (placeholder)
+//! - Prediction and reconciliation against server snapshots
 //! - Interpolation for remote entity states
 //! - Rendering abstraction wiring (placeholder)
 
 pub mod client;
 pub mod input;
 pub mod interp;
+pub mod prediction;
+pub mod reconnect;
+pub mod resolve;
 
 pub use client::GameClient;
@@ -1,52 +1,130 @@
 //! Standalone client binary.
 //!
 //! Usage:
-//!   cargo run -p engine_client -- [--addr 127.0.0.1:40000] [--maps-dir maps]
+//!   cargo run -p engine_client -- [--config client.toml] [--addr host:40000] [--maps-dir maps] [--listen]
+//!
+//! Settings are layered: defaults < config file < `PS_*` environment
+//! variables < command-line arguments.
 //!
 //! The client connects to the server, loads the map, sends input commands,
 //! and displays received snapshots.
 //!
+//! With `--listen` the client hosts a server in the same process (bound to
+//! `--addr`, so others can still join) and plays on it over an in-memory
+//! loopback channel. `map` then changes the listen server's map. This needs
+//! the default `listen-server` feature.
+//!
 //! Console commands:
-//!   connect <host:port> - Connect to server (not yet implemented, pass --addr)
+//!   connect <host:port> - Switch to another server
 //!   disconnect          - Disconnect from server
 //!   status              - Show client status
-//!   map <mapname>       - Load a map locally (for testing)
+//!   map <mapname>       - Load a map locally, or on the listen server
 //!   say <message>       - Send chat message
 //!   quit                - Exit client
 
 use std::env;
 use std::io::{BufRead, Write};
+use std::path::Path;
 use std::time::Duration;
 
 use anyhow::Context;
 use engine_client::client::{ClientState, GameClient};
 use engine_client::input::InputState;
-use engine_shared::config::EngineConfig;
+use engine_shared::config::{CliArgs, EngineConfig};
 use tokio::sync::mpsc;
 use tracing::info;
+#[cfg(feature = "listen-server")]
+use {
+    engine_server::server::{GameServer, ServerState},
+    std::path::PathBuf,
+    tracing::warn,
+};
 
-fn parse_args() -> EngineConfig {
-    let mut cfg = EngineConfig::default();
+fn parse_args() -> anyhow::Result<EngineConfig> {
+    let mut cli = CliArgs::default();
+    let mut config_path = None;
     let args: Vec<String> = env::args().collect();
     let mut i = 1;
     while i < args.len() {
         match args[i].as_str() {
+            "--config" if i + 1 < args.len() => {
+                config_path = Some(args[i + 1].clone());
+                i += 2;
+            }
             "--addr" if i + 1 < args.len() => {
-                cfg.server_addr = args[i + 1].clone();
+                cli.server_addr = Some(args[i + 1].clone());
                 i += 2;
             }
             "--maps-dir" if i + 1 < args.len() => {
-                cfg.maps_dir = args[i + 1].clone();
+                cli.maps_dir = Some(args[i + 1].clone());
                 i += 2;
             }
             "--name" if i + 1 < args.len() => {
-                cfg.player_name = args[i + 1].clone();
+                cli.player_name = Some(args[i + 1].clone());
                 i += 2;
             }
+            "--listen" => {
+                cli.listen = Some(true);
+                i += 1;
+            }
             _ => i += 1,
         }
     }
-    cfg
+
+    let mut cfg = match config_path {
+        Some(path) => EngineConfig::from_file(Path::new(&path))?,
+        None => EngineConfig::default(),
+    };
+    cfg.apply_env()?;
+    let cfg = cfg.merge_cli(&cli);
+    cfg.validate()?;
+    Ok(cfg)
+}
+
+/// Starts a server in this process and connects to it over loopback.
+///
+/// Returns the client and the server's console input.
+#[cfg(feature = "listen-server")]
+async fn host_listen_server(
+    cfg: &EngineConfig,
+) -> anyhow::Result<(GameClient, mpsc::Sender<String>)> {
+    let mut server = GameServer::new(cfg.clone(), PathBuf::from(&cfg.maps_dir))
+        .await
+        .context("start listen server")?;
+    let (console_tx, console_rx) = mpsc::channel::<String>(32);
+    server.set_console_input(console_rx);
+    let connector = server.loopback_connector();
+
+    let tick_interval = Duration::from_secs_f32(1.0 / cfg.tick_hz as f32);
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(tick_interval);
+        loop {
+            ticks.tick().await;
+            // Remote players can still join; the local one is accepted in
+            // `step`.
+            if let Ok(Some(cid)) = server.try_accept(Duration::from_millis(1)).await {
+                info!(client_id = ?cid, "Remote client joined listen server");
+            }
+            if let Err(e) = server.step(tick_interval.as_secs_f32()).await {
+                warn!(error = %e, "Listen server step failed");
+            }
+            if *server.state() == ServerState::Stopped {
+                break;
+            }
+        }
+    });
+
+    let client = GameClient::connect_loopback(cfg, connector)
+        .await
+        .context("connect to listen server")?;
+    Ok((client, console_tx))
+}
+
+#[cfg(not(feature = "listen-server"))]
+async fn host_listen_server(
+    _cfg: &EngineConfig,
+) -> anyhow::Result<(GameClient, mpsc::Sender<String>)> {
+    anyhow::bail!("--listen needs a client built with the `listen-server` feature")
 }
 
 #[tokio::main]
@@ -57,10 +135,15 @@ async fn main() -> anyhow::Result<()> {
         )
         .init();
 
-    let cfg = parse_args();
+    let cfg = parse_args()?;
     info!(server = %cfg.server_addr, maps_dir = %cfg.maps_dir, "Starting client");
 
-    let mut client = GameClient::connect(&cfg).await.context("connect")?;
+    let (mut client, listen_console) = if cfg.listen {
+        let (client, console) = host_listen_server(&cfg).await?;
+        (client, Some(console))
+    } else {
+        (GameClient::connect(&cfg).await.context("connect")?, None)
+    };
     info!(client_id = ?client.client_id, "Connected to server");
 
     // Set up console input channel.
@@ -107,6 +190,16 @@ async fn main() -> anyhow::Result<()> {
     loop {
         // Process console commands.
         while let Ok(line) = console_rx.try_recv() {
+            // On a listen server, `map` changes the server's map and the
+            // client follows via MapInfo.
+            if let Some(server_console) = &listen_console {
+                if line.split_whitespace().next() == Some("map") {
+                    if server_console.send(line).await.is_err() {
+                        println!("Listen server has stopped");
+                    }
+                    continue;
+                }
+            }
             match client.exec_console(&line).await {
                 Ok(output) => {
                     for line in output {
@@ -119,10 +212,20 @@ async fn main() -> anyhow::Result<()> {
             }
         }
 
-        // Check for reliable messages (map changes, etc.).
+        // Check for reliable messages (map changes, etc.), telling the
+        // server once a new map has loaded.
         client.poll_reliable().await?;
+        if client.state == ClientState::Ready && client.needs_ready() {
+            client.send_ready().await?;
+        }
 
-        // If disconnected, exit.
+        // Retry a dropped connection; exit on a clean disconnect.
+        if client.state == ClientState::Disconnected && client.connection_lost() {
+            println!("Connection lost, reconnecting...");
+            if let Err(e) = client.reconnect().await {
+                println!("Reconnect failed: {e:#}");
+            }
+        }
         if client.state == ClientState::Disconnected {
             println!("Disconnected from server.");
             break;
@@ -135,6 +238,7 @@ async fn main() -> anyhow::Result<()> {
                 forward: 0.0,
                 right: 0.0,
                 up: 0.0,
+                ..Default::default()
             };
 
             if let Err(e) = client.tick(input).await {
@@ -0,0 +1,152 @@
+//! Client-side prediction and reconciliation.
+//!
+//! Each `UserCmd` is applied to the local player as soon as it's built, so
+//! movement responds without waiting a round trip. The command is kept in a
+//! `CommandBuffer` until a snapshot acknowledges it; the ack carries the
+//! server's player state after that command, and the client rewinds to it and
+//! replays whatever the server hasn't seen yet.
+//!
+//! Small mispredictions are hidden by an error offset that decays over the
+//! next few commands; anything beyond `snap_distance` is applied at once.
+
+use engine_shared::{
+    input::{CommandBuffer, InButtons, InputState, UserCmd},
+    math::Vec3,
+    net::CommandAck,
+    physics::{self, GroundInfo, PlayerPhysics},
+};
+
+/// Misprediction distance beyond which the player snaps to the corrected
+/// position instead of blending towards it.
+pub const DEFAULT_SNAP_DISTANCE: f32 = 2.0;
+
+/// Differences below this are float noise, not a misprediction.
+pub const PREDICTION_EPSILON: f32 = 0.001;
+
+/// Fraction of the correction offset kept after each predicted command.
+const ERROR_DECAY: f32 = 0.8;
+
+/// How a reconciliation changed the predicted state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Correction {
+    /// The replay matched the prediction.
+    None,
+    /// Mispredicted by `error` units; blended out over the next commands.
+    Smoothed { error: f32 },
+    /// Mispredicted by `error` units, at least `snap_distance`; applied at once.
+    Snapped { error: f32 },
+}
+
+/// Predicted local player state and the commands it was built from.
+#[derive(Debug, Clone)]
+pub struct Prediction {
+    commands: CommandBuffer,
+    /// Player state after every command created so far.
+    state: PlayerPhysics,
+    /// Fixed step each command is simulated for; must match the server.
+    dt: f32,
+    pub snap_distance: f32,
+    /// Rendered offset left over from a smoothed correction.
+    error: Vec3,
+}
+
+impl Prediction {
+    /// Creates a predictor that steps each command by `dt` seconds.
+    pub fn new(dt: f32) -> Self {
+        Self {
+            commands: CommandBuffer::default(),
+            state: PlayerPhysics::default(),
+            dt,
+            snap_distance: DEFAULT_SNAP_DISTANCE,
+            error: Vec3::ZERO,
+        }
+    }
+
+    /// Forgets all commands and starts over from `state`.
+    pub fn reset(&mut self, state: PlayerPhysics) {
+        self.commands = CommandBuffer::default();
+        self.state = state;
+        self.error = Vec3::ZERO;
+    }
+
+    /// Builds the next command from `input`, applies it locally and keeps
+    /// it for replay.
+    pub fn predict(&mut self, tick: u32, input: InputState, view_angles: (f32, f32)) -> UserCmd {
+        let cmd = self.commands.create(tick, input, view_angles);
+        self.apply(&cmd);
+        self.error = Vec3::new(
+            self.error.x * ERROR_DECAY,
+            self.error.y * ERROR_DECAY,
+            self.error.z * ERROR_DECAY,
+        );
+        if self.error.len_sq() < PREDICTION_EPSILON * PREDICTION_EPSILON {
+            self.error = Vec3::ZERO;
+        }
+        cmd
+    }
+
+    /// Rewinds to the server's state in `ack` and replays the commands it
+    /// hasn't processed yet.
+    ///
+    /// Acks older than one already reconciled are ignored.
+    pub fn reconcile(&mut self, ack: &CommandAck) -> Correction {
+        if ack.sequence < self.commands.last_acked() {
+            return Correction::None;
+        }
+        self.commands.ack(ack.sequence);
+
+        let predicted = self.state.position;
+        self.state = PlayerPhysics {
+            position: ack.position,
+            velocity: ack.velocity,
+        };
+        let pending: Vec<UserCmd> = self.commands.pending().copied().collect();
+        for cmd in &pending {
+            self.apply(cmd);
+        }
+
+        let corrected = self.state.position;
+        let error = predicted.distance_sq(corrected).sqrt();
+        if error < PREDICTION_EPSILON {
+            Correction::None
+        } else if error >= self.snap_distance {
+            self.error = Vec3::ZERO;
+            Correction::Snapped { error }
+        } else {
+            // Keep drawing the player where they were and blend from there.
+            self.error = Vec3::new(
+                self.error.x + predicted.x - corrected.x,
+                self.error.y + predicted.y - corrected.y,
+                self.error.z + predicted.z - corrected.z,
+            );
+            Correction::Smoothed { error }
+        }
+    }
+
+    fn apply(&mut self, cmd: &UserCmd) {
+        let input = InputState {
+            forward: cmd.forward,
+            right: cmd.right,
+            up: cmd.up,
+            buttons: InButtons::from_bits_truncate(cmd.buttons),
+        };
+        physics::move_player(&mut self.state, &input, self.dt, GroundInfo::FLAT);
+    }
+
+    /// Predicted player state.
+    pub fn state(&self) -> PlayerPhysics {
+        self.state
+    }
+
+    /// Where to draw the player: the predicted position plus what's left of
+    /// the last smoothed correction.
+    pub fn render_position(&self) -> Vec3 {
+        let p = self.state.position;
+        Vec3::new(p.x + self.error.x, p.y + self.error.y, p.z + self.error.z)
+    }
+
+    /// Commands not yet acknowledged by the server.
+    pub fn commands(&self) -> &CommandBuffer {
+        &self.commands
+    }
+}
@@ -0,0 +1,81 @@
+//! Reconnection with exponential backoff.
+//!
+//! The retry loop is transport-agnostic: it takes the connect attempt and
+//! the sleep as closures, so `GameClient::reconnect` drives it with a real
+//! handshake and `tokio::time::sleep`, and tests drive it with mocks.
+
+use std::{future::Future, time::Duration};
+
+use engine_shared::config::EngineConfig;
+use tracing::{info, warn};
+
+/// Longest wait between two reconnect attempts.
+pub const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// How often and how patiently to retry a lost connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Wait before the first attempt; doubles for each one after.
+    pub base_delay: Duration,
+    /// Cap on the doubled wait.
+    pub max_delay: Duration,
+    /// Attempts before giving up.
+    pub max_attempts: u32,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self::from_config(&EngineConfig::default())
+    }
+}
+
+impl ReconnectPolicy {
+    pub fn from_config(cfg: &EngineConfig) -> Self {
+        Self {
+            base_delay: Duration::from_millis(cfg.reconnect_base_delay_ms),
+            max_delay: MAX_RECONNECT_DELAY,
+            max_attempts: cfg.reconnect_max_attempts,
+        }
+    }
+
+    /// Wait before attempt `attempt` (1-based).
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(31);
+        self.base_delay
+            .saturating_mul(1 << doublings)
+            .min(self.max_delay)
+    }
+}
+
+/// Runs `attempt` until it succeeds, sleeping [`ReconnectPolicy::delay_for`]
+/// before each try. Fails with the last error after `max_attempts` tries.
+pub async fn retry_with_backoff<T, A, AF, S, SF>(
+    policy: &ReconnectPolicy,
+    mut sleep: S,
+    mut attempt: A,
+) -> anyhow::Result<T>
+where
+    A: FnMut(u32) -> AF,
+    AF: Future<Output = anyhow::Result<T>>,
+    S: FnMut(Duration) -> SF,
+    SF: Future<Output = ()>,
+{
+    let mut last_err = None;
+    for n in 1..=policy.max_attempts {
+        let delay = policy.delay_for(n);
+        info!(attempt = n, ?delay, "Reconnecting");
+        sleep(delay).await;
+        match attempt(n).await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                warn!(attempt = n, error = %e, "Reconnect attempt failed");
+                last_err = Some(e);
+            }
+        }
+    }
+    let attempts = policy.max_attempts;
+    Err(match last_err {
+        Some(e) => e.context(format!("gave up reconnecting after {attempts} attempts")),
+        None => anyhow::anyhow!("reconnecting is disabled (max attempts is 0)"),
+    })
+}
@@ -0,0 +1,115 @@
+//! Server address resolution.
+//!
+//! A `host:port` string is checked with [`HostPort::parse`], IP literals are
+//! used as-is, and names go through a [`Resolver`] — DNS via tokio in the
+//! client, a mock in tests. A name can resolve to several addresses (IPv6
+//! and IPv4, round-robin records); [`connect_any`] tries each in turn.
+
+use std::{fmt, future::Future, io, net::SocketAddr};
+
+use engine_shared::net::{AddrError, HostPort};
+use tracing::{debug, warn};
+
+/// Looks up the socket addresses for a host name.
+pub trait Resolver {
+    fn lookup(&self, target: &HostPort)
+        -> impl Future<Output = io::Result<Vec<SocketAddr>>> + Send;
+}
+
+/// Resolves through the system resolver with `tokio::net::lookup_host`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioResolver;
+
+impl Resolver for TokioResolver {
+    fn lookup(
+        &self,
+        target: &HostPort,
+    ) -> impl Future<Output = io::Result<Vec<SocketAddr>>> + Send {
+        let query = (target.host.clone(), target.port);
+        async move { Ok(tokio::net::lookup_host(query).await?.collect()) }
+    }
+}
+
+/// Why a server address couldn't be turned into socket addresses.
+#[derive(Debug)]
+pub enum ResolveError {
+    /// The string isn't a valid `host:port`.
+    Invalid(AddrError),
+    /// The lookup itself failed.
+    Lookup { host: String, source: io::Error },
+    /// The lookup succeeded but returned nothing.
+    NoAddresses { host: String },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::Invalid(e) => write!(f, "invalid server address: {}", e),
+            ResolveError::Lookup { host, source } => {
+                write!(f, "could not resolve '{}': {}", host, source)
+            }
+            ResolveError::NoAddresses { host } => {
+                write!(f, "'{}' did not resolve to any address", host)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ResolveError::Invalid(e) => Some(e),
+            ResolveError::Lookup { source, .. } => Some(source),
+            ResolveError::NoAddresses { .. } => None,
+        }
+    }
+}
+
+/// Turns a `host:port` string into the addresses to try, in resolver order.
+///
+/// IP literals are returned directly without consulting `resolver`.
+pub async fn resolve(
+    resolver: &impl Resolver,
+    addr: &str,
+) -> Result<Vec<SocketAddr>, ResolveError> {
+    let target = HostPort::parse(addr).map_err(ResolveError::Invalid)?;
+    if let Some(ip) = target.ip() {
+        return Ok(vec![SocketAddr::new(ip, target.port)]);
+    }
+    let addrs = resolver
+        .lookup(&target)
+        .await
+        .map_err(|source| ResolveError::Lookup {
+            host: target.host.clone(),
+            source,
+        })?;
+    if addrs.is_empty() {
+        return Err(ResolveError::NoAddresses { host: target.host });
+    }
+    debug!(host = %target.host, ?addrs, "Resolved server address");
+    Ok(addrs)
+}
+
+/// Runs `attempt` against each address in order until one succeeds.
+///
+/// Fails with the last attempt's error if none do.
+pub async fn connect_any<T, F, Fut>(addrs: &[SocketAddr], mut attempt: F) -> anyhow::Result<T>
+where
+    F: FnMut(SocketAddr) -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut last_err = None;
+    for &addr in addrs {
+        match attempt(addr).await {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                warn!(%addr, error = %e, "Connection attempt failed");
+                last_err = Some(e);
+            }
+        }
+    }
+    match last_err {
+        Some(e) => Err(e.context(format!("all {} resolved addresses failed", addrs.len()))),
+        None => anyhow::bail!("no addresses to connect to"),
+    }
+}
@@ -1,7 +1,10 @@
 //! Standalone server binary.
 //!
 //! Usage:
-//!   cargo run -p engine_server -- [--addr 127.0.0.1:40000] [--tick-hz 64] [--maps-dir maps]
+//!   cargo run -p engine_server -- [--config server.toml] [--addr 127.0.0.1:40000] [--tick-hz 64] [--maps-dir maps]
+//!
+//! Settings are layered: defaults < config file < `PS_*` environment
+//! variables < command-line arguments.
 //!
 //! The server listens for client connections, runs a fixed timestep simulation,
 //! and broadcasts snapshots to connected clients.
@@ -13,36 +16,49 @@
 
 use std::env;
 use std::io::{BufRead, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use engine_server::server::{GameServer, ServerState};
-use engine_shared::config::EngineConfig;
+use engine_shared::config::{CliArgs, EngineConfig};
 use tokio::sync::mpsc;
 use tracing::info;
 
-fn parse_args() -> EngineConfig {
-    let mut cfg = EngineConfig::default();
+fn parse_args() -> anyhow::Result<EngineConfig> {
+    let mut cli = CliArgs::default();
+    let mut config_path = None;
     let args: Vec<String> = env::args().collect();
     let mut i = 1;
     while i < args.len() {
         match args[i].as_str() {
+            "--config" if i + 1 < args.len() => {
+                config_path = Some(args[i + 1].clone());
+                i += 2;
+            }
             "--addr" if i + 1 < args.len() => {
-                cfg.server_addr = args[i + 1].clone();
+                cli.server_addr = Some(args[i + 1].clone());
                 i += 2;
             }
             "--tick-hz" if i + 1 < args.len() => {
-                cfg.tick_hz = args[i + 1].parse().unwrap_or(64);
+                cli.tick_hz = Some(args[i + 1].parse().context("parse --tick-hz")?);
                 i += 2;
             }
             "--maps-dir" if i + 1 < args.len() => {
-                cfg.maps_dir = args[i + 1].clone();
+                cli.maps_dir = Some(args[i + 1].clone());
                 i += 2;
             }
             _ => i += 1,
         }
     }
-    cfg
+
+    let mut cfg = match config_path {
+        Some(path) => EngineConfig::from_file(Path::new(&path))?,
+        None => EngineConfig::default(),
+    };
+    cfg.apply_env()?;
+    let cfg = cfg.merge_cli(&cli);
+    cfg.validate_server()?;
+    Ok(cfg)
 }
 
 #[tokio::main]
@@ -53,7 +69,7 @@ async fn main() -> anyhow::Result<()> {
         )
         .init();
 
-    let cfg = parse_args();
+    let cfg = parse_args()?;
     info!(addr = %cfg.server_addr, tick_hz = cfg.tick_hz, maps_dir = %cfg.maps_dir, "Starting server");
 
     let mut server = GameServer::new(cfg.clone(), PathBuf::from(&cfg.maps_dir))
@@ -110,6 +126,11 @@ async fn main() -> anyhow::Result<()> {
             server.step(0.0).await?;
         }
 
+        if *server.state() == ServerState::Stopped {
+            info!("Server stopped");
+            return Ok(());
+        }
+
         // Wait for next tick.
         next_tick += tick_interval;
         tokio::time::sleep_until(next_tick).await;
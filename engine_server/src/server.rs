@@ -5,8 +5,11 @@
 //! - BSP map loading
 //! - Console commands (map, status, kick, quit)
 //! - Client connection with map transfer flow
+//! - DLC-gated maps (clients must own the map's DLC to join)
 //! - Entity spawning from BSP entities
-//! - Snapshot replication
+//! - Snapshot replication, capped per client by `sv_maxrate` and optionally
+//!   quantized (`sv_quantize`)
+//! - Chat relayed to the recipients `ChatManager` picks
 //!
 //! Determinism notes:
 //! - Keep simulation in a fixed timestep.
@@ -15,35 +18,63 @@
 
 use anyhow::Context;
 use engine_shared::{
-    bsp::BspMap,
+    bsp::{self, BspMap, LoadedMap},
+    chat::{ChatChannel, ChatManager, ChatMessage},
     config::EngineConfig,
-    console::{Console, CvarFlags, CvarValue},
-    ecs::{EntityId, Position, World},
+    console::{ConsoleRegistry, CvarFlags, CvarValue},
+    dlc::{AppId, DlcManager},
+    ecs::{self, EntityId, Position, Schedule, World},
+    input::{InButtons, InputState},
     math::Vec3,
     net::{
-        ClientId, EntitySpawn, EntityState, MapInfo, NetMsg, PlayerCommand, ReliableConn,
-        ReliableListener, Snapshot, PROTOCOL_VERSION,
+        decode_from_bytes, BandwidthTracker, ClientId, CommandAck, EntitySpawn, EntityState,
+        MapInfo, NetMsg, PlayerCommand, QuantizeConfig, RejectReason, ReliableConn,
+        ReliableListener, Snapshot, SnapshotHistory, DEFAULT_MAX_RATE, PROTOCOL_VERSION,
     },
+    physics::{self, GroundInfo, PlayerPhysics},
+    steam_id::SteamId,
+    transport::{loopback_listener, LoopbackConnector, LoopbackListener, LoopbackTransport},
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::PathBuf,
+    sync::Arc,
     time::Duration,
 };
 use tokio::{net::UdpSocket, sync::mpsc, time::Instant};
 use tracing::{debug, info, warn};
 
+/// Stand-in `udp_peer` for in-process clients, which have no address.
+const LOOPBACK_PEER: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+
 /// Connected client state.
 struct ClientState {
     _id: ClientId,
+    /// Steam ID presented in the handshake (validated as a player account).
+    steam_id: SteamId,
+    /// Name presented in the handshake.
+    player_name: String,
+    /// DLC the client owns, checked against each map's requirement.
+    owned_dlc: Vec<AppId>,
     reliable: ReliableConn,
     udp_peer: SocketAddr,
+    /// Datagram channel of an in-process client; `udp_peer` is unused
+    /// when set.
+    loopback: Option<LoopbackTransport>,
     last_cmd_tick: u32,
+    /// Sequence of the last predicted command applied; 0 if none yet.
+    last_cmd_sequence: u32,
+    /// Movement state of `player_entity`.
+    physics: PlayerPhysics,
     /// Whether the client has finished loading the map.
     ready: bool,
     /// Entity ID assigned to this client's player.
     player_entity: Option<EntityId>,
+    /// When the client last sent anything over UDP.
+    last_packet_at: Instant,
+    /// When the client was last sent a `Ping`.
+    last_ping_at: Instant,
 }
 
 /// Server state enum for connection flow.
@@ -55,28 +86,83 @@ pub enum ServerState {
     LoadingMap,
     /// Map loaded, accepting clients and running simulation.
     Running,
+    /// Map changed with clients connected; simulation is paused until they
+    /// have all loaded it.
+    ChangingMap,
+    /// `quit` was issued; clients are disconnected on the next step.
+    ShuttingDown,
+    /// Clients have been disconnected and the main loop should exit.
+    Stopped,
+}
+
+/// Disconnect reason sent to clients when the server shuts down.
+pub const SHUTDOWN_REASON: &str = "Server shutting down";
+/// Disconnect reason sent to clients that stopped sending packets.
+pub const TIMED_OUT_REASON: &str = "Timed out";
+/// Disconnect reason for clients whose reliable connection closed.
+pub const CONNECTION_LOST_REASON: &str = "Connection lost";
+/// Disconnect reason for clients that don't load a new map in time.
+pub const MAP_LOAD_TIMED_OUT_REASON: &str = "Map load timed out";
+/// How long clients get to send `ClientReady` after a map change.
+pub const MAP_LOAD_TIMEOUT: Duration = Duration::from_secs(60);
+/// How often idle clients are pinged so they have something to answer.
+pub const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(1);
+/// Chat messages kept in the server's history.
+const CHAT_HISTORY: usize = 100;
+
+/// Disconnect reason for a client lacking the DLC that ships `map`.
+fn map_dlc_reason(map: &str, dlc_app_id: AppId) -> String {
+    format!("Map {map} requires DLC {dlc_app_id}")
 }
 
+/// Source of the current time, replaceable in tests.
+pub type Clock = Arc<dyn Fn() -> Instant + Send + Sync>;
+
 /// Game server.
 pub struct GameServer {
     pub cfg: EngineConfig,
-    pub console: Console,
+    pub console: ConsoleRegistry,
     world: World,
+    schedule: Schedule,
     clients: HashMap<ClientId, ClientState>,
 
     tcp: ReliableListener,
     udp: UdpSocket,
+    /// In-process clients of a listen server.
+    loopback: LoopbackListener,
+    loopback_connector: LoopbackConnector,
 
     tick: u32,
     state: ServerState,
+    /// Recent snapshots for lag compensation.
+    history: SnapshotHistory,
 
     /// Currently loaded map.
     current_map: Option<BspMap>,
+    /// Size and CRC of the current map file, if it was loaded from disk.
+    current_map_file: Option<LoadedMap>,
     /// Path to maps directory.
     maps_dir: PathBuf,
+    /// DLC app that ships each gated map, by map name.
+    map_dlc: HashMap<String, AppId>,
 
     /// Channel for console commands from stdin.
     console_rx: Option<mpsc::Receiver<String>>,
+    clock: Clock,
+    next_ping_seq: u32,
+    /// When the current map change started, while in `ChangingMap`.
+    map_change_started: Option<Instant>,
+    /// Whether clients still need `MapInfo` for the current map change.
+    map_info_pending: bool,
+    /// Clients lacking the new map's DLC, with the map and DLC, to be
+    /// dropped before `MapInfo` goes out.
+    map_dlc_denied: Vec<(ClientId, String, AppId)>,
+    /// Players (e.g. party members) holding a slot until they join.
+    reserved_slots: HashSet<SteamId>,
+    /// Datagram traffic per UDP client, for `sv_maxrate`.
+    bandwidth: BandwidthTracker,
+    /// Chat permissions, rate limits and history, by Steam ID.
+    chat: ChatManager,
 }
 
 impl GameServer {
@@ -86,21 +172,38 @@ impl GameServer {
         let tcp = ReliableListener::bind(addr).await?;
         let udp = UdpSocket::bind(addr).await.context("udp bind")?;
 
-        let mut console = Console::new();
-        Self::register_cvars(&mut console);
+        let mut console = ConsoleRegistry::new();
+        Self::register_cvars(&mut console, &cfg);
+        // Rewind up to one second, like Source's sv_maxunlag.
+        let history = SnapshotHistory::new(cfg.tick_hz);
+        let (loopback_connector, loopback) = loopback_listener();
 
         Ok(Self {
             cfg,
             console,
             world: World::default(),
+            schedule: Self::default_schedule(),
             clients: HashMap::new(),
             tcp,
             udp,
+            loopback,
+            loopback_connector,
             tick: 0,
             state: ServerState::Idle,
+            history,
             current_map: None,
+            current_map_file: None,
             maps_dir,
+            map_dlc: HashMap::new(),
             console_rx: None,
+            clock: Arc::new(Instant::now),
+            next_ping_seq: 0,
+            map_change_started: None,
+            map_info_pending: false,
+            map_dlc_denied: Vec::new(),
+            reserved_slots: HashSet::new(),
+            bandwidth: BandwidthTracker::default(),
+            chat: ChatManager::new(CHAT_HISTORY),
         })
     }
 
@@ -109,7 +212,7 @@ impl GameServer {
         Self::new(cfg, PathBuf::from("maps")).await
     }
 
-    fn register_cvars(console: &mut Console) {
+    fn register_cvars(console: &mut ConsoleRegistry, cfg: &EngineConfig) {
         console.register_cvar(
             "sv_tickrate",
             CvarValue::Int(64),
@@ -118,10 +221,22 @@ impl GameServer {
         );
         console.register_cvar(
             "sv_maxclients",
-            CvarValue::Int(16),
+            CvarValue::Int(cfg.max_clients.into()),
             "Max connected clients",
             CvarFlags::NONE,
         );
+        console.register_cvar(
+            "sv_maxrate",
+            CvarValue::Int(DEFAULT_MAX_RATE.into()),
+            "Max bytes/sec sent to each client (0 = unlimited)",
+            CvarFlags::NONE,
+        );
+        console.register_cvar(
+            "sv_quantize",
+            CvarValue::Bool(false),
+            "Send snapshot positions and angles as fixed-point",
+            CvarFlags::NONE,
+        );
         console.register_cvar(
             "sv_cheats",
             CvarValue::Bool(false),
@@ -135,6 +250,68 @@ impl GameServer {
         self.console_rx = Some(rx);
     }
 
+    /// Replaces the clock used for client timeouts and keepalives.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    fn now(&self) -> Instant {
+        (self.clock)()
+    }
+
+    /// Player slot limit: `sv_maxclients`, which starts at the configured
+    /// `max_clients`.
+    pub fn max_clients(&self) -> usize {
+        self.console
+            .get_cvar("sv_maxclients")
+            .and_then(|v| v.as_int())
+            .map_or(self.cfg.max_clients as usize, |n| n.max(0) as usize)
+    }
+
+    /// Holds a slot for `steam_id` (e.g. a party member still connecting)
+    /// so public joins can't take it.
+    pub fn reserve_slot(&mut self, steam_id: SteamId) {
+        self.reserved_slots.insert(steam_id);
+    }
+
+    /// Releases a reservation that won't be used. Returns whether one was
+    /// held.
+    pub fn cancel_reservation(&mut self, steam_id: SteamId) -> bool {
+        self.reserved_slots.remove(&steam_id)
+    }
+
+    /// Whether `steam_id` can take a slot right now.
+    fn has_slot_for(&self, steam_id: SteamId) -> bool {
+        let max = self.max_clients();
+        if self.reserved_slots.contains(&steam_id) {
+            return self.clients.len() < max;
+        }
+        self.clients.len() + self.reserved_slots.len() < max
+    }
+
+    /// Rejects a handshake when no slot is free for `steam_id`.
+    async fn check_capacity(
+        &self,
+        conn: &mut ReliableConn,
+        steam_id: SteamId,
+    ) -> anyhow::Result<()> {
+        if self.has_slot_for(steam_id) {
+            return Ok(());
+        }
+        warn!(%steam_id, max_clients = self.max_clients(), "Rejecting client, server full");
+        let _ = conn
+            .send(&NetMsg::Reject {
+                reason: RejectReason::ServerFull,
+            })
+            .await;
+        anyhow::bail!("server full, rejected {steam_id}");
+    }
+
+    /// Number of clients currently connected.
+    pub fn connected_client_count(&self) -> usize {
+        self.clients.len()
+    }
+
     /// Returns the local address (after binding).
     pub fn local_addr(&self) -> anyhow::Result<SocketAddr> {
         self.tcp.local_addr()
@@ -151,8 +328,15 @@ impl GameServer {
         info!(map = %map_name, "Loading map");
 
         let path = self.maps_dir.join(format!("{}.bsp", map_name));
-        let bsp = BspMap::load(&path).with_context(|| format!("load map {}", path.display()))?;
+        let (file, bsp) =
+            bsp::read_map(&path).with_context(|| format!("load map {}", path.display()))?;
+        self.load_bsp(bsp);
+        self.current_map_file = Some(file);
+        Ok(())
+    }
 
+    /// Installs an already-parsed map as the current map.
+    pub fn load_bsp(&mut self, bsp: BspMap) {
         info!(
             map = %bsp.name,
             entities = bsp.entities.len(),
@@ -166,15 +350,115 @@ impl GameServer {
         self.spawn_bsp_entities(&bsp);
 
         self.current_map = Some(bsp);
+        self.current_map_file = None;
+        // Ticks restart with the new world, so older snapshots would be
+        // mistaken for new-map ones.
         self.tick = 0;
+        self.history = SnapshotHistory::new(self.cfg.tick_hz);
         self.state = ServerState::Running;
+    }
 
-        // Notify connected clients about map change.
-        // (Clients will need to reconnect or we send MapInfo).
-
+    /// Switches to `map_name` mid-session, bringing connected clients along.
+    ///
+    /// Every client is sent the new `MapInfo` and must answer `ClientReady`
+    /// before simulation resumes; clients that haven't within
+    /// [`MAP_LOAD_TIMEOUT`] are dropped.
+    pub async fn change_map(&mut self, map_name: &str) -> anyhow::Result<()> {
+        self.load_map(map_name)?;
+        self.begin_map_change();
+        self.broadcast_map_info().await;
         Ok(())
     }
 
+    /// Like [`change_map`](Self::change_map) with an already-parsed map.
+    pub async fn change_map_bsp(&mut self, bsp: BspMap) {
+        self.load_bsp(bsp);
+        self.begin_map_change();
+        self.broadcast_map_info().await;
+    }
+
+    /// Puts connected clients back into loading after a map load. Their
+    /// players went away with the old world.
+    ///
+    /// Clients that don't own the new map's DLC are queued to be dropped.
+    fn begin_map_change(&mut self) {
+        if self.clients.is_empty() {
+            return;
+        }
+        let mut denied: Vec<_> = self
+            .clients
+            .iter()
+            .filter_map(|(id, c)| {
+                let (map, dlc_app_id) = self.missing_map_dlc(&c.owned_dlc)?;
+                Some((*id, map.to_string(), dlc_app_id))
+            })
+            .collect();
+        denied.sort_by_key(|(id, _, _)| id.0);
+        self.map_dlc_denied = denied;
+        for client in self.clients.values_mut() {
+            client.ready = false;
+            client.player_entity = None;
+        }
+        self.state = ServerState::ChangingMap;
+        self.map_change_started = Some(self.now());
+        self.map_info_pending = true;
+    }
+
+    async fn broadcast_map_info(&mut self) {
+        if !self.map_info_pending {
+            return;
+        }
+        self.map_info_pending = false;
+        for (id, map, dlc_app_id) in std::mem::take(&mut self.map_dlc_denied) {
+            warn!(client_id = ?id, map = %map, dlc_app_id, "Dropping client without map DLC");
+            self.disconnect_client(id, &map_dlc_reason(&map, dlc_app_id))
+                .await;
+        }
+        let Some(info) = self.map_info() else {
+            return;
+        };
+        let msg = NetMsg::MapInfo(info);
+        for (id, client) in self.clients.iter_mut() {
+            if let Err(e) = client.reliable.send(&msg).await {
+                warn!(client_id = ?id, error = %e, "Failed to send map info");
+            }
+        }
+    }
+
+    /// Resumes simulation once every client is ready, dropping stragglers
+    /// after [`MAP_LOAD_TIMEOUT`].
+    async fn check_map_change(&mut self) {
+        if self.state != ServerState::ChangingMap {
+            return;
+        }
+        let started = self.map_change_started.unwrap_or_else(|| self.now());
+        if self.now().saturating_duration_since(started) >= MAP_LOAD_TIMEOUT {
+            let mut stragglers: Vec<ClientId> = self
+                .clients
+                .iter()
+                .filter(|(_, c)| !c.ready)
+                .map(|(id, _)| *id)
+                .collect();
+            stragglers.sort_by_key(|id| id.0);
+            for id in stragglers {
+                info!(client_id = ?id, "Client didn't load the map in time");
+                self.disconnect_client(id, MAP_LOAD_TIMED_OUT_REASON).await;
+            }
+        }
+
+        if self.clients.values().all(|c| c.ready) {
+            info!("All clients loaded the map, resuming");
+            self.state = ServerState::Running;
+            self.map_change_started = None;
+        }
+    }
+
+    /// Marks a map as DLC content; clients must own `dlc_app_id` to join
+    /// while it is loaded.
+    pub fn set_map_dlc(&mut self, map_name: &str, dlc_app_id: AppId) {
+        self.map_dlc.insert(map_name.to_string(), dlc_app_id);
+    }
+
     fn spawn_bsp_entities(&mut self, bsp: &BspMap) {
         for ent in &bsp.entities {
             // Skip worldspawn.
@@ -201,10 +485,11 @@ impl GameServer {
 
     /// Returns map info for network transmission.
     pub fn map_info(&self) -> Option<MapInfo> {
+        let file = self.current_map_file.as_ref();
         self.current_map.as_ref().map(|m| MapInfo {
             name: m.name.clone(),
-            crc: 0, // TODO: compute CRC
-            size: 0,
+            crc: file.map_or(0, |f| f.crc),
+            size: file.map_or(0, |f| f.size),
         })
     }
 
@@ -213,7 +498,16 @@ impl GameServer {
         let (mut conn, peer) = self.tcp.accept().await?;
         let msg = conn.recv().await?;
         match msg {
-            NetMsg::Hello { protocol } if protocol == PROTOCOL_VERSION => {
+            NetMsg::Hello {
+                protocol,
+                steam_id,
+                owned_dlc,
+                player_name,
+            } if protocol == PROTOCOL_VERSION => {
+                Self::check_player_steam_id(&mut conn, steam_id).await?;
+                self.check_capacity(&mut conn, steam_id).await?;
+                self.check_map_access(&mut conn, &owned_dlc).await?;
+
                 // Expect the client to announce its UDP port next.
                 let udp_hello = conn.recv().await?;
                 let client_udp_port = match udp_hello {
@@ -228,23 +522,35 @@ impl GameServer {
                 if let Some(map_info) = self.map_info() {
                     conn.send(&NetMsg::MapInfo(map_info)).await?;
                 }
+                self.send_replicated_cvars(&mut conn).await?;
 
                 let udp_peer = SocketAddr::new(peer.ip(), client_udp_port);
+                let now = self.now();
                 self.clients.insert(
                     id,
                     ClientState {
                         _id: id,
+                        steam_id,
+                        player_name,
+                        owned_dlc,
                         reliable: conn,
                         udp_peer,
+                        loopback: None,
                         last_cmd_tick: 0,
+                        last_cmd_sequence: 0,
+                        physics: PlayerPhysics::default(),
                         // In many flows the client sends `ClientReady` after loading a map.
                         // In tests we may not load a map at all, so allow snapshots immediately.
                         ready: true,
                         player_entity: None,
+                        last_packet_at: now,
+                        last_ping_at: now,
                     },
                 );
 
-                info!(client_id = ?id, %udp_peer, "Client connected");
+                self.reserved_slots.remove(&steam_id);
+                self.chat.add_player(steam_id);
+                info!(client_id = ?id, %steam_id, %udp_peer, "Client connected");
                 Ok(id)
             }
             other => anyhow::bail!("unexpected handshake msg: {other:?}"),
@@ -256,21 +562,60 @@ impl GameServer {
         match tokio::time::timeout(timeout, self.tcp.accept()).await {
             Ok(Ok((conn, peer))) => {
                 // Handle handshake inline.
-                self.handle_new_connection(conn, peer).await.map(Some)
+                self.handle_new_connection(conn, peer, None).await.map(Some)
             }
             Ok(Err(e)) => Err(e),
             Err(_) => Ok(None), // Timeout
         }
     }
 
+    /// Opens the in-process connections a listen server's local client
+    /// uses; they're accepted by [`accept_local`](Self::accept_local) or on
+    /// the next [`step`](Self::step).
+    pub fn loopback_connector(&self) -> LoopbackConnector {
+        self.loopback_connector.clone()
+    }
+
+    /// Waits for one in-process client and runs its handshake.
+    pub async fn accept_local(&mut self) -> anyhow::Result<ClientId> {
+        let (conn, transport) = self.loopback.accept().await?;
+        self.handle_new_connection(conn, LOOPBACK_PEER, Some(transport))
+            .await
+    }
+
+    /// Handshakes every in-process client that connected since last step.
+    async fn accept_loopback_clients(&mut self) {
+        while let Some((conn, transport)) = self.loopback.try_accept() {
+            match self
+                .handle_new_connection(conn, LOOPBACK_PEER, Some(transport))
+                .await
+            {
+                Ok(id) => info!(client_id = ?id, "Local client accepted"),
+                Err(e) => warn!(error = %e, "Local client handshake failed"),
+            }
+        }
+    }
+
+    /// Handshakes a client on `conn`; `loopback` carries its datagrams if it
+    /// is in-process, in which case `peer` is a placeholder.
     async fn handle_new_connection(
         &mut self,
         mut conn: ReliableConn,
         peer: SocketAddr,
+        loopback: Option<LoopbackTransport>,
     ) -> anyhow::Result<ClientId> {
         let msg = conn.recv().await?;
         match msg {
-            NetMsg::Hello { protocol } if protocol == PROTOCOL_VERSION => {
+            NetMsg::Hello {
+                protocol,
+                steam_id,
+                owned_dlc,
+                player_name,
+            } if protocol == PROTOCOL_VERSION => {
+                Self::check_player_steam_id(&mut conn, steam_id).await?;
+                self.check_capacity(&mut conn, steam_id).await?;
+                self.check_map_access(&mut conn, &owned_dlc).await?;
+
                 let udp_hello = conn.recv().await?;
                 let client_udp_port = match udp_hello {
                     NetMsg::UdpHello { client_udp_port } => client_udp_port,
@@ -283,29 +628,107 @@ impl GameServer {
                 if let Some(map_info) = self.map_info() {
                     conn.send(&NetMsg::MapInfo(map_info)).await?;
                 }
+                self.send_replicated_cvars(&mut conn).await?;
 
                 let udp_peer = SocketAddr::new(peer.ip(), client_udp_port);
+                let now = self.now();
                 self.clients.insert(
                     id,
                     ClientState {
                         _id: id,
+                        steam_id,
+                        player_name,
+                        owned_dlc,
                         reliable: conn,
                         udp_peer,
+                        loopback,
                         last_cmd_tick: 0,
+                        last_cmd_sequence: 0,
+                        physics: PlayerPhysics::default(),
                         ready: false,
                         player_entity: None,
+                        last_packet_at: now,
+                        last_ping_at: now,
                     },
                 );
 
-                info!(client_id = ?id, %udp_peer, "Client connected");
+                self.reserved_slots.remove(&steam_id);
+                self.chat.add_player(steam_id);
+                info!(client_id = ?id, %steam_id, %udp_peer, "Client connected");
                 Ok(id)
             }
             other => anyhow::bail!("unexpected handshake msg: {other:?}"),
         }
     }
 
+    /// Rejects a handshake whose Steam ID is not a valid player account.
+    async fn check_player_steam_id(
+        conn: &mut ReliableConn,
+        steam_id: SteamId,
+    ) -> anyhow::Result<()> {
+        if let Err(e) = steam_id.validate_for_player_connection() {
+            warn!(%steam_id, error = %e, "Rejecting client with invalid Steam ID");
+            let _ = conn
+                .send(&NetMsg::Disconnect {
+                    reason: format!("Invalid Steam ID: {e}"),
+                })
+                .await;
+            anyhow::bail!("invalid player Steam ID {steam_id}: {e}");
+        }
+        Ok(())
+    }
+
+    /// Rejects a handshake if the current map ships with DLC the client
+    /// doesn't own.
+    async fn check_map_access(
+        &self,
+        conn: &mut ReliableConn,
+        owned_dlc: &[AppId],
+    ) -> anyhow::Result<()> {
+        let Some((map, dlc_app_id)) = self.missing_map_dlc(owned_dlc) else {
+            return Ok(());
+        };
+        warn!(map = %map, dlc_app_id, "Rejecting client without map DLC");
+        let _ = conn
+            .send(&NetMsg::Disconnect {
+                reason: map_dlc_reason(map, dlc_app_id),
+            })
+            .await;
+        anyhow::bail!("client lacks DLC {dlc_app_id} for map {map}");
+    }
+
+    /// The current map and the DLC it needs, if `owned_dlc` lacks it.
+    fn missing_map_dlc(&self, owned_dlc: &[AppId]) -> Option<(&str, AppId)> {
+        let map = self.current_map.as_ref()?;
+        let &dlc_app_id = self.map_dlc.get(&map.name)?;
+
+        // The server transfers the map itself, so owned DLC counts as
+        // installed. The base app ID is never queried.
+        let mut dlc = DlcManager::new(0);
+        for &app_id in owned_dlc {
+            dlc.set_dlc_owned(app_id, true);
+            dlc.install_dlc(app_id);
+        }
+        match dlc.require_access(dlc_app_id) {
+            Ok(()) => None,
+            Err(_) => Some((&map.name, dlc_app_id)),
+        }
+    }
+
     /// Marks a client as ready and spawns their player entity.
+    ///
+    /// A client that is already ready keeps its player, so a repeated
+    /// `ClientReady` returns the existing entity.
     pub fn client_ready(&mut self, client_id: ClientId) -> anyhow::Result<EntityId> {
+        if let Some(ent) = self
+            .clients
+            .get(&client_id)
+            .filter(|c| c.ready)
+            .and_then(|c| c.player_entity)
+        {
+            return Ok(ent);
+        }
+
         let spawn_points = self
             .current_map
             .as_ref()
@@ -327,6 +750,10 @@ impl GameServer {
         if let Some(client) = self.clients.get_mut(&client_id) {
             client.ready = true;
             client.player_entity = Some(ent);
+            client.physics = PlayerPhysics {
+                position: spawn_pos,
+                velocity: Vec3::ZERO,
+            };
         }
 
         info!(client_id = ?client_id, entity = ?ent, "Client ready, player spawned");
@@ -348,16 +775,120 @@ impl GameServer {
 
     /// Executes one fixed simulation step.
     pub async fn step(&mut self, dt_sec: f32) -> anyhow::Result<()> {
+        if self.state == ServerState::Stopped {
+            return Ok(());
+        }
         self.process_console_commands().await?;
+        if self.state == ServerState::ShuttingDown {
+            self.shutdown().await;
+            return Ok(());
+        }
+        self.accept_loopback_clients().await;
+        self.broadcast_cvar_updates().await;
+        self.broadcast_map_info().await;
         self.recv_commands().await?;
-        self.simulate(dt_sec);
+        self.recv_reliable().await;
+        self.drop_timed_out_clients().await;
+        self.send_keepalives().await;
+        self.check_map_change().await;
         if self.state == ServerState::Running {
+            self.simulate(dt_sec);
             self.send_snapshots().await?;
         }
         self.tick += 1;
         Ok(())
     }
 
+    /// Disconnects every client with [`SHUTDOWN_REASON`] and stops the
+    /// server. Send failures are logged; a client that already went away
+    /// doesn't hold up the others.
+    pub async fn shutdown(&mut self) {
+        self.state = ServerState::ShuttingDown;
+        info!(clients = self.clients.len(), "Server shutting down");
+
+        for (id, mut client) in self.clients.drain() {
+            Self::send_disconnect(id, &mut client, SHUTDOWN_REASON).await;
+            self.bandwidth.remove_client(id);
+            self.chat.remove_player(client.steam_id);
+        }
+
+        self.state = ServerState::Stopped;
+    }
+
+    /// Tells a client why it's being dropped and closes its connection.
+    async fn send_disconnect(id: ClientId, client: &mut ClientState, reason: &str) {
+        let msg = NetMsg::Disconnect {
+            reason: reason.to_string(),
+        };
+        if let Err(e) = client.reliable.send(&msg).await {
+            warn!(client_id = ?id, error = %e, "Failed to send disconnect");
+            return;
+        }
+        if let Err(e) = client.reliable.close().await {
+            debug!(client_id = ?id, error = %e, "Failed to close connection");
+        }
+    }
+
+    /// Drops clients that have sent nothing for `client_timeout_secs`,
+    /// despawning their players.
+    async fn drop_timed_out_clients(&mut self) {
+        let now = self.now();
+        let timeout = Duration::from_secs(self.cfg.client_timeout_secs);
+        let mut timed_out: Vec<ClientId> = self
+            .clients
+            .iter()
+            .filter(|(_, c)| now.saturating_duration_since(c.last_packet_at) > timeout)
+            .map(|(id, _)| *id)
+            .collect();
+        timed_out.sort_by_key(|id| id.0);
+
+        for id in timed_out {
+            info!(client_id = ?id, "Client timed out");
+            self.disconnect_client(id, TIMED_OUT_REASON).await;
+        }
+    }
+
+    /// Drops a client, telling it `reason` and despawning its player.
+    /// Returns false if no such client is connected.
+    pub async fn disconnect_client(&mut self, id: ClientId, reason: &str) -> bool {
+        let Some(mut client) = self.clients.remove(&id) else {
+            return false;
+        };
+        self.bandwidth.remove_client(id);
+        // Another connection may share the Steam ID (e.g. test clients).
+        if !self.clients.values().any(|c| c.steam_id == client.steam_id) {
+            self.chat.remove_player(client.steam_id);
+        }
+        Self::send_disconnect(id, &mut client, reason).await;
+        if let Some(ent) = client.player_entity {
+            self.world.despawn(ent);
+        }
+        true
+    }
+
+    /// Pings clients not pinged within [`KEEPALIVE_INTERVAL`].
+    async fn send_keepalives(&mut self) {
+        let now = self.now();
+        let seq = self.next_ping_seq;
+        let mut sent = false;
+        for (id, client) in self.clients.iter_mut() {
+            if now.saturating_duration_since(client.last_ping_at) < KEEPALIVE_INTERVAL {
+                continue;
+            }
+            client.last_ping_at = now;
+            sent = true;
+            let Ok(payload) = serde_json::to_vec(&NetMsg::Ping { seq }) else {
+                continue;
+            };
+            if let Err(e) = Self::send_datagram(&self.udp, client, &payload).await {
+                debug!(client_id = ?id, error = %e, "Failed to send ping");
+            }
+        }
+        if sent {
+            self.next_ping_seq = seq.wrapping_add(1);
+        }
+    }
+
     async fn process_console_commands(&mut self) -> anyhow::Result<()> {
         // Collect lines first to avoid borrow conflict
         let lines: Vec<String> = if let Some(ref mut rx) = self.console_rx {
@@ -376,6 +907,30 @@ impl GameServer {
         Ok(())
     }
 
+    /// Sends the current value of every replicated cvar to a new client.
+    async fn send_replicated_cvars(&self, conn: &mut ReliableConn) -> anyhow::Result<()> {
+        for msg in self.console.replicated_cvars() {
+            conn.send(&msg).await?;
+        }
+        Ok(())
+    }
+
+    /// Broadcasts replicated cvars changed since the last call.
+    async fn broadcast_cvar_updates(&mut self) {
+        let updates = self.console.replicated_changes();
+        if updates.is_empty() {
+            return;
+        }
+        for (id, client) in self.clients.iter_mut() {
+            for msg in &updates {
+                if let Err(e) = client.reliable.send(msg).await {
+                    warn!(client_id = ?id, error = %e, "Failed to send cvar update");
+                    break;
+                }
+            }
+        }
+    }
+
     /// Executes a console command.
     pub fn exec_console(&mut self, line: &str) -> anyhow::Result<Vec<String>> {
         let line = line.trim();
@@ -392,7 +947,11 @@ impl GameServer {
                     return Ok(vec!["Usage: map <mapname>".to_string()]);
                 }
                 match self.load_map(tokens[1]) {
-                    Ok(()) => Ok(vec![format!("Map '{}' loaded", tokens[1])]),
+                    Ok(()) => {
+                        // Connected clients get MapInfo on the next step.
+                        self.begin_map_change();
+                        Ok(vec![format!("Map '{}' loaded", tokens[1])])
+                    }
                     Err(e) => Ok(vec![format!("Failed to load map: {}", e)]),
                 }
             }
@@ -406,19 +965,24 @@ impl GameServer {
                 out.push(format!("Clients: {}", self.clients.len()));
                 for (id, client) in &self.clients {
                     out.push(format!(
-                        "  {:?}: udp={} ready={} entity={:?}",
-                        id, client.udp_peer, client.ready, client.player_entity
+                        "  {:?}: steamid={} udp={} ready={} entity={:?}",
+                        id,
+                        client.steam_id.to_steam3(),
+                        client.udp_peer,
+                        client.ready,
+                        client.player_entity
                     ));
                 }
                 Ok(out)
             }
             "quit" | "exit" => {
-                info!("Server shutting down");
-                std::process::exit(0);
+                // Clients are notified on the next step, which can await.
+                self.state = ServerState::ShuttingDown;
+                Ok(vec![SHUTDOWN_REASON.to_string()])
             }
             _ => {
                 // Delegate to console system.
-                self.console.exec(line)
+                Ok(self.console.exec(line)?)
             }
         }
     }
@@ -427,28 +991,81 @@ impl GameServer {
         let mut buf = vec![0u8; 64 * 1024];
         loop {
             match self.udp.try_recv_from(&mut buf) {
-                Ok((n, from)) => {
-                    if let Ok(msg) = serde_json::from_slice::<NetMsg>(&buf[..n]) {
-                        self.handle_udp_message(from, msg).await;
-                    }
-                }
+                Ok((n, from)) => match decode_from_bytes(&buf[..n]) {
+                    Ok(msg) => self.handle_udp_message(from, msg, n).await,
+                    Err(e) => debug!(%from, error = %e, "Dropping malformed datagram"),
+                },
                 Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
                 Err(e) => return Err(e).context("udp recv")?,
             }
         }
+
+        let mut local = Vec::new();
+        for c in self.clients.values() {
+            let Some(transport) = &c.loopback else {
+                continue;
+            };
+            while let Ok(Some(msg)) = transport.try_recv() {
+                local.push((c.udp_peer, msg));
+            }
+        }
+        for (from, msg) in local {
+            // Loopback traffic isn't metered, so its size doesn't matter.
+            self.handle_udp_message(from, msg, 0).await;
+        }
         Ok(())
     }
 
-    async fn handle_udp_message(&mut self, from: SocketAddr, msg: NetMsg) {
+    /// Sends an encoded datagram to `client`, in-process or over UDP.
+    async fn send_datagram(
+        udp: &UdpSocket,
+        client: &ClientState,
+        payload: &[u8],
+    ) -> anyhow::Result<()> {
+        match &client.loopback {
+            Some(transport) => transport.send_bytes(payload),
+            None => {
+                udp.send_to(payload, client.udp_peer)
+                    .await
+                    .context("udp send")?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Handles a datagram of `len` bytes.
+    async fn handle_udp_message(&mut self, from: SocketAddr, msg: NetMsg, len: usize) {
+        let sender = match &msg {
+            NetMsg::PlayerCommand(cmd) => Some(cmd.client_id),
+            NetMsg::ClientReady { client_id } | NetMsg::Pong { client_id, .. } => Some(*client_id),
+            _ => None,
+        };
+        if let Some(id) = sender {
+            let now = self.now();
+            // Anyone can put a client's ID in a datagram; only its own
+            // address speaks for it.
+            let Some(client) = self.clients.get_mut(&id).filter(|c| c.udp_peer == from) else {
+                debug!(client_id = ?id, %from, "Dropping datagram from a foreign address");
+                return;
+            };
+            client.last_packet_at = now;
+            if client.loopback.is_none() {
+                self.bandwidth.record_received_at(id, len, now.into_std());
+            }
+        }
+
         match msg {
             NetMsg::PlayerCommand(cmd) => {
-                self.on_command(from, cmd);
+                self.on_command(cmd);
             }
             NetMsg::ClientReady { client_id } => {
                 if let Err(e) = self.client_ready(client_id) {
                     warn!(client_id = ?client_id, error = %e, "Failed to mark client ready");
                 }
             }
+            NetMsg::Pong { client_id, seq } => {
+                debug!(client_id = ?client_id, seq, "Pong received");
+            }
             NetMsg::ClientCommand { command } => {
                 debug!(command = %command, "Client command received");
                 // TODO: handle client console commands (say, etc.)
@@ -459,49 +1076,192 @@ impl GameServer {
         }
     }
 
-    fn on_command(&mut self, from: SocketAddr, cmd: PlayerCommand) {
+    /// Handles messages clients sent over their reliable connections, and
+    /// drops clients whose connection has closed.
+    async fn recv_reliable(&mut self) {
+        let mut received = Vec::new();
+        let mut lost = Vec::new();
+        for (&id, client) in self.clients.iter_mut() {
+            loop {
+                match client.reliable.try_recv().await {
+                    Ok(Some(msg)) => received.push((id, msg)),
+                    Ok(None) => break,
+                    Err(e) => {
+                        debug!(client_id = ?id, error = %e, "Reliable connection lost");
+                        lost.push(id);
+                        break;
+                    }
+                }
+            }
+        }
+
+        for (id, msg) in received {
+            match msg {
+                NetMsg::Chat(chat) => self.on_chat(id, chat).await,
+                _ => debug!(client_id = ?id, ?msg, "Unexpected reliable message"),
+            }
+        }
+        for id in lost {
+            self.disconnect_client(id, CONNECTION_LOST_REASON).await;
+        }
+    }
+
+    /// Relays a client's chat message to its recipients and back to the
+    /// sender, so every client shows chat as the server accepted it.
+    ///
+    /// The sender's Steam ID and name are taken from the client's
+    /// connection, not the message. Server-only channels are refused.
+    async fn on_chat(&mut self, id: ClientId, chat: ChatMessage) {
+        let Some(client) = self.clients.get(&id) else {
+            return;
+        };
+        let sender = client.steam_id;
+        if matches!(chat.channel, ChatChannel::Server | ChatChannel::Console) {
+            debug!(%sender, channel = ?chat.channel, "Dropping chat on a server-only channel");
+            return;
+        }
+
+        let (message, recipients) = match self.chat.send_message_with_recipients(
+            sender,
+            &client.player_name,
+            chat.channel,
+            &chat.content,
+        ) {
+            Ok(sent) => sent,
+            Err(result) => {
+                debug!(%sender, ?result, "Chat message refused");
+                return;
+            }
+        };
+        let msg = NetMsg::Chat(message);
+        for (id, client) in self.clients.iter_mut() {
+            if client.steam_id != sender && !recipients.contains(&client.steam_id) {
+                continue;
+            }
+            if let Err(e) = client.reliable.send(&msg).await {
+                warn!(client_id = ?id, error = %e, "Failed to send chat");
+            }
+        }
+    }
+
+    fn on_command(&mut self, cmd: PlayerCommand) {
+        let dt = 1.0 / self.cfg.tick_hz as f32;
         if let Some(c) = self.clients.get_mut(&cmd.client_id) {
-            c.udp_peer = from;
             c.last_cmd_tick = cmd.tick;
 
-            // Apply movement to client's player entity.
+            // A predicting client numbers its commands; drop duplicates and
+            // stragglers so its replay lines up with what we applied.
+            if cmd.sequence != 0 {
+                if cmd.sequence <= c.last_cmd_sequence {
+                    return;
+                }
+                c.last_cmd_sequence = cmd.sequence;
+            }
+
+            // Apply movement to client's player entity, as the client
+            // predicts it.
             if let Some(eid) = c.player_entity {
+                let input = InputState {
+                    forward: cmd.wish.x,
+                    right: cmd.wish.y,
+                    up: cmd.wish.z,
+                    buttons: InButtons::empty(),
+                };
+                physics::move_player(&mut c.physics, &input, dt, GroundInfo::FLAT);
                 if let Some(pos) = self.world.get_mut::<Position>(eid) {
-                    pos.x += cmd.wish.x * 0.1;
-                    pos.y += cmd.wish.y * 0.1;
-                    pos.z += cmd.wish.z * 0.1;
+                    let p = c.physics.position;
+                    *pos = Position {
+                        x: p.x,
+                        y: p.y,
+                        z: p.z,
+                    };
                 }
             }
         }
     }
 
-    fn simulate(&mut self, _dt_sec: f32) {
-        // Placeholder for deterministic simulation systems.
+    /// Simulation systems, in the order they run each tick.
+    fn default_schedule() -> Schedule {
+        let mut schedule = Schedule::new();
+        schedule.add_system("integrate_velocity", ecs::integrate_velocity);
+        schedule
     }
 
-    async fn send_snapshots(&self) -> anyhow::Result<()> {
-        let mut entities = Vec::new();
-        for (eid, pos) in self.world.iter::<Position>() {
-            entities.push(EntityState {
-                id: eid,
-                position: Vec3::new(pos.x, pos.y, pos.z),
-            });
-        }
+    fn simulate(&mut self, dt_sec: f32) {
+        self.schedule.run(&mut self.world, dt_sec);
+    }
+
+    /// Gets an entity's state as it was at `tick`, for lag-compensated
+    /// hit checks.
+    pub fn entity_state_at(&self, entity: EntityId, tick: u32) -> Option<EntityState> {
+        self.history.state_at(entity, tick)
+    }
 
-        let snap = NetMsg::Snapshot(Snapshot {
-            tick: self.tick,
-            entities,
-        });
-        let payload = serde_json::to_vec(&snap).context("serialize snapshot")?;
+    async fn send_snapshots(&mut self) -> anyhow::Result<()> {
+        let snapshot = self.world.to_snapshot(self.tick);
+        self.history.push(snapshot.clone());
+        let quantize = self
+            .console
+            .get_cvar("sv_quantize")
+            .is_some_and(|v| v.as_bool())
+            .then(QuantizeConfig::default);
+        let encode = |snap: Snapshot| {
+            let msg = match quantize {
+                Some(cfg) => NetMsg::QuantizedSnapshot(cfg.quantize_snapshot(&snap)),
+                None => NetMsg::Snapshot(snap),
+            };
+            serde_json::to_vec(&msg).context("serialize snapshot")
+        };
+        let payload = encode(snapshot.clone())?;
+        let max_rate = self
+            .console
+            .get_cvar("sv_maxrate")
+            .and_then(|v| v.as_int())
+            .map_or(DEFAULT_MAX_RATE, |n| n.clamp(0, u32::MAX.into()) as u32);
+        self.bandwidth.set_max_rate(max_rate);
+        let now = self.now().into_std();
+
+        for (&id, c) in &self.clients {
+            if !c.ready {
+                continue;
+            }
+            // Predicting clients also get their own command ack.
+            let own;
+            let bytes = if c.last_cmd_sequence == 0 {
+                &payload
+            } else {
+                let snap = Snapshot {
+                    ack: Some(CommandAck {
+                        sequence: c.last_cmd_sequence,
+                        position: c.physics.position,
+                        velocity: c.physics.velocity,
+                    }),
+                    ..snapshot.clone()
+                };
+                own = encode(snap)?;
+                &own
+            };
 
-        for c in self.clients.values() {
-            if c.ready {
-                let _ = self.udp.send_to(&payload, c.udp_peer).await;
+            // Over its rate the client skips this snapshot; the next one
+            // carries the full state anyway.
+            if c.loopback.is_none() {
+                if !self.bandwidth.can_send_at(id, bytes.len(), now) {
+                    debug!(client_id = ?id, bytes = bytes.len(), "Over sv_maxrate, dropping snapshot");
+                    continue;
+                }
+                self.bandwidth.record_sent_at(id, bytes.len(), now);
             }
+            let _ = Self::send_datagram(&self.udp, c, bytes).await;
         }
         Ok(())
     }
 
+    /// Outgoing datagram rate to a client, in bytes per second.
+    pub fn client_bytes_per_sec(&mut self, id: ClientId) -> u32 {
+        let now = self.now().into_std();
+        self.bandwidth.bytes_per_sec_at(id, now)
+    }
+
     /// Sends entity spawn packets to a client.
     pub async fn send_entity_spawns(&mut self, client_id: ClientId) -> anyhow::Result<()> {
         let Some(map) = &self.current_map else {
@@ -553,22 +1313,37 @@ pub async fn bind_ephemeral(tick_hz: u32) -> anyhow::Result<(GameServer, EngineC
     let udp_bind = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), addr.port());
     let udp = UdpSocket::bind(udp_bind).await?;
 
-    let mut console = Console::new();
-    GameServer::register_cvars(&mut console);
+    let mut console = ConsoleRegistry::new();
+    GameServer::register_cvars(&mut console, &cfg);
+    let (loopback_connector, loopback) = loopback_listener();
 
     Ok((
         GameServer {
             cfg: cfg.clone(),
             console,
             world: World::default(),
+            schedule: GameServer::default_schedule(),
             clients: HashMap::new(),
             tcp,
             udp,
+            loopback,
+            loopback_connector,
             tick: 0,
             state: ServerState::Running, // For tests, assume running
+            history: SnapshotHistory::new(tick_hz),
             current_map: None,
+            current_map_file: None,
             maps_dir: PathBuf::from("maps"),
+            map_dlc: HashMap::new(),
             console_rx: None,
+            clock: Arc::new(Instant::now),
+            next_ping_seq: 0,
+            map_change_started: None,
+            map_info_pending: false,
+            map_dlc_denied: Vec::new(),
+            reserved_slots: HashSet::new(),
+            bandwidth: BandwidthTracker::default(),
+            chat: ChatManager::new(CHAT_HISTORY),
         },
         cfg,
     ))
@@ -12,16 +12,26 @@
 //! 4. Steam backend sends `ValidateAuthTicketResponse_t` callback
 //! 5. Server accepts/rejects based on response
 //!
+//! [`MockAuthProvider::begin_auth_session`] and
+//! [`MockAuthProvider::poll_callbacks`] model steps 3-4 asynchronously: the
+//! result only arrives once polled, after an optional simulated delay.
+//!
 //! # Ticket Lifetime
 //! - Tickets are valid until cancelled or Steam disconnection
 //! - Maximum ticket size: 1024 bytes
 //! - Tickets are bound to the requesting SteamID
+//!
+//! # Game Server Identity
+//! Dedicated servers log on with a `GameServer` SteamID and can issue tickets
+//! of their own. A client validates one with
+//! [`MockAuthProvider::validate_server_ticket`] to check it reached the server
+//! it meant to, rather than something impersonating it.
 
 use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 
-use crate::steam_id::SteamId;
+use crate::steam_id::{SteamId, SteamIdError};
 
 /// Maximum size of an auth ticket in bytes.
 /// Reference: <https://partner.steamgames.com/doc/api/ISteamUser#GetAuthSessionTicket>
@@ -378,6 +388,21 @@ pub struct MockAuthProvider {
     app_id: u32,
     /// Active tickets (handle -> is_valid).
     active_tickets: std::collections::HashMap<u32, bool>,
+    /// Handles issued to each owner and not yet cancelled by owner.
+    owner_tickets: std::collections::HashMap<SteamId, Vec<u32>>,
+    /// Handles issued by `create_game_server_ticket`.
+    server_tickets: std::collections::HashSet<u32>,
+    /// Sessions begun but not yet reported, in begin order.
+    pending_validations: std::collections::VecDeque<PendingValidation>,
+    /// How long a begun session waits before its callback is ready.
+    callback_delay: Duration,
+}
+
+/// A `BeginAuthSession` call waiting for its callback.
+struct PendingValidation {
+    ticket: AuthTicket,
+    expected_owner: SteamId,
+    ready_at: Instant,
 }
 
 impl MockAuthProvider {
@@ -386,9 +411,67 @@ impl MockAuthProvider {
             next_handle: 1,
             app_id,
             active_tickets: std::collections::HashMap::new(),
+            owner_tickets: std::collections::HashMap::new(),
+            server_tickets: std::collections::HashSet::new(),
+            pending_validations: std::collections::VecDeque::new(),
+            callback_delay: Duration::ZERO,
         }
     }
 
+    /// Set the simulated delay between `begin_auth_session` and its callback.
+    pub fn set_callback_delay(&mut self, delay: Duration) {
+        self.callback_delay = delay;
+    }
+
+    /// Queue validation of `ticket`, like `BeginAuthSession()`.
+    ///
+    /// The result is reported by [`poll_callbacks`](Self::poll_callbacks)
+    /// once the callback delay has passed. Validation happens then, so a
+    /// ticket cancelled in the meantime reports `AuthTicketCanceled`.
+    pub fn begin_auth_session(&mut self, ticket: &AuthTicket, expected_owner: SteamId) {
+        self.begin_auth_session_at(ticket, expected_owner, Instant::now());
+    }
+
+    pub fn begin_auth_session_at(
+        &mut self,
+        ticket: &AuthTicket,
+        expected_owner: SteamId,
+        now: Instant,
+    ) {
+        self.pending_validations.push_back(PendingValidation {
+            ticket: ticket.clone(),
+            expected_owner,
+            ready_at: now + self.callback_delay,
+        });
+    }
+
+    /// Number of begun sessions whose callback hasn't been polled yet.
+    pub fn pending_callbacks(&self) -> usize {
+        self.pending_validations.len()
+    }
+
+    /// Drain completed validations, like `ValidateAuthTicketResponse_t`.
+    ///
+    /// Results are keyed by the expected owner and come back in the order
+    /// the sessions were begun.
+    pub fn poll_callbacks(&mut self) -> Vec<(SteamId, AuthSessionResponse)> {
+        self.poll_callbacks_at(Instant::now())
+    }
+
+    pub fn poll_callbacks_at(&mut self, now: Instant) -> Vec<(SteamId, AuthSessionResponse)> {
+        let (ready, waiting) = std::mem::take(&mut self.pending_validations)
+            .into_iter()
+            .partition::<Vec<_>, _>(|pending| pending.ready_at <= now);
+        self.pending_validations = waiting.into();
+        ready
+            .into_iter()
+            .map(|pending| {
+                let response = self.validate_ticket(&pending.ticket, pending.expected_owner);
+                (pending.expected_owner, response)
+            })
+            .collect()
+    }
+
     /// Generate a mock auth ticket.
     pub fn get_auth_ticket(&mut self, owner: SteamId) -> AuthTicket {
         let handle = AuthTicketHandle::new(self.next_handle);
@@ -396,6 +479,10 @@ impl MockAuthProvider {
 
         // Track active ticket
         self.active_tickets.insert(handle.as_u32(), true);
+        self.owner_tickets
+            .entry(owner)
+            .or_default()
+            .push(handle.as_u32());
 
         // Generate deterministic ticket data based on owner
         let mut data = Vec::with_capacity(64);
@@ -408,6 +495,19 @@ impl MockAuthProvider {
         AuthTicket::new(handle, data, owner, self.app_id)
     }
 
+    /// Generate a ticket identifying a dedicated server to its clients.
+    ///
+    /// Fails unless `server_id` is a `GameServer` account.
+    pub fn create_game_server_ticket(
+        &mut self,
+        server_id: SteamId,
+    ) -> Result<AuthTicket, SteamIdError> {
+        server_id.validate_for_game_server()?;
+        let ticket = self.get_auth_ticket(server_id);
+        self.server_tickets.insert(ticket.handle.as_u32());
+        Ok(ticket)
+    }
+
     /// Cancel a ticket by handle.
     pub fn cancel_ticket(&mut self, handle: AuthTicketHandle) {
         if let Some(valid) = self.active_tickets.get_mut(&handle.as_u32()) {
@@ -415,6 +515,23 @@ impl MockAuthProvider {
         }
     }
 
+    /// Cancel every ticket issued to `owner`, e.g. on logout.
+    ///
+    /// Returns how many tickets were still active.
+    pub fn cancel_all_for_owner(&mut self, owner: SteamId) -> usize {
+        let handles = self.owner_tickets.remove(&owner).unwrap_or_default();
+        let mut cancelled = 0;
+        for handle in handles {
+            if let Some(valid) = self.active_tickets.get_mut(&handle) {
+                if *valid {
+                    *valid = false;
+                    cancelled += 1;
+                }
+            }
+        }
+        cancelled
+    }
+
     /// Check if a ticket handle is still valid (not cancelled).
     pub fn is_ticket_valid(&self, handle: AuthTicketHandle) -> bool {
         self.active_tickets
@@ -462,6 +579,11 @@ impl MockAuthProvider {
             return AuthSessionResponse::AuthTicketInvalid;
         }
 
+        // A server's ticket can't stand in for a player's
+        if self.server_tickets.contains(&ticket.handle.as_u32()) {
+            return AuthSessionResponse::AuthTicketInvalid;
+        }
+
         // Check app ID (would be embedded in real ticket)
         if ticket.app_id != self.app_id {
             return AuthSessionResponse::NoLicenseOrExpired;
@@ -469,11 +591,42 @@ impl MockAuthProvider {
 
         AuthSessionResponse::Ok
     }
+
+    /// Validate a ticket presented by a game server (mock implementation).
+    ///
+    /// Accepts only tickets from `create_game_server_ticket` whose owner is
+    /// `expected_server` and a `GameServer` account.
+    pub fn validate_server_ticket(
+        &self,
+        ticket: &AuthTicket,
+        expected_server: SteamId,
+    ) -> AuthSessionResponse {
+        if expected_server.validate_for_game_server().is_err()
+            || ticket.owner.validate_for_game_server().is_err()
+        {
+            return AuthSessionResponse::AuthTicketInvalid;
+        }
+        if !ticket.is_valid_size() || !ticket.handle.is_valid() {
+            return AuthSessionResponse::AuthTicketInvalid;
+        }
+        if !self.is_ticket_valid(ticket.handle) {
+            return AuthSessionResponse::AuthTicketCanceled;
+        }
+        if !self.server_tickets.contains(&ticket.handle.as_u32()) || ticket.owner != expected_server
+        {
+            return AuthSessionResponse::AuthTicketInvalid;
+        }
+        if ticket.app_id != self.app_id {
+            return AuthSessionResponse::NoLicenseOrExpired;
+        }
+        AuthSessionResponse::Ok
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::steam_id::{AccountType, Universe};
 
     // =============================================================================
     // AUTH-001: Valid Steam Login
@@ -901,4 +1054,184 @@ mod tests {
         assert_eq!(provider.validate_ticket(&ticket2, steam_id), AuthSessionResponse::Ok);
         assert_eq!(provider.validate_ticket(&ticket3, steam_id), AuthSessionResponse::Ok);
     }
+
+    // =============================================================================
+    // GS-001: Game Server Identity
+    // Reference: https://partner.steamgames.com/doc/api/ISteamGameServer#GetAuthSessionTicket
+    // =============================================================================
+
+    fn game_server_id(account_id: u32) -> SteamId {
+        SteamId::from_parts(account_id, 1, AccountType::GameServer, Universe::Public)
+    }
+
+    #[test]
+    fn gs_001_server_ticket_validates_for_game_server() {
+        let mut provider = MockAuthProvider::new(730);
+        let server_id = game_server_id(777);
+
+        let ticket = provider.create_game_server_ticket(server_id).unwrap();
+        assert_eq!(ticket.owner, server_id);
+        assert_eq!(
+            provider.validate_server_ticket(&ticket, server_id),
+            AuthSessionResponse::Ok
+        );
+
+        // Another server can't claim it.
+        assert_eq!(
+            provider.validate_server_ticket(&ticket, game_server_id(778)),
+            AuthSessionResponse::AuthTicketInvalid
+        );
+
+        provider.cancel_ticket(ticket.handle);
+        assert_eq!(
+            provider.validate_server_ticket(&ticket, server_id),
+            AuthSessionResponse::AuthTicketCanceled
+        );
+    }
+
+    #[test]
+    fn gs_001_server_ticket_requires_game_server_id() {
+        let mut provider = MockAuthProvider::new(730);
+        let player = SteamId::from_account_id(12345);
+        assert_eq!(
+            provider.create_game_server_ticket(player).err(),
+            Some(SteamIdError::NotGameServer(AccountType::Individual))
+        );
+
+        let anon = SteamId::from_parts(777, 1, AccountType::AnonGameServer, Universe::Public);
+        assert!(provider.create_game_server_ticket(anon).is_err());
+    }
+
+    #[test]
+    fn gs_001_server_ticket_rejected_as_individual() {
+        let mut provider = MockAuthProvider::new(730);
+        let server_id = game_server_id(777);
+        let server_ticket = provider.create_game_server_ticket(server_id).unwrap();
+
+        // Presented on the player path, even under its own owner.
+        assert_eq!(
+            provider.validate_ticket(&server_ticket, server_id),
+            AuthSessionResponse::AuthTicketInvalid
+        );
+
+        // A player's ticket can't pass as a server's either.
+        let player = SteamId::from_account_id(12345);
+        let player_ticket = provider.get_auth_ticket(player);
+        assert_eq!(
+            provider.validate_server_ticket(&player_ticket, player),
+            AuthSessionResponse::AuthTicketInvalid
+        );
+        assert_eq!(
+            provider.validate_ticket(&player_ticket, player),
+            AuthSessionResponse::Ok
+        );
+    }
+
+    // =============================================================================
+    // AUTH-CB: Asynchronous validation callbacks
+    // Reference: https://partner.steamgames.com/doc/api/ISteamUser#ValidateAuthTicketResponse_t
+    // =============================================================================
+
+    #[test]
+    fn auth_cb_result_arrives_only_after_poll() {
+        let mut provider = MockAuthProvider::new(730);
+        let player = SteamId::from_account_id(12345);
+        let ticket = provider.get_auth_ticket(player);
+
+        let mut session = AuthSession::new(player);
+        provider.begin_auth_session(&ticket, player);
+        session.begin_validation();
+        assert_eq!(session.state, AuthSessionState::Pending);
+        assert_eq!(provider.pending_callbacks(), 1);
+
+        for (steam_id, response) in provider.poll_callbacks() {
+            assert_eq!(steam_id, player);
+            session.on_validation_response(response);
+        }
+        assert!(session.is_valid());
+        assert_eq!(provider.pending_callbacks(), 0);
+        assert!(provider.poll_callbacks().is_empty());
+    }
+
+    #[test]
+    fn auth_cb_simulated_delay() {
+        let mut provider = MockAuthProvider::new(730);
+        provider.set_callback_delay(Duration::from_millis(200));
+        let alice = SteamId::from_account_id(1);
+        let bob = SteamId::from_account_id(2);
+        let alice_ticket = provider.get_auth_ticket(alice);
+        let bob_ticket = provider.get_auth_ticket(bob);
+
+        let start = Instant::now();
+        provider.begin_auth_session_at(&alice_ticket, alice, start);
+        provider.begin_auth_session_at(&bob_ticket, alice, start + Duration::from_millis(100));
+
+        assert!(provider
+            .poll_callbacks_at(start + Duration::from_millis(199))
+            .is_empty());
+        assert_eq!(
+            provider.poll_callbacks_at(start + Duration::from_millis(200)),
+            vec![(alice, AuthSessionResponse::Ok)]
+        );
+        assert_eq!(provider.pending_callbacks(), 1);
+        // Bob's ticket presented as Alice's fails once it completes.
+        assert_eq!(
+            provider.poll_callbacks_at(start + Duration::from_millis(300)),
+            vec![(alice, AuthSessionResponse::AuthTicketInvalid)]
+        );
+    }
+
+    #[test]
+    fn auth_cb_cancelled_before_callback() {
+        let mut provider = MockAuthProvider::new(730);
+        let player = SteamId::from_account_id(12345);
+        let ticket = provider.get_auth_ticket(player);
+
+        provider.begin_auth_session(&ticket, player);
+        provider.cancel_ticket(ticket.handle);
+
+        assert_eq!(
+            provider.poll_callbacks(),
+            vec![(player, AuthSessionResponse::AuthTicketCanceled)]
+        );
+    }
+
+    // =============================================================================
+    // AUTH-LOGOUT: Cancelling every ticket for an owner
+    // =============================================================================
+
+    #[test]
+    fn auth_logout_cancels_all_owner_tickets() {
+        let mut provider = MockAuthProvider::new(730);
+        let player = SteamId::from_account_id(12345);
+        let other = SteamId::from_account_id(67890);
+        let tickets: Vec<_> = (0..3).map(|_| provider.get_auth_ticket(player)).collect();
+        let others_ticket = provider.get_auth_ticket(other);
+        provider.cancel_ticket(tickets[1].handle);
+
+        // The one already cancelled doesn't count.
+        assert_eq!(provider.cancel_all_for_owner(player), 2);
+        for ticket in &tickets {
+            assert_eq!(
+                provider.validate_ticket(ticket, player),
+                AuthSessionResponse::AuthTicketCanceled
+            );
+            assert_eq!(
+                provider.validate_ticket_by_handle(ticket.handle),
+                AuthSessionResponse::AuthTicketCanceled
+            );
+        }
+        assert_eq!(
+            provider.validate_ticket(&others_ticket, other),
+            AuthSessionResponse::Ok
+        );
+
+        // Logging back in issues working tickets again.
+        assert_eq!(provider.cancel_all_for_owner(player), 0);
+        let fresh = provider.get_auth_ticket(player);
+        assert_eq!(
+            provider.validate_ticket(&fresh, player),
+            AuthSessionResponse::Ok
+        );
+    }
 }
@@ -5,11 +5,14 @@
 //! - [Avatar System](https://partner.steamgames.com/doc/features/avatars)
 //!
 //! # Features
-//! - Avatar retrieval in multiple sizes (32x32, 64x64, 128x128)
+//! - Avatar retrieval in multiple sizes (32x32, 64x64, 184x184)
 //! - Async avatar loading with callbacks
 //! - Avatar caching for performance
 
 use std::collections::HashMap;
+use std::fmt;
+
+use crate::steam_id::SteamId;
 
 /// Avatar size variants.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -18,7 +21,7 @@ pub enum AvatarSize {
     Small,
     /// 64x64 pixels.
     Medium,
-    /// 128x128 pixels.
+    /// 184x184 pixels.
     Large,
 }
 
@@ -28,7 +31,7 @@ impl AvatarSize {
         match self {
             AvatarSize::Small => (32, 32),
             AvatarSize::Medium => (64, 64),
-            AvatarSize::Large => (128, 128),
+            AvatarSize::Large => (184, 184),
         }
     }
 
@@ -39,6 +42,76 @@ impl AvatarSize {
     }
 }
 
+/// Errors from building or caching an [`Avatar`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvatarError {
+    /// RGBA buffer length doesn't match `width * height * 4`.
+    BufferSizeMismatch { expected: usize, actual: usize },
+    /// Image dimensions don't match the size tier it was cached under.
+    WrongDimensions { width: u32, height: u32 },
+}
+
+impl fmt::Display for AvatarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AvatarError::BufferSizeMismatch { expected, actual } => {
+                write!(f, "RGBA buffer is {actual} bytes, expected {expected}")
+            }
+            AvatarError::WrongDimensions { width, height } => {
+                write!(f, "{width}x{height} image doesn't match its size tier")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AvatarError {}
+
+/// RGBA avatar image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Avatar {
+    pub width: u32,
+    pub height: u32,
+    /// Row-major RGBA pixels, `width * height * 4` bytes.
+    pub rgba: Vec<u8>,
+}
+
+impl Avatar {
+    /// Create an avatar, checking the buffer matches the dimensions.
+    pub fn new(width: u32, height: u32, rgba: Vec<u8>) -> Result<Self, AvatarError> {
+        let expected = width as usize * height as usize * 4;
+        if rgba.len() != expected {
+            return Err(AvatarError::BufferSizeMismatch {
+                expected,
+                actual: rgba.len(),
+            });
+        }
+        Ok(Self {
+            width,
+            height,
+            rgba,
+        })
+    }
+
+    /// Nearest-neighbor resample to the given size.
+    pub fn resized(&self, size: AvatarSize) -> Self {
+        let (width, height) = size.dimensions();
+        let mut rgba = Vec::with_capacity(size.byte_size());
+        for y in 0..height {
+            let src_y = y * self.height / height;
+            for x in 0..width {
+                let src_x = x * self.width / width;
+                let i = ((src_y * self.width + src_x) * 4) as usize;
+                rgba.extend_from_slice(&self.rgba[i..i + 4]);
+            }
+        }
+        Self {
+            width,
+            height,
+            rgba,
+        }
+    }
+}
+
 /// Result of avatar request.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AvatarResult {
@@ -175,7 +248,7 @@ impl AvatarManager {
         self.get_avatar(steam_id, AvatarSize::Medium)
     }
 
-    /// Get large avatar (128x128) for a user.
+    /// Get large avatar (184x184) for a user.
     pub fn get_large_friend_avatar(&mut self, steam_id: u64) -> AvatarResult {
         self.get_avatar(steam_id, AvatarSize::Large)
     }
@@ -295,6 +368,122 @@ impl AvatarManager {
     }
 }
 
+/// Cells per side of a placeholder identicon.
+const PLACEHOLDER_GRID: u32 = 5;
+
+/// Background color of placeholder identicons.
+const PLACEHOLDER_BACKGROUND: [u8; 4] = [240, 240, 240, 255];
+
+/// Generate a deterministic identicon for a user without a loaded avatar.
+///
+/// A 5x5 grid, mirrored left to right, is filled from a hash of the account
+/// ID, so the same user always gets the same image on every platform.
+pub fn generate_placeholder(steam_id: SteamId, size: AvatarSize) -> Avatar {
+    // SplitMix64 finalizer: cheap, well-mixed, and fully integer.
+    let mut h = u64::from(steam_id.account_id()).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    h ^= h >> 31;
+
+    // Low 15 bits fill the left three columns; the next bytes pick a color
+    // dark enough to stand out on the light background.
+    let filled = |col: u32, row: u32| h >> (row * 3 + col) & 1 == 1;
+    let foreground = [
+        (h >> 16) as u8 & 0x9F,
+        (h >> 24) as u8 & 0x9F,
+        (h >> 32) as u8 & 0x9F,
+        255,
+    ];
+
+    let (width, height) = size.dimensions();
+    let mut rgba = Vec::with_capacity(size.byte_size());
+    for y in 0..height {
+        let row = y * PLACEHOLDER_GRID / height;
+        for x in 0..width {
+            // Mirror pixels rather than cells so odd sizes stay symmetric.
+            let col = x.min(width - 1 - x) * PLACEHOLDER_GRID / width;
+            let color = if filled(col, row) {
+                foreground
+            } else {
+                PLACEHOLDER_BACKGROUND
+            };
+            rgba.extend_from_slice(&color);
+        }
+    }
+
+    Avatar {
+        width,
+        height,
+        rgba,
+    }
+}
+
+/// Loaded avatars keyed by Steam ID and size tier.
+#[derive(Debug, Default)]
+pub struct AvatarCache {
+    avatars: HashMap<(SteamId, AvatarSize), Avatar>,
+}
+
+impl AvatarCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cache an avatar, checking its buffer and that it matches `size`.
+    pub fn insert(
+        &mut self,
+        steam_id: SteamId,
+        size: AvatarSize,
+        avatar: Avatar,
+    ) -> Result<(), AvatarError> {
+        let avatar = Avatar::new(avatar.width, avatar.height, avatar.rgba)?;
+        if (avatar.width, avatar.height) != size.dimensions() {
+            return Err(AvatarError::WrongDimensions {
+                width: avatar.width,
+                height: avatar.height,
+            });
+        }
+        self.avatars.insert((steam_id, size), avatar);
+        Ok(())
+    }
+
+    /// Get the cached avatar for exactly this size.
+    pub fn get(&self, steam_id: SteamId, size: AvatarSize) -> Option<&Avatar> {
+        self.avatars.get(&(steam_id, size))
+    }
+
+    /// Remove all sizes for a user (avatar was updated).
+    pub fn invalidate(&mut self, steam_id: SteamId) {
+        self.avatars.retain(|&(id, _), _| id != steam_id);
+    }
+
+    /// Number of cached images.
+    pub fn len(&self) -> usize {
+        self.avatars.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.avatars.is_empty()
+    }
+
+    /// Get an avatar at `size`.
+    ///
+    /// Falls back to resampling another cached size (largest first), then to
+    /// [`generate_placeholder`].
+    pub fn get_avatar(&self, steam_id: SteamId, size: AvatarSize) -> Avatar {
+        if let Some(avatar) = self.get(steam_id, size) {
+            return avatar.clone();
+        }
+        [AvatarSize::Large, AvatarSize::Medium, AvatarSize::Small]
+            .into_iter()
+            .find_map(|other| self.get(steam_id, other))
+            .map(|avatar| avatar.resized(size))
+            .unwrap_or_else(|| generate_placeholder(steam_id, size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -365,8 +554,8 @@ mod tests {
         mgr.process_pending();
 
         let avatar = mgr.get_cached_avatar(67890, AvatarSize::Large).unwrap();
-        assert_eq!(avatar.width, 128);
-        assert_eq!(avatar.height, 128);
+        assert_eq!(avatar.width, 184);
+        assert_eq!(avatar.height, 184);
     }
 
     // =============================================================================
@@ -601,6 +790,128 @@ mod tests {
     fn avatar_byte_sizes() {
         assert_eq!(AvatarSize::Small.byte_size(), 32 * 32 * 4);
         assert_eq!(AvatarSize::Medium.byte_size(), 64 * 64 * 4);
-        assert_eq!(AvatarSize::Large.byte_size(), 128 * 128 * 4);
+        assert_eq!(AvatarSize::Large.byte_size(), 184 * 184 * 4);
+    }
+
+    // =============================================================================
+    // AVT-011: Avatar Cache Tiers
+    // =============================================================================
+
+    fn solid(size: AvatarSize, rgba: [u8; 4]) -> Avatar {
+        let (w, h) = size.dimensions();
+        Avatar::new(w, h, rgba.repeat((w * h) as usize)).unwrap()
+    }
+
+    #[test]
+    fn avt_011_cache_hit_and_miss() {
+        let mut cache = AvatarCache::new();
+        let user = SteamId::from_account_id(67890);
+        let red = solid(AvatarSize::Medium, [255, 0, 0, 255]);
+
+        assert!(cache.get(user, AvatarSize::Medium).is_none());
+        assert_eq!(
+            cache.get_avatar(user, AvatarSize::Medium),
+            generate_placeholder(user, AvatarSize::Medium)
+        );
+
+        cache.insert(user, AvatarSize::Medium, red.clone()).unwrap();
+        assert_eq!(cache.get(user, AvatarSize::Medium), Some(&red));
+        assert_eq!(cache.get_avatar(user, AvatarSize::Medium), red);
+
+        cache.invalidate(user);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn avt_011_size_fallback_resamples() {
+        let mut cache = AvatarCache::new();
+        let user = SteamId::from_account_id(67890);
+        cache
+            .insert(
+                user,
+                AvatarSize::Large,
+                solid(AvatarSize::Large, [0, 0, 255, 255]),
+            )
+            .unwrap();
+
+        let small = cache.get_avatar(user, AvatarSize::Small);
+        assert_eq!((small.width, small.height), (32, 32));
+        assert_eq!(small, solid(AvatarSize::Small, [0, 0, 255, 255]));
+
+        // Other users still get a placeholder.
+        let other = SteamId::from_account_id(1);
+        assert_eq!(
+            cache.get_avatar(other, AvatarSize::Small),
+            generate_placeholder(other, AvatarSize::Small)
+        );
+    }
+
+    #[test]
+    fn avt_011_dimension_invariant() {
+        assert_eq!(
+            Avatar::new(2, 2, vec![0; 15]),
+            Err(AvatarError::BufferSizeMismatch {
+                expected: 16,
+                actual: 15
+            })
+        );
+
+        let mut cache = AvatarCache::new();
+        let user = SteamId::from_account_id(67890);
+        let bad = Avatar {
+            width: 32,
+            height: 32,
+            rgba: vec![0; 10],
+        };
+        assert!(matches!(
+            cache.insert(user, AvatarSize::Small, bad),
+            Err(AvatarError::BufferSizeMismatch { .. })
+        ));
+        assert_eq!(
+            cache.insert(user, AvatarSize::Large, solid(AvatarSize::Small, [0; 4])),
+            Err(AvatarError::WrongDimensions {
+                width: 32,
+                height: 32
+            })
+        );
+        assert!(cache.is_empty());
+    }
+
+    // =============================================================================
+    // AVT-012: Placeholder Identicons
+    // =============================================================================
+
+    #[test]
+    fn avt_012_placeholder_is_deterministic() {
+        let user = SteamId::from_account_id(67890);
+
+        for size in [AvatarSize::Small, AvatarSize::Medium, AvatarSize::Large] {
+            let a = generate_placeholder(user, size);
+            let b = generate_placeholder(user, size);
+            assert_eq!(a, b);
+            assert!(Avatar::new(a.width, a.height, a.rgba.clone()).is_ok());
+            assert_eq!((a.width, a.height), size.dimensions());
+        }
+    }
+
+    #[test]
+    fn avt_012_placeholder_differs_by_user() {
+        let a = generate_placeholder(SteamId::from_account_id(1), AvatarSize::Medium);
+        let b = generate_placeholder(SteamId::from_account_id(2), AvatarSize::Medium);
+        assert_ne!(a.rgba, b.rgba);
+    }
+
+    #[test]
+    fn avt_012_placeholder_is_symmetric() {
+        let avatar = generate_placeholder(SteamId::from_account_id(67890), AvatarSize::Large);
+        let w = avatar.width as usize;
+        let pixel = |x: usize, y: usize| &avatar.rgba[(y * w + x) * 4..(y * w + x) * 4 + 4];
+
+        for y in 0..avatar.height as usize {
+            for x in 0..w {
+                assert_eq!(pixel(x, y), pixel(w - 1 - x, y));
+                assert_eq!(pixel(x, y)[3], 255);
+            }
+        }
     }
 }
@@ -13,11 +13,11 @@
 //! ```
 
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::fmt;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
 use std::path::Path;
 
-use anyhow::{bail, Context};
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
 
 use crate::math::Vec3;
@@ -32,10 +32,129 @@ pub const BSP_VERSION_MAX: u32 = 21;
 /// Number of lumps in a BSP file.
 pub const HEADER_LUMPS: usize = 64;
 
+/// Size of the BSP header: magic, version, lump directory, map revision.
+pub const HEADER_SIZE: usize = 8 + HEADER_LUMPS * 16 + 4;
+
+/// Errors from reading BSP data.
+#[derive(Debug)]
+pub enum BspError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// Data ends before the header does.
+    Truncated,
+    /// The file doesn't start with `VBSP`.
+    BadMagic(u32),
+    /// BSP version outside `BSP_VERSION_MIN..=BSP_VERSION_MAX`.
+    UnsupportedVersion(u32),
+    /// A lump's offset/length points past the end of the data.
+    LumpOutOfRange { index: usize },
+}
+
+impl fmt::Display for BspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BspError::Io(e) => write!(f, "I/O error: {e}"),
+            BspError::Truncated => write!(f, "BSP data is truncated"),
+            BspError::BadMagic(magic) => write!(f, "invalid BSP magic: {magic:#x}"),
+            BspError::UnsupportedVersion(v) => write!(f, "unsupported BSP version: {v}"),
+            BspError::LumpOutOfRange { index } => write!(f, "lump {index} is out of range"),
+        }
+    }
+}
+
+impl std::error::Error for BspError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BspError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BspError {
+    fn from(e: io::Error) -> Self {
+        BspError::Io(e)
+    }
+}
+
+/// Map identity advertised to clients in `MapInfo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedMap {
+    /// Map name (file stem).
+    pub name: String,
+    /// File size in bytes.
+    pub size: u64,
+    /// [`compute_map_crc`] of the file contents.
+    pub crc: u32,
+}
+
+impl LoadedMap {
+    fn from_bytes(name: String, bytes: &[u8]) -> Self {
+        Self {
+            name,
+            size: bytes.len() as u64,
+            crc: compute_map_crc(bytes),
+        }
+    }
+}
+
+/// CRC-32 lookup table (reflected polynomial `0xEDB88320`).
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes the map CRC sent in `MapInfo.crc`.
+///
+/// This is the standard CRC-32 (IEEE, as in Source's `CRC32_ProcessBuffer`).
+pub fn compute_map_crc(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &b| {
+        CRC32_TABLE[((crc ^ u32::from(b)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Reads a map file and computes its name, size, and CRC.
+pub fn load_map<P: AsRef<Path>>(path: P) -> Result<LoadedMap, BspError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)?;
+    Ok(LoadedMap::from_bytes(map_name(path), &bytes))
+}
+
+/// Reads a map file once, checksumming and parsing the same buffer.
+pub fn read_map<P: AsRef<Path>>(path: P) -> anyhow::Result<(LoadedMap, BspMap)> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).with_context(|| format!("open {}", path.display()))?;
+    let file = LoadedMap::from_bytes(map_name(path), &bytes);
+    let map = BspMap::parse(file.name.clone(), &bytes)?;
+    Ok((file, map))
+}
+
+fn map_name(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
 /// Lump indices (subset of what we care about).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(usize)]
-pub enum LumpIndex {
+pub enum LumpType {
     Entities = 0,
     Planes = 1,
     TexData = 2,
@@ -53,23 +172,81 @@ pub enum LumpIndex {
     PakFile = 40,
 }
 
+/// Former name of [`LumpType`].
+#[deprecated(note = "renamed to `LumpType`")]
+pub type LumpIndex = LumpType;
+
 /// Lump descriptor from BSP header.
-#[derive(Debug, Clone, Copy, Default)]
-pub struct LumpEntry {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LumpInfo {
     pub offset: u32,
     pub length: u32,
     pub version: u32,
     pub fourcc: [u8; 4],
 }
 
+/// Former name of [`LumpInfo`].
+#[deprecated(note = "renamed to `LumpInfo`")]
+pub type LumpEntry = LumpInfo;
+
 /// BSP file header.
 #[derive(Debug, Clone)]
 pub struct BspHeader {
     pub version: u32,
-    pub lumps: [LumpEntry; HEADER_LUMPS],
+    pub lumps: [LumpInfo; HEADER_LUMPS],
     pub map_revision: u32,
 }
 
+impl BspHeader {
+    /// Parses and validates the header at the start of `bytes`.
+    ///
+    /// Every non-empty lump must lie within `bytes`, so pass the whole file.
+    pub fn parse(bytes: &[u8]) -> Result<Self, BspError> {
+        let u32_at = |at: usize| -> Result<u32, BspError> {
+            let b = bytes.get(at..at + 4).ok_or(BspError::Truncated)?;
+            Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+
+        let magic = u32_at(0)?;
+        if magic != BSP_MAGIC {
+            return Err(BspError::BadMagic(magic));
+        }
+        let version = u32_at(4)?;
+        if !(BSP_VERSION_MIN..=BSP_VERSION_MAX).contains(&version) {
+            return Err(BspError::UnsupportedVersion(version));
+        }
+        if bytes.len() < HEADER_SIZE {
+            return Err(BspError::Truncated);
+        }
+
+        let mut lumps = [LumpInfo::default(); HEADER_LUMPS];
+        for (index, lump) in lumps.iter_mut().enumerate() {
+            let at = 8 + index * 16;
+            lump.offset = u32_at(at)?;
+            lump.length = u32_at(at + 4)?;
+            lump.version = u32_at(at + 8)?;
+            lump.fourcc.copy_from_slice(&bytes[at + 12..at + 16]);
+
+            let end = u64::from(lump.offset) + u64::from(lump.length);
+            if lump.length > 0 && end > bytes.len() as u64 {
+                return Err(BspError::LumpOutOfRange { index });
+            }
+        }
+
+        Ok(BspHeader {
+            version,
+            lumps,
+            map_revision: u32_at(HEADER_SIZE - 4)?,
+        })
+    }
+
+    /// Gets a lump's directory entry, or `None` if the lump is empty.
+    pub fn lump(&self, lump: LumpType) -> Option<&LumpInfo> {
+        let info = &self.lumps[lump as usize];
+        (info.length > 0).then_some(info)
+    }
+}
+
 /// A 3D plane.
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct Plane {
@@ -152,31 +329,92 @@ impl BspEntity {
 
     /// Parses the "origin" property as Vec3.
     pub fn origin(&self) -> Option<Vec3> {
-        let s = self.get("origin")?;
-        let parts: Vec<f32> = s
-            .split_whitespace()
-            .filter_map(|p| p.parse().ok())
-            .collect();
-        if parts.len() == 3 {
-            Some(Vec3::new(parts[0], parts[1], parts[2]))
-        } else {
-            None
-        }
+        parse_vec3(self.get("origin")?)
     }
 
     /// Parses the "angles" property as Vec3 (pitch, yaw, roll).
     pub fn angles(&self) -> Option<Vec3> {
-        let s = self.get("angles")?;
-        let parts: Vec<f32> = s
-            .split_whitespace()
-            .filter_map(|p| p.parse().ok())
-            .collect();
-        if parts.len() == 3 {
-            Some(Vec3::new(parts[0], parts[1], parts[2]))
-        } else {
-            None
+        parse_vec3(self.get("angles")?)
+    }
+}
+
+/// Raw entity from the entity lump: its key/value pairs.
+pub type Entity = HashMap<String, String>;
+
+/// Parses entity lump text into key/value blocks.
+///
+/// Tokens are quoted strings and braces, so layout doesn't matter and
+/// values may contain spaces. Duplicate keys keep the last value, and
+/// `//` comments and unterminated trailing blocks are ignored.
+pub fn parse_entities(lump_text: &str) -> Vec<Entity> {
+    let mut entities = Vec::new();
+    let mut current: Option<Entity> = None;
+    let mut pending_key: Option<String> = None;
+    let mut rest = lump_text;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '\0');
+        let Some(c) = rest.chars().next() else {
+            break;
+        };
+        match c {
+            '{' => {
+                current = Some(Entity::new());
+                pending_key = None;
+                rest = &rest[1..];
+            }
+            '}' => {
+                entities.extend(current.take());
+                pending_key = None;
+                rest = &rest[1..];
+            }
+            '"' => {
+                let body = &rest[1..];
+                let end = body.find('"').unwrap_or(body.len());
+                let token = &body[..end];
+                rest = body.get(end + 1..).unwrap_or("");
+                if let Some(entity) = current.as_mut() {
+                    match pending_key.take() {
+                        Some(key) => {
+                            entity.insert(key, token.to_string());
+                        }
+                        None => pending_key = Some(token.to_string()),
+                    }
+                }
+            }
+            '/' if rest.starts_with("//") => {
+                rest = rest.find('\n').map_or("", |i| &rest[i..]);
+            }
+            _ => {
+                // Stray character outside quotes; skip it.
+                rest = &rest[c.len_utf8()..];
+            }
         }
     }
+
+    entities
+}
+
+/// Gets the origins of all `info_player_start` entities.
+pub fn spawn_points(entities: &[Entity]) -> Vec<Vec3> {
+    entities
+        .iter()
+        .filter(|e| e.get("classname").map(String::as_str) == Some("info_player_start"))
+        .filter_map(|e| parse_vec3(e.get("origin")?))
+        .collect()
+}
+
+/// Parses a whitespace-separated "x y z" triple.
+fn parse_vec3(s: &str) -> Option<Vec3> {
+    let parts: Vec<f32> = s
+        .split_whitespace()
+        .filter_map(|p| p.parse().ok())
+        .collect();
+    if parts.len() == 3 {
+        Some(Vec3::new(parts[0], parts[1], parts[2]))
+    } else {
+        None
+    }
 }
 
 /// Loaded BSP map.
@@ -201,16 +439,15 @@ impl BspMap {
     /// Loads a BSP file from disk.
     pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
         let path = path.as_ref();
-        let name = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown")
-            .to_string();
+        let bytes = std::fs::read(path).with_context(|| format!("open {}", path.display()))?;
+        Self::parse(map_name(path), &bytes)
+    }
 
-        let file = File::open(path).with_context(|| format!("open {}", path.display()))?;
-        let mut reader = BufReader::new(file);
+    /// Parses a whole BSP file already in memory.
+    pub fn parse(name: String, bytes: &[u8]) -> anyhow::Result<Self> {
+        let header = BspHeader::parse(bytes)?;
+        let mut reader = Cursor::new(bytes);
 
-        let header = Self::read_header(&mut reader)?;
         let mut map = BspMap {
             name,
             version: header.version,
@@ -231,38 +468,10 @@ impl BspMap {
         Ok(map)
     }
 
-    fn read_header<R: Read + Seek>(r: &mut R) -> anyhow::Result<BspHeader> {
-        let magic = read_u32(r)?;
-        if magic != BSP_MAGIC {
-            bail!("invalid BSP magic: {:#x}", magic);
-        }
-
-        let version = read_u32(r)?;
-        if !(BSP_VERSION_MIN..=BSP_VERSION_MAX).contains(&version) {
-            bail!("unsupported BSP version: {}", version);
-        }
-
-        let mut lumps = [LumpEntry::default(); HEADER_LUMPS];
-        for lump in &mut lumps {
-            lump.offset = read_u32(r)?;
-            lump.length = read_u32(r)?;
-            lump.version = read_u32(r)?;
-            r.read_exact(&mut lump.fourcc)?;
-        }
-
-        let map_revision = read_u32(r)?;
-
-        Ok(BspHeader {
-            version,
-            lumps,
-            map_revision,
-        })
-    }
-
     fn read_lump<R: Read + Seek>(
         r: &mut R,
         header: &BspHeader,
-        idx: LumpIndex,
+        idx: LumpType,
     ) -> anyhow::Result<Vec<u8>> {
         let lump = &header.lumps[idx as usize];
         if lump.length == 0 {
@@ -278,13 +487,13 @@ impl BspMap {
         r: &mut R,
         header: &BspHeader,
     ) -> anyhow::Result<Vec<BspEntity>> {
-        let data = Self::read_lump(r, header, LumpIndex::Entities)?;
+        let data = Self::read_lump(r, header, LumpType::Entities)?;
         let text = String::from_utf8_lossy(&data);
         parse_entity_lump(&text)
     }
 
     fn read_planes<R: Read + Seek>(r: &mut R, header: &BspHeader) -> anyhow::Result<Vec<Plane>> {
-        let data = Self::read_lump(r, header, LumpIndex::Planes)?;
+        let data = Self::read_lump(r, header, LumpType::Planes)?;
         const SIZE: usize = 20;
         let count = data.len() / SIZE;
         let mut planes = Vec::with_capacity(count);
@@ -307,7 +516,7 @@ impl BspMap {
     }
 
     fn read_vertices<R: Read + Seek>(r: &mut R, header: &BspHeader) -> anyhow::Result<Vec<Vertex>> {
-        let data = Self::read_lump(r, header, LumpIndex::Vertices)?;
+        let data = Self::read_lump(r, header, LumpType::Vertices)?;
         const SIZE: usize = 12;
         let count = data.len() / SIZE;
         let mut verts = Vec::with_capacity(count);
@@ -323,7 +532,7 @@ impl BspMap {
     }
 
     fn read_edges<R: Read + Seek>(r: &mut R, header: &BspHeader) -> anyhow::Result<Vec<Edge>> {
-        let data = Self::read_lump(r, header, LumpIndex::Edges)?;
+        let data = Self::read_lump(r, header, LumpType::Edges)?;
         const SIZE: usize = 4;
         let count = data.len() / SIZE;
         let mut edges = Vec::with_capacity(count);
@@ -340,7 +549,7 @@ impl BspMap {
     }
 
     fn read_surf_edges<R: Read + Seek>(r: &mut R, header: &BspHeader) -> anyhow::Result<Vec<i32>> {
-        let data = Self::read_lump(r, header, LumpIndex::SurfEdges)?;
+        let data = Self::read_lump(r, header, LumpType::SurfEdges)?;
         const SIZE: usize = 4;
         let count = data.len() / SIZE;
         let mut surf_edges = Vec::with_capacity(count);
@@ -351,7 +560,7 @@ impl BspMap {
     }
 
     fn read_faces<R: Read + Seek>(r: &mut R, header: &BspHeader) -> anyhow::Result<Vec<Face>> {
-        let data = Self::read_lump(r, header, LumpIndex::Faces)?;
+        let data = Self::read_lump(r, header, LumpType::Faces)?;
         const SIZE: usize = 56;
         let count = data.len() / SIZE;
         let mut faces = Vec::with_capacity(count);
@@ -382,7 +591,7 @@ impl BspMap {
     }
 
     fn read_brushes<R: Read + Seek>(r: &mut R, header: &BspHeader) -> anyhow::Result<Vec<Brush>> {
-        let data = Self::read_lump(r, header, LumpIndex::Brushes)?;
+        let data = Self::read_lump(r, header, LumpType::Brushes)?;
         const SIZE: usize = 12;
         let count = data.len() / SIZE;
         let mut brushes = Vec::with_capacity(count);
@@ -401,7 +610,7 @@ impl BspMap {
         r: &mut R,
         header: &BspHeader,
     ) -> anyhow::Result<Vec<BrushSide>> {
-        let data = Self::read_lump(r, header, LumpIndex::BrushSides)?;
+        let data = Self::read_lump(r, header, LumpType::BrushSides)?;
         const SIZE: usize = 8;
         let count = data.len() / SIZE;
         let mut sides = Vec::with_capacity(count);
@@ -418,7 +627,7 @@ impl BspMap {
     }
 
     fn read_models<R: Read + Seek>(r: &mut R, header: &BspHeader) -> anyhow::Result<Vec<Model>> {
-        let data = Self::read_lump(r, header, LumpIndex::Models)?;
+        let data = Self::read_lump(r, header, LumpType::Models)?;
         const SIZE: usize = 48;
         let count = data.len() / SIZE;
         let mut models = Vec::with_capacity(count);
@@ -466,86 +675,16 @@ impl BspMap {
 
 /// Parses the entity lump text into structured entities.
 fn parse_entity_lump(text: &str) -> anyhow::Result<Vec<BspEntity>> {
-    let mut entities = Vec::new();
-    let mut current: Option<BspEntity> = None;
-    let mut in_entity = false;
-
-    for line in text.lines() {
-        let line = line.trim();
-        if line == "{" {
-            in_entity = true;
-            current = Some(BspEntity::default());
-        } else if line == "}" {
-            if let Some(ent) = current.take() {
-                entities.push(ent);
-            }
-            in_entity = false;
-        } else if in_entity {
-            // Parse "key" "value"
-            if let Some((key, value)) = parse_kv_line(line) {
-                if let Some(ref mut ent) = current {
-                    if key == "classname" {
-                        ent.classname = value.to_string();
-                    }
-                    ent.properties.insert(key.to_string(), value.to_string());
-                }
-            }
-        }
-    }
-
-    Ok(entities)
-}
-
-fn parse_kv_line(line: &str) -> Option<(&str, &str)> {
-    // Parse "key" "value" format
-    let mut chars = line.char_indices();
-
-    // Find first quote
-    let key_start = loop {
-        match chars.next() {
-            Some((i, '"')) => break i + 1,
-            Some(_) => continue,
-            None => return None,
-        }
-    };
-
-    // Find end of key (next quote)
-    let key_end = loop {
-        match chars.next() {
-            Some((i, '"')) => break i,
-            Some(_) => continue,
-            None => return None,
-        }
-    };
-
-    // Find start of value (next quote)
-    let value_start = loop {
-        match chars.next() {
-            Some((i, '"')) => break i + 1,
-            Some(_) => continue,
-            None => return None,
-        }
-    };
-
-    // Find end of value (next quote)
-    let value_end = loop {
-        match chars.next() {
-            Some((i, '"')) => break i,
-            Some(_) => continue,
-            None => return None,
-        }
-    };
-
-    Some((&line[key_start..key_end], &line[value_start..value_end]))
+    Ok(parse_entities(text)
+        .into_iter()
+        .map(|properties| BspEntity {
+            classname: properties.get("classname").cloned().unwrap_or_default(),
+            properties,
+        })
+        .collect())
 }
 
 // Binary reading helpers.
-fn read_u32<R: Read>(r: &mut R) -> anyhow::Result<u32> {
-    let mut buf = [0u8; 4];
-    r.read_exact(&mut buf)?;
-    Ok(u32::from_le_bytes(buf))
-}
-
 fn read_u32_slice(d: &[u8]) -> anyhow::Result<u32> {
     Ok(u32::from_le_bytes(d[..4].try_into()?))
 }
@@ -570,6 +709,125 @@ fn read_f32_slice(d: &[u8]) -> anyhow::Result<f32> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn map_crc_known_values() {
+        assert_eq!(compute_map_crc(b""), 0);
+        assert_eq!(compute_map_crc(b"123456789"), 0xCBF4_3926);
+        assert_eq!(
+            compute_map_crc(b"The quick brown fox jumps over the lazy dog"),
+            0x414F_A339
+        );
+    }
+
+    #[test]
+    fn load_map_reports_name_size_and_crc() {
+        let path = std::env::temp_dir().join(format!("bsp_crc_{}.bsp", std::process::id()));
+        std::fs::write(&path, b"123456789").unwrap();
+
+        let loaded = load_map(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.name, format!("bsp_crc_{}", std::process::id()));
+        assert_eq!(loaded.size, 9);
+        assert_eq!(loaded.crc, 0xCBF4_3926);
+    }
+
+    /// Minimal header with all lumps empty except the entity lump, which
+    /// holds `entities` right after the header.
+    fn minimal_bsp(version: u32, entities: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + entities.len());
+        bytes.extend_from_slice(b"VBSP");
+        bytes.extend_from_slice(&version.to_le_bytes());
+        for index in 0..HEADER_LUMPS {
+            let (offset, length) = if index == LumpType::Entities as usize {
+                (HEADER_SIZE as u32, entities.len() as u32)
+            } else {
+                (0, 0)
+            };
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(&length.to_le_bytes());
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+            bytes.extend_from_slice(&[0; 4]);
+        }
+        bytes.extend_from_slice(&7u32.to_le_bytes());
+        bytes.extend_from_slice(entities);
+        bytes
+    }
+
+    #[test]
+    fn header_parses_minimal_map() {
+        let bytes = minimal_bsp(20, b"{\n}\n");
+        let header = BspHeader::parse(&bytes).unwrap();
+
+        assert_eq!(header.version, 20);
+        assert_eq!(header.map_revision, 7);
+        let entities = header.lump(LumpType::Entities).unwrap();
+        assert_eq!(entities.offset as usize, HEADER_SIZE);
+        assert_eq!(entities.length, 4);
+        assert!(header.lump(LumpType::Planes).is_none());
+    }
+
+    #[test]
+    fn header_rejects_bad_magic_and_version() {
+        let mut bytes = minimal_bsp(20, b"");
+        bytes[0] = b'X';
+        assert!(matches!(
+            BspHeader::parse(&bytes),
+            Err(BspError::BadMagic(_))
+        ));
+
+        let bytes = minimal_bsp(30, b"");
+        assert!(matches!(
+            BspHeader::parse(&bytes),
+            Err(BspError::UnsupportedVersion(30))
+        ));
+
+        let bytes = minimal_bsp(20, b"");
+        assert!(matches!(
+            BspHeader::parse(&bytes[..100]),
+            Err(BspError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn read_map_checksums_and_parses_one_buffer() {
+        let bytes = minimal_bsp(20, b"{\n\"classname\" \"worldspawn\"\n}\n");
+        let path = std::env::temp_dir().join(format!("bsp_read_{}.bsp", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+
+        let read = read_map(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let (file, map) = read.unwrap();
+        assert_eq!(file.name, map.name);
+        assert_eq!(file.size, bytes.len() as u64);
+        assert_eq!(file.crc, compute_map_crc(&bytes));
+        assert_eq!(map.entities.len(), 1);
+    }
+
+    #[test]
+    fn header_rejects_out_of_range_lump() {
+        let mut bytes = minimal_bsp(20, b"{\n}\n");
+        // Point the plane lump past the end of the file.
+        let at = 8 + LumpType::Planes as usize * 16;
+        bytes[at..at + 4].copy_from_slice(&10_000u32.to_le_bytes());
+        bytes[at + 4..at + 8].copy_from_slice(&20u32.to_le_bytes());
+
+        assert!(matches!(
+            BspHeader::parse(&bytes),
+            Err(BspError::LumpOutOfRange { index: 1 })
+        ));
+    }
+
+    #[test]
+    fn load_map_missing_file_is_io_error() {
+        assert!(matches!(
+            load_map("does/not/exist.bsp"),
+            Err(BspError::Io(_))
+        ));
+    }
+
     #[test]
     fn parse_entity_lump_basic() {
         let text = r#"
@@ -588,4 +846,48 @@ mod tests {
         assert_eq!(ents[1].classname, "info_player_start");
         assert_eq!(ents[1].origin(), Some(Vec3::new(0.0, 0.0, 64.0)));
     }
+
+    #[test]
+    fn parse_entities_two_entities() {
+        let text = r#"{
+"classname" "worldspawn"
+"message" "Dust II: remastered edition"
+}
+{ "classname" "info_player_start" "origin" "128 -64.5 32" "angles" "0 90 0" }
+"#;
+        let ents = parse_entities(text);
+        assert_eq!(ents.len(), 2);
+        assert_eq!(ents[0]["classname"], "worldspawn");
+        assert_eq!(ents[0]["message"], "Dust II: remastered edition");
+        assert_eq!(ents[1]["classname"], "info_player_start");
+        assert_eq!(ents[1]["angles"], "0 90 0");
+        assert_eq!(ents[1].len(), 3);
+    }
+
+    #[test]
+    fn spawn_points_reads_player_start_origins() {
+        let text = r#"
+{
+"classname" "info_player_start"
+"origin" "0 0 64"
+}
+{
+"classname" "info_target"
+"origin" "1 2 3"
+}
+{
+"classname" "info_player_start"
+"origin" "-16 32 8"
+}
+{
+"classname" "info_player_start"
+"origin" "bad"
+}
+"#;
+        let spawns = spawn_points(&parse_entities(text));
+        assert_eq!(
+            spawns,
+            vec![Vec3::new(0.0, 0.0, 64.0), Vec3::new(-16.0, 32.0, 8.0)]
+        );
+    }
 }
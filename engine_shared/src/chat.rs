@@ -7,7 +7,8 @@
 //! # Chat Channels
 //! - **Global**: All players on server
 //! - **Team**: Team-only messages
-//! - **Squad**: Squad/party messages
+//! - **Squad**: In-game squad messages
+//! - **Party**: Party members, independent of team or squad
 //! - **Private**: Direct messages between players
 //!
 //! # Moderation Features
@@ -15,12 +16,22 @@
 //! - Mute system
 //! - Profanity filtering (stub)
 //! - Admin commands
+//!
+//! # Timestamps
+//! Each message records the server tick it was sent on (`timestamp`, for
+//! replays and determinism) and the wall-clock send time (`sent_unix`, for
+//! display).
+//!
+//! Migration: `sent_unix` was added after `ChatMessage` was already being
+//! serialized. Older messages deserialize with `sent_unix == 0`, which
+//! [`ChatMessage::sent_at`] reports as unknown.
 
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 
+use crate::party::PartyId;
 use crate::steam_id::SteamId;
 
 /// Chat message channel types.
@@ -30,8 +41,10 @@ pub enum ChatChannel {
     Global,
     /// Team-only.
     Team(u8),
-    /// Squad/party.
+    /// In-game squad.
     Squad(u32),
+    /// Party, whatever game or team its members are in.
+    Party(PartyId),
     /// Private message.
     Private(SteamId),
     /// Console output.
@@ -49,7 +62,7 @@ pub const RATE_LIMIT_MESSAGES: u32 = 5;
 pub const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(10);
 
 /// A chat message.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChatMessage {
     /// Message sender.
     pub sender: SteamId,
@@ -61,6 +74,9 @@ pub struct ChatMessage {
     pub content: String,
     /// Server timestamp (tick).
     pub timestamp: u64,
+    /// Wall-clock send time in Unix seconds; 0 if unknown.
+    #[serde(default)]
+    pub sent_unix: u64,
 }
 
 impl ChatMessage {
@@ -77,9 +93,18 @@ impl ChatMessage {
             channel,
             content: content.to_string(),
             timestamp,
+            sent_unix: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
         }
     }
 
+    /// Wall-clock send time, if it was recorded.
+    pub fn sent_at(&self) -> Option<SystemTime> {
+        (self.sent_unix != 0).then(|| UNIX_EPOCH + Duration::from_secs(self.sent_unix))
+    }
+
     /// Check if content exceeds max length.
     pub fn is_valid_length(&self) -> bool {
         self.content.len() <= MAX_MESSAGE_LENGTH
@@ -184,6 +209,8 @@ pub struct PlayerChatState {
     pub team_id: Option<u8>,
     /// Current squad ID.
     pub squad_id: Option<u32>,
+    /// Current party ID.
+    pub party_id: Option<PartyId>,
 }
 
 impl PlayerChatState {
@@ -196,6 +223,7 @@ impl PlayerChatState {
             mute_expires: None,
             team_id: None,
             squad_id: None,
+            party_id: None,
         }
     }
 
@@ -206,17 +234,16 @@ impl PlayerChatState {
 
     /// Check if player can send messages.
     pub fn can_send(&self) -> bool {
-        if self.server_muted {
-            if let Some(expires) = self.mute_expires {
-                if Instant::now() < expires {
-                    return false;
-                }
-                // Mute expired, will be cleared on next check
-            } else {
-                return false; // Permanent mute
-            }
-        }
-        self.rate_limiter.can_send()
+        !self.is_server_muted() && self.rate_limiter.can_send()
+    }
+
+    /// Check if a server mute is in effect (expired mutes don't count).
+    pub fn is_server_muted(&self) -> bool {
+        // An expired mute is cleared on the next send.
+        self.server_muted
+            && self
+                .mute_expires
+                .is_none_or(|expires| Instant::now() < expires)
     }
 
     /// Mute a player.
@@ -317,7 +344,16 @@ impl ChatManager {
         }
     }
 
+    /// Set player's party.
+    pub fn set_player_party(&mut self, steam_id: SteamId, party_id: Option<PartyId>) {
+        if let Some(state) = self.players.get_mut(&steam_id) {
+            state.party_id = party_id;
+        }
+    }
+
     /// Send a chat message.
+    ///
+    /// Party messages can only be sent by members of that party.
     pub fn send_message(
         &mut self,
         sender: SteamId,
@@ -325,6 +361,19 @@ impl ChatManager {
         channel: ChatChannel,
         content: &str,
     ) -> Result<Vec<SteamId>, ChatResult> {
+        self.send_message_with_recipients(sender, sender_name, channel, content)
+            .map(|(_, recipients)| recipients)
+    }
+
+    /// Like [`send_message`](Self::send_message), also returning the message
+    /// as stored in history.
+    pub fn send_message_with_recipients(
+        &mut self,
+        sender: SteamId,
+        sender_name: &str,
+        channel: ChatChannel,
+        content: &str,
+    ) -> Result<(ChatMessage, Vec<SteamId>), ChatResult> {
         // Validate sender
         let sender_state = self
             .players
@@ -344,6 +393,13 @@ impl ChatManager {
             }
         }
 
+        // Check party membership
+        if let ChatChannel::Party(party) = channel {
+            if sender_state.party_id != Some(party) {
+                return Err(ChatResult::InvalidChannel);
+            }
+        }
+
         // Check rate limit
         if !sender_state.rate_limiter.record_message() {
             return Err(ChatResult::RateLimited);
@@ -375,6 +431,7 @@ impl ChatManager {
                     ChatChannel::Global | ChatChannel::Server | ChatChannel::Console => true,
                     ChatChannel::Team(team) => state.team_id == Some(team),
                     ChatChannel::Squad(squad) => state.squad_id == Some(squad),
+                    ChatChannel::Party(party) => state.party_id == Some(party),
                     ChatChannel::Private(target) => pid == target,
                 }
             })
@@ -382,15 +439,17 @@ impl ChatManager {
             .collect();
 
         // Store in history
-        self.history.push_back(message);
+        self.history.push_back(message.clone());
         if self.history.len() > self.max_history {
             self.history.pop_front();
         }
 
-        Ok(recipients)
+        Ok((message, recipients))
     }
 
-    /// Get recent history.
+    /// Get recent history, newest first.
+    ///
+    /// Each message carries both its tick and its wall-clock send time.
     pub fn get_history(&self, count: usize) -> Vec<&ChatMessage> {
         self.history.iter().rev().take(count).collect()
     }
@@ -566,6 +625,73 @@ mod tests {
         assert!(recipients.contains(&squadmate));
     }
 
+    // =============================================================================
+    // CHAT-005: Party Chat
+    // =============================================================================
+
+    #[test]
+    fn chat_005_party_only() {
+        let mut manager = ChatManager::new(100);
+
+        let sender = test_steam_id(1);
+        let partymate = test_steam_id(2);
+        let outsider = test_steam_id(3);
+
+        manager.add_player(sender);
+        manager.add_player(partymate);
+        manager.add_player(outsider);
+
+        // The outsider shares the sender's team and squad, the partymate neither.
+        manager.set_player_team(sender, Some(1));
+        manager.set_player_team(outsider, Some(1));
+        manager.set_player_squad(sender, Some(100));
+        manager.set_player_squad(outsider, Some(100));
+        manager.set_player_team(partymate, Some(2));
+
+        let party = PartyId::new(7);
+        manager.set_player_party(sender, Some(party));
+        manager.set_player_party(partymate, Some(party));
+        manager.set_player_party(outsider, Some(PartyId::new(8)));
+
+        let recipients = manager
+            .send_message(
+                sender,
+                "Player1",
+                ChatChannel::Party(party),
+                "Party message",
+            )
+            .unwrap();
+
+        assert_eq!(recipients, vec![partymate]);
+    }
+
+    #[test]
+    fn chat_005_non_member_cannot_send_to_party() {
+        let mut manager = ChatManager::new(100);
+
+        let member = test_steam_id(1);
+        let outsider = test_steam_id(2);
+        manager.add_player(member);
+        manager.add_player(outsider);
+
+        let party = PartyId::new(7);
+        manager.set_player_party(member, Some(party));
+
+        assert_eq!(
+            manager.send_message(outsider, "Player2", ChatChannel::Party(party), "hi"),
+            Err(ChatResult::InvalidChannel)
+        );
+        assert!(manager.get_history(10).is_empty());
+
+        // Leaving the party stops delivery.
+        manager.set_player_party(outsider, Some(party));
+        manager.set_player_party(member, None);
+        let recipients = manager
+            .send_message(outsider, "Player2", ChatChannel::Party(party), "hi")
+            .unwrap();
+        assert!(recipients.is_empty());
+    }
+
     // =============================================================================
     // CHAT-004: Private Message
     // =============================================================================
@@ -798,6 +924,66 @@ mod tests {
         assert_eq!(history.len(), 3);
     }
 
+    #[test]
+    fn chat_010_history_has_tick_and_wall_clock() {
+        let mut manager = ChatManager::new(5);
+        let sender = test_steam_id(1);
+        manager.add_player(sender);
+        manager.set_tick(640);
+
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        manager
+            .send_message(sender, "Player1", ChatChannel::Global, "gl hf")
+            .unwrap();
+
+        let history = manager.get_history(1);
+        assert_eq!(history[0].timestamp, 640);
+        assert!(history[0].sent_unix >= before);
+        assert_eq!(
+            history[0].sent_at(),
+            Some(UNIX_EPOCH + Duration::from_secs(history[0].sent_unix))
+        );
+    }
+
+    #[test]
+    fn chat_010_sent_message_matches_history() {
+        let mut manager = ChatManager::new(5);
+        let sender = test_steam_id(1);
+        let receiver = test_steam_id(2);
+        manager.add_player(sender);
+        manager.add_player(receiver);
+
+        let (message, recipients) = manager
+            .send_message_with_recipients(sender, "Player1", ChatChannel::Global, "gl hf")
+            .unwrap();
+
+        assert_eq!(recipients, vec![receiver]);
+        assert_eq!(*manager.get_history(1)[0], message);
+    }
+
+    #[test]
+    fn chat_010_old_message_without_wall_clock() {
+        // Serialized before `sent_unix` existed.
+        let json = r#"{
+            "sender": 76561197960265729,
+            "sender_name": "Player1",
+            "channel": "Global",
+            "content": "hello",
+            "timestamp": 128
+        }"#;
+        let message: ChatMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(message.timestamp, 128);
+        assert_eq!(message.sent_unix, 0);
+        assert_eq!(message.sent_at(), None);
+
+        let roundtrip: ChatMessage =
+            serde_json::from_str(&serde_json::to_string(&message).unwrap()).unwrap();
+        assert_eq!(roundtrip.sent_unix, 0);
+    }
+
     #[test]
     fn chat_010_history_limit() {
         let mut manager = ChatManager::new(3);
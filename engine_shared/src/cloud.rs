@@ -10,7 +10,7 @@
 //! - File enumeration
 //! - Sync conflict detection
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
@@ -88,14 +88,37 @@ pub struct CloudConflict {
     pub remote_timestamp: u64,
 }
 
+/// Result of comparing a local file against its cloud copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncOutcome {
+    /// Neither side changed since the last sync.
+    UpToDate,
+    /// Only the cloud copy changed; download it.
+    RemoteNewer,
+    /// Only the local copy changed (or there is no cloud copy); upload it.
+    LocalNewer,
+    /// Both sides changed; resolve with [`CloudStorage::resolve_conflict`].
+    Conflict,
+}
+
+/// Local and remote modification times recorded at the last sync.
+///
+/// `None` forces that side to count as changed on the next sync.
+#[derive(Debug, Clone, Copy)]
+struct SyncPoint {
+    local_mtime: Option<u64>,
+    remote_mtime: Option<u64>,
+}
+
 /// Mock cloud storage for testing.
 ///
+/// Files are kept sorted by name, so index-based enumeration is stable.
 /// In production, this would interface with Steamworks SDK.
 pub struct CloudStorage {
     /// Stored files.
-    files: HashMap<String, Vec<u8>>,
+    files: BTreeMap<String, Vec<u8>>,
     /// File metadata.
-    metadata: HashMap<String, CloudFileInfo>,
+    metadata: BTreeMap<String, CloudFileInfo>,
     /// Storage quota.
     quota: CloudQuota,
     /// Whether cloud is enabled for account.
@@ -104,14 +127,21 @@ pub struct CloudStorage {
     app_enabled: bool,
     /// Pending conflicts.
     conflicts: Vec<CloudConflict>,
+    /// Last sync point per file.
+    sync_points: BTreeMap<String, SyncPoint>,
+    /// Local mtimes of files whose last sync was a conflict.
+    sync_conflicts: BTreeMap<String, u64>,
 }
 
+/// Cloud storage under its ISteamRemoteStorage name.
+pub type RemoteStorage = CloudStorage;
+
 impl CloudStorage {
     /// Create a new cloud storage with given quota.
     pub fn new(total_bytes: u64) -> Self {
         CloudStorage {
-            files: HashMap::new(),
-            metadata: HashMap::new(),
+            files: BTreeMap::new(),
+            metadata: BTreeMap::new(),
             quota: CloudQuota {
                 total_bytes,
                 used_bytes: 0,
@@ -119,6 +149,8 @@ impl CloudStorage {
             account_enabled: true,
             app_enabled: true,
             conflicts: Vec::new(),
+            sync_points: BTreeMap::new(),
+            sync_conflicts: BTreeMap::new(),
         }
     }
 
@@ -162,6 +194,32 @@ impl CloudStorage {
         self.metadata.values().nth(index)
     }
 
+    /// Get file name by index.
+    pub fn get_file_name_by_index(&self, index: usize) -> Option<&str> {
+        self.metadata.keys().nth(index).map(|name| name.as_str())
+    }
+
+    /// Check if a file is persisted to the cloud (not forgotten).
+    pub fn file_persisted(&self, name: &str) -> bool {
+        self.metadata.get(name).is_some_and(|m| m.persisted)
+    }
+
+    /// Stop syncing a file to the cloud while keeping the local copy.
+    ///
+    /// The file no longer counts against the quota. Writing it again
+    /// persists it again.
+    pub fn file_forget(&mut self, name: &str) -> Result<(), CloudResult> {
+        let info = self
+            .metadata
+            .get_mut(name)
+            .ok_or(CloudResult::FileNotFound)?;
+        if info.persisted {
+            info.persisted = false;
+            self.quota.used_bytes = self.quota.used_bytes.saturating_sub(info.size);
+        }
+        Ok(())
+    }
+
     /// Check if file exists.
     pub fn file_exists(&self, name: &str) -> bool {
         self.files.contains_key(name)
@@ -188,7 +246,11 @@ impl CloudStorage {
         }
 
         let new_size = data.len() as u64;
-        let old_size = self.files.get(name).map(|f| f.len() as u64).unwrap_or(0);
+        let old_size = self
+            .metadata
+            .get(name)
+            .filter(|m| m.persisted)
+            .map_or(0, |m| m.size);
 
         // Check quota
         let size_change = new_size.saturating_sub(old_size);
@@ -239,9 +301,10 @@ impl CloudStorage {
             return Err(CloudResult::CloudDisabled);
         }
 
-        if let Some(data) = self.files.remove(name) {
-            self.quota.used_bytes = self.quota.used_bytes.saturating_sub(data.len() as u64);
-            self.metadata.remove(name);
+        if self.files.remove(name).is_some() {
+            if let Some(info) = self.metadata.remove(name).filter(|m| m.persisted) {
+                self.quota.used_bytes = self.quota.used_bytes.saturating_sub(info.size);
+            }
             Ok(())
         } else {
             Err(CloudResult::FileNotFound)
@@ -263,12 +326,82 @@ impl CloudStorage {
         &self.conflicts
     }
 
+    /// Compare a local file's modification time against the cloud copy.
+    ///
+    /// A file that has never been synced is up to date only if both
+    /// timestamps match, and in conflict otherwise. Transfers are left to the
+    /// caller, who records the result with [`mark_synced`](Self::mark_synced).
+    pub fn sync(&mut self, name: &str, local_mtime: u64) -> SyncOutcome {
+        let Some(remote_mtime) = self.metadata.get(name).map(|m| m.timestamp) else {
+            return SyncOutcome::LocalNewer;
+        };
+
+        let outcome = match self.sync_points.get(name) {
+            Some(point) => {
+                let local_changed = point.local_mtime != Some(local_mtime);
+                let remote_changed = point.remote_mtime != Some(remote_mtime);
+                match (local_changed, remote_changed) {
+                    (false, false) => SyncOutcome::UpToDate,
+                    (false, true) => SyncOutcome::RemoteNewer,
+                    (true, false) => SyncOutcome::LocalNewer,
+                    (true, true) => SyncOutcome::Conflict,
+                }
+            }
+            None if local_mtime == remote_mtime => SyncOutcome::UpToDate,
+            None => SyncOutcome::Conflict,
+        };
+
+        match outcome {
+            SyncOutcome::UpToDate => self.mark_synced(name, local_mtime),
+            SyncOutcome::Conflict => {
+                self.sync_conflicts.insert(name.to_string(), local_mtime);
+            }
+            SyncOutcome::RemoteNewer | SyncOutcome::LocalNewer => {}
+        }
+        outcome
+    }
+
+    /// Record that the local copy (modified at `local_mtime`) now matches
+    /// the cloud copy.
+    pub fn mark_synced(&mut self, name: &str, local_mtime: u64) {
+        let remote_mtime = self.metadata.get(name).map(|m| m.timestamp);
+        self.sync_points.insert(
+            name.to_string(),
+            SyncPoint {
+                local_mtime: Some(local_mtime),
+                remote_mtime,
+            },
+        );
+        self.sync_conflicts.remove(name);
+    }
+
     /// Resolve a conflict.
+    ///
+    /// For a conflict reported by [`sync`](Self::sync), this picks which
+    /// side the next sync treats as newer: `KeepLocal` yields `LocalNewer`,
+    /// while `KeepRemote` and `KeepBoth` yield `RemoteNewer` (with
+    /// `KeepBoth`, the caller keeps its local copy under another name).
     pub fn resolve_conflict(
         &mut self,
         name: &str,
         resolution: ConflictResolution,
     ) -> Result<(), CloudResult> {
+        if let Some(local_mtime) = self.sync_conflicts.remove(name) {
+            let remote_mtime = self.metadata.get(name).map(|m| m.timestamp);
+            let point = match resolution {
+                ConflictResolution::KeepLocal => SyncPoint {
+                    local_mtime: None,
+                    remote_mtime,
+                },
+                ConflictResolution::KeepRemote | ConflictResolution::KeepBoth => SyncPoint {
+                    local_mtime: Some(local_mtime),
+                    remote_mtime: None,
+                },
+            };
+            self.sync_points.insert(name.to_string(), point);
+            return Ok(());
+        }
+
         let conflict_idx = self.conflicts.iter().position(|c| c.name == name);
 
         if let Some(idx) = conflict_idx {
@@ -295,7 +428,7 @@ impl CloudStorage {
 
     /// Has pending conflicts.
     pub fn has_conflicts(&self) -> bool {
-        !self.conflicts.is_empty()
+        !self.conflicts.is_empty() || !self.sync_conflicts.is_empty()
     }
 }
 
@@ -544,6 +677,69 @@ mod tests {
         assert_eq!(data, b"remote version");
     }
 
+    // =============================================================================
+    // CLD-009: Sync Outcomes
+    // =============================================================================
+
+    /// Simulate another device writing `name` at `timestamp`.
+    fn remote_write(cloud: &mut CloudStorage, name: &str, data: &[u8], timestamp: u64) {
+        cloud.file_write(name, data).unwrap();
+        cloud.metadata.get_mut(name).unwrap().timestamp = timestamp;
+    }
+
+    #[test]
+    fn cld_009_sync_up_to_date() {
+        let mut cloud = CloudStorage::new(1024);
+        remote_write(&mut cloud, "save.dat", b"v1", 100);
+
+        assert_eq!(cloud.sync("save.dat", 100), SyncOutcome::UpToDate);
+        assert_eq!(cloud.sync("save.dat", 100), SyncOutcome::UpToDate);
+    }
+
+    #[test]
+    fn cld_009_sync_remote_and_local_newer() {
+        let mut cloud = CloudStorage::new(1024);
+        assert_eq!(cloud.sync("save.dat", 50), SyncOutcome::LocalNewer);
+
+        remote_write(&mut cloud, "save.dat", b"v1", 100);
+        cloud.mark_synced("save.dat", 90);
+
+        // Another device uploaded a new version.
+        remote_write(&mut cloud, "save.dat", b"v2", 200);
+        assert_eq!(cloud.sync("save.dat", 90), SyncOutcome::RemoteNewer);
+        cloud.mark_synced("save.dat", 210);
+
+        // Only the local copy changed.
+        assert_eq!(cloud.sync("save.dat", 300), SyncOutcome::LocalNewer);
+    }
+
+    #[test]
+    fn cld_009_sync_conflict_when_both_changed() {
+        let mut cloud = CloudStorage::new(1024);
+        remote_write(&mut cloud, "save.dat", b"v1", 100);
+        cloud.mark_synced("save.dat", 100);
+
+        remote_write(&mut cloud, "save.dat", b"remote edit", 200);
+        assert_eq!(cloud.sync("save.dat", 150), SyncOutcome::Conflict);
+        assert!(cloud.has_conflicts());
+
+        cloud
+            .resolve_conflict("save.dat", ConflictResolution::KeepLocal)
+            .unwrap();
+        assert!(!cloud.has_conflicts());
+        assert_eq!(cloud.sync("save.dat", 150), SyncOutcome::LocalNewer);
+
+        // Never-synced files that differ are conflicts too.
+        remote_write(&mut cloud, "other.dat", b"x", 100);
+        assert_eq!(cloud.sync("other.dat", 120), SyncOutcome::Conflict);
+        cloud
+            .resolve_conflict("other.dat", ConflictResolution::KeepRemote)
+            .unwrap();
+        assert_eq!(cloud.sync("other.dat", 120), SyncOutcome::RemoteNewer);
+        cloud.mark_synced("other.dat", 130);
+        assert_eq!(cloud.sync("other.dat", 130), SyncOutcome::UpToDate);
+    }
+
     // =============================================================================
     // Additional Tests
     // =============================================================================
@@ -581,6 +777,48 @@ mod tests {
         assert!(timestamp.unwrap() > 0);
     }
 
+    #[test]
+    fn file_names_by_index_are_sorted() {
+        let mut cloud = CloudStorage::new(1024);
+
+        cloud.file_write("b.sav", b"b").unwrap();
+        cloud.file_write("c.sav", b"c").unwrap();
+        cloud.file_write("a.sav", b"a").unwrap();
+
+        assert_eq!(cloud.get_file_count(), 3);
+        assert_eq!(cloud.get_file_name_by_index(0), Some("a.sav"));
+        assert_eq!(cloud.get_file_name_by_index(2), Some("c.sav"));
+        assert_eq!(cloud.get_file_name_by_index(3), None);
+        assert_eq!(cloud.get_file_by_index(1).unwrap().name, "b.sav");
+    }
+
+    #[test]
+    fn file_forget_and_persisted() {
+        let mut cloud = RemoteStorage::new(1000);
+
+        cloud.file_write("save.dat", &[0u8; 400]).unwrap();
+        assert!(cloud.file_persisted("save.dat"));
+        assert!(!cloud.file_persisted("missing.dat"));
+
+        cloud.file_forget("save.dat").unwrap();
+        assert!(!cloud.file_persisted("save.dat"));
+        assert!(cloud.file_exists("save.dat"));
+        assert_eq!(cloud.get_quota().used_bytes, 0);
+
+        // Rewriting persists it again and charges the quota.
+        cloud.file_write("save.dat", &[0u8; 400]).unwrap();
+        assert!(cloud.file_persisted("save.dat"));
+        assert_eq!(cloud.get_quota().used_bytes, 400);
+
+        cloud.file_forget("save.dat").unwrap();
+        cloud.file_delete("save.dat").unwrap();
+        assert_eq!(cloud.get_quota().used_bytes, 0);
+        assert_eq!(
+            cloud.file_forget("save.dat"),
+            Err(CloudResult::FileNotFound)
+        );
+    }
+
     #[test]
     fn invalid_file_name() {
         let mut cloud = CloudStorage::new(1024 * 1024);
@@ -1,13 +1,23 @@
 //! Configuration system.
 //!
-//! Loads engine configuration from JSON strings/files (file IO left to app).
+//! Loads engine configuration from JSON strings or TOML/JSON files.
+//!
+//! Precedence, lowest to highest: built-in defaults < config file <
+//! environment variables ([`EngineConfig::apply_env`]) < command-line
+//! arguments ([`EngineConfig::merge_cli`]).
+
+use std::{fmt, net::SocketAddr, ops::RangeInclusive, path::Path};
 
 use serde::{Deserialize, Serialize};
 
+use crate::{net::HostPort, steam_id::SteamId};
+
 /// Root configuration shared by client/server.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct EngineConfig {
-    /// Server listen address, e.g. `127.0.0.1:40000`.
+    /// Server listen address, e.g. `127.0.0.1:40000`; clients may also use
+    /// a hostname such as `play.example.com:40000`.
     pub server_addr: String,
     /// Fixed simulation tick rate.
     pub tick_hz: u32,
@@ -17,6 +27,22 @@ pub struct EngineConfig {
     /// Player name (client only).
     #[serde(default = "default_player_name")]
     pub player_name: String,
+    /// Steam ID presented to the server on connect (client only).
+    #[serde(default = "default_steam_id")]
+    pub steam_id: SteamId,
+    /// DLC app IDs reported as owned in the handshake (client only).
+    pub owned_dlc: Vec<u32>,
+    /// Seconds without any packet before a client is dropped (server only).
+    pub client_timeout_secs: u64,
+    /// Player slots, including reserved ones (server only).
+    pub max_clients: u32,
+    /// Wait before the first reconnect attempt, doubling after (client only).
+    pub reconnect_base_delay_ms: u64,
+    /// Reconnect attempts before giving up (client only).
+    pub reconnect_max_attempts: u32,
+    /// Host an in-process listen server on `server_addr` and play on it
+    /// over loopback (client only).
+    pub listen: bool,
 }
 
 fn default_maps_dir() -> String {
@@ -27,6 +53,11 @@ fn default_player_name() -> String {
     "Player".to_string()
 }
 
+/// Placeholder individual/public ID used when no real Steam identity is configured.
+fn default_steam_id() -> SteamId {
+    SteamId::from_account_id(1)
+}
+
 impl Default for EngineConfig {
     fn default() -> Self {
         Self {
@@ -34,13 +65,417 @@ impl Default for EngineConfig {
             tick_hz: 64,
             maps_dir: default_maps_dir(),
             player_name: default_player_name(),
+            steam_id: default_steam_id(),
+            owned_dlc: Vec::new(),
+            client_timeout_secs: 30,
+            max_clients: 16,
+            reconnect_base_delay_ms: 500,
+            reconnect_max_attempts: 5,
+            listen: false,
+        }
+    }
+}
+
+/// Accepted range for `tick_hz`.
+pub const TICK_HZ_RANGE: RangeInclusive<u32> = 1..=512;
+
+/// Environment variable overriding `server_addr`.
+pub const ENV_SERVER_ADDR: &str = "PS_SERVER_ADDR";
+/// Environment variable overriding `tick_hz`.
+pub const ENV_TICK_HZ: &str = "PS_TICK_HZ";
+/// Environment variable overriding `maps_dir`.
+pub const ENV_MAPS_DIR: &str = "PS_MAPS_DIR";
+/// Environment variable overriding `player_name`.
+pub const ENV_PLAYER_NAME: &str = "PS_PLAYER_NAME";
+
+/// Values given on the command line. `None` means "not specified".
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliArgs {
+    pub server_addr: Option<String>,
+    pub tick_hz: Option<u32>,
+    pub maps_dir: Option<String>,
+    pub player_name: Option<String>,
+    pub listen: Option<bool>,
+}
+
+/// Error type for configuration loading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The config file could not be read.
+    Io { path: String, message: String },
+    /// The config file contents could not be parsed.
+    Parse { path: String, message: String },
+    /// `tick_hz` is outside `TICK_HZ_RANGE`.
+    InvalidTickRate(u32),
+    /// `server_addr` is not a valid `host:port`.
+    InvalidAddress(String),
+    /// `maps_dir` is empty.
+    EmptyMapsDir,
+    /// `max_clients` is zero.
+    NoClientSlots,
+    /// An environment variable holds a value of the wrong type.
+    InvalidEnv { var: String, value: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, message } => {
+                write!(f, "failed to read config '{}': {}", path, message)
+            }
+            ConfigError::Parse { path, message } => {
+                write!(f, "failed to parse config '{}': {}", path, message)
+            }
+            ConfigError::InvalidTickRate(hz) => write!(
+                f,
+                "tick_hz {} out of range {}..={}",
+                hz,
+                TICK_HZ_RANGE.start(),
+                TICK_HZ_RANGE.end()
+            ),
+            ConfigError::InvalidAddress(addr) => {
+                write!(f, "server_addr '{}' is not a valid host:port", addr)
+            }
+            ConfigError::EmptyMapsDir => write!(f, "maps_dir must not be empty"),
+            ConfigError::NoClientSlots => write!(f, "max_clients must be at least 1"),
+            ConfigError::InvalidEnv { var, value } => {
+                write!(
+                    f,
+                    "invalid value '{}' for environment variable {}",
+                    value, var
+                )
+            }
         }
     }
 }
 
+impl std::error::Error for ConfigError {}
+
 impl EngineConfig {
     /// Parses config from JSON.
     pub fn from_json_str(s: &str) -> serde_json::Result<Self> {
         serde_json::from_str(s)
     }
+
+    /// Parses config from TOML.
+    pub fn from_toml_str(s: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(s)
+    }
+
+    /// Loads config from a file.
+    ///
+    /// Files ending in `.json` are parsed as JSON; anything else as TOML.
+    /// Keys missing from the file keep their default values; unknown keys
+    /// are rejected so typos don't go unnoticed.
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let display = path.display().to_string();
+        let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Io {
+            path: display.clone(),
+            message: e.to_string(),
+        })?;
+
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let parsed = if is_json {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&text).map_err(|e| e.to_string())
+        };
+
+        parsed.map_err(|message| ConfigError::Parse {
+            path: display,
+            message,
+        })
+    }
+
+    /// Checks that values are usable before anything is started with them.
+    ///
+    /// `server_addr` may name a host, which clients resolve when they
+    /// connect. Servers bind to it and should use
+    /// [`validate_server`](Self::validate_server).
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !TICK_HZ_RANGE.contains(&self.tick_hz) {
+            return Err(ConfigError::InvalidTickRate(self.tick_hz));
+        }
+        if HostPort::parse(&self.server_addr).is_err() {
+            return Err(ConfigError::InvalidAddress(self.server_addr.clone()));
+        }
+        if self.maps_dir.is_empty() {
+            return Err(ConfigError::EmptyMapsDir);
+        }
+        if self.max_clients == 0 {
+            return Err(ConfigError::NoClientSlots);
+        }
+        Ok(())
+    }
+
+    /// Like [`validate`](Self::validate), but `server_addr` must be an
+    /// `ip:port` the server can bind to.
+    pub fn validate_server(&self) -> Result<(), ConfigError> {
+        self.validate()?;
+        if self.server_addr.parse::<SocketAddr>().is_err() {
+            return Err(ConfigError::InvalidAddress(self.server_addr.clone()));
+        }
+        Ok(())
+    }
+
+    /// Applies `PS_*` environment variable overrides.
+    ///
+    /// Call after loading the config file and before `merge_cli`.
+    pub fn apply_env(&mut self) -> Result<(), ConfigError> {
+        self.apply_env_from(|var| std::env::var(var).ok())
+    }
+
+    fn apply_env_from(
+        &mut self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<(), ConfigError> {
+        if let Some(addr) = lookup(ENV_SERVER_ADDR) {
+            self.server_addr = addr;
+        }
+        if let Some(value) = lookup(ENV_TICK_HZ) {
+            self.tick_hz = value.trim().parse().map_err(|_| ConfigError::InvalidEnv {
+                var: ENV_TICK_HZ.to_string(),
+                value,
+            })?;
+        }
+        if let Some(maps_dir) = lookup(ENV_MAPS_DIR) {
+            self.maps_dir = maps_dir;
+        }
+        if let Some(name) = lookup(ENV_PLAYER_NAME) {
+            self.player_name = name;
+        }
+        Ok(())
+    }
+
+    /// Applies command-line values on top of this config.
+    ///
+    /// CLI arguments always take precedence over file values.
+    pub fn merge_cli(mut self, args: &CliArgs) -> Self {
+        if let Some(addr) = &args.server_addr {
+            self.server_addr = addr.clone();
+        }
+        if let Some(tick_hz) = args.tick_hz {
+            self.tick_hz = tick_hz;
+        }
+        if let Some(maps_dir) = &args.maps_dir {
+            self.maps_dir = maps_dir.clone();
+        }
+        if let Some(name) = &args.player_name {
+            self.player_name = name.clone();
+        }
+        if let Some(listen) = args.listen {
+            self.listen = listen;
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("engine_cfg_{}_{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn load_toml_file() {
+        let path = write_temp(
+            "server.toml",
+            "server_addr = \"0.0.0.0:27015\"\ntick_hz = 128\nmaps_dir = \"/srv/maps\"\n",
+        );
+        let cfg = EngineConfig::from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(cfg.server_addr, "0.0.0.0:27015");
+        assert_eq!(cfg.tick_hz, 128);
+        assert_eq!(cfg.maps_dir, "/srv/maps");
+        assert_eq!(cfg.player_name, "Player");
+    }
+
+    #[test]
+    fn load_json_file() {
+        let path = write_temp("client.json", r#"{ "player_name": "Gordon" }"#);
+        let cfg = EngineConfig::from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(cfg.player_name, "Gordon");
+        assert_eq!(cfg.tick_hz, 64);
+    }
+
+    #[test]
+    fn cli_overrides_file() {
+        let path = write_temp("override.toml", "tick_hz = 128\nmaps_dir = \"/srv/maps\"\n");
+        let cfg = EngineConfig::from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let cfg = cfg.merge_cli(&CliArgs {
+            tick_hz: Some(66),
+            ..Default::default()
+        });
+        assert_eq!(cfg.tick_hz, 66);
+        assert_eq!(cfg.maps_dir, "/srv/maps");
+    }
+
+    #[test]
+    fn cli_enables_listen_mode() {
+        let path = write_temp(
+            "listen.toml",
+            "listen = false
+",
+        );
+        let cfg = EngineConfig::from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(!cfg.listen);
+
+        let cfg = cfg.merge_cli(&CliArgs {
+            listen: Some(true),
+            ..Default::default()
+        });
+        assert!(cfg.listen);
+    }
+
+    #[test]
+    fn missing_file_is_io_error() {
+        let err = EngineConfig::from_file(Path::new("/nonexistent/engine.toml")).unwrap_err();
+        assert!(matches!(err, ConfigError::Io { .. }));
+    }
+
+    #[test]
+    fn malformed_key_is_parse_error() {
+        let path = write_temp("bad.toml", "tick_rate = 64\n");
+        let err = EngineConfig::from_file(&path).unwrap_err();
+        std::fs::remove_file(&path).ok();
+
+        match err {
+            ConfigError::Parse { message, .. } => assert!(message.contains("tick_rate")),
+            other => panic!("expected parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn validate_default_config() {
+        assert_eq!(EngineConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_zero_tick_rate() {
+        let cfg = EngineConfig {
+            tick_hz: 0,
+            ..Default::default()
+        };
+        assert_eq!(cfg.validate(), Err(ConfigError::InvalidTickRate(0)));
+
+        let cfg = EngineConfig {
+            tick_hz: 1000,
+            ..Default::default()
+        };
+        assert_eq!(cfg.validate(), Err(ConfigError::InvalidTickRate(1000)));
+    }
+
+    #[test]
+    fn validate_rejects_bad_address() {
+        let cfg = EngineConfig {
+            server_addr: "localhost".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            cfg.validate(),
+            Err(ConfigError::InvalidAddress("localhost".to_string()))
+        );
+    }
+
+    #[test]
+    fn validate_accepts_hostnames() {
+        let cfg = EngineConfig {
+            server_addr: "localhost:40000".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(cfg.validate(), Ok(()));
+        assert_eq!(
+            cfg.validate_server(),
+            Err(ConfigError::InvalidAddress("localhost:40000".to_string()))
+        );
+        assert_eq!(EngineConfig::default().validate_server(), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_empty_maps_dir() {
+        let cfg = EngineConfig {
+            maps_dir: String::new(),
+            ..Default::default()
+        };
+        assert_eq!(cfg.validate(), Err(ConfigError::EmptyMapsDir));
+    }
+
+    #[test]
+    fn validate_rejects_zero_max_clients() {
+        let cfg = EngineConfig {
+            max_clients: 0,
+            ..Default::default()
+        };
+        assert_eq!(cfg.validate(), Err(ConfigError::NoClientSlots));
+    }
+
+    #[test]
+    fn env_overrides_land_in_struct() {
+        // The only test touching the real process environment.
+        std::env::set_var(ENV_SERVER_ADDR, "10.0.0.5:27015");
+        std::env::set_var(ENV_TICK_HZ, "100");
+        std::env::set_var(ENV_MAPS_DIR, "/data/maps");
+        std::env::set_var(ENV_PLAYER_NAME, "Alyx");
+
+        let mut cfg = EngineConfig::default();
+        let result = cfg.apply_env();
+
+        for var in [ENV_SERVER_ADDR, ENV_TICK_HZ, ENV_MAPS_DIR, ENV_PLAYER_NAME] {
+            std::env::remove_var(var);
+        }
+
+        result.unwrap();
+        assert_eq!(cfg.server_addr, "10.0.0.5:27015");
+        assert_eq!(cfg.tick_hz, 100);
+        assert_eq!(cfg.maps_dir, "/data/maps");
+        assert_eq!(cfg.player_name, "Alyx");
+    }
+
+    #[test]
+    fn env_invalid_tick_is_error() {
+        let mut cfg = EngineConfig::default();
+        let err = cfg
+            .apply_env_from(|var| (var == ENV_TICK_HZ).then(|| "fast".to_string()))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ConfigError::InvalidEnv {
+                var: ENV_TICK_HZ.to_string(),
+                value: "fast".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn env_precedence_between_file_and_cli() {
+        let path = write_temp("env.toml", "tick_hz = 128\nmaps_dir = \"/srv/maps\"\n");
+        let mut cfg = EngineConfig::from_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        cfg.apply_env_from(|var| match var {
+            ENV_TICK_HZ => Some("100".to_string()),
+            ENV_MAPS_DIR => Some("/env/maps".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        let cfg = cfg.merge_cli(&CliArgs {
+            tick_hz: Some(66),
+            ..Default::default()
+        });
+
+        assert_eq!(cfg.tick_hz, 66);
+        assert_eq!(cfg.maps_dir, "/env/maps");
+    }
 }
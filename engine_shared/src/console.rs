@@ -8,16 +8,17 @@
 //!
 //! # Usage
 //! ```ignore
-//! let mut console = Console::new();
-//! console.register_cvar("sv_cheats", CvarValue::Int(0));
-//! console.register_command("map", |args, ctx| { /* load map */ Ok(()) });
+//! let mut console = ConsoleRegistry::new();
+//! console.register_cvar("sv_cheats", CvarValue::Bool(false), "Allow cheats", CvarFlags::NONE);
+//! console.register_command("map", "map <name>", |args| { /* load map */ Ok(Vec::new()) });
 //! console.exec("map de_dust2")?;
 //! ```
 
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use anyhow::{bail, Context};
+use crate::net::NetMsg;
 
 /// Console variable value.
 #[derive(Debug, Clone, PartialEq)]
@@ -105,131 +106,160 @@ impl Default for CvarFlags {
     }
 }
 
-/// Command handler function type.
-pub type CommandHandler =
-    Box<dyn Fn(&[&str], &mut ConsoleContext) -> anyhow::Result<()> + Send + Sync>;
-
-/// Context passed to command handlers.
-pub struct ConsoleContext {
-    /// Output buffer for command responses.
-    pub output: Vec<String>,
-    /// Reference to cvars (for commands that need to read/write them).
-    pub cvars: Arc<RwLock<HashMap<String, Cvar>>>,
+/// Error from [`ConsoleRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleError {
+    /// No cvar has this name.
+    UnknownCvar(String),
+    /// The value doesn't parse as the cvar's type.
+    InvalidValue { name: String, value: String },
+    /// The cvar is flagged CHEAT and `sv_cheats` is off.
+    CheatProtected(String),
+    /// A replicated update named a cvar that isn't flagged REPLICATED.
+    NotReplicated(String),
+    /// A command rejected its arguments.
+    Usage(String),
+    /// A command failed.
+    Failed(String),
 }
 
-impl ConsoleContext {
-    pub fn print(&mut self, msg: impl Into<String>) {
-        self.output.push(msg.into());
+impl fmt::Display for ConsoleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsoleError::UnknownCvar(name) => write!(f, "unknown cvar: {name}"),
+            ConsoleError::InvalidValue { name, value } => {
+                write!(f, "invalid value for {name}: {value}")
+            }
+            ConsoleError::CheatProtected(name) => {
+                write!(f, "{name} is cheat protected; set sv_cheats 1")
+            }
+            ConsoleError::NotReplicated(name) => write!(f, "{name} is not replicated"),
+            ConsoleError::Usage(usage) => write!(f, "usage: {usage}"),
+            ConsoleError::Failed(msg) => write!(f, "{msg}"),
+        }
     }
+}
 
-    pub fn get_cvar(&self, name: &str) -> Option<CvarValue> {
-        self.cvars.read().ok()?.get(name).map(|c| c.value.clone())
-    }
+impl std::error::Error for ConsoleError {}
 
-    pub fn set_cvar(&self, name: &str, value: CvarValue) -> anyhow::Result<()> {
-        let mut cvars = self
-            .cvars
-            .write()
-            .map_err(|_| anyhow::anyhow!("lock poisoned"))?;
-        if let Some(cvar) = cvars.get_mut(name) {
-            cvar.value = value;
-            Ok(())
-        } else {
-            bail!("unknown cvar: {}", name);
-        }
-    }
+/// Handler for a [`ConsoleRegistry`] command: takes the arguments after the
+/// command name and returns output lines.
+pub type RegistryHandler = Box<dyn Fn(&[&str]) -> Result<Vec<String>, ConsoleError> + Send + Sync>;
+
+/// Called with the old and new value after a cvar changes.
+pub type ChangeCallback = Box<dyn Fn(&CvarValue, &CvarValue) + Send + Sync>;
+
+/// Suggests completions for a command's first argument, given its prefix.
+pub type ArgCompleter = Box<dyn Fn(&str) -> Vec<String> + Send + Sync>;
+
+struct RegisteredCommand {
+    handler: RegistryHandler,
+    help: String,
+    completer: Option<ArgCompleter>,
 }
 
-/// The console.
-pub struct Console {
-    cvars: Arc<RwLock<HashMap<String, Cvar>>>,
-    commands: HashMap<String, CommandHandler>,
-    history: Vec<String>,
-    max_history: usize,
+/// Registry of console commands and cvars.
+///
+/// Handlers take arguments and return output. Cvars are typed: setting one
+/// from text parses it as the type of its default value.
+///
+/// `echo`, `help [name]`, `cvarlist` and `set <cvar> <value>` are always
+/// available. Unknown commands print a message rather than failing.
+pub struct ConsoleRegistry {
+    commands: HashMap<String, RegisteredCommand>,
+    cvars: RwLock<HashMap<String, Cvar>>,
+    callbacks: HashMap<String, Vec<ChangeCallback>>,
+    /// REPLICATED cvars changed since the last `replicated_changes`, in
+    /// change order.
+    dirty: Mutex<Vec<String>>,
 }
 
-impl Default for Console {
+impl Default for ConsoleRegistry {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl Console {
+impl ConsoleRegistry {
     pub fn new() -> Self {
-        let mut console = Self {
-            cvars: Arc::new(RwLock::new(HashMap::new())),
+        let mut registry = Self {
             commands: HashMap::new(),
-            history: Vec::new(),
-            max_history: 100,
+            cvars: RwLock::new(HashMap::new()),
+            callbacks: HashMap::new(),
+            dirty: Mutex::new(Vec::new()),
         };
-
-        // Register built-in commands.
-        console.register_builtin_commands();
-        console
-    }
-
-    fn register_builtin_commands(&mut self) {
-        // echo <text>
-        self.register_command("echo", |args, ctx| {
-            ctx.print(args.join(" "));
-            Ok(())
+        registry.register_command("echo", "echo <text>: print text", |args| {
+            Ok(vec![args.join(" ")])
         });
+        registry
+    }
 
-        // help [command]
-        self.register_command("help", |args, ctx| {
-            if args.is_empty() {
-                ctx.print("Available commands: echo, help, cvarlist, set, quit");
-            } else {
-                ctx.print(format!("Help for '{}': not implemented", args[0]));
-            }
-            Ok(())
-        });
+    /// Registers a command, replacing any existing one with the same name.
+    pub fn register_command<F>(&mut self, name: &str, help: &str, handler: F)
+    where
+        F: Fn(&[&str]) -> Result<Vec<String>, ConsoleError> + Send + Sync + 'static,
+    {
+        self.commands.insert(
+            name.to_string(),
+            RegisteredCommand {
+                handler: Box::new(handler),
+                help: help.to_string(),
+                completer: None,
+            },
+        );
+    }
 
-        // cvarlist
-        self.register_command("cvarlist", |_args, ctx| {
-            let cvars = ctx.cvars.read().map_err(|_| anyhow::anyhow!("lock"))?;
-            let lines: Vec<String> = cvars
-                .iter()
-                .map(|(name, cvar)| {
-                    format!("  {} = {} (default: {})", name, cvar.value, cvar.default)
-                })
-                .collect();
-            drop(cvars);
-            for line in lines {
-                ctx.print(line);
+    /// Attaches a first-argument completer to a registered command.
+    ///
+    /// Returns false if there is no such command.
+    pub fn register_completer<F>(&mut self, command: &str, completer: F) -> bool
+    where
+        F: Fn(&str) -> Vec<String> + Send + Sync + 'static,
+    {
+        match self.commands.get_mut(command) {
+            Some(cmd) => {
+                cmd.completer = Some(Box::new(completer));
+                true
             }
-            Ok(())
-        });
+            None => false,
+        }
+    }
 
-        // set <cvar> <value>
-        self.register_command("set", |args, ctx| {
-            if args.len() < 2 {
-                bail!("usage: set <cvar> <value>");
+    /// Completes a partial console line, returning sorted candidates.
+    ///
+    /// Without a space, `prefix` is matched against command and cvar names.
+    /// After a command name and a space, the rest is passed to that
+    /// command's completer and candidates come back as full lines, such as
+    /// `map de_dust2`.
+    pub fn complete(&self, prefix: &str) -> Vec<String> {
+        let prefix = prefix.trim_start();
+        let mut out: Vec<String> = match prefix.split_once(' ') {
+            Some((name, partial)) => {
+                let Some(completer) = self.commands.get(name).and_then(|c| c.completer.as_ref())
+                else {
+                    return Vec::new();
+                };
+                let partial = partial.trim_start();
+                completer(partial)
+                    .into_iter()
+                    .filter(|arg| arg.starts_with(partial))
+                    .map(|arg| format!("{name} {arg}"))
+                    .collect()
             }
-            let name = args[0];
-            let value_str = args[1..].join(" ");
-
-            // Try to parse as int, then float, then string.
-            let value = if let Ok(v) = value_str.parse::<i64>() {
-                CvarValue::Int(v)
-            } else if let Ok(v) = value_str.parse::<f64>() {
-                CvarValue::Float(v)
-            } else if value_str == "true" {
-                CvarValue::Bool(true)
-            } else if value_str == "false" {
-                CvarValue::Bool(false)
-            } else {
-                CvarValue::String(value_str.trim_matches('"').to_string())
-            };
-
-            let value_clone = value.clone();
-            ctx.set_cvar(name, value)?;
-            ctx.print(format!("{} = {}", name, value_clone));
-            Ok(())
-        });
+            None => self
+                .commands
+                .keys()
+                .chain(self.cvars().keys())
+                .filter(|name| name.starts_with(prefix))
+                .cloned()
+                .collect(),
+        };
+        out.sort();
+        out.dedup();
+        out
     }
 
-    /// Registers a console variable.
+    /// Registers a cvar. Its default value fixes the cvar's type.
     pub fn register_cvar(
         &mut self,
         name: &str,
@@ -244,97 +274,329 @@ impl Console {
             description: description.to_string(),
             flags,
         };
-        self.cvars.write().unwrap().insert(name.to_string(), cvar);
+        self.cvars_mut().insert(name.to_string(), cvar);
     }
 
-    /// Registers a command.
-    pub fn register_command<F>(&mut self, name: &str, handler: F)
+    /// Calls `callback` with the old and new value whenever `name` changes.
+    ///
+    /// Setting a cvar to its current value doesn't count as a change.
+    /// Returns false if there is no such cvar.
+    pub fn on_change<F>(&mut self, name: &str, callback: F) -> bool
     where
-        F: Fn(&[&str], &mut ConsoleContext) -> anyhow::Result<()> + Send + Sync + 'static,
+        F: Fn(&CvarValue, &CvarValue) + Send + Sync + 'static,
     {
-        self.commands.insert(name.to_string(), Box::new(handler));
+        if !self.cvars().contains_key(name) {
+            return false;
+        }
+        self.callbacks
+            .entry(name.to_string())
+            .or_default()
+            .push(Box::new(callback));
+        true
     }
 
-    /// Executes a console command line.
-    pub fn exec(&mut self, line: &str) -> anyhow::Result<Vec<String>> {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with("//") {
-            return Ok(Vec::new());
+    /// Help text for a command, or the description of a cvar.
+    pub fn help(&self, name: &str) -> Option<String> {
+        if let Some(cmd) = self.commands.get(name) {
+            return Some(cmd.help.clone());
         }
+        self.cvars().get(name).map(|c| c.description.clone())
+    }
+
+    pub fn get_cvar(&self, name: &str) -> Option<CvarValue> {
+        self.cvars().get(name).map(|c| c.value.clone())
+    }
+
+    pub fn cvar_flags(&self, name: &str) -> Option<CvarFlags> {
+        self.cvars().get(name).map(|c| c.flags)
+    }
 
-        // Add to history.
-        self.history.push(line.to_string());
-        if self.history.len() > self.max_history {
-            self.history.remove(0);
+    /// Sets a cvar, converting `value` to the cvar's type.
+    pub fn set_cvar(&self, name: &str, value: CvarValue) -> Result<(), ConsoleError> {
+        self.set_cvar_str(name, &value.as_string())
+    }
+
+    /// Sets a cvar from text, parsed as the cvar's type.
+    ///
+    /// CHEAT cvars can only be changed while `sv_cheats` is on.
+    pub fn set_cvar_str(&self, name: &str, text: &str) -> Result<(), ConsoleError> {
+        let cheats = self.get_cvar("sv_cheats").is_some_and(|v| v.as_bool());
+        self.update_cvar(name, text, |cvar| {
+            if cvar.flags.contains(CvarFlags::CHEAT) && !cheats {
+                return Err(ConsoleError::CheatProtected(name.to_string()));
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Applies a value received from the server.
+    ///
+    /// Only REPLICATED cvars accept updates, and the CHEAT check is skipped
+    /// since the server already allowed the change. Change callbacks run,
+    /// but the update isn't queued for replication again.
+    pub fn apply_replicated(&self, name: &str, text: &str) -> Result<(), ConsoleError> {
+        let changed = self.update_cvar(name, text, |cvar| {
+            if !cvar.flags.contains(CvarFlags::REPLICATED) {
+                return Err(ConsoleError::NotReplicated(name.to_string()));
+            }
+            Ok(())
+        })?;
+        if changed {
+            self.dirty_names().retain(|n| n != name);
         }
+        Ok(())
+    }
 
-        // Parse command and arguments.
-        let tokens = parse_command_line(line);
-        if tokens.is_empty() {
-            return Ok(Vec::new());
+    /// Drains the REPLICATED cvars changed since the last call, as messages
+    /// to broadcast to clients.
+    pub fn replicated_changes(&self) -> Vec<NetMsg> {
+        let names = std::mem::take(&mut *self.dirty_names());
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let value = self.get_cvar(&name)?.as_string();
+                Some(NetMsg::CvarUpdate { name, value })
+            })
+            .collect()
+    }
+
+    /// Current values of all REPLICATED cvars, sorted by name, for syncing a
+    /// newly connected client.
+    pub fn replicated_cvars(&self) -> Vec<NetMsg> {
+        let cvars = self.cvars();
+        let mut replicated: Vec<&Cvar> = cvars
+            .values()
+            .filter(|c| c.flags.contains(CvarFlags::REPLICATED))
+            .collect();
+        replicated.sort_by(|a, b| a.name.cmp(&b.name));
+        replicated
+            .into_iter()
+            .map(|c| NetMsg::CvarUpdate {
+                name: c.name.clone(),
+                value: c.value.as_string(),
+            })
+            .collect()
+    }
+
+    /// Parses and stores a cvar value after `check` accepts the cvar, then
+    /// runs change callbacks and queues replication. Returns whether the
+    /// value changed.
+    fn update_cvar(
+        &self,
+        name: &str,
+        text: &str,
+        check: impl FnOnce(&Cvar) -> Result<(), ConsoleError>,
+    ) -> Result<bool, ConsoleError> {
+        let (old, new, flags) = {
+            let mut cvars = self.cvars_mut();
+            let cvar = cvars
+                .get_mut(name)
+                .ok_or_else(|| ConsoleError::UnknownCvar(name.to_string()))?;
+            check(cvar)?;
+            let new =
+                parse_typed(&cvar.default, text).ok_or_else(|| ConsoleError::InvalidValue {
+                    name: name.to_string(),
+                    value: text.to_string(),
+                })?;
+            let old = std::mem::replace(&mut cvar.value, new.clone());
+            (old, new, cvar.flags)
+        };
+        if old == new {
+            return Ok(false);
         }
 
-        let cmd_name = &tokens[0];
-        let args: Vec<&str> = tokens[1..].iter().map(|s| s.as_str()).collect();
+        // Callbacks run without the lock held so they may read cvars.
+        for callback in self.callbacks.get(name).into_iter().flatten() {
+            callback(&old, &new);
+        }
+        if flags.contains(CvarFlags::REPLICATED) {
+            let mut dirty = self.dirty_names();
+            if !dirty.iter().any(|n| n == name) {
+                dirty.push(name.to_string());
+            }
+        }
+        Ok(true)
+    }
 
-        let mut ctx = ConsoleContext {
-            output: Vec::new(),
-            cvars: Arc::clone(&self.cvars),
+    /// Executes a line: a command with arguments, a bare cvar name to print
+    /// it, or a cvar name (optionally after `set`) followed by a new value.
+    ///
+    /// Arguments are split on whitespace; double quotes group words.
+    pub fn exec(&self, line: &str) -> Result<Vec<String>, ConsoleError> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") {
+            return Ok(Vec::new());
+        }
+        let tokens = parse_command_line(line);
+        let Some((name, args)) = tokens.split_first() else {
+            return Ok(Vec::new());
         };
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
 
-        // Check if it's a cvar query/set (just typing the name).
-        if !self.commands.contains_key(cmd_name.as_str()) {
-            let cvar_info = self.cvars.read().ok().and_then(|cvars| {
-                cvars
-                    .get(cmd_name.as_str())
-                    .map(|cvar| (cvar.name.clone(), cvar.value.clone(), cvar.default.clone()))
-            });
-
-            if let Some((name, value, default)) = cvar_info {
-                if args.is_empty() {
-                    ctx.print(format!("{} = {} (default: {})", name, value, default));
-                    return Ok(ctx.output);
-                } else {
-                    // Set cvar.
-                    return self.exec(&format!("set {} {}", cmd_name, args.join(" ")));
+        if let Some(cmd) = self.commands.get(name.as_str()) {
+            return (cmd.handler)(&args);
+        }
+        match name.as_str() {
+            "help" => return Ok(self.help_lines(args.first().copied())),
+            "cvarlist" => return Ok(self.cvar_lines()),
+            "set" => {
+                let [cvar, value @ ..] = args.as_slice() else {
+                    return Err(ConsoleError::Usage("set <cvar> <value>".into()));
+                };
+                if value.is_empty() {
+                    return Err(ConsoleError::Usage("set <cvar> <value>".into()));
                 }
+                return self.assign(cvar, &value.join(" "));
             }
+            _ => {}
         }
 
-        // Execute command.
-        if let Some(handler) = self.commands.get(cmd_name.as_str()) {
-            handler(&args, &mut ctx).with_context(|| format!("command '{}'", cmd_name))?;
-        } else {
-            ctx.print(format!("Unknown command: {}", cmd_name));
+        let Some(value) = self.get_cvar(name) else {
+            return Ok(vec![format!("Unknown command: {name}")]);
+        };
+        if args.is_empty() {
+            return Ok(vec![format!("{name} = {value}")]);
         }
+        self.assign(name, &args.join(" "))
+    }
 
-        Ok(ctx.output)
+    /// Sets a cvar from text and echoes its new value.
+    fn assign(&self, name: &str, text: &str) -> Result<Vec<String>, ConsoleError> {
+        self.set_cvar_str(name, text)?;
+        let value = self
+            .get_cvar(name)
+            .ok_or_else(|| ConsoleError::UnknownCvar(name.to_string()))?;
+        Ok(vec![format!("{name} = {value}")])
     }
 
-    /// Gets a cvar value.
-    pub fn get_cvar(&self, name: &str) -> Option<CvarValue> {
-        self.cvars.read().ok()?.get(name).map(|c| c.value.clone())
+    fn help_lines(&self, name: Option<&str>) -> Vec<String> {
+        match name {
+            Some(name) => match self.help(name) {
+                Some(help) => vec![format!("{name}: {help}")],
+                None => vec![format!("Unknown command: {name}")],
+            },
+            None => {
+                let mut names: Vec<&String> = self.commands.keys().collect();
+                names.sort();
+                let names: Vec<&str> = names.into_iter().map(String::as_str).collect();
+                vec![format!("Available commands: {}", names.join(", "))]
+            }
+        }
     }
 
-    /// Sets a cvar value.
-    pub fn set_cvar(&self, name: &str, value: CvarValue) -> anyhow::Result<()> {
-        let mut cvars = self.cvars.write().map_err(|_| anyhow::anyhow!("lock"))?;
-        if let Some(cvar) = cvars.get_mut(name) {
-            cvar.value = value;
-            Ok(())
-        } else {
-            bail!("unknown cvar: {}", name);
+    fn cvar_lines(&self) -> Vec<String> {
+        let cvars = self.cvars();
+        let mut lines: Vec<String> = cvars
+            .values()
+            .map(|c| format!("  {} = {} (default: {})", c.name, c.value, c.default))
+            .collect();
+        lines.sort();
+        lines
+    }
+
+    fn dirty_names(&self) -> MutexGuard<'_, Vec<String>> {
+        self.dirty.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn cvars(&self) -> RwLockReadGuard<'_, HashMap<String, Cvar>> {
+        self.cvars.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn cvars_mut(&self) -> RwLockWriteGuard<'_, HashMap<String, Cvar>> {
+        self.cvars.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Fixed-size line history with up/down-arrow style navigation.
+///
+/// `prev` walks toward older lines and stops at the oldest; `next` walks
+/// back toward newer lines and returns `None` once past the newest, which
+/// stands for the fresh input line. Pushing resets navigation.
+#[derive(Debug, Clone)]
+pub struct History {
+    lines: VecDeque<String>,
+    capacity: usize,
+    /// Index into `lines` while navigating; `None` when on the input line.
+    cursor: Option<usize>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            capacity: capacity.max(1),
+            cursor: None,
+        }
+    }
+
+    /// Records a line, overwriting the oldest when full.
+    ///
+    /// Blank lines and repeats of the newest line are not recorded.
+    pub fn push(&mut self, line: &str) {
+        self.cursor = None;
+        let line = line.trim();
+        if line.is_empty() || self.lines.back().is_some_and(|last| last == line) {
+            return;
+        }
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
         }
+        self.lines.push_back(line.to_string());
     }
 
-    /// Gets command history.
-    pub fn history(&self) -> &[String] {
-        &self.history
+    /// Moves to the previous (older) line.
+    pub fn prev(&mut self) -> Option<&str> {
+        if self.lines.is_empty() {
+            return None;
+        }
+        let i = match self.cursor {
+            None => self.lines.len() - 1,
+            Some(i) => i.saturating_sub(1),
+        };
+        self.cursor = Some(i);
+        self.lines.get(i).map(String::as_str)
+    }
+
+    /// Moves to the next (newer) line, or back to the input line.
+    // Pairs with `prev`; this is cursor movement, not iteration.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&str> {
+        let i = self.cursor? + 1;
+        if i >= self.lines.len() {
+            self.cursor = None;
+            return None;
+        }
+        self.cursor = Some(i);
+        self.lines.get(i).map(String::as_str)
     }
 
-    /// Gets a shared reference to cvars for use in handlers.
-    pub fn cvars_ref(&self) -> Arc<RwLock<HashMap<String, Cvar>>> {
-        Arc::clone(&self.cvars)
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Lines from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(String::as_str)
+    }
+}
+
+/// Parses `text` as the same kind of value as `like`.
+fn parse_typed(like: &CvarValue, text: &str) -> Option<CvarValue> {
+    let text = text.trim();
+    match like {
+        CvarValue::Int(_) => text.parse().ok().map(CvarValue::Int),
+        CvarValue::Float(_) => text.parse().ok().map(CvarValue::Float),
+        CvarValue::Bool(_) => match text.to_ascii_lowercase().as_str() {
+            "1" | "true" => Some(CvarValue::Bool(true)),
+            "0" | "false" => Some(CvarValue::Bool(false)),
+            _ => None,
+        },
+        CvarValue::String(_) => Some(CvarValue::String(text.to_string())),
     }
 }
 
@@ -374,7 +636,7 @@ mod tests {
 
     #[test]
     fn console_cvar_roundtrip() {
-        let mut console = Console::new();
+        let mut console = ConsoleRegistry::new();
         console.register_cvar(
             "test_var",
             CvarValue::Int(42),
@@ -384,8 +646,19 @@ mod tests {
 
         assert_eq!(console.get_cvar("test_var"), Some(CvarValue::Int(42)));
 
-        console.exec("set test_var 100").unwrap();
+        assert_eq!(
+            console.exec("set test_var 100").unwrap(),
+            vec!["test_var = 100"]
+        );
         assert_eq!(console.get_cvar("test_var"), Some(CvarValue::Int(100)));
+        assert_eq!(
+            console.exec("set test_var"),
+            Err(ConsoleError::Usage("set <cvar> <value>".into()))
+        );
+        assert_eq!(
+            console.exec("set nope 1"),
+            Err(ConsoleError::UnknownCvar("nope".into()))
+        );
     }
 
     #[test]
@@ -393,4 +666,243 @@ mod tests {
         let tokens = parse_command_line(r#"say "hello world" test"#);
         assert_eq!(tokens, vec!["say", "hello world", "test"]);
     }
+
+    fn registry() -> ConsoleRegistry {
+        let mut reg = ConsoleRegistry::new();
+        reg.register_command("say", "say <text>: broadcast a message", |args| {
+            if args.is_empty() {
+                return Err(ConsoleError::Usage("say <text>".into()));
+            }
+            Ok(args.iter().map(|a| format!("[{a}]")).collect())
+        });
+        reg.register_cvar(
+            "sv_cheats",
+            CvarValue::Bool(false),
+            "Allow cheats",
+            CvarFlags::REPLICATED,
+        );
+        reg.register_cvar(
+            "mp_timelimit",
+            CvarValue::Int(20),
+            "Map time limit",
+            CvarFlags::NONE,
+        );
+        reg.register_cvar(
+            "sv_gravity",
+            CvarValue::Float(800.0),
+            "Gravity",
+            CvarFlags::CHEAT,
+        );
+        reg
+    }
+
+    #[test]
+    fn registry_command_with_args() {
+        let reg = registry();
+        let out = reg.exec(r#"say "hello world" again"#).unwrap();
+        assert_eq!(out, vec!["[hello world]", "[again]"]);
+        assert_eq!(
+            reg.exec("say"),
+            Err(ConsoleError::Usage("say <text>".into()))
+        );
+        assert_eq!(
+            reg.help("say").as_deref(),
+            Some("say <text>: broadcast a message")
+        );
+    }
+
+    #[test]
+    fn registry_unknown_command() {
+        let reg = registry();
+        assert_eq!(
+            reg.exec("nosuchthing 1 2").unwrap(),
+            vec!["Unknown command: nosuchthing"]
+        );
+        assert_eq!(reg.exec("   ").unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn registry_cvar_roundtrip() {
+        let reg = registry();
+        assert_eq!(reg.exec("mp_timelimit").unwrap(), vec!["mp_timelimit = 20"]);
+        assert_eq!(
+            reg.exec("mp_timelimit 45").unwrap(),
+            vec!["mp_timelimit = 45"]
+        );
+        assert_eq!(reg.get_cvar("mp_timelimit"), Some(CvarValue::Int(45)));
+
+        // Values keep the cvar's type.
+        assert_eq!(
+            reg.exec("mp_timelimit soon"),
+            Err(ConsoleError::InvalidValue {
+                name: "mp_timelimit".into(),
+                value: "soon".into()
+            })
+        );
+        reg.set_cvar("mp_timelimit", CvarValue::Float(30.0))
+            .unwrap();
+        assert_eq!(reg.get_cvar("mp_timelimit"), Some(CvarValue::Int(30)));
+        assert_eq!(
+            reg.set_cvar("nope", CvarValue::Int(1)),
+            Err(ConsoleError::UnknownCvar("nope".into()))
+        );
+    }
+
+    #[test]
+    fn registry_cheat_cvars_need_sv_cheats() {
+        let reg = registry();
+        assert_eq!(
+            reg.exec("sv_gravity 400"),
+            Err(ConsoleError::CheatProtected("sv_gravity".into()))
+        );
+        reg.exec("sv_cheats 1").unwrap();
+        reg.exec("sv_gravity 400").unwrap();
+        assert_eq!(reg.get_cvar("sv_gravity"), Some(CvarValue::Float(400.0)));
+        assert_eq!(reg.cvar_flags("sv_cheats"), Some(CvarFlags::REPLICATED));
+    }
+
+    #[test]
+    fn registry_completes_names_with_multiple_matches() {
+        let mut reg = registry();
+        reg.register_command("sv_restart", "Restart the round", |_| Ok(Vec::new()));
+
+        assert_eq!(
+            reg.complete("sv_"),
+            vec!["sv_cheats", "sv_gravity", "sv_restart"]
+        );
+        assert_eq!(reg.complete("mp"), vec!["mp_timelimit"]);
+        assert!(reg.complete("zz").is_empty());
+    }
+
+    #[test]
+    fn registry_completes_first_argument() {
+        let mut reg = registry();
+        reg.register_command("map", "map <name>", |_| Ok(Vec::new()));
+        assert!(reg.register_completer("map", |_| {
+            vec!["de_nuke".into(), "de_dust2".into(), "cs_office".into()]
+        }));
+        assert!(!reg.register_completer("missing", |_| Vec::new()));
+
+        assert_eq!(reg.complete("map de_"), vec!["map de_dust2", "map de_nuke"]);
+        assert_eq!(reg.complete("map ").len(), 3);
+        // Commands without a completer offer nothing.
+        assert!(reg.complete("say h").is_empty());
+    }
+
+    #[test]
+    fn history_navigation() {
+        let mut h = History::new(10);
+        assert_eq!(h.prev(), None);
+
+        h.push("status");
+        h.push("map de_dust2");
+        h.push("map de_dust2");
+        h.push("  ");
+        h.push("quit");
+        assert_eq!(h.len(), 3);
+
+        assert_eq!(h.prev(), Some("quit"));
+        assert_eq!(h.prev(), Some("map de_dust2"));
+        assert_eq!(h.prev(), Some("status"));
+        // Stops at the oldest line.
+        assert_eq!(h.prev(), Some("status"));
+
+        assert_eq!(h.next(), Some("map de_dust2"));
+        assert_eq!(h.next(), Some("quit"));
+        // Past the newest is the empty input line; the next prev starts over.
+        assert_eq!(h.next(), None);
+        assert_eq!(h.next(), None);
+        assert_eq!(h.prev(), Some("quit"));
+
+        h.push("echo hi");
+        assert_eq!(h.prev(), Some("echo hi"));
+    }
+
+    #[test]
+    fn history_ring_buffer_wraps() {
+        let mut h = History::new(3);
+        for line in ["a", "b", "c", "d", "e"] {
+            h.push(line);
+        }
+        assert_eq!(h.iter().collect::<Vec<_>>(), vec!["c", "d", "e"]);
+        assert_eq!(h.prev(), Some("e"));
+        assert_eq!(h.prev(), Some("d"));
+        assert_eq!(h.prev(), Some("c"));
+        assert_eq!(h.prev(), Some("c"));
+    }
+
+    #[test]
+    fn registry_change_callback_fires_on_change() {
+        use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let mut reg = registry();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen = Arc::new(AtomicI64::new(0));
+        let (c, v) = (Arc::clone(&calls), Arc::clone(&seen));
+        assert!(reg.on_change("mp_timelimit", move |old, new| {
+            assert_eq!(old, &CvarValue::Int(20));
+            c.fetch_add(1, Ordering::SeqCst);
+            v.store(new.as_int().unwrap(), Ordering::SeqCst);
+        }));
+        assert!(!reg.on_change("nope", |_, _| {}));
+
+        reg.exec("mp_timelimit 30").unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(seen.load(Ordering::SeqCst), 30);
+
+        // Same value again is not a change; a rejected value never applies.
+        reg.set_cvar_str("mp_timelimit", "30").unwrap();
+        assert!(reg.set_cvar_str("mp_timelimit", "x").is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn registry_only_replicated_cvars_are_broadcast() {
+        let reg = registry();
+        reg.exec("mp_timelimit 30").unwrap();
+        reg.exec("sv_cheats 1").unwrap();
+        reg.exec("sv_gravity 600").unwrap();
+        reg.exec("sv_cheats 0").unwrap();
+        reg.exec("sv_cheats 1").unwrap();
+
+        assert_eq!(
+            reg.replicated_changes(),
+            vec![NetMsg::CvarUpdate {
+                name: "sv_cheats".into(),
+                value: "true".into()
+            }]
+        );
+        assert!(reg.replicated_changes().is_empty());
+        assert_eq!(reg.replicated_cvars().len(), 1);
+    }
+
+    #[test]
+    fn registry_applies_replicated_updates() {
+        let client = registry();
+        client.apply_replicated("sv_cheats", "true").unwrap();
+        assert_eq!(client.get_cvar("sv_cheats"), Some(CvarValue::Bool(true)));
+        // Applied updates aren't echoed back out.
+        assert!(client.replicated_changes().is_empty());
+
+        assert_eq!(
+            client.apply_replicated("mp_timelimit", "5"),
+            Err(ConsoleError::NotReplicated("mp_timelimit".into()))
+        );
+        assert_eq!(client.get_cvar("mp_timelimit"), Some(CvarValue::Int(20)));
+    }
+
+    #[test]
+    fn registry_builtin_help_and_cvarlist() {
+        let reg = registry();
+        assert_eq!(
+            reg.exec("help").unwrap(),
+            vec!["Available commands: echo, say"]
+        );
+        assert_eq!(
+            reg.exec("help mp_timelimit").unwrap(),
+            vec!["mp_timelimit: Map time limit"]
+        );
+        assert_eq!(reg.exec("cvarlist").unwrap().len(), 3);
+    }
 }
@@ -10,7 +10,7 @@
 //! - Family sharing detection
 //! - Free weekend handling
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
@@ -48,6 +48,17 @@ pub enum LicenseType {
     None,
 }
 
+/// Whether a player may load DLC-gated content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccessResult {
+    /// Content is owned and installed (or belongs to the base game).
+    Granted,
+    /// The DLC providing the content is not owned.
+    NotOwned,
+    /// The DLC is owned but not installed.
+    NotInstalled,
+}
+
 /// Free weekend status.
 #[derive(Debug, Clone)]
 pub struct FreeWeekendInfo {
@@ -72,6 +83,9 @@ pub struct GameBan {
     pub reason: Option<String>,
 }
 
+/// ISteamApps-style name for [`EntitlementManager`].
+pub type DlcManager = EntitlementManager;
+
 /// Entitlement manager for DLC and license verification.
 ///
 /// In production, this would interface with Steamworks SDK.
@@ -82,8 +96,8 @@ pub struct EntitlementManager {
     owned_dlc: HashSet<AppId>,
     /// Installed DLC.
     installed_dlc: HashSet<AppId>,
-    /// DLC metadata.
-    dlc_info: HashMap<AppId, DlcInfo>,
+    /// DLC metadata, ordered by app ID so index enumeration is stable.
+    dlc_info: BTreeMap<AppId, DlcInfo>,
     /// License types.
     licenses: HashMap<AppId, LicenseType>,
     /// Family sharing lender.
@@ -103,7 +117,7 @@ impl EntitlementManager {
             base_app_id,
             owned_dlc: HashSet::new(),
             installed_dlc: HashSet::new(),
-            dlc_info: HashMap::new(),
+            dlc_info: BTreeMap::new(),
             licenses: HashMap::new(),
             family_sharing_lender: None,
             free_weekend_active: false,
@@ -116,10 +130,10 @@ impl EntitlementManager {
     // DLC Methods
     // =========================================================================
 
-    /// Check if DLC is owned.
+    /// Check if DLC is owned and installed.
     /// Reference: <https://partner.steamgames.com/doc/api/ISteamApps#BIsDlcInstalled>
     pub fn is_dlc_installed(&self, dlc_app_id: AppId) -> bool {
-        self.installed_dlc.contains(&dlc_app_id)
+        self.owned_dlc.contains(&dlc_app_id) && self.installed_dlc.contains(&dlc_app_id)
     }
 
     /// Get DLC count.
@@ -128,10 +142,10 @@ impl EntitlementManager {
         self.dlc_info.len()
     }
 
-    /// Get DLC data by index.
+    /// Get DLC data by index, in ascending app ID order.
     /// Reference: <https://partner.steamgames.com/doc/api/ISteamApps#BGetDLCDataByIndex>
-    pub fn get_dlc_data_by_index(&self, index: usize) -> Option<&DlcInfo> {
-        self.dlc_info.values().nth(index)
+    pub fn get_dlc_data_by_index(&self, index: usize) -> Option<DlcInfo> {
+        self.dlc_info.values().nth(index).cloned()
     }
 
     /// Get DLC info by app ID.
@@ -178,17 +192,56 @@ impl EntitlementManager {
         }
     }
 
+    /// Grant or revoke ownership of a DLC (for testing).
+    ///
+    /// Revoking ownership also uninstalls the DLC.
+    pub fn set_dlc_owned(&mut self, dlc_app_id: AppId, owned: bool) {
+        if owned {
+            self.owned_dlc.insert(dlc_app_id);
+        } else {
+            self.owned_dlc.remove(&dlc_app_id);
+            self.uninstall_dlc(dlc_app_id);
+        }
+        if let Some(info) = self.dlc_info.get_mut(&dlc_app_id) {
+            info.available = owned;
+        }
+    }
+
+    /// Decide whether content shipped by `content_app_id` may be loaded.
+    pub fn can_access(&self, content_app_id: AppId) -> AccessResult {
+        if content_app_id == self.base_app_id {
+            AccessResult::Granted
+        } else if !self.owned_dlc.contains(&content_app_id) {
+            AccessResult::NotOwned
+        } else if !self.installed_dlc.contains(&content_app_id) {
+            AccessResult::NotInstalled
+        } else {
+            AccessResult::Granted
+        }
+    }
+
+    /// Like [`can_access`](Self::can_access), but returns the denial reason
+    /// as an error.
+    pub fn require_access(&self, content_app_id: AppId) -> Result<(), AccessResult> {
+        match self.can_access(content_app_id) {
+            AccessResult::Granted => Ok(()),
+            denied => Err(denied),
+        }
+    }
+
     // =========================================================================
     // License Methods
     // =========================================================================
 
-    /// Check if app is subscribed (owned).
+    /// Check if app or DLC is subscribed (owned).
     /// Reference: <https://partner.steamgames.com/doc/api/ISteamApps#BIsSubscribedApp>
     pub fn is_subscribed_app(&self, app_id: AppId) -> bool {
-        self.licenses
-            .get(&app_id)
-            .map(|l| *l != LicenseType::None)
-            .unwrap_or(false)
+        self.owned_dlc.contains(&app_id)
+            || self
+                .licenses
+                .get(&app_id)
+                .map(|l| *l != LicenseType::None)
+                .unwrap_or(false)
     }
 
     /// Get license type for an app.
@@ -383,6 +436,60 @@ mod tests {
         assert!(!manager.is_dlc_installed(1001));
     }
 
+    // =============================================================================
+    // DLC-006: Ownership and Enumeration
+    // Reference: https://partner.steamgames.com/doc/api/ISteamApps#BIsSubscribedApp
+    // =============================================================================
+
+    #[test]
+    fn dlc_006_unowned_dlc_not_installed() {
+        let mut manager = EntitlementManager::new(730);
+        manager.add_dlc(1001, "Test DLC", false);
+
+        assert!(!manager.is_subscribed_app(1001));
+        assert!(!manager.install_dlc(1001));
+        assert!(!manager.is_dlc_installed(1001));
+        assert!(!manager.get_dlc_data_by_index(0).unwrap().installed);
+    }
+
+    #[test]
+    fn dlc_006_injected_ownership_allows_install() {
+        let mut manager = EntitlementManager::new(730);
+        manager.add_dlc(1001, "Test DLC", false);
+
+        manager.set_dlc_owned(1001, true);
+        assert!(manager.is_subscribed_app(1001));
+        assert!(manager.install_dlc(1001));
+        assert!(manager.is_dlc_installed(1001));
+
+        let info = manager.get_dlc_data_by_index(0).unwrap();
+        assert!(info.available);
+        assert!(info.installed);
+
+        // Revoking ownership uninstalls.
+        manager.set_dlc_owned(1001, false);
+        assert!(!manager.is_dlc_installed(1001));
+        assert!(!manager.get_dlc_data_by_index(0).unwrap().available);
+    }
+
+    #[test]
+    fn dlc_006_index_enumeration_is_stable() {
+        let mut manager = EntitlementManager::new(730);
+        for (id, name) in [(3003, "C"), (1001, "A"), (2002, "B")] {
+            manager.add_dlc(id, name, true);
+        }
+        manager.install_dlc(2002);
+
+        let ids = |m: &EntitlementManager| {
+            (0..m.get_dlc_count())
+                .map(|i| m.get_dlc_data_by_index(i).unwrap().app_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&manager), vec![1001, 2002, 3003]);
+        assert_eq!(ids(&manager), ids(&manager));
+        assert!(manager.get_dlc_data_by_index(3).is_none());
+    }
+
     // =============================================================================
     // DLC-007: Early Access / Free Weekend
     // Reference: https://partner.steamgames.com/doc/api/ISteamApps#BIsSubscribedFromFreeWeekend
@@ -423,6 +530,40 @@ mod tests {
         );
     }
 
+    // =============================================================================
+    // DLC-009: Content Gating
+    // =============================================================================
+
+    #[test]
+    fn dlc_009_can_access_outcomes() {
+        let mut manager = DlcManager::new(730);
+        manager.add_dlc(1001, "Map Pack", false);
+        manager.add_dlc(1002, "Weapon Pack", true);
+        manager.add_dlc(1003, "Soundtrack", true);
+        manager.install_dlc(1003);
+
+        assert_eq!(manager.can_access(730), AccessResult::Granted);
+        assert_eq!(manager.can_access(1001), AccessResult::NotOwned);
+        assert_eq!(manager.can_access(9999), AccessResult::NotOwned);
+        assert_eq!(manager.can_access(1002), AccessResult::NotInstalled);
+        assert_eq!(manager.can_access(1003), AccessResult::Granted);
+    }
+
+    #[test]
+    fn dlc_009_require_access() {
+        let mut manager = DlcManager::new(730);
+        manager.add_dlc(1001, "Map Pack", true);
+
+        assert_eq!(
+            manager.require_access(1001),
+            Err(AccessResult::NotInstalled)
+        );
+        manager.install_dlc(1001);
+        assert_eq!(manager.require_access(1001), Ok(()));
+        manager.set_dlc_owned(1001, false);
+        assert_eq!(manager.require_access(1001), Err(AccessResult::NotOwned));
+    }
+
     // =============================================================================
     // LIC-001: Game Ownership
     // Reference: https://partner.steamgames.com/doc/api/ISteamApps#BIsSubscribedApp
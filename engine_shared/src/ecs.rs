@@ -1,75 +1,659 @@
 //! Entity/component system (minimal ECS).
 //!
 //! This is a deliberately small ECS suitable for deterministic simulation and
-//! net replication. It is not archetype-based; instead it uses typed component
-//! storages keyed by entity id.
+//! net replication. It is not archetype-based; instead each component type
+//! has a sparse-set [`ComponentStorage`] indexed by entity slot.
+//!
+//! Entities with a [`Parent`] form a hierarchy: their [`Transform`] is local
+//! to the parent, and [`World::world_transform`] composes it up the chain.
 
 use std::{
     any::{Any, TypeId},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    fmt,
 };
 
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    math::{Quat, Vec3},
+    net::{EntityState, Snapshot, MAX_SNAPSHOT_ENTITIES},
+};
+
+/// Highest slot index [`World::apply_snapshot`] will mirror. Indices are
+/// reused, so a server's stay near its peak entity count; the slack covers
+/// entities that aren't replicated.
+pub const MAX_MIRRORED_INDEX: u32 = MAX_SNAPSHOT_ENTITIES as u32 * 4;
+
 /// Opaque entity id.
+///
+/// The low 32 bits are a slot index and the high 32 bits the slot's
+/// generation, which is bumped on despawn so handles to a recycled slot
+/// don't alias the new entity. `EntityId(n)` for small `n` is slot `n`,
+/// generation 0.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct EntityId(pub u64);
 
+impl EntityId {
+    pub const fn new(index: u32, generation: u32) -> Self {
+        Self(((generation as u64) << 32) | index as u64)
+    }
+
+    pub const fn index(self) -> u32 {
+        self.0 as u32
+    }
+
+    pub const fn generation(self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+}
+
+/// Sparse-set storage for one component type.
+///
+/// Components are packed densely (iteration is a slice walk in insertion
+/// order, modulo swap-removes); `sparse` maps an entity's slot index to its
+/// position in the dense arrays.
+pub struct ComponentStorage<T> {
+    sparse: Vec<Option<usize>>,
+    entities: Vec<EntityId>,
+    dense: Vec<T>,
+}
+
+impl<T> Default for ComponentStorage<T> {
+    fn default() -> Self {
+        Self {
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            dense: Vec::new(),
+        }
+    }
+}
+
+impl<T> ComponentStorage<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn dense_index(&self, entity: EntityId) -> Option<usize> {
+        let i = (*self.sparse.get(entity.index() as usize)?)?;
+        (self.entities[i] == entity).then_some(i)
+    }
+
+    /// Inserts or replaces `entity`'s component, returning the old value.
+    ///
+    /// A component left behind by a previous occupant of the same slot is
+    /// replaced.
+    pub fn insert(&mut self, entity: EntityId, component: T) -> Option<T> {
+        let slot = entity.index() as usize;
+        if slot >= self.sparse.len() {
+            self.sparse.resize(slot + 1, None);
+        }
+        match self.sparse[slot] {
+            Some(i) if self.entities[i] == entity => {
+                Some(std::mem::replace(&mut self.dense[i], component))
+            }
+            Some(i) => {
+                self.entities[i] = entity;
+                self.dense[i] = component;
+                None
+            }
+            None => {
+                self.sparse[slot] = Some(self.dense.len());
+                self.entities.push(entity);
+                self.dense.push(component);
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, entity: EntityId) -> Option<&T> {
+        self.dense_index(entity).map(|i| &self.dense[i])
+    }
+
+    pub fn get_mut(&mut self, entity: EntityId) -> Option<&mut T> {
+        self.dense_index(entity).map(|i| &mut self.dense[i])
+    }
+
+    pub fn contains(&self, entity: EntityId) -> bool {
+        self.dense_index(entity).is_some()
+    }
+
+    /// Removes `entity`'s component. The last component is moved into the
+    /// hole, so iteration order is not preserved.
+    pub fn remove(&mut self, entity: EntityId) -> Option<T> {
+        let i = self.dense_index(entity)?;
+        self.sparse[entity.index() as usize] = None;
+        self.entities.swap_remove(i);
+        let component = self.dense.swap_remove(i);
+        if let Some(moved) = self.entities.get(i) {
+            self.sparse[moved.index() as usize] = Some(i);
+        }
+        Some(component)
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &T)> {
+        self.entities.iter().copied().zip(self.dense.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (EntityId, &mut T)> {
+        self.entities.iter().copied().zip(self.dense.iter_mut())
+    }
+}
+
+/// Type-erased component storage, so the world can drop a despawned
+/// entity's components without knowing their types.
+trait AnyStorage: Send + Sync {
+    fn remove_entity(&mut self, entity: EntityId);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static + Send + Sync> AnyStorage for ComponentStorage<T> {
+    fn remove_entity(&mut self, entity: EntityId) {
+        self.remove(entity);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// What happens to an entity's children when it's despawned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChildPolicy {
+    /// Despawn them too, recursively.
+    #[default]
+    Despawn,
+    /// Attach them to the despawned entity's own parent (or make them
+    /// roots), keeping their world transform.
+    Reparent,
+}
+
+/// Error walking a [`Parent`] chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyError {
+    /// The entity isn't alive.
+    NotAlive(EntityId),
+    /// Following parents from the entity loops back to this one.
+    Cycle(EntityId),
+}
+
+impl fmt::Display for HierarchyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HierarchyError::NotAlive(e) => write!(f, "entity {} is not alive", e.0),
+            HierarchyError::Cycle(e) => write!(f, "parent chain loops at entity {}", e.0),
+        }
+    }
+}
+
+impl std::error::Error for HierarchyError {}
+
 /// Simple world that can store typed components.
 #[derive(Default)]
 pub struct World {
-    next_id: u64,
-    storages: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    /// Current generation of each slot.
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    /// Despawned slots available for reuse.
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+    /// Applied to children of despawned entities.
+    child_policy: ChildPolicy,
 }
 
 impl World {
-    /// Creates a new entity.
+    /// Creates a new entity, reusing a despawned slot if there is one.
     pub fn spawn(&mut self) -> EntityId {
-        let id = EntityId(self.next_id);
-        self.next_id += 1;
-        id
+        if let Some(index) = self.free.pop() {
+            self.alive[index as usize] = true;
+            return EntityId::new(index, self.generations[index as usize]);
+        }
+        let index = u32::try_from(self.generations.len()).expect("entity slots exhausted");
+        self.generations.push(0);
+        self.alive.push(true);
+        EntityId::new(index, 0)
     }
 
-    /// Inserts/replaces a component for an entity.
-    pub fn insert<T: 'static + Send + Sync>(&mut self, entity: EntityId, component: T) {
-        let storage = self
-            .storages
+    /// Destroys an entity and its components. Returns false if the handle
+    /// was already dead.
+    ///
+    /// Its children are handled per [`child_policy`](Self::child_policy).
+    pub fn despawn(&mut self, entity: EntityId) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        // Without any `Parent`s there are no children to look for.
+        if self.storage::<Parent>().is_none_or(|s| s.is_empty()) {
+            self.despawn_one(entity);
+            return true;
+        }
+
+        match self.child_policy {
+            ChildPolicy::Despawn => {
+                for e in self.descendants_and_self(entity) {
+                    self.despawn_one(e);
+                }
+            }
+            ChildPolicy::Reparent => {
+                let children = self.children(entity);
+                let parent = self.get::<Parent>(entity).copied();
+                let local = self.get::<Transform>(entity).copied();
+                self.despawn_one(entity);
+
+                for child in children {
+                    if let Some(local) = local {
+                        let child_local = self.get::<Transform>(child).copied().unwrap_or_default();
+                        self.insert(child, local.compose(&child_local));
+                    }
+                    match parent {
+                        Some(parent) if self.is_alive(parent.0) => {
+                            self.insert(child, parent);
+                        }
+                        _ => {
+                            self.remove::<Parent>(child);
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Frees a live entity's slot and components, leaving its children be.
+    fn despawn_one(&mut self, entity: EntityId) {
+        let index = entity.index() as usize;
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(entity.index());
+        for storage in self.storages.values_mut() {
+            storage.remove_entity(entity);
+        }
+    }
+
+    /// `entity` and everything below it, parents before children and
+    /// siblings by index, from a single pass over the `Parent`s.
+    fn descendants_and_self(&self, entity: EntityId) -> Vec<EntityId> {
+        let mut children: HashMap<EntityId, Vec<EntityId>> = HashMap::new();
+        for (child, parent) in self.iter::<Parent>() {
+            if self.is_alive(child) {
+                children.entry(parent.0).or_default().push(child);
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+        let mut stack = vec![entity];
+        while let Some(e) = stack.pop() {
+            // A cycle leads back to an entity already listed.
+            if !visited.insert(e) {
+                continue;
+            }
+            order.push(e);
+            if let Some(kids) = children.get_mut(&e) {
+                kids.sort_by_key(|c| std::cmp::Reverse(c.index()));
+                stack.extend(kids.iter().copied());
+            }
+        }
+        order
+    }
+
+    /// How children of despawned entities are handled.
+    pub fn child_policy(&self) -> ChildPolicy {
+        self.child_policy
+    }
+
+    pub fn set_child_policy(&mut self, policy: ChildPolicy) {
+        self.child_policy = policy;
+    }
+
+    /// Live entities whose [`Parent`] is `entity`, in slot order.
+    pub fn children(&self, entity: EntityId) -> Vec<EntityId> {
+        let mut children: Vec<EntityId> = self
+            .iter::<Parent>()
+            .filter(|(child, parent)| parent.0 == entity && self.is_alive(*child))
+            .map(|(child, _)| child)
+            .collect();
+        children.sort_by_key(|c| c.index());
+        children
+    }
+
+    /// An entity's transform in world space: its local [`Transform`]
+    /// composed with every ancestor's.
+    ///
+    /// Entities without a `Transform` count as the identity, and a parent
+    /// that's no longer alive ends the chain.
+    pub fn world_transform(&self, entity: EntityId) -> Result<Transform, HierarchyError> {
+        if !self.is_alive(entity) {
+            return Err(HierarchyError::NotAlive(entity));
+        }
+        let mut chain = vec![entity];
+        let mut current = entity;
+        while let Some(&Parent(parent)) = self.get::<Parent>(current) {
+            if !self.is_alive(parent) {
+                break;
+            }
+            if chain.contains(&parent) {
+                return Err(HierarchyError::Cycle(parent));
+            }
+            chain.push(parent);
+            current = parent;
+        }
+
+        Ok(chain.iter().rev().fold(Transform::default(), |world, &e| {
+            world.compose(&self.get::<Transform>(e).copied().unwrap_or_default())
+        }))
+    }
+
+    /// Makes `entity` alive with exactly that index and generation, for
+    /// mirroring ids chosen by another world (e.g. the server's).
+    ///
+    /// Whatever currently occupies the slot is despawned first. Returns
+    /// false, doing nothing, if the index is above [`MAX_MIRRORED_INDEX`].
+    fn spawn_at(&mut self, entity: EntityId) -> bool {
+        if self.is_alive(entity) {
+            return true;
+        }
+        if entity.index() > MAX_MIRRORED_INDEX {
+            return false;
+        }
+        let index = entity.index() as usize;
+        while self.generations.len() <= index {
+            self.free.push(self.generations.len() as u32);
+            self.generations.push(0);
+            self.alive.push(false);
+        }
+        if self.alive[index] {
+            let current = EntityId::new(entity.index(), self.generations[index]);
+            self.despawn(current);
+        }
+        self.free.retain(|&i| i != entity.index());
+        self.generations[index] = entity.generation();
+        self.alive[index] = true;
+        true
+    }
+
+    /// Whether `entity` refers to a live entity (and not a recycled slot).
+    pub fn is_alive(&self, entity: EntityId) -> bool {
+        let index = entity.index() as usize;
+        self.alive.get(index).copied().unwrap_or(false)
+            && self.generations[index] == entity.generation()
+    }
+
+    /// Number of live entities.
+    pub fn entity_count(&self) -> usize {
+        self.alive.len() - self.free.len()
+    }
+
+    /// Iterates live entities in slot order.
+    pub fn entities(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.alive
+            .iter()
+            .enumerate()
+            .filter(|(_, alive)| **alive)
+            .map(|(i, _)| EntityId::new(i as u32, self.generations[i]))
+    }
+
+    /// Registers storage for `T` (done implicitly by the first insert).
+    pub fn register<T: 'static + Send + Sync>(&mut self) -> &mut ComponentStorage<T> {
+        self.storages
             .entry(TypeId::of::<T>())
-            .or_insert_with(|| Box::new(HashMap::<EntityId, T>::new()));
+            .or_insert_with(|| Box::new(ComponentStorage::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()
+            .expect("storage type mismatch")
+    }
+
+    /// The storage for `T`, if any component of that type was registered.
+    pub fn storage<T: 'static + Send + Sync>(&self) -> Option<&ComponentStorage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|boxed| boxed.as_any().downcast_ref::<ComponentStorage<T>>())
+    }
+
+    pub fn storage_mut<T: 'static + Send + Sync>(&mut self) -> Option<&mut ComponentStorage<T>> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|boxed| boxed.as_any_mut().downcast_mut::<ComponentStorage<T>>())
+    }
 
-        let storage = storage
-            .downcast_mut::<HashMap<EntityId, T>>()
-            .expect("storage type mismatch");
+    /// Inserts/replaces a component for an entity.
+    ///
+    /// Ignored (returning false) if the entity isn't alive.
+    pub fn insert<T: 'static + Send + Sync>(&mut self, entity: EntityId, component: T) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        self.register::<T>().insert(entity, component);
+        true
+    }
 
-        storage.insert(entity, component);
+    /// Removes and returns an entity's component.
+    pub fn remove<T: 'static + Send + Sync>(&mut self, entity: EntityId) -> Option<T> {
+        self.storage_mut::<T>()?.remove(entity)
     }
 
     /// Gets a component reference.
     pub fn get<T: 'static + Send + Sync>(&self, entity: EntityId) -> Option<&T> {
-        self.storages
-            .get(&TypeId::of::<T>())
-            .and_then(|boxed| boxed.downcast_ref::<HashMap<EntityId, T>>())
-            .and_then(|storage| storage.get(&entity))
+        self.storage::<T>()?.get(entity)
     }
 
     /// Gets a mutable component reference.
     pub fn get_mut<T: 'static + Send + Sync>(&mut self, entity: EntityId) -> Option<&mut T> {
-        self.storages
-            .get_mut(&TypeId::of::<T>())
-            .and_then(|boxed| boxed.downcast_mut::<HashMap<EntityId, T>>())
-            .and_then(|storage| storage.get_mut(&entity))
+        self.storage_mut::<T>()?.get_mut(entity)
     }
 
     /// Iterates entities with a given component.
     pub fn iter<T: 'static + Send + Sync>(&self) -> impl Iterator<Item = (EntityId, &T)> {
-        self.storages
-            .get(&TypeId::of::<T>())
-            .and_then(|boxed| boxed.downcast_ref::<HashMap<EntityId, T>>())
-            .into_iter()
-            .flat_map(|storage| storage.iter().map(|(k, v)| (*k, v)))
+        self.storage::<T>().into_iter().flat_map(|s| s.iter())
+    }
+
+    fn networked<T: Networked + Copy + 'static + Send + Sync>(
+        &self,
+        entity: EntityId,
+    ) -> Option<T> {
+        self.get::<T>(entity).copied()
+    }
+
+    /// Captures the networked components of every live entity with a
+    /// [`Position`], in slot order.
+    pub fn to_snapshot(&self, tick: u32) -> Snapshot {
+        let entities = self
+            .entities()
+            .filter_map(|id| {
+                let pos = self.networked::<Position>(id)?;
+                let angles = self.networked::<Angles>(id).unwrap_or_default();
+                Some(EntityState {
+                    id,
+                    position: Vec3::new(pos.x, pos.y, pos.z),
+                    angles: Vec3::new(angles.pitch, angles.yaw, angles.roll),
+                    health: self.networked::<Health>(id).map(|h| h.0),
+                })
+            })
+            .collect();
+        Snapshot {
+            tick,
+            entities,
+            ack: None,
+        }
+    }
+
+    /// Makes this world mirror `snapshot`: entities in it are spawned with
+    /// the same ids or updated, and every other live entity is despawned.
+    ///
+    /// Intended for a client world that only holds replicated entities;
+    /// non-networked components of surviving entities are left alone.
+    /// Entities with an index above [`MAX_MIRRORED_INDEX`] are skipped.
+    pub fn apply_snapshot(&mut self, snapshot: &Snapshot) {
+        let ids: HashSet<EntityId> = snapshot.entities.iter().map(|e| e.id).collect();
+        let stale: Vec<EntityId> = self.entities().filter(|id| !ids.contains(id)).collect();
+        for id in stale {
+            self.despawn(id);
+        }
+
+        for state in &snapshot.entities {
+            let id = state.id;
+            if !self.spawn_at(id) {
+                continue;
+            }
+            self.insert(
+                id,
+                Position {
+                    x: state.position.x,
+                    y: state.position.y,
+                    z: state.position.z,
+                },
+            );
+            self.insert(
+                id,
+                Angles {
+                    pitch: state.angles.x,
+                    yaw: state.angles.y,
+                    roll: state.angles.z,
+                },
+            );
+            match state.health {
+                Some(health) => {
+                    self.insert(id, Health(health));
+                }
+                None => {
+                    self.remove::<Health>(id);
+                }
+            }
+        }
+    }
+
+    /// Iterates entities that have every component in `Q`, e.g.
+    /// `world.query::<(Position, Velocity)>()` yields
+    /// `(EntityId, (&Position, &Velocity))`.
+    pub fn query<Q: Query>(&self) -> impl Iterator<Item = (EntityId, Q::Item<'_>)> {
+        Q::iter(self)
+    }
+}
+
+/// A tuple of component types that can be queried together.
+pub trait Query {
+    type Item<'w>;
+
+    fn iter(world: &World) -> Box<dyn Iterator<Item = (EntityId, Self::Item<'_>)> + '_>;
+}
+
+impl<A, B> Query for (A, B)
+where
+    A: 'static + Send + Sync,
+    B: 'static + Send + Sync,
+{
+    type Item<'w> = (&'w A, &'w B);
+
+    fn iter(world: &World) -> Box<dyn Iterator<Item = (EntityId, Self::Item<'_>)> + '_> {
+        let (Some(a), Some(b)) = (world.storage::<A>(), world.storage::<B>()) else {
+            return Box::new(std::iter::empty());
+        };
+        Box::new(a.iter().filter_map(move |(e, a)| Some((e, (a, b.get(e)?)))))
     }
 }
 
+impl<A, B, C> Query for (A, B, C)
+where
+    A: 'static + Send + Sync,
+    B: 'static + Send + Sync,
+    C: 'static + Send + Sync,
+{
+    type Item<'w> = (&'w A, &'w B, &'w C);
+
+    fn iter(world: &World) -> Box<dyn Iterator<Item = (EntityId, Self::Item<'_>)> + '_> {
+        let (Some(a), Some(b), Some(c)) = (
+            world.storage::<A>(),
+            world.storage::<B>(),
+            world.storage::<C>(),
+        ) else {
+            return Box::new(std::iter::empty());
+        };
+        Box::new(
+            a.iter()
+                .filter_map(move |(e, a)| Some((e, (a, b.get(e)?, c.get(e)?)))),
+        )
+    }
+}
+
+/// A system run by a [`Schedule`]: mutates the world given the tick length
+/// in seconds.
+pub type System = Box<dyn Fn(&mut World, f32) + Send + Sync>;
+
+/// Ordered list of systems run once per simulation tick.
+///
+/// Systems run strictly in registration order, so each one sees the writes
+/// of those before it in the same tick.
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<(String, System)>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a system to run after all previously added ones.
+    pub fn add_system<F>(&mut self, name: &str, system: F) -> &mut Self
+    where
+        F: Fn(&mut World, f32) + Send + Sync + 'static,
+    {
+        self.systems.push((name.to_string(), Box::new(system)));
+        self
+    }
+
+    /// System names in execution order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.systems.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.systems.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
+
+    /// Runs every system once, in order.
+    pub fn run(&self, world: &mut World, dt: f32) {
+        for (_, system) in &self.systems {
+            system(world, dt);
+        }
+    }
+}
+
+/// Moves every entity with a [`Velocity`] by `velocity * dt`.
+pub fn integrate_velocity(world: &mut World, dt: f32) {
+    let moving: Vec<(EntityId, Velocity)> = world
+        .query::<(Position, Velocity)>()
+        .map(|(e, (_, v))| (e, *v))
+        .collect();
+    for (e, v) in moving {
+        if let Some(pos) = world.get_mut::<Position>(e) {
+            pos.x += v.x * dt;
+            pos.y += v.y * dt;
+            pos.z += v.z * dt;
+        }
+    }
+}
+
+/// Marker for components replicated to clients in snapshots.
+pub trait Networked {}
+
 /// Common component: position.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
 pub struct Position {
@@ -78,6 +662,79 @@ pub struct Position {
     pub z: f32,
 }
 
+impl Networked for Position {}
+
+/// Common component: view/facing angles in degrees.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub struct Angles {
+    pub pitch: f32,
+    pub yaw: f32,
+    pub roll: f32,
+}
+
+impl Networked for Angles {}
+
+/// Common component: hit points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct Health(pub u32);
+
+impl Networked for Health {}
+
+/// Common component: transform, relative to the [`Parent`] if there is one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    /// Uniform scale.
+    pub scale: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            scale: 1.0,
+        }
+    }
+}
+
+impl Transform {
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Default::default()
+        }
+    }
+
+    /// Maps a point from this transform's local space to its parent's.
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let r = self.rotation.rotate(Vec3::new(
+            p.x * self.scale,
+            p.y * self.scale,
+            p.z * self.scale,
+        ));
+        Vec3::new(
+            r.x + self.translation.x,
+            r.y + self.translation.y,
+            r.z + self.translation.z,
+        )
+    }
+
+    /// The transform of a `child` local to `self`, in `self`'s parent space.
+    pub fn compose(&self, child: &Transform) -> Transform {
+        Transform {
+            translation: self.transform_point(child.translation),
+            rotation: self.rotation.compose(child.rotation),
+            scale: self.scale * child.scale,
+        }
+    }
+}
+
+/// Attaches an entity to another, making its [`Transform`] local to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Parent(pub EntityId);
+
 /// Common component: velocity.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
 pub struct Velocity {
@@ -104,4 +761,420 @@ mod tests {
         );
         assert_eq!(world.get::<Position>(e).unwrap().x, 1.0);
     }
+
+    #[test]
+    fn despawn_then_spawn_recycles_slot_with_new_generation() {
+        let mut world = World::default();
+        let a = world.spawn();
+        let b = world.spawn();
+        assert_eq!((a.index(), a.generation()), (0, 0));
+        assert_eq!((b.index(), b.generation()), (1, 0));
+
+        assert!(world.despawn(a));
+        assert!(!world.is_alive(a));
+        assert_eq!(world.entity_count(), 1);
+
+        let c = world.spawn();
+        assert_eq!(c.index(), a.index());
+        assert_eq!(c.generation(), 1);
+        assert_ne!(c, a);
+        assert!(world.is_alive(c));
+        assert_eq!(world.entities().collect::<Vec<_>>(), vec![c, b]);
+    }
+
+    #[test]
+    fn stale_handle_is_rejected() {
+        let mut world = World::default();
+        let old = world.spawn();
+        world.insert(old, Position::default());
+        world.despawn(old);
+        let new = world.spawn();
+        world.insert(
+            new,
+            Position {
+                x: 5.0,
+                ..Default::default()
+            },
+        );
+
+        assert!(!world.is_alive(old));
+        assert!(!world.despawn(old));
+        assert!(world.get::<Position>(old).is_none());
+        assert!(!world.insert(old, Velocity::default()));
+        assert!(world.get::<Velocity>(new).is_none());
+        assert_eq!(world.get::<Position>(new).unwrap().x, 5.0);
+        assert!(!world.is_alive(EntityId::new(99, 0)));
+    }
+
+    #[test]
+    fn query_yields_only_entities_with_all_components() {
+        let mut world = World::default();
+        let moving = world.spawn();
+        let still = world.spawn();
+        let other = world.spawn();
+        world.insert(moving, Position::default());
+        world.insert(
+            moving,
+            Velocity {
+                x: 1.0,
+                ..Default::default()
+            },
+        );
+        world.insert(still, Position::default());
+        world.insert(other, Velocity::default());
+
+        let hits: Vec<_> = world.query::<(Position, Velocity)>().collect();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, moving);
+        assert_eq!(hits[0].1 .1.x, 1.0);
+
+        world.remove::<Velocity>(moving);
+        assert_eq!(world.query::<(Position, Velocity)>().count(), 0);
+        assert_eq!(world.query::<(Position, Velocity, u32)>().count(), 0);
+    }
+
+    #[test]
+    fn storage_remove_keeps_sparse_indices_consistent() {
+        let mut storage = ComponentStorage::new();
+        let ids: Vec<_> = (0..4).map(|i| EntityId::new(i, 0)).collect();
+        for (i, id) in ids.iter().enumerate() {
+            assert!(storage.insert(*id, i).is_none());
+        }
+        assert_eq!(storage.insert(ids[2], 20), Some(2));
+
+        assert_eq!(storage.remove(ids[0]), Some(0));
+        assert_eq!(storage.remove(ids[0]), None);
+        assert_eq!(storage.len(), 3);
+        assert_eq!(storage.get(ids[3]), Some(&3));
+        assert_eq!(storage.get(ids[2]), Some(&20));
+        *storage.get_mut(ids[1]).unwrap() += 10;
+        assert_eq!(storage.get(ids[1]), Some(&11));
+
+        // A newer generation of slot 3 doesn't see the old component.
+        assert!(!storage.contains(EntityId::new(3, 1)));
+    }
+
+    #[test]
+    fn schedule_runs_systems_in_registration_order() {
+        let mut world = World::default();
+        let e = world.spawn();
+        world.insert(e, Vec::<&str>::new());
+
+        let mut schedule = Schedule::new();
+        for name in ["input", "physics", "triggers"] {
+            schedule.add_system(name, move |w, _| {
+                w.get_mut::<Vec<&str>>(e).unwrap().push(name);
+            });
+        }
+        schedule.run(&mut world, 0.0);
+        schedule.run(&mut world, 0.0);
+
+        assert_eq!(
+            schedule.names().collect::<Vec<_>>(),
+            ["input", "physics", "triggers"]
+        );
+        assert_eq!(
+            world.get::<Vec<&str>>(e).unwrap(),
+            &["input", "physics", "triggers", "input", "physics", "triggers"]
+        );
+    }
+
+    #[test]
+    fn later_system_sees_earlier_writes_in_same_tick() {
+        let mut world = World::default();
+        let e = world.spawn();
+        world.insert(e, Position::default());
+        world.insert(
+            e,
+            Velocity {
+                x: 2.0,
+                ..Default::default()
+            },
+        );
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system("movement", integrate_velocity)
+            .add_system("record", |w, _| {
+                let ids: Vec<_> = w.entities().collect();
+                for id in ids {
+                    let x = w.get::<Position>(id).map(|p| p.x);
+                    if let Some(x) = x {
+                        w.insert(id, x.to_bits());
+                    }
+                }
+            });
+        schedule.run(&mut world, 0.5);
+
+        assert_eq!(world.get::<Position>(e).unwrap().x, 1.0);
+        assert_eq!(world.get::<u32>(e), Some(&1.0f32.to_bits()));
+    }
+
+    #[test]
+    fn snapshot_round_trips_networked_components() {
+        let mut server = World::default();
+        let player = server.spawn();
+        server.insert(
+            player,
+            Position {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            },
+        );
+        server.insert(
+            player,
+            Angles {
+                yaw: 90.0,
+                ..Default::default()
+            },
+        );
+        server.insert(player, Health(75));
+        server.insert(
+            player,
+            Velocity {
+                x: 9.0,
+                ..Default::default()
+            },
+        );
+        let prop = server.spawn();
+        server.insert(prop, Position::default());
+        // No position: not replicated.
+        let logic = server.spawn();
+        server.insert(logic, Health(1));
+
+        let snap = server.to_snapshot(7);
+        assert_eq!(snap.tick, 7);
+        assert_eq!(snap.entities.len(), 2);
+
+        let mut client = World::default();
+        client.apply_snapshot(&snap);
+        assert_eq!(client.entity_count(), 2);
+        assert!(client.is_alive(player));
+        assert_eq!(client.get::<Position>(player).unwrap().y, 2.0);
+        assert_eq!(client.get::<Angles>(player).unwrap().yaw, 90.0);
+        assert_eq!(client.get::<Health>(player), Some(&Health(75)));
+        assert!(client.get::<Velocity>(player).is_none());
+        assert!(client.get::<Health>(prop).is_none());
+        assert!(!client.is_alive(logic));
+    }
+
+    #[test]
+    fn apply_snapshot_despawns_missing_and_tracks_recycled_ids() {
+        let mut server = World::default();
+        let a = server.spawn();
+        let b = server.spawn();
+        server.insert(a, Position::default());
+        server.insert(b, Position::default());
+
+        let mut client = World::default();
+        client.apply_snapshot(&server.to_snapshot(1));
+        assert_eq!(client.entity_count(), 2);
+
+        server.despawn(a);
+        let c = server.spawn();
+        server.insert(
+            c,
+            Position {
+                x: 4.0,
+                ..Default::default()
+            },
+        );
+        client.apply_snapshot(&server.to_snapshot(2));
+
+        assert!(!client.is_alive(a));
+        assert!(client.is_alive(b));
+        assert!(client.is_alive(c));
+        assert_eq!(client.get::<Position>(c).unwrap().x, 4.0);
+        assert_eq!(client.entity_count(), 2);
+
+        client.apply_snapshot(&Snapshot {
+            tick: 3,
+            entities: Vec::new(),
+            ack: None,
+        });
+        assert_eq!(client.entity_count(), 0);
+    }
+
+    #[test]
+    fn apply_snapshot_skips_out_of_range_indices() {
+        let state = |id| EntityState {
+            id,
+            position: Vec3::ZERO,
+            angles: Vec3::ZERO,
+            health: None,
+        };
+        let far = EntityId::new(u32::MAX, 0);
+        let edge = EntityId::new(MAX_MIRRORED_INDEX, 0);
+
+        let mut client = World::default();
+        client.apply_snapshot(&Snapshot {
+            tick: 1,
+            entities: vec![state(far), state(edge)],
+            ack: None,
+        });
+
+        assert!(!client.is_alive(far));
+        assert!(client.is_alive(edge));
+        assert_eq!(client.entity_count(), 1);
+    }
+
+    fn vec_approx(a: Vec3, b: Vec3) -> bool {
+        (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4 && (a.z - b.z).abs() < 1e-4
+    }
+
+    /// A player yawed 90 degrees holding a weapon, with a scope on the weapon.
+    fn player_weapon_scope(world: &mut World) -> (EntityId, EntityId, EntityId) {
+        let player = world.spawn();
+        world.insert(
+            player,
+            Transform {
+                translation: Vec3::new(100.0, 0.0, 0.0),
+                rotation: Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 90.0),
+                scale: 1.0,
+            },
+        );
+        let weapon = world.spawn();
+        world.insert(weapon, Parent(player));
+        world.insert(
+            weapon,
+            Transform {
+                translation: Vec3::new(10.0, 0.0, 50.0),
+                rotation: Quat::IDENTITY,
+                scale: 2.0,
+            },
+        );
+        let scope = world.spawn();
+        world.insert(scope, Parent(weapon));
+        world.insert(scope, Transform::from_translation(Vec3::new(5.0, 0.0, 1.0)));
+        (player, weapon, scope)
+    }
+
+    #[test]
+    fn world_transform_composes_two_levels() {
+        let mut world = World::default();
+        let (player, weapon, scope) = player_weapon_scope(&mut world);
+
+        assert_eq!(
+            world.world_transform(player).unwrap().translation,
+            Vec3::new(100.0, 0.0, 0.0)
+        );
+        // The weapon's +x offset turns to +y with the player.
+        let weapon_world = world.world_transform(weapon).unwrap();
+        assert!(vec_approx(
+            weapon_world.translation,
+            Vec3::new(100.0, 10.0, 50.0)
+        ));
+        assert_eq!(weapon_world.scale, 2.0);
+
+        // The scope's offset is scaled by the weapon and rotated by the player.
+        let scope_world = world.world_transform(scope).unwrap();
+        assert!(vec_approx(
+            scope_world.translation,
+            Vec3::new(100.0, 20.0, 52.0)
+        ));
+        assert!(vec_approx(
+            scope_world.rotation.rotate(Vec3::new(1.0, 0.0, 0.0)),
+            Vec3::new(0.0, 1.0, 0.0)
+        ));
+
+        // Moving the parent moves the children.
+        world.get_mut::<Transform>(player).unwrap().translation = Vec3::ZERO;
+        assert!(vec_approx(
+            world.world_transform(scope).unwrap().translation,
+            Vec3::new(0.0, 20.0, 52.0)
+        ));
+    }
+
+    #[test]
+    fn world_transform_detects_cycles() {
+        let mut world = World::default();
+        let a = world.spawn();
+        let b = world.spawn();
+        let c = world.spawn();
+        world.insert(a, Parent(b));
+        world.insert(b, Parent(c));
+        world.insert(c, Parent(a));
+        assert_eq!(world.world_transform(a), Err(HierarchyError::Cycle(a)));
+        assert_eq!(world.world_transform(b), Err(HierarchyError::Cycle(b)));
+
+        let selfish = world.spawn();
+        world.insert(selfish, Parent(selfish));
+        assert_eq!(
+            world.world_transform(selfish),
+            Err(HierarchyError::Cycle(selfish))
+        );
+
+        let dead = world.spawn();
+        world.despawn(dead);
+        assert_eq!(
+            world.world_transform(dead),
+            Err(HierarchyError::NotAlive(dead))
+        );
+    }
+
+    #[test]
+    fn despawn_parent_despawns_children_by_default() {
+        let mut world = World::default();
+        let (player, weapon, scope) = player_weapon_scope(&mut world);
+        assert_eq!(world.children(player), vec![weapon]);
+
+        world.despawn(player);
+        assert!(!world.is_alive(weapon));
+        assert!(!world.is_alive(scope));
+        assert_eq!(world.entity_count(), 0);
+
+        // A cycle doesn't recurse forever.
+        let a = world.spawn();
+        let b = world.spawn();
+        world.insert(a, Parent(b));
+        world.insert(b, Parent(a));
+        assert!(world.despawn(a));
+        assert_eq!(world.entity_count(), 0);
+    }
+
+    #[test]
+    fn despawn_deep_hierarchy_frees_every_level() {
+        let mut world = World::default();
+        let root = world.spawn();
+        let mut parent = root;
+        for _ in 0..2000 {
+            let child = world.spawn();
+            world.insert(child, Parent(parent));
+            parent = child;
+        }
+        let unrelated = world.spawn();
+
+        assert!(world.despawn(root));
+        assert_eq!(world.entity_count(), 1);
+        assert!(world.is_alive(unrelated));
+        assert!(world.storage::<Parent>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn despawn_parent_can_reparent_children() {
+        let mut world = World::default();
+        world.set_child_policy(ChildPolicy::Reparent);
+        let (player, weapon, scope) = player_weapon_scope(&mut world);
+        let scope_before = world.world_transform(scope).unwrap();
+
+        // The scope moves up to the player, staying where it was.
+        world.despawn(weapon);
+        assert_eq!(world.get::<Parent>(scope), Some(&Parent(player)));
+        let scope_after = world.world_transform(scope).unwrap();
+        assert!(vec_approx(
+            scope_after.translation,
+            scope_before.translation
+        ));
+        assert_eq!(scope_after.scale, scope_before.scale);
+
+        // With no grandparent it becomes a root.
+        world.despawn(player);
+        assert!(world.is_alive(scope));
+        assert_eq!(world.get::<Parent>(scope), None);
+        assert!(vec_approx(
+            world.world_transform(scope).unwrap().translation,
+            scope_before.translation
+        ));
+    }
 }
@@ -3,16 +3,45 @@
 //! This is a small typed event bus.
 //! - Client: use for input/events, prediction reconciliation, UI.
 //! - Server: use for gameplay events, networking notifications.
+//!
+//! Events can be pulled (`push`/`drain`) or delivered to subscribers,
+//! either immediately (`publish`) or at the next tick boundary
+//! (`deferred_publish` + `dispatch_pending`).
 
 use std::{
     any::{Any, TypeId},
     collections::HashMap,
 };
 
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gsi::{GsiEvent, PlayerTeam, RoundPhase},
+    steam_id::SteamId,
+};
+
+/// Marker for types that can travel on an [`EventBus`].
+///
+/// Implemented for every `'static + Send + Sync` type.
+pub trait Event: Any + Send + Sync {}
+
+impl<T: Any + Send + Sync> Event for T {}
+
+/// Handle returned by [`EventBus::subscribe`], used to unsubscribe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type ErasedHandler = Box<dyn Fn(&dyn Any) + Send + Sync>;
+type ErasedEvent = Box<dyn Any + Send + Sync>;
+
 /// Typed event bus.
 #[derive(Default)]
 pub struct EventBus {
     queues: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    subscribers: HashMap<TypeId, Vec<(SubscriptionId, ErasedHandler)>>,
+    next_subscription: u64,
+    /// Deferred events of every type, in the order they were deferred.
+    pending: Vec<(TypeId, ErasedEvent)>,
 }
 
 impl EventBus {
@@ -34,4 +63,297 @@ impl EventBus {
             .map(|boxed| *boxed)
             .unwrap_or_default()
     }
+
+    /// Registers a handler for events of type `E`.
+    pub fn subscribe<E: Event>(
+        &mut self,
+        handler: impl Fn(&E) + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_subscription);
+        self.next_subscription += 1;
+
+        let erased: ErasedHandler = Box::new(move |event| {
+            if let Some(event) = event.downcast_ref::<E>() {
+                handler(event);
+            }
+        });
+        self.subscribers
+            .entry(TypeId::of::<E>())
+            .or_default()
+            .push((id, erased));
+        id
+    }
+
+    /// Removes a handler. Returns false if it was already gone.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        for handlers in self.subscribers.values_mut() {
+            if let Some(pos) = handlers.iter().position(|(h, _)| *h == id) {
+                drop(handlers.remove(pos));
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Delivers an event to every handler subscribed to its type, in
+    /// subscription order.
+    pub fn publish<E: Event>(&self, e: E) {
+        if let Some(handlers) = self.subscribers.get(&TypeId::of::<E>()) {
+            for (_, handler) in handlers {
+                handler(&e);
+            }
+        }
+    }
+
+    /// Queues an event for the next [`dispatch_pending`](Self::dispatch_pending).
+    pub fn deferred_publish<E: Event>(&mut self, e: E) {
+        self.pending.push((TypeId::of::<E>(), Box::new(e)));
+    }
+
+    /// Delivers all deferred events to subscribers. Call once per tick.
+    ///
+    /// Events are dispatched in the order they were deferred, whatever
+    /// their type, so delivery doesn't depend on hashing or on which system
+    /// ran first within the tick. Returns the number of events dispatched.
+    pub fn dispatch_pending(&mut self) -> usize {
+        let pending = std::mem::take(&mut self.pending);
+        for (type_id, event) in &pending {
+            let Some(handlers) = self.subscribers.get(type_id) else {
+                continue;
+            };
+            let event: &dyn Any = &**event;
+            for (_, handler) in handlers {
+                handler(event);
+            }
+        }
+        pending.len()
+    }
+
+    /// Number of deferred events waiting for dispatch.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Number of handlers subscribed to `E`.
+    pub fn subscriber_count<E: Event>(&self) -> usize {
+        self.subscribers.get(&TypeId::of::<E>()).map_or(0, Vec::len)
+    }
+}
+
+/// Networked gameplay events, after Source's `gameevents.res`.
+///
+/// Serialized with the Source event name in a `name` field, e.g.
+/// `{"name":"player_death","victim":...}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "snake_case")]
+pub enum GameEvent {
+    PlayerDeath {
+        victim: SteamId,
+        /// `None` for world damage and suicides.
+        attacker: Option<SteamId>,
+        weapon: String,
+        headshot: bool,
+    },
+    RoundStart,
+    RoundEnd {
+        winner: PlayerTeam,
+    },
+    BombPlanted,
+}
+
+impl GameEvent {
+    /// The GSI events a receiver watching `observed` would see for this
+    /// event.
+    ///
+    /// Deaths only produce [`GsiEvent::PlayerDied`] for the observed player,
+    /// matching GSI's single-player `player` block.
+    pub fn to_gsi_events(&self, observed: Option<SteamId>) -> Vec<GsiEvent> {
+        match self {
+            GameEvent::PlayerDeath { victim, .. } if Some(*victim) == observed => {
+                vec![GsiEvent::PlayerDied]
+            }
+            GameEvent::PlayerDeath { .. } => Vec::new(),
+            GameEvent::RoundStart => vec![GsiEvent::RoundPhaseChanged {
+                old: RoundPhase::Over,
+                new: RoundPhase::Freezetime,
+            }],
+            GameEvent::RoundEnd { winner } => vec![
+                GsiEvent::RoundPhaseChanged {
+                    old: RoundPhase::Live,
+                    new: RoundPhase::Over,
+                },
+                GsiEvent::RoundWon { team: *winner },
+            ],
+            GameEvent::BombPlanted => vec![GsiEvent::BombPlanted],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct ChatMessage(String);
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct PlayerLoggedIn(u64);
+
+    #[test]
+    fn event_reaches_only_subscribers_of_its_type() {
+        let mut bus = EventBus::default();
+        let chat = Arc::new(Mutex::new(Vec::new()));
+        let logins = Arc::new(Mutex::new(Vec::new()));
+
+        let c = Arc::clone(&chat);
+        bus.subscribe(move |e: &ChatMessage| c.lock().unwrap().push(e.0.clone()));
+        let c = Arc::clone(&chat);
+        bus.subscribe(move |e: &ChatMessage| c.lock().unwrap().push(e.0.to_uppercase()));
+        let l = Arc::clone(&logins);
+        bus.subscribe(move |e: &PlayerLoggedIn| l.lock().unwrap().push(e.0));
+
+        bus.publish(ChatMessage("gg".into()));
+        assert_eq!(*chat.lock().unwrap(), vec!["gg", "GG"]);
+        assert!(logins.lock().unwrap().is_empty());
+
+        bus.publish(PlayerLoggedIn(7));
+        assert_eq!(*logins.lock().unwrap(), vec![7]);
+        assert_eq!(chat.lock().unwrap().len(), 2);
+
+        // Events with no subscribers are dropped.
+        bus.publish(42u32);
+    }
+
+    #[test]
+    fn unsubscribed_handlers_stop_receiving() {
+        let mut bus = EventBus::default();
+        let count = Arc::new(Mutex::new(0));
+        let c = Arc::clone(&count);
+        let id = bus.subscribe(move |_: &PlayerLoggedIn| *c.lock().unwrap() += 1);
+        assert_eq!(bus.subscriber_count::<PlayerLoggedIn>(), 1);
+
+        bus.publish(PlayerLoggedIn(1));
+        assert!(bus.unsubscribe(id));
+        assert!(!bus.unsubscribe(id));
+        bus.publish(PlayerLoggedIn(2));
+
+        assert_eq!(*count.lock().unwrap(), 1);
+        assert_eq!(bus.subscriber_count::<PlayerLoggedIn>(), 0);
+    }
+
+    #[test]
+    fn push_and_drain_still_queue_by_type() {
+        let mut bus = EventBus::default();
+        bus.push(ChatMessage("a".into()));
+        bus.push(PlayerLoggedIn(1));
+        bus.push(ChatMessage("b".into()));
+
+        assert_eq!(
+            bus.drain::<ChatMessage>(),
+            vec![ChatMessage("a".into()), ChatMessage("b".into())]
+        );
+        assert!(bus.drain::<ChatMessage>().is_empty());
+        assert_eq!(bus.drain::<PlayerLoggedIn>(), vec![PlayerLoggedIn(1)]);
+    }
+
+    #[test]
+    fn deferred_events_wait_for_dispatch_and_keep_order() {
+        let mut bus = EventBus::default();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let l = Arc::clone(&log);
+        bus.subscribe(move |e: &ChatMessage| l.lock().unwrap().push(e.0.clone()));
+        let l = Arc::clone(&log);
+        bus.subscribe(move |e: &PlayerLoggedIn| l.lock().unwrap().push(format!("login {}", e.0)));
+
+        // Mid-tick: nothing is delivered yet.
+        bus.deferred_publish(PlayerLoggedIn(1));
+        bus.deferred_publish(ChatMessage("a".into()));
+        bus.deferred_publish(PlayerLoggedIn(2));
+        bus.deferred_publish(ChatMessage("b".into()));
+        bus.deferred_publish(ChatMessage("c".into()));
+        assert!(log.lock().unwrap().is_empty());
+        assert_eq!(bus.pending_count(), 5);
+
+        assert_eq!(bus.dispatch_pending(), 5);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["login 1", "a", "login 2", "b", "c"]
+        );
+        assert_eq!(bus.pending_count(), 0);
+        assert_eq!(bus.dispatch_pending(), 0);
+    }
+
+    #[test]
+    fn dispatch_interleaves_types_in_deferral_order() {
+        let mut bus = EventBus::default();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let l = Arc::clone(&log);
+        bus.subscribe(move |e: &u32| l.lock().unwrap().push(u64::from(*e)));
+        let l = Arc::clone(&log);
+        bus.subscribe(move |e: &u64| l.lock().unwrap().push(*e));
+
+        for i in 0..7u32 {
+            if i % 3 == 0 {
+                bus.deferred_publish(u64::from(i) + 1000);
+            } else {
+                bus.deferred_publish(i);
+            }
+        }
+        bus.dispatch_pending();
+
+        assert_eq!(*log.lock().unwrap(), vec![1000, 1, 2, 1003, 4, 5, 1006]);
+    }
+
+    fn death() -> GameEvent {
+        GameEvent::PlayerDeath {
+            victim: SteamId::from_account_id(100),
+            attacker: Some(SteamId::from_account_id(200)),
+            weapon: "ak47".into(),
+            headshot: true,
+        }
+    }
+
+    #[test]
+    fn player_death_roundtrips_over_net() {
+        use crate::net::{decode_from_bytes, encode_to_bytes, NetMsg};
+
+        let msg = NetMsg::GameEvent(death());
+        let bytes = encode_to_bytes(&msg).unwrap();
+        assert_eq!(decode_from_bytes(&bytes).unwrap(), msg);
+
+        let json = serde_json::to_value(death()).unwrap();
+        assert_eq!(json["name"], "player_death");
+        assert_eq!(json["headshot"], true);
+    }
+
+    #[test]
+    fn game_events_map_to_gsi_events() {
+        let observed = Some(SteamId::from_account_id(100));
+        assert_eq!(death().to_gsi_events(observed), vec![GsiEvent::PlayerDied]);
+        assert!(death()
+            .to_gsi_events(Some(SteamId::from_account_id(200)))
+            .is_empty());
+        assert!(death().to_gsi_events(None).is_empty());
+
+        assert_eq!(
+            GameEvent::RoundEnd {
+                winner: PlayerTeam::CT
+            }
+            .to_gsi_events(None),
+            vec![
+                GsiEvent::RoundPhaseChanged {
+                    old: RoundPhase::Live,
+                    new: RoundPhase::Over
+                },
+                GsiEvent::RoundWon {
+                    team: PlayerTeam::CT
+                }
+            ]
+        );
+        assert_eq!(
+            GameEvent::BombPlanted.to_gsi_events(None),
+            vec![GsiEvent::BombPlanted]
+        );
+    }
 }
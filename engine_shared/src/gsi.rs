@@ -13,13 +13,16 @@
 //! - **provider**: Game identity (name, appid, version, steamid)
 //! - **map**: Current map state (name, phase, round, scores)
 //! - **player**: Local player data (team, state, weapons)
+//! - **allplayers**: Every player keyed by SteamID64; only sent to spectators
 //! - **round**: Round timing and phase
 //! - **previously**: Changed values from last update
 //! - **added**: New values since last update
 
 use std::collections::HashMap;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -128,9 +131,10 @@ impl GsiMap {
 }
 
 /// Player activity states.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum PlayerActivity {
+    #[default]
     Playing,
     Menu,
     TextInput,
@@ -181,7 +185,8 @@ pub struct PlayerWeapon {
 /// Player information in GSI payload.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct GsiPlayer {
-    /// Player's Steam ID.
+    /// Player's Steam ID. Absent inside `allplayers`, where it's the key.
+    #[serde(default)]
     pub steamid: String,
     /// Clan tag.
     #[serde(default)]
@@ -193,7 +198,8 @@ pub struct GsiPlayer {
     pub observer_slot: u32,
     /// Team.
     pub team: PlayerTeam,
-    /// Current activity.
+    /// Current activity. Absent inside `allplayers`.
+    #[serde(default)]
     pub activity: PlayerActivity,
     /// Player state (health, armor, etc.).
     pub state: PlayerState,
@@ -265,6 +271,49 @@ pub struct GsiAuth {
     pub token: String,
 }
 
+bitflags::bitflags! {
+    /// Top-level payload blocks.
+    ///
+    /// Used both to choose which blocks a receiver deserializes and to report
+    /// which blocks a payload contained. `provider` and `auth` are always
+    /// parsed.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct GsiSubscription: u32 {
+        const PROVIDER = 1 << 0;
+        const MAP = 1 << 1;
+        const PLAYER = 1 << 2;
+        const ROUND = 1 << 3;
+        const ALLPLAYERS = 1 << 4;
+        const PHASE_COUNTDOWNS = 1 << 5;
+        const PREVIOUSLY = 1 << 6;
+        const ADDED = 1 << 7;
+        const AUTH = 1 << 8;
+    }
+}
+
+impl GsiSubscription {
+    fn from_key(key: &str) -> Self {
+        match key {
+            "provider" => Self::PROVIDER,
+            "map" => Self::MAP,
+            "player" => Self::PLAYER,
+            "round" => Self::ROUND,
+            "allplayers" => Self::ALLPLAYERS,
+            "phase_countdowns" => Self::PHASE_COUNTDOWNS,
+            "previously" => Self::PREVIOUSLY,
+            "added" => Self::ADDED,
+            "auth" => Self::AUTH,
+            _ => Self::empty(),
+        }
+    }
+}
+
+impl Default for GsiSubscription {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
 /// Full GSI payload.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GsiPayload {
@@ -273,6 +322,14 @@ pub struct GsiPayload {
     pub map: Option<GsiMap>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub player: Option<GsiPlayer>,
+    /// Every player, for spectators (observers, casters) only.
+    #[serde(
+        rename = "allplayers",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "all_players"
+    )]
+    pub all_players: Option<HashMap<SteamId, GsiPlayer>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub round: Option<GsiRound>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -291,6 +348,7 @@ impl GsiPayload {
             provider,
             map: None,
             player: None,
+            all_players: None,
             round: None,
             phase_countdowns: None,
             previously: None,
@@ -299,6 +357,23 @@ impl GsiPayload {
         }
     }
 
+    /// Look up a player by Steam ID.
+    ///
+    /// Checks `allplayers` first, which only spectators receive; otherwise
+    /// only the local `player` can match.
+    pub fn player_by_id(&self, steam_id: SteamId) -> Option<&GsiPlayer> {
+        if let Some(player) = self
+            .all_players
+            .as_ref()
+            .and_then(|players| players.get(&steam_id))
+        {
+            return Some(player);
+        }
+        self.player
+            .as_ref()
+            .filter(|p| p.steamid.parse::<SteamId>().ok() == Some(steam_id))
+    }
+
     /// Set auth token.
     pub fn with_auth(mut self, token: &str) -> Self {
         self.auth = Some(GsiAuth {
@@ -321,6 +396,145 @@ impl GsiPayload {
     pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
         serde_json::from_str(json)
     }
+
+    /// Parse from JSON, deserializing only the subscribed blocks.
+    ///
+    /// Returns the payload along with the blocks present in the JSON,
+    /// whether or not they were subscribed.
+    pub fn from_json_subscribed(
+        json: &str,
+        subscription: GsiSubscription,
+    ) -> Result<(Self, GsiSubscription), serde_json::Error> {
+        let mut de = serde_json::Deserializer::from_str(json);
+        let parsed = SubscribedPayload(subscription).deserialize(&mut de)?;
+        de.end()?;
+        Ok(parsed)
+    }
+}
+
+/// The `allplayers` block: an object keyed by SteamID64 strings.
+///
+/// Entries don't repeat their ID, so `steamid` is filled in from the key.
+struct AllPlayers(HashMap<SteamId, GsiPlayer>);
+
+impl<'de> Deserialize<'de> for AllPlayers {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = HashMap::<String, GsiPlayer>::deserialize(deserializer)?;
+        let mut players = HashMap::with_capacity(raw.len());
+        for (key, mut player) in raw {
+            let steam_id: SteamId = key
+                .parse()
+                .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&key), &"a SteamID64"))?;
+            if player.steamid.is_empty() {
+                player.steamid = steam_id.to_string();
+            }
+            players.insert(steam_id, player);
+        }
+        Ok(AllPlayers(players))
+    }
+}
+
+mod all_players {
+    use super::*;
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(
+        players: &Option<HashMap<SteamId, GsiPlayer>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let keyed: Option<HashMap<String, &GsiPlayer>> = players.as_ref().map(|players| {
+            players
+                .iter()
+                .map(|(id, player)| (id.to_string(), player))
+                .collect()
+        });
+        keyed.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<HashMap<SteamId, GsiPlayer>>, D::Error> {
+        Ok(Option::<AllPlayers>::deserialize(deserializer)?.map(|p| p.0))
+    }
+}
+
+/// Deserializes a payload, skipping unsubscribed blocks with [`IgnoredAny`].
+struct SubscribedPayload(GsiSubscription);
+
+/// Optional payload blocks collected while visiting the JSON object.
+#[derive(Default)]
+struct PartialPayload {
+    map: Option<GsiMap>,
+    player: Option<GsiPlayer>,
+    all_players: Option<AllPlayers>,
+    round: Option<GsiRound>,
+    phase_countdowns: Option<GsiPhaseCountdowns>,
+    previously: Option<Value>,
+    added: Option<Value>,
+    auth: Option<GsiAuth>,
+}
+
+impl<'de> DeserializeSeed<'de> for SubscribedPayload {
+    type Value = (GsiPayload, GsiSubscription);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for SubscribedPayload {
+    type Value = (GsiPayload, GsiSubscription);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a GSI payload object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let wants = |block| self.0.contains(block);
+        let mut present = GsiSubscription::empty();
+        let mut provider = None;
+        let mut payload = PartialPayload::default();
+
+        while let Some(key) = access.next_key::<String>()? {
+            let block = GsiSubscription::from_key(&key);
+            present |= block;
+            if block == GsiSubscription::PROVIDER {
+                provider = Some(access.next_value()?);
+            } else if block == GsiSubscription::AUTH {
+                payload.auth = access.next_value()?;
+            } else if block == GsiSubscription::MAP && wants(block) {
+                payload.map = access.next_value()?;
+            } else if block == GsiSubscription::PLAYER && wants(block) {
+                payload.player = access.next_value()?;
+            } else if block == GsiSubscription::ALLPLAYERS && wants(block) {
+                payload.all_players = access.next_value()?;
+            } else if block == GsiSubscription::ROUND && wants(block) {
+                payload.round = access.next_value()?;
+            } else if block == GsiSubscription::PHASE_COUNTDOWNS && wants(block) {
+                payload.phase_countdowns = access.next_value()?;
+            } else if block == GsiSubscription::PREVIOUSLY && wants(block) {
+                payload.previously = access.next_value()?;
+            } else if block == GsiSubscription::ADDED && wants(block) {
+                payload.added = access.next_value()?;
+            } else {
+                access.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        let provider = provider.ok_or_else(|| de::Error::missing_field("provider"))?;
+        let payload = GsiPayload {
+            provider,
+            map: payload.map,
+            player: payload.player,
+            all_players: payload.all_players.map(|p| p.0),
+            round: payload.round,
+            phase_countdowns: payload.phase_countdowns,
+            previously: payload.previously,
+            added: payload.added,
+            auth: payload.auth,
+        };
+        Ok((payload, present))
+    }
 }
 
 /// GSI configuration parsed from gamestate_integration_*.cfg files.
@@ -355,6 +569,27 @@ pub struct GsiDataSubscriptions {
     pub bomb: bool,
 }
 
+impl GsiConfig {
+    /// Whether a provider should POST now, given whether the state changed
+    /// and how long ago it last sent.
+    ///
+    /// Changes are rate-limited by `throttle`; an unchanged state is still
+    /// re-sent every `heartbeat`.
+    pub fn should_send(&self, changed: bool, since_last_send: Duration) -> bool {
+        if changed {
+            since_last_send >= self.throttle
+        } else {
+            since_last_send >= self.heartbeat
+        }
+    }
+
+    /// How long a receiver may go without payloads before the provider
+    /// should be considered gone.
+    pub fn stale_after(&self) -> Duration {
+        self.heartbeat + self.timeout
+    }
+}
+
 impl Default for GsiConfig {
     fn default() -> Self {
         GsiConfig {
@@ -369,11 +604,96 @@ impl Default for GsiConfig {
     }
 }
 
+/// Change detected between two consecutive payloads.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GsiEvent {
+    /// First payload received; there is nothing to diff against.
+    Baseline,
+    MapPhaseChanged {
+        old: MapPhase,
+        new: MapPhase,
+    },
+    RoundPhaseChanged {
+        old: RoundPhase,
+        new: RoundPhase,
+    },
+    RoundWon {
+        team: PlayerTeam,
+    },
+    BombPlanted,
+    BombDefused,
+    BombExploded,
+    PlayerHealthChanged {
+        old: u32,
+        new: u32,
+    },
+    PlayerDied,
+}
+
+/// Compute the events implied by moving from `prev` to `next`.
+///
+/// Player events are only emitted while the same player is reported, so
+/// switching spectator targets doesn't look like damage.
+pub fn diff_payloads(prev: Option<&GsiPayload>, next: &GsiPayload) -> Vec<GsiEvent> {
+    let Some(prev) = prev else {
+        return vec![GsiEvent::Baseline];
+    };
+    let mut events = Vec::new();
+
+    if let (Some(old), Some(new)) = (&prev.map, &next.map) {
+        if old.phase != new.phase {
+            events.push(GsiEvent::MapPhaseChanged {
+                old: old.phase,
+                new: new.phase,
+            });
+        }
+    }
+
+    if let (Some(old), Some(new)) = (&prev.round, &next.round) {
+        if old.phase != new.phase {
+            events.push(GsiEvent::RoundPhaseChanged {
+                old: old.phase,
+                new: new.phase,
+            });
+        }
+        if let Some(team) = new.win_team {
+            if old.win_team != Some(team) {
+                events.push(GsiEvent::RoundWon { team });
+            }
+        }
+        if old.bomb != new.bomb {
+            match new.bomb.as_deref() {
+                Some("planted") => events.push(GsiEvent::BombPlanted),
+                Some("defused") => events.push(GsiEvent::BombDefused),
+                Some("exploded") => events.push(GsiEvent::BombExploded),
+                _ => {}
+            }
+        }
+    }
+
+    if let (Some(old), Some(new)) = (&prev.player, &next.player) {
+        if old.steamid == new.steamid && old.state.health != new.state.health {
+            events.push(GsiEvent::PlayerHealthChanged {
+                old: old.state.health,
+                new: new.state.health,
+            });
+            if new.state.health == 0 {
+                events.push(GsiEvent::PlayerDied);
+            }
+        }
+    }
+
+    events
+}
+
 /// GSI receiver for accepting payloads.
 pub struct GsiReceiver {
     expected_token: Option<String>,
     last_payload: Option<GsiPayload>,
     payload_count: u64,
+    last_seen: Option<Instant>,
+    subscription: GsiSubscription,
+    present_blocks: GsiSubscription,
 }
 
 impl GsiReceiver {
@@ -382,13 +702,30 @@ impl GsiReceiver {
             expected_token,
             last_payload: None,
             payload_count: 0,
+            last_seen: None,
+            subscription: GsiSubscription::all(),
+            present_blocks: GsiSubscription::empty(),
         }
     }
 
+    /// Only deserialize the given blocks of incoming payloads.
+    pub fn with_subscription(mut self, subscription: GsiSubscription) -> Self {
+        self.subscription = subscription;
+        self
+    }
+
     /// Process a received payload.
     pub fn process(&mut self, json: &str) -> Result<&GsiPayload, GsiError> {
-        let payload: GsiPayload =
-            serde_json::from_str(json).map_err(|e| GsiError::ParseError(e.to_string()))?;
+        self.process_at(json, Instant::now())
+    }
+
+    /// Process a payload received at `now`.
+    ///
+    /// Any accepted payload refreshes [`last_seen`](Self::last_seen), including
+    /// heartbeats whose state is unchanged.
+    pub fn process_at(&mut self, json: &str, now: Instant) -> Result<&GsiPayload, GsiError> {
+        let (payload, present) = GsiPayload::from_json_subscribed(json, self.subscription)
+            .map_err(|e| GsiError::ParseError(e.to_string()))?;
 
         // Validate auth token if required
         if let Some(ref expected) = self.expected_token {
@@ -400,10 +737,25 @@ impl GsiReceiver {
         }
 
         self.payload_count += 1;
+        self.last_seen = Some(now);
+        self.present_blocks = present;
         self.last_payload = Some(payload);
         Ok(self.last_payload.as_ref().unwrap())
     }
 
+    /// Process a received payload and return what changed since the
+    /// previous one.
+    pub fn process_events(&mut self, json: &str) -> Result<Vec<GsiEvent>, GsiError> {
+        let prev = self.last_payload.take();
+        match self.process(json) {
+            Ok(next) => Ok(diff_payloads(prev.as_ref(), next)),
+            Err(e) => {
+                self.last_payload = prev;
+                Err(e)
+            }
+        }
+    }
+
     /// Get the last received payload.
     pub fn last_payload(&self) -> Option<&GsiPayload> {
         self.last_payload.as_ref()
@@ -413,6 +765,30 @@ impl GsiReceiver {
     pub fn payload_count(&self) -> u64 {
         self.payload_count
     }
+
+    /// Blocks present in the last accepted payload, including unsubscribed
+    /// ones that were skipped.
+    pub fn present_blocks(&self) -> GsiSubscription {
+        self.present_blocks
+    }
+
+    /// When the last accepted payload arrived.
+    pub fn last_seen(&self) -> Option<Instant> {
+        self.last_seen
+    }
+
+    /// Whether the provider has been silent for longer than `timeout`
+    /// (e.g. the game was closed). A receiver that never got a payload is
+    /// stale.
+    pub fn is_stale(&self, timeout: Duration) -> bool {
+        self.is_stale_at(Instant::now(), timeout)
+    }
+
+    /// [`is_stale`](Self::is_stale) evaluated at `now`.
+    pub fn is_stale_at(&self, now: Instant, timeout: Duration) -> bool {
+        self.last_seen
+            .is_none_or(|seen| now.saturating_duration_since(seen) > timeout)
+    }
 }
 
 /// GSI errors.
@@ -622,6 +998,204 @@ mod tests {
         assert!(!json.contains("\"auth\""));
     }
 
+    // =============================================================================
+    // GSI-011: Event Diffing
+    // =============================================================================
+
+    fn live_payload(health: u32) -> GsiPayload {
+        let mut payload = GsiPayload::new(GsiProvider::new("Test", 730, 1, test_steam_id()));
+        let mut map = GsiMap::new("de_dust2", GameMode::Competitive);
+        map.phase = MapPhase::Live;
+        let mut player = GsiPlayer::new(test_steam_id(), "Player1", PlayerTeam::CT);
+        player.state.health = health;
+        payload.map = Some(map);
+        payload.player = Some(player);
+        payload.round = Some(GsiRound {
+            phase: RoundPhase::Live,
+            ..GsiRound::default()
+        });
+        payload
+    }
+
+    #[test]
+    fn gsi_011_first_payload_is_baseline() {
+        let mut receiver = GsiReceiver::new(None);
+        let events = receiver
+            .process_events(&live_payload(100).to_json().unwrap())
+            .unwrap();
+
+        assert_eq!(events, vec![GsiEvent::Baseline]);
+    }
+
+    #[test]
+    fn gsi_011_health_drop_emits_event() {
+        let mut receiver = GsiReceiver::new(None);
+        receiver
+            .process_events(&live_payload(100).to_json().unwrap())
+            .unwrap();
+
+        let events = receiver
+            .process_events(&live_payload(73).to_json().unwrap())
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![GsiEvent::PlayerHealthChanged { old: 100, new: 73 }]
+        );
+
+        let events = receiver
+            .process_events(&live_payload(0).to_json().unwrap())
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                GsiEvent::PlayerHealthChanged { old: 73, new: 0 },
+                GsiEvent::PlayerDied
+            ]
+        );
+    }
+
+    #[test]
+    fn gsi_011_round_and_map_events() {
+        let prev = live_payload(100);
+        let mut next = live_payload(100);
+        next.map.as_mut().unwrap().phase = MapPhase::GameOver;
+        next.round = Some(GsiRound {
+            phase: RoundPhase::Over,
+            bomb: Some("planted".to_string()),
+            win_team: Some(PlayerTeam::T),
+        });
+
+        assert_eq!(
+            diff_payloads(Some(&prev), &next),
+            vec![
+                GsiEvent::MapPhaseChanged {
+                    old: MapPhase::Live,
+                    new: MapPhase::GameOver
+                },
+                GsiEvent::RoundPhaseChanged {
+                    old: RoundPhase::Live,
+                    new: RoundPhase::Over
+                },
+                GsiEvent::RoundWon {
+                    team: PlayerTeam::T
+                },
+                GsiEvent::BombPlanted,
+            ]
+        );
+        assert!(diff_payloads(Some(&next), &next).is_empty());
+    }
+
+    #[test]
+    fn gsi_011_rejected_payload_keeps_previous_state() {
+        let mut receiver = GsiReceiver::new(None);
+        receiver
+            .process_events(&live_payload(100).to_json().unwrap())
+            .unwrap();
+
+        assert!(receiver.process_events("not json").is_err());
+        let events = receiver
+            .process_events(&live_payload(50).to_json().unwrap())
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![GsiEvent::PlayerHealthChanged { old: 100, new: 50 }]
+        );
+    }
+
+    // =============================================================================
+    // GSI-012: Heartbeat / Timeout
+    // =============================================================================
+
+    #[test]
+    fn gsi_012_receiver_goes_stale_and_recovers() {
+        let mut receiver = GsiReceiver::new(None);
+        let timeout = Duration::from_secs(5);
+        let start = Instant::now();
+        let json = live_payload(100).to_json().unwrap();
+
+        assert!(receiver.is_stale_at(start, timeout));
+        assert!(receiver.last_seen().is_none());
+
+        receiver.process_at(&json, start).unwrap();
+        assert_eq!(receiver.last_seen(), Some(start));
+        assert!(!receiver.is_stale_at(start + Duration::from_secs(5), timeout));
+        assert!(receiver.is_stale_at(start + Duration::from_secs(6), timeout));
+
+        // An unchanged heartbeat payload still counts as activity.
+        let later = start + Duration::from_secs(10);
+        receiver.process_at(&json, later).unwrap();
+        assert!(!receiver.is_stale_at(later + Duration::from_secs(1), timeout));
+    }
+
+    #[test]
+    fn gsi_012_rejected_payload_does_not_refresh() {
+        let mut receiver = GsiReceiver::new(Some("token".to_string()));
+        let start = Instant::now();
+
+        assert!(receiver.process_at("{}", start).is_err());
+        assert!(receiver.last_seen().is_none());
+    }
+
+    #[test]
+    fn gsi_012_throttle_and_heartbeat() {
+        let config = GsiConfig::default();
+
+        assert!(!config.should_send(true, Duration::from_millis(50)));
+        assert!(config.should_send(true, Duration::from_millis(100)));
+        assert!(!config.should_send(false, Duration::from_secs(30)));
+        assert!(config.should_send(false, Duration::from_secs(60)));
+        assert_eq!(config.stale_after(), Duration::from_secs(61));
+    }
+
+    // =============================================================================
+    // GSI-013: Component Subscription
+    // =============================================================================
+
+    #[test]
+    fn gsi_013_subscription_skips_round() {
+        let mut receiver = GsiReceiver::new(Some("token".to_string()))
+            .with_subscription(GsiSubscription::MAP | GsiSubscription::PLAYER);
+        let json = live_payload(100).with_auth("token").to_json().unwrap();
+
+        let payload = receiver.process(&json).unwrap();
+        assert!(payload.map.is_some());
+        assert!(payload.player.is_some());
+        assert!(payload.round.is_none());
+        assert_eq!(payload.provider.appid, 730);
+
+        let present = receiver.present_blocks();
+        assert!(present.contains(GsiSubscription::ROUND));
+        assert!(present.contains(GsiSubscription::MAP | GsiSubscription::PLAYER));
+        assert!(!present.contains(GsiSubscription::ALLPLAYERS));
+    }
+
+    #[test]
+    fn gsi_013_ignored_blocks_need_not_be_valid() {
+        let json = r#"{
+            "provider": {"name": "Test", "appid": 730, "version": 1, "steamid": "1", "timestamp": 0},
+            "round": {"phase": "not-a-phase"},
+            "allplayers": {"76561197960265728": {"name": "x"}}
+        }"#;
+
+        assert!(GsiPayload::from_json(json).is_err());
+        let (payload, present) =
+            GsiPayload::from_json_subscribed(json, GsiSubscription::MAP).unwrap();
+        assert!(payload.round.is_none());
+        assert_eq!(
+            present,
+            GsiSubscription::PROVIDER | GsiSubscription::ROUND | GsiSubscription::ALLPLAYERS
+        );
+    }
+
+    #[test]
+    fn gsi_013_full_subscription_matches_derive() {
+        let json = live_payload(42).with_auth("t").to_json().unwrap();
+        let (payload, _) = GsiPayload::from_json_subscribed(&json, GsiSubscription::all()).unwrap();
+
+        assert_eq!(payload, GsiPayload::from_json(&json).unwrap());
+        assert!(GsiPayload::from_json_subscribed("{}", GsiSubscription::all()).is_err());
+    }
+
     // =============================================================================
     // Payload Counter Tests
     // =============================================================================
@@ -662,4 +1236,90 @@ mod tests {
         assert!(config.uri.starts_with("http"));
         assert!(config.timeout.as_secs() > 0);
     }
+
+    // =============================================================================
+    // GSI-014: All Players (Spectators)
+    // =============================================================================
+
+    const ALLPLAYERS_JSON: &str = r#"{
+        "provider": {"name": "Test", "appid": 730, "version": 1, "steamid": "76561197960265728", "timestamp": 0},
+        "player": {
+            "steamid": "76561197960265728", "name": "Caster", "team": "spectator",
+            "activity": "playing",
+            "state": {"health": 0, "armor": 0, "helmet": false, "flashed": 0, "smoked": 0,
+                      "burning": 0, "money": 0, "round_kills": 0, "round_killhs": 0,
+                      "round_totaldmg": 0, "equip_value": 0}
+        },
+        "allplayers": {
+            "76561197960265729": {
+                "name": "Alice", "observer_slot": 1, "team": "CT",
+                "state": {"health": 100, "armor": 100, "helmet": true, "flashed": 0, "smoked": 0,
+                          "burning": 0, "money": 800, "round_kills": 0, "round_killhs": 0,
+                          "round_totaldmg": 0, "equip_value": 1000},
+                "match_stats": {"kills": 3, "deaths": 1}
+            },
+            "76561197960265730": {
+                "name": "Bob", "observer_slot": 6, "team": "T",
+                "state": {"health": 42, "armor": 0, "helmet": false, "flashed": 0, "smoked": 0,
+                          "burning": 0, "money": 2350, "round_kills": 1, "round_killhs": 1,
+                          "round_totaldmg": 100, "equip_value": 2700}
+            }
+        }
+    }"#;
+
+    #[test]
+    fn gsi_014_allplayers_keyed_by_steam_id() {
+        let payload = GsiPayload::from_json(ALLPLAYERS_JSON).unwrap();
+        let players = payload.all_players.as_ref().unwrap();
+        assert_eq!(players.len(), 2);
+
+        let bob = payload
+            .player_by_id(SteamId::from_u64(76561197960265730))
+            .unwrap();
+        assert_eq!(bob.name, "Bob");
+        assert_eq!(bob.team, PlayerTeam::T);
+        assert_eq!(bob.state.health, 42);
+        assert_eq!(bob.observer_slot, 6);
+        // Filled in from the key.
+        assert_eq!(bob.steamid, "76561197960265730");
+
+        // The spectator themself is only in `player`.
+        let caster = payload
+            .player_by_id(SteamId::from_u64(76561197960265728))
+            .unwrap();
+        assert_eq!(caster.team, PlayerTeam::Spectator);
+        assert!(payload.player_by_id(SteamId::from_u64(1)).is_none());
+    }
+
+    #[test]
+    fn gsi_014_allplayers_only_for_spectators() {
+        // A playing client's payload has no `allplayers`; only they can be found.
+        let payload = live_payload(100);
+        assert!(payload.all_players.is_none());
+        assert!(!payload.to_json().unwrap().contains("allplayers"));
+        assert_eq!(
+            payload
+                .player_by_id(test_steam_id())
+                .map(|p| p.name.as_str()),
+            Some("Player1")
+        );
+    }
+
+    #[test]
+    fn gsi_014_allplayers_roundtrip_and_subscription() {
+        let payload = GsiPayload::from_json(ALLPLAYERS_JSON).unwrap();
+        let reparsed = GsiPayload::from_json(&payload.to_json().unwrap()).unwrap();
+        assert_eq!(reparsed, payload);
+
+        let (subscribed, _) =
+            GsiPayload::from_json_subscribed(ALLPLAYERS_JSON, GsiSubscription::all()).unwrap();
+        assert_eq!(subscribed, payload);
+        let (skipped, present) =
+            GsiPayload::from_json_subscribed(ALLPLAYERS_JSON, GsiSubscription::PLAYER).unwrap();
+        assert!(skipped.all_players.is_none());
+        assert!(present.contains(GsiSubscription::ALLPLAYERS));
+
+        let bad = ALLPLAYERS_JSON.replace("76561197960265730", "not-an-id");
+        assert!(GsiPayload::from_json(&bad).is_err());
+    }
 }
@@ -0,0 +1,329 @@
+//! Sampled player input.
+//!
+//! Shared so the client, the server and the movement simulation agree on
+//! what a tick's input looks like.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::math::Vec3;
+
+bitflags::bitflags! {
+    /// Held buttons, with Source's `IN_*` bit layout.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub struct InButtons: u32 {
+        const ATTACK = 1 << 0;
+        const JUMP = 1 << 1;
+        const DUCK = 1 << 2;
+        const FORWARD = 1 << 3;
+        const BACK = 1 << 4;
+        const USE = 1 << 5;
+        const CANCEL = 1 << 6;
+        const LEFT = 1 << 7;
+        const RIGHT = 1 << 8;
+        const MOVELEFT = 1 << 9;
+        const MOVERIGHT = 1 << 10;
+        const ATTACK2 = 1 << 11;
+        const RUN = 1 << 12;
+        const RELOAD = 1 << 13;
+        const SCORE = 1 << 16;
+        const SPEED = 1 << 17;
+        const WALK = 1 << 18;
+    }
+}
+
+/// Key name to button bindings.
+///
+/// Key names are matched case-insensitively. Several keys may map to the
+/// same button, and one key may press several buttons.
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    keys: HashMap<String, InButtons>,
+}
+
+impl Default for Bindings {
+    /// Source's default movement and action keys.
+    fn default() -> Self {
+        let mut b = Self::empty();
+        b.bind("w", InButtons::FORWARD);
+        b.bind("s", InButtons::BACK);
+        b.bind("a", InButtons::MOVELEFT);
+        b.bind("d", InButtons::MOVERIGHT);
+        b.bind("space", InButtons::JUMP);
+        b.bind("ctrl", InButtons::DUCK);
+        b.bind("shift", InButtons::SPEED);
+        b.bind("e", InButtons::USE);
+        b.bind("r", InButtons::RELOAD);
+        b.bind("tab", InButtons::SCORE);
+        b.bind("mouse1", InButtons::ATTACK);
+        b.bind("mouse2", InButtons::ATTACK2);
+        b
+    }
+}
+
+impl Bindings {
+    /// Creates bindings with no keys bound.
+    pub fn empty() -> Self {
+        Self {
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Binds `key` to `buttons`, replacing any previous binding.
+    pub fn bind(&mut self, key: &str, buttons: InButtons) {
+        self.keys.insert(key.to_ascii_lowercase(), buttons);
+    }
+
+    pub fn unbind(&mut self, key: &str) {
+        self.keys.remove(&key.to_ascii_lowercase());
+    }
+
+    /// Buttons bound to `key`; empty if unbound.
+    pub fn get(&self, key: &str) -> InButtons {
+        self.keys
+            .get(&key.to_ascii_lowercase())
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// User input state at a moment in time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputState {
+    pub forward: f32,
+    pub right: f32,
+    pub up: f32,
+    pub buttons: InButtons,
+}
+
+impl InputState {
+    /// Builds input from the currently pressed keys.
+    ///
+    /// Movement axes come from the FORWARD/BACK and MOVERIGHT/MOVELEFT
+    /// buttons; opposing buttons cancel out. Unbound keys are ignored.
+    pub fn from_keys(pressed: &[&str], bindings: &Bindings) -> Self {
+        let buttons = pressed
+            .iter()
+            .fold(InButtons::empty(), |acc, key| acc | bindings.get(key));
+        let axis = |pos: InButtons, neg: InButtons| {
+            f32::from(u8::from(buttons.contains(pos))) - f32::from(u8::from(buttons.contains(neg)))
+        };
+        Self {
+            forward: axis(InButtons::FORWARD, InButtons::BACK),
+            right: axis(InButtons::MOVERIGHT, InButtons::MOVELEFT),
+            up: 0.0,
+            buttons,
+        }
+    }
+
+    pub fn wish_vector(self) -> Vec3 {
+        Vec3::new(self.forward, self.right, self.up)
+    }
+}
+
+/// Commands kept for prediction before the oldest is dropped unacked.
+pub const MULTIPLAYER_BACKUP: usize = 90;
+
+/// One tick of numbered player input, as sent to the server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserCmd {
+    /// Client-assigned, increasing by one per command and starting at 1.
+    pub sequence: u32,
+    pub tick: u32,
+    pub forward: f32,
+    pub right: f32,
+    pub up: f32,
+    pub buttons: u32,
+    /// Pitch and yaw in degrees.
+    pub view_angles: (f32, f32),
+}
+
+/// Numbers outgoing commands and keeps the ones the server hasn't
+/// acknowledged, so the client can replay them after a correction.
+#[derive(Debug, Clone)]
+pub struct CommandBuffer {
+    next_sequence: u32,
+    last_acked: u32,
+    pending: VecDeque<UserCmd>,
+    capacity: usize,
+}
+
+impl Default for CommandBuffer {
+    fn default() -> Self {
+        Self::new(MULTIPLAYER_BACKUP)
+    }
+}
+
+impl CommandBuffer {
+    /// Creates a buffer holding at most `capacity` unacknowledged commands.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            next_sequence: 1,
+            last_acked: 0,
+            pending: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Builds the next command from sampled input and retains it.
+    ///
+    /// If the buffer is full, the oldest unacknowledged command is dropped.
+    pub fn create(&mut self, tick: u32, input: InputState, view_angles: (f32, f32)) -> UserCmd {
+        let cmd = UserCmd {
+            sequence: self.next_sequence,
+            tick,
+            forward: input.forward,
+            right: input.right,
+            up: input.up,
+            buttons: input.buttons.bits(),
+            view_angles,
+        };
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+
+        self.pending.push_back(cmd);
+        while self.pending.len() > self.capacity {
+            self.pending.pop_front();
+        }
+        cmd
+    }
+
+    /// Drops every command up to and including `sequence`.
+    ///
+    /// Stale acks (at or below the last one) are ignored.
+    pub fn ack(&mut self, sequence: u32) {
+        if sequence <= self.last_acked {
+            return;
+        }
+        self.last_acked = sequence;
+        while self
+            .pending
+            .front()
+            .is_some_and(|cmd| cmd.sequence <= sequence)
+        {
+            self.pending.pop_front();
+        }
+    }
+
+    /// Highest sequence the server has acknowledged; 0 if none.
+    pub fn last_acked(&self) -> u32 {
+        self.last_acked
+    }
+
+    /// Unacknowledged commands, oldest first.
+    pub fn pending(&self) -> impl Iterator<Item = &UserCmd> {
+        self.pending.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forward() -> InputState {
+        InputState {
+            forward: 1.0,
+            right: 0.0,
+            up: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn commands_get_monotonic_sequences() {
+        let mut buf = CommandBuffer::default();
+        let cmds: Vec<_> = (0..5)
+            .map(|tick| buf.create(tick, forward(), (0.0, 90.0)))
+            .collect();
+
+        let seqs: Vec<_> = cmds.iter().map(|c| c.sequence).collect();
+        assert_eq!(seqs, vec![1, 2, 3, 4, 5]);
+        assert_eq!(cmds[2].tick, 2);
+        assert_eq!(cmds[2].forward, 1.0);
+        assert_eq!(cmds[2].view_angles, (0.0, 90.0));
+        assert_eq!(buf.len(), 5);
+    }
+
+    #[test]
+    fn acked_commands_are_pruned() {
+        let mut buf = CommandBuffer::default();
+        for tick in 0..5 {
+            buf.create(tick, forward(), (0.0, 0.0));
+        }
+
+        buf.ack(3);
+        assert_eq!(buf.last_acked(), 3);
+        let left: Vec<_> = buf.pending().map(|c| c.sequence).collect();
+        assert_eq!(left, vec![4, 5]);
+
+        // A late, older ack changes nothing.
+        buf.ack(2);
+        assert_eq!(buf.last_acked(), 3);
+        assert_eq!(buf.len(), 2);
+
+        buf.ack(5);
+        assert!(buf.is_empty());
+        assert_eq!(buf.create(5, forward(), (0.0, 0.0)).sequence, 6);
+    }
+
+    #[test]
+    fn full_buffer_drops_oldest() {
+        let mut buf = CommandBuffer::new(3);
+        for tick in 0..5 {
+            buf.create(tick, forward(), (0.0, 0.0));
+        }
+        let left: Vec<_> = buf.pending().map(|c| c.sequence).collect();
+        assert_eq!(left, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn space_binds_to_jump() {
+        let mut bindings = Bindings::empty();
+        bindings.bind("space", InButtons::JUMP);
+
+        let input = InputState::from_keys(&["SPACE"], &bindings);
+        assert_eq!(input.buttons, InButtons::JUMP);
+        assert_eq!(input.buttons.bits(), 1 << 1);
+        assert_eq!((input.forward, input.right), (0.0, 0.0));
+
+        bindings.unbind("space");
+        assert!(InputState::from_keys(&["space"], &bindings)
+            .buttons
+            .is_empty());
+    }
+
+    #[test]
+    fn default_bindings_produce_mask_and_axes() {
+        let bindings = Bindings::default();
+        let input = InputState::from_keys(&["w", "d", "mouse1", "space", "f13"], &bindings);
+
+        assert_eq!(
+            input.buttons,
+            InButtons::FORWARD | InButtons::MOVERIGHT | InButtons::ATTACK | InButtons::JUMP
+        );
+        assert_eq!(input.forward, 1.0);
+        assert_eq!(input.right, 1.0);
+
+        let input = InputState::from_keys(&["w", "s", "a"], &bindings);
+        assert_eq!(input.forward, 0.0);
+        assert_eq!(input.right, -1.0);
+    }
+
+    #[test]
+    fn command_carries_button_mask() {
+        let input = InputState::from_keys(&["e", "ctrl"], &Bindings::default());
+        let cmd = CommandBuffer::default().create(1, input, (0.0, 0.0));
+        assert_eq!(
+            InButtons::from_bits_truncate(cmd.buttons),
+            InButtons::USE | InButtons::DUCK
+        );
+    }
+}
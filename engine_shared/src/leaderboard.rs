@@ -9,8 +9,12 @@
 //! - Score upload and retrieval
 //! - Statistics tracking
 //! - Achievement progress
+//! - JSON persistence, checked against the boards defined in code
 
 use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
 
 use serde::{Deserialize, Serialize};
 
@@ -52,6 +56,58 @@ pub enum LeaderboardDisplayType {
     TimeMilliSeconds,
 }
 
+impl LeaderboardDisplayType {
+    /// Format a score for display.
+    ///
+    /// Times of an hour or more include an hours field (e.g. "1:02:03").
+    pub fn format_score(&self, score: i32) -> String {
+        fn clock(total_secs: u32) -> String {
+            let (h, m, s) = (total_secs / 3600, total_secs / 60 % 60, total_secs % 60);
+            if h > 0 {
+                format!("{}:{:02}:{:02}", h, m, s)
+            } else {
+                format!("{}:{:02}", m, s)
+            }
+        }
+
+        let sign = if score < 0 { "-" } else { "" };
+        let abs = score.unsigned_abs();
+        match self {
+            LeaderboardDisplayType::Numeric => score.to_string(),
+            LeaderboardDisplayType::TimeSeconds => format!("{}{}", sign, clock(abs)),
+            LeaderboardDisplayType::TimeMilliSeconds => {
+                let (secs, millis) = (abs / 1000, abs % 1000);
+                if secs >= 60 {
+                    format!("{}{}.{:03}", sign, clock(secs), millis)
+                } else {
+                    format!("{}{}.{:03}", sign, secs, millis)
+                }
+            }
+        }
+    }
+}
+
+/// Inclusive range of 1-based global ranks to download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeaderboardRange {
+    /// First rank (a start of 0 is treated as 1).
+    pub start: u32,
+    /// Last rank.
+    pub end: u32,
+}
+
+impl LeaderboardRange {
+    /// Ranks `start..=end`.
+    pub fn new(start: u32, end: u32) -> Self {
+        Self { start, end }
+    }
+
+    /// The top `count` entries.
+    pub fn top(count: u32) -> Self {
+        Self::new(1, count)
+    }
+}
+
 /// Leaderboard data request type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LeaderboardDataRequest {
@@ -72,6 +128,57 @@ pub enum LeaderboardUploadScoreMethod {
     KeepBest,
 }
 
+/// Maximum number of details values attached to an entry.
+pub const MAX_DETAILS: usize = 64;
+
+/// Leaderboard errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaderboardError {
+    /// More than [`MAX_DETAILS`] details values; carries the count given.
+    DetailsTooLong(usize),
+    /// No leaderboard with the given handle.
+    NotFound,
+}
+
+/// A leaderboard as stored on disk, entries in rank order.
+#[derive(Serialize, Deserialize)]
+struct LeaderboardFile {
+    name: String,
+    sort_method: LeaderboardSortMethod,
+    display_type: LeaderboardDisplayType,
+    entries: Vec<LeaderboardEntry>,
+}
+
+impl LeaderboardFile {
+    fn read<T: serde::de::DeserializeOwned>(path: &Path) -> io::Result<T> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn write<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(value)?)
+    }
+
+    /// Checks the file describes `board`; a mismatch is `InvalidData`.
+    fn check_matches(&self, board: &Leaderboard) -> io::Result<()> {
+        let field = if self.name != board.name {
+            "name"
+        } else if self.sort_method != board.sort_method {
+            "sort method"
+        } else if self.display_type != board.display_type {
+            "display type"
+        } else {
+            return Ok(());
+        };
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "leaderboard '{}' was saved with a different {field}",
+                board.name
+            ),
+        ))
+    }
+}
+
 /// Leaderboard entry.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LeaderboardEntry {
@@ -81,7 +188,7 @@ pub struct LeaderboardEntry {
     pub global_rank: u32,
     /// Score value.
     pub score: i32,
-    /// Optional details (extra data).
+    /// Optional details (extra data), at most [`MAX_DETAILS`] values.
     pub details: Vec<i32>,
 }
 
@@ -123,6 +230,10 @@ impl Leaderboard {
     }
 
     /// Upload a score.
+    ///
+    /// Returns whether the stored entry changed; `KeepBest` leaves a better
+    /// or equal existing score in place, and details over [`MAX_DETAILS`]
+    /// reject the upload.
     pub fn upload_score(
         &mut self,
         steam_id: SteamId,
@@ -130,6 +241,21 @@ impl Leaderboard {
         method: LeaderboardUploadScoreMethod,
         details: Vec<i32>,
     ) -> bool {
+        self.try_upload_score(steam_id, score, method, details).unwrap_or(false)
+    }
+
+    /// Upload a score, reporting why it was rejected.
+    pub fn try_upload_score(
+        &mut self,
+        steam_id: SteamId,
+        score: i32,
+        method: LeaderboardUploadScoreMethod,
+        details: Vec<i32>,
+    ) -> Result<bool, LeaderboardError> {
+        if details.len() > MAX_DETAILS {
+            return Err(LeaderboardError::DetailsTooLong(details.len()));
+        }
+
         let existing = self.entries.iter_mut().find(|e| e.steam_id == steam_id);
 
         match existing {
@@ -146,10 +272,8 @@ impl Leaderboard {
                     entry.score = score;
                     entry.details = details;
                     self.recalculate_ranks();
-                    true
-                } else {
-                    false
                 }
+                Ok(should_update)
             }
             None => {
                 self.entries.push(LeaderboardEntry {
@@ -159,7 +283,7 @@ impl Leaderboard {
                     details,
                 });
                 self.recalculate_ranks();
-                true
+                Ok(true)
             }
         }
     }
@@ -178,11 +302,59 @@ impl Leaderboard {
 
     /// Get entries in range.
     pub fn get_entries(&self, start: u32, count: u32) -> Vec<&LeaderboardEntry> {
-        let start = start.saturating_sub(1) as usize;
-        let end = (start + count as usize).min(self.entries.len());
+        let start = (start.saturating_sub(1) as usize).min(self.entries.len());
+        let end = start.saturating_add(count as usize).min(self.entries.len());
         self.entries[start..end].iter().collect()
     }
 
+    /// Download ranked entries in a global rank range.
+    ///
+    /// Ranks past the end of the board are ignored, so an out-of-range or
+    /// inverted range yields no entries.
+    pub fn download_entries(&self, range: LeaderboardRange) -> Vec<LeaderboardEntry> {
+        let start = range.start.max(1);
+        if range.end < start {
+            return Vec::new();
+        }
+        self.get_entries(start, range.end - start + 1)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Download the entries ranked around a user, including the user.
+    ///
+    /// The window holds up to `before` entries ranked above the user and
+    /// `after` below, clamped at the ends of the board. Returns no entries if
+    /// the user has no score.
+    pub fn download_around_user(
+        &self,
+        steam_id: SteamId,
+        before: u32,
+        after: u32,
+    ) -> Vec<LeaderboardEntry> {
+        self.get_entries_around_user(steam_id, before, after)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Download the entries of the given friends, in rank order.
+    ///
+    /// Friends without a score are skipped.
+    pub fn download_friends(&self, friends: &[SteamId]) -> Vec<LeaderboardEntry> {
+        self.entries
+            .iter()
+            .filter(|e| friends.contains(&e.steam_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Format a score using this leaderboard's display type.
+    pub fn format_score(&self, score: i32) -> String {
+        self.display_type.format_score(score)
+    }
+
     /// Get entries around a user.
     pub fn get_entries_around_user(
         &self,
@@ -206,6 +378,36 @@ impl Leaderboard {
     pub fn get_user_entry(&self, steam_id: SteamId) -> Option<&LeaderboardEntry> {
         self.entries.iter().find(|e| e.steam_id == steam_id)
     }
+
+    fn to_file(&self) -> LeaderboardFile {
+        LeaderboardFile {
+            name: self.name.clone(),
+            sort_method: self.sort_method,
+            display_type: self.display_type,
+            entries: self.entries.clone(),
+        }
+    }
+
+    /// Replaces the entries with a saved file's. Ties keep their saved order.
+    fn restore(&mut self, file: LeaderboardFile) -> io::Result<()> {
+        file.check_matches(self)?;
+        self.entries = file.entries;
+        self.recalculate_ranks();
+        Ok(())
+    }
+
+    /// Save the board's metadata and entries as JSON.
+    pub fn save_json(&self, path: &Path) -> io::Result<()> {
+        LeaderboardFile::write(path, &self.to_file())
+    }
+
+    /// Replace the entries with those saved by [`save_json`](Self::save_json).
+    ///
+    /// Fails with `InvalidData`, leaving the board unchanged, if the file was
+    /// saved from a board with a different name, sort method or display type.
+    pub fn load_json(&mut self, path: &Path) -> io::Result<()> {
+        self.restore(LeaderboardFile::read(path)?)
+    }
 }
 
 /// Stat value types.
@@ -306,6 +508,10 @@ pub struct LeaderboardManager {
     user_stats: UserStats,
     /// Next handle ID.
     next_handle: u64,
+    /// Player that `GlobalAroundUser` downloads are centred on.
+    local_user: Option<SteamId>,
+    /// Friends of the local user, for `Friends` downloads.
+    friends: Vec<SteamId>,
 }
 
 impl LeaderboardManager {
@@ -315,9 +521,21 @@ impl LeaderboardManager {
             leaderboards: HashMap::new(),
             user_stats: UserStats::new(),
             next_handle: 1,
+            local_user: None,
+            friends: Vec::new(),
         }
     }
 
+    /// Set the player that around-user and friends downloads are for.
+    pub fn set_local_user(&mut self, steam_id: SteamId) {
+        self.local_user = Some(steam_id);
+    }
+
+    /// Set the local user's friends.
+    pub fn set_friends(&mut self, friends: Vec<SteamId>) {
+        self.friends = friends;
+    }
+
     /// Find a leaderboard by name.
     /// Reference: <https://partner.steamgames.com/doc/api/ISteamUserStats#FindLeaderboard>
     pub fn find_leaderboard(&self, name: &str) -> Option<LeaderboardHandle> {
@@ -363,14 +581,30 @@ impl LeaderboardManager {
         score: i32,
         method: LeaderboardUploadScoreMethod,
     ) -> bool {
-        if let Some(lb) = self.get_leaderboard_mut(handle) {
-            lb.upload_score(steam_id, score, method, Vec::new())
-        } else {
-            false
-        }
+        self.upload_score_with_details(handle, steam_id, score, method, Vec::new())
+            .unwrap_or(false)
+    }
+
+    /// Upload score with attached details to leaderboard.
+    pub fn upload_score_with_details(
+        &mut self,
+        handle: LeaderboardHandle,
+        steam_id: SteamId,
+        score: i32,
+        method: LeaderboardUploadScoreMethod,
+        details: Vec<i32>,
+    ) -> Result<bool, LeaderboardError> {
+        self.get_leaderboard_mut(handle)
+            .ok_or(LeaderboardError::NotFound)?
+            .try_upload_score(steam_id, score, method, details)
     }
 
     /// Download leaderboard entries.
+    ///
+    /// For `Global`, `start` and `end` are global ranks. For
+    /// `GlobalAroundUser` they are the number of entries above and below the
+    /// local user. `Friends` ignores them and returns the local user and
+    /// their friends. Both need [`set_local_user`](Self::set_local_user).
     /// Reference: <https://partner.steamgames.com/doc/api/ISteamUserStats#DownloadLeaderboardEntries>
     pub fn download_entries(
         &self,
@@ -379,13 +613,22 @@ impl LeaderboardManager {
         start: u32,
         end: u32,
     ) -> Vec<LeaderboardEntry> {
-        if let Some(lb) = self.get_leaderboard(handle) {
-            lb.get_entries(start, end - start + 1)
-                .into_iter()
-                .cloned()
-                .collect()
-        } else {
-            Vec::new()
+        let Some(lb) = self.get_leaderboard(handle) else {
+            return Vec::new();
+        };
+        match (data_request, self.local_user) {
+            (LeaderboardDataRequest::Global, _) => {
+                lb.download_entries(LeaderboardRange::new(start, end))
+            }
+            (LeaderboardDataRequest::GlobalAroundUser, Some(user)) => {
+                lb.download_around_user(user, start, end)
+            }
+            (LeaderboardDataRequest::Friends, Some(user)) => {
+                let mut players = self.friends.clone();
+                players.push(user);
+                lb.download_friends(&players)
+            }
+            (_, None) => Vec::new(),
         }
     }
 
@@ -424,6 +667,40 @@ impl LeaderboardManager {
             false
         }
     }
+
+    /// Save every leaderboard to one JSON file, ordered by name.
+    pub fn save_json(&self, path: &Path) -> io::Result<()> {
+        let mut boards: Vec<LeaderboardFile> = self
+            .leaderboards
+            .values()
+            .map(Leaderboard::to_file)
+            .collect();
+        boards.sort_by(|a, b| a.name.cmp(&b.name));
+        LeaderboardFile::write(path, &boards)
+    }
+
+    /// Load leaderboards saved by [`save_json`](Self::save_json).
+    ///
+    /// Boards that already exist must match the saved sort method and
+    /// display type; any mismatch fails the whole load with `InvalidData`,
+    /// leaving every board unchanged. Saved boards that don't exist yet are
+    /// created.
+    pub fn load_json(&mut self, path: &Path) -> io::Result<()> {
+        let boards: Vec<LeaderboardFile> = LeaderboardFile::read(path)?;
+        for file in &boards {
+            if let Some(board) = self.leaderboards.get(&file.name) {
+                file.check_matches(board)?;
+            }
+        }
+        for file in boards {
+            let handle =
+                self.find_or_create_leaderboard(&file.name, file.sort_method, file.display_type);
+            if let Some(board) = self.get_leaderboard_mut(handle) {
+                board.restore(file)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -633,6 +910,296 @@ mod tests {
         assert_eq!(entry.score, 25000);
     }
 
+    fn board(sort_method: LeaderboardSortMethod) -> Leaderboard {
+        Leaderboard::new(
+            LeaderboardHandle::new(1),
+            "Test",
+            sort_method,
+            LeaderboardDisplayType::Numeric,
+        )
+    }
+
+    #[test]
+    fn ldb_010_keep_best_does_not_lower() {
+        let mut lb = board(LeaderboardSortMethod::Descending);
+        let player = test_steam_id(1);
+
+        assert!(lb.upload_score(
+            player,
+            500,
+            LeaderboardUploadScoreMethod::KeepBest,
+            Vec::new()
+        ));
+        assert!(!lb.upload_score(
+            player,
+            400,
+            LeaderboardUploadScoreMethod::KeepBest,
+            Vec::new()
+        ));
+        assert!(!lb.upload_score(
+            player,
+            500,
+            LeaderboardUploadScoreMethod::KeepBest,
+            Vec::new()
+        ));
+        assert_eq!(lb.get_user_entry(player).unwrap().score, 500);
+    }
+
+    #[test]
+    fn ldb_010_force_update_overwrites() {
+        let mut lb = board(LeaderboardSortMethod::Descending);
+        let player = test_steam_id(1);
+        lb.upload_score(
+            test_steam_id(2),
+            300,
+            LeaderboardUploadScoreMethod::ForceUpdate,
+            Vec::new(),
+        );
+
+        lb.upload_score(
+            player,
+            500,
+            LeaderboardUploadScoreMethod::ForceUpdate,
+            Vec::new(),
+        );
+        assert_eq!(lb.get_user_entry(player).unwrap().global_rank, 1);
+
+        assert!(lb.upload_score(
+            player,
+            100,
+            LeaderboardUploadScoreMethod::ForceUpdate,
+            Vec::new()
+        ));
+        let entry = lb.get_user_entry(player).unwrap();
+        assert_eq!(entry.score, 100);
+        assert_eq!(entry.global_rank, 2);
+        assert_eq!(lb.entry_count(), 2);
+    }
+
+    #[test]
+    fn ldb_004_download_range_both_orders() {
+        for (sort, expected) in [
+            (LeaderboardSortMethod::Descending, [(2, 400), (3, 300)]),
+            (LeaderboardSortMethod::Ascending, [(2, 200), (3, 300)]),
+        ] {
+            let mut lb = board(sort);
+            for (n, score) in [(1, 300), (2, 100), (3, 400), (4, 200), (5, 500)] {
+                lb.upload_score(
+                    test_steam_id(n),
+                    score,
+                    LeaderboardUploadScoreMethod::ForceUpdate,
+                    Vec::new(),
+                );
+            }
+
+            let ranked: Vec<_> = lb
+                .download_entries(LeaderboardRange::new(2, 3))
+                .iter()
+                .map(|e| (e.global_rank, e.score))
+                .collect();
+            assert_eq!(ranked, expected, "{:?}", sort);
+        }
+    }
+
+    #[test]
+    fn ldb_004_download_out_of_range() {
+        let mut lb = board(LeaderboardSortMethod::Descending);
+        lb.upload_score(
+            test_steam_id(1),
+            100,
+            LeaderboardUploadScoreMethod::ForceUpdate,
+            Vec::new(),
+        );
+
+        assert_eq!(lb.download_entries(LeaderboardRange::top(10)).len(), 1);
+        assert!(lb.download_entries(LeaderboardRange::new(5, 10)).is_empty());
+        assert!(lb.download_entries(LeaderboardRange::new(3, 1)).is_empty());
+    }
+
+    fn ranked_board() -> Leaderboard {
+        let mut lb = board(LeaderboardSortMethod::Descending);
+        // Player n scores n * 100, so player 10 is rank 1 and player 1 rank 10.
+        for n in 1..=10 {
+            lb.upload_score(
+                test_steam_id(n),
+                n as i32 * 100,
+                LeaderboardUploadScoreMethod::ForceUpdate,
+                Vec::new(),
+            );
+        }
+        lb
+    }
+
+    fn ranks(entries: &[LeaderboardEntry]) -> Vec<u32> {
+        entries.iter().map(|e| e.global_rank).collect()
+    }
+
+    #[test]
+    fn ldb_005_download_around_user() {
+        let lb = ranked_board();
+
+        // Player 5 is rank 6.
+        assert_eq!(
+            ranks(&lb.download_around_user(test_steam_id(5), 2, 2)),
+            vec![4, 5, 6, 7, 8]
+        );
+        // Clamped at the top and bottom of the board.
+        assert_eq!(
+            ranks(&lb.download_around_user(test_steam_id(9), 3, 1)),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            ranks(&lb.download_around_user(test_steam_id(2), 1, 5)),
+            vec![8, 9, 10]
+        );
+        // Unknown user.
+        assert!(lb.download_around_user(test_steam_id(99), 2, 2).is_empty());
+    }
+
+    #[test]
+    fn ldb_006_download_friends() {
+        let lb = ranked_board();
+
+        let friends = [test_steam_id(3), test_steam_id(8), test_steam_id(42)];
+        let entries = lb.download_friends(&friends);
+        assert_eq!(ranks(&entries), vec![3, 8]);
+        assert_eq!(entries[0].steam_id, test_steam_id(8));
+        assert_eq!(entries[1].steam_id, test_steam_id(3));
+        assert!(lb.download_friends(&[]).is_empty());
+    }
+
+    #[test]
+    fn ldb_005_download_entries_by_request() {
+        let mut manager = LeaderboardManager::new();
+        let handle = manager.find_or_create_leaderboard(
+            "Score",
+            LeaderboardSortMethod::Descending,
+            LeaderboardDisplayType::Numeric,
+        );
+        // Player n scores n * 100, so player 10 is rank 1 and player 1 rank 10.
+        for n in 1..=10 {
+            manager.upload_score(
+                handle,
+                test_steam_id(n),
+                n as i32 * 100,
+                LeaderboardUploadScoreMethod::ForceUpdate,
+            );
+        }
+        let download = |manager: &LeaderboardManager, request, start, end| {
+            ranks(&manager.download_entries(handle, request, start, end))
+        };
+
+        // Without a local user only global downloads work.
+        assert!(download(&manager, LeaderboardDataRequest::GlobalAroundUser, 1, 1).is_empty());
+        assert!(download(&manager, LeaderboardDataRequest::Friends, 0, 0).is_empty());
+
+        manager.set_local_user(test_steam_id(5));
+        manager.set_friends(vec![test_steam_id(9), test_steam_id(2), test_steam_id(42)]);
+        assert_eq!(
+            download(&manager, LeaderboardDataRequest::Global, 2, 4),
+            vec![2, 3, 4]
+        );
+        assert_eq!(
+            download(&manager, LeaderboardDataRequest::GlobalAroundUser, 1, 2),
+            vec![5, 6, 7, 8]
+        );
+        assert_eq!(
+            download(&manager, LeaderboardDataRequest::Friends, 0, 0),
+            vec![2, 6, 9]
+        );
+    }
+
+    #[test]
+    fn ldb_011_upload_details() {
+        let mut manager = LeaderboardManager::new();
+        let handle = manager.find_or_create_leaderboard(
+            "Rounds",
+            LeaderboardSortMethod::Descending,
+            LeaderboardDisplayType::Numeric,
+        );
+        let player = test_steam_id(1);
+
+        let details = vec![16, 14, 3, 27];
+        manager
+            .upload_score_with_details(
+                handle,
+                player,
+                900,
+                LeaderboardUploadScoreMethod::KeepBest,
+                details.clone(),
+            )
+            .unwrap();
+
+        let entries = manager.download_entries(handle, LeaderboardDataRequest::Global, 1, 10);
+        assert_eq!(entries[0].details, details);
+        let lb = manager.get_leaderboard(handle).unwrap();
+        assert_eq!(lb.download_around_user(player, 0, 0)[0].details, details);
+        assert_eq!(lb.download_friends(&[player])[0].details, details);
+    }
+
+    #[test]
+    fn ldb_011_details_length_limit() {
+        let mut lb = board(LeaderboardSortMethod::Descending);
+        let player = test_steam_id(1);
+
+        let max = vec![7; MAX_DETAILS];
+        assert_eq!(
+            lb.try_upload_score(player, 1, LeaderboardUploadScoreMethod::ForceUpdate, max),
+            Ok(true)
+        );
+
+        let too_long = vec![7; MAX_DETAILS + 1];
+        assert_eq!(
+            lb.try_upload_score(
+                player,
+                2,
+                LeaderboardUploadScoreMethod::ForceUpdate,
+                too_long.clone()
+            ),
+            Err(LeaderboardError::DetailsTooLong(65))
+        );
+        assert!(!lb.upload_score(
+            player,
+            2,
+            LeaderboardUploadScoreMethod::ForceUpdate,
+            too_long
+        ));
+        assert_eq!(lb.get_user_entry(player).unwrap().score, 1);
+
+        let mut manager = LeaderboardManager::new();
+        assert_eq!(
+            manager.upload_score_with_details(
+                LeaderboardHandle::INVALID,
+                player,
+                1,
+                LeaderboardUploadScoreMethod::ForceUpdate,
+                Vec::new()
+            ),
+            Err(LeaderboardError::NotFound)
+        );
+    }
+
+    #[test]
+    fn ldb_display_type_formatting() {
+        assert_eq!(LeaderboardDisplayType::Numeric.format_score(-42), "-42");
+        assert_eq!(
+            LeaderboardDisplayType::TimeSeconds.format_score(123),
+            "2:03"
+        );
+        assert_eq!(
+            LeaderboardDisplayType::TimeSeconds.format_score(3723),
+            "1:02:03"
+        );
+        assert_eq!(
+            LeaderboardDisplayType::TimeMilliSeconds.format_score(12345),
+            "12.345"
+        );
+        assert_eq!(
+            LeaderboardDisplayType::TimeMilliSeconds.format_score(83005),
+            "1:23.005"
+        );
+    }
+
     // =============================================================================
     // STAT-001: Request Stats
     // Reference: https://partner.steamgames.com/doc/api/ISteamUserStats#RequestCurrentStats
@@ -761,8 +1328,175 @@ mod tests {
         assert_eq!(int_val.as_int(), Some(42));
         assert_eq!(int_val.as_float(), Some(42.0));
 
-        let float_val = StatValue::Float(3.14);
+        let float_val = StatValue::Float(std::f32::consts::PI);
         assert_eq!(float_val.as_int(), Some(3));
-        assert!((float_val.as_float().unwrap() - 3.14).abs() < 0.001);
+        assert!((float_val.as_float().unwrap() - std::f32::consts::PI).abs() < 0.001);
+    }
+
+    // =============================================================================
+    // LDB-PERSIST: JSON persistence
+    // =============================================================================
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("ldb_{}_{}.json", std::process::id(), name))
+    }
+
+    #[test]
+    fn ldb_persist_roundtrip() {
+        let mut manager = LeaderboardManager::new();
+        let speedrun = manager.find_or_create_leaderboard(
+            "Speedrun",
+            LeaderboardSortMethod::Ascending,
+            LeaderboardDisplayType::TimeMilliSeconds,
+        );
+        let kills = manager.find_or_create_leaderboard(
+            "Kills",
+            LeaderboardSortMethod::Descending,
+            LeaderboardDisplayType::Numeric,
+        );
+        for (i, time) in [(1, 95_000), (2, 61_250), (3, 72_500), (4, 61_250)] {
+            manager.upload_score(
+                speedrun,
+                test_steam_id(i),
+                time,
+                LeaderboardUploadScoreMethod::ForceUpdate,
+            );
+        }
+        manager
+            .upload_score_with_details(
+                kills,
+                test_steam_id(1),
+                30,
+                LeaderboardUploadScoreMethod::ForceUpdate,
+                vec![7, 8],
+            )
+            .unwrap();
+
+        let path = temp_path("roundtrip");
+        manager.save_json(&path).unwrap();
+
+        // A fresh process defines the same boards, then loads.
+        let mut restored = LeaderboardManager::new();
+        let restored_speedrun = restored.find_or_create_leaderboard(
+            "Speedrun",
+            LeaderboardSortMethod::Ascending,
+            LeaderboardDisplayType::TimeMilliSeconds,
+        );
+        restored.load_json(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let ranked = |m: &LeaderboardManager, h| -> Vec<(SteamId, u32, i32)> {
+            m.get_leaderboard(h)
+                .unwrap()
+                .download_entries(LeaderboardRange::top(10))
+                .into_iter()
+                .map(|e| (e.steam_id, e.global_rank, e.score))
+                .collect()
+        };
+        assert_eq!(
+            ranked(&restored, restored_speedrun),
+            ranked(&manager, speedrun)
+        );
+
+        // Boards only in the file are created.
+        let restored_kills = restored.find_leaderboard("Kills").unwrap();
+        let entry = restored
+            .get_leaderboard(restored_kills)
+            .unwrap()
+            .get_user_entry(test_steam_id(1))
+            .unwrap();
+        assert_eq!((entry.score, entry.details.clone()), (30, vec![7, 8]));
+    }
+
+    #[test]
+    fn ldb_persist_single_board_roundtrip() {
+        let handle = LeaderboardHandle::new(1);
+        let mut board = Leaderboard::new(
+            handle,
+            "Score",
+            LeaderboardSortMethod::Descending,
+            LeaderboardDisplayType::Numeric,
+        );
+        for i in 1..=5 {
+            board.upload_score(
+                test_steam_id(i),
+                i as i32 * 10,
+                LeaderboardUploadScoreMethod::ForceUpdate,
+                Vec::new(),
+            );
+        }
+        let path = temp_path("single");
+        board.save_json(&path).unwrap();
+
+        let mut loaded = Leaderboard::new(
+            handle,
+            "Score",
+            LeaderboardSortMethod::Descending,
+            LeaderboardDisplayType::Numeric,
+        );
+        loaded.load_json(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let ids: Vec<_> = loaded
+            .get_entries(1, 5)
+            .iter()
+            .map(|e| e.steam_id)
+            .collect();
+        assert_eq!(ids, (1..=5).rev().map(test_steam_id).collect::<Vec<_>>());
+        assert_eq!(
+            loaded.get_user_entry(test_steam_id(5)).unwrap().global_rank,
+            1
+        );
+    }
+
+    #[test]
+    fn ldb_persist_rejects_metadata_mismatch() {
+        let mut board = Leaderboard::new(
+            LeaderboardHandle::new(1),
+            "Speedrun",
+            LeaderboardSortMethod::Descending,
+            LeaderboardDisplayType::Numeric,
+        );
+        board.upload_score(
+            test_steam_id(1),
+            500,
+            LeaderboardUploadScoreMethod::ForceUpdate,
+            Vec::new(),
+        );
+        let path = temp_path("mismatch");
+        board.save_json(&path).unwrap();
+
+        let mut ascending = Leaderboard::new(
+            LeaderboardHandle::new(1),
+            "Speedrun",
+            LeaderboardSortMethod::Ascending,
+            LeaderboardDisplayType::Numeric,
+        );
+        let err = ascending.load_json(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "leaderboard 'Speedrun' was saved with a different sort method"
+        );
+        assert_eq!(ascending.entry_count(), 0);
+
+        let mut manager = LeaderboardManager::new();
+        let timed = manager.find_or_create_leaderboard(
+            "Speedrun",
+            LeaderboardSortMethod::Descending,
+            LeaderboardDisplayType::TimeSeconds,
+        );
+        let mut boards = LeaderboardManager::new();
+        boards.find_or_create_leaderboard(
+            "Speedrun",
+            LeaderboardSortMethod::Descending,
+            LeaderboardDisplayType::Numeric,
+        );
+        boards.save_json(&path).unwrap();
+        let err = manager.load_json(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().ends_with("different display type"));
+        assert_eq!(manager.get_entry_count(timed), 0);
+        std::fs::remove_file(&path).ok();
     }
 }
@@ -19,6 +19,7 @@ pub mod dlc;
 pub mod ecs;
 pub mod event;
 pub mod gsi;
+pub mod input;
 pub mod leaderboard;
 pub mod lobby;
 pub mod matchmaking;
@@ -26,12 +27,14 @@ pub mod math;
 pub mod net;
 pub mod party;
 pub mod physics;
+pub mod rcon;
 pub mod render;
 pub mod resources;
 pub mod rich_presence;
 pub mod social;
 pub mod steam_id;
 pub mod test_report;
+pub mod transport;
 pub mod voice;
 pub mod workshop;
 
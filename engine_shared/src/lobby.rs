@@ -17,8 +17,21 @@
 //! 4. When ready, owner sets game server info
 //! 5. All members connect to game server
 //! 6. Lobby persists until empty
+//!
+//! # Join Codes
+//! A lobby can be shared as a short code (see [`Lobby::to_join_code`]): the
+//! `LobbyId` in Crockford base32 followed by two checksum characters. Codes
+//! are case-insensitive, ignore dashes and spaces, and read `O` as `0` and
+//! `I`/`L` as `1`, so they survive being typed in by hand.
+//!
+//! # Permissions
+//! As in Steam, only the owner may change lobby data, the game server, the
+//! member limit or the owner, and each member may only change their own
+//! member data. The `*_as` methods enforce this for a request made by an
+//! `actor`; the unchecked setters are for the authority applying changes.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::time::Instant;
 
 use serde::{Deserialize, Serialize};
@@ -100,8 +113,116 @@ impl LobbyId {
     pub fn as_u64(&self) -> u64 {
         self.0
     }
+
+    /// Encode as a join code: base32 digits, then a 2-character checksum.
+    pub fn to_join_code(&self) -> String {
+        let mut digits = Vec::new();
+        let mut rest = self.0;
+        loop {
+            digits.push(JOIN_CODE_ALPHABET[(rest & 0x1F) as usize]);
+            rest >>= 5;
+            if rest == 0 {
+                break;
+            }
+        }
+        digits.reverse();
+        let check = join_code_checksum(self.0);
+        digits.push(JOIN_CODE_ALPHABET[(check >> 5) as usize]);
+        digits.push(JOIN_CODE_ALPHABET[(check & 0x1F) as usize]);
+        String::from_utf8(digits).expect("alphabet is ASCII")
+    }
+
+    /// Decode a join code, verifying its checksum.
+    pub fn from_join_code(code: &str) -> Result<LobbyId, JoinCodeError> {
+        let mut values = Vec::with_capacity(code.len());
+        for c in code.chars() {
+            if c == '-' || c.is_whitespace() {
+                continue;
+            }
+            values.push(join_code_value(c).ok_or(JoinCodeError::InvalidCharacter(c))?);
+        }
+        if values.len() < 3 {
+            return Err(JoinCodeError::TooShort);
+        }
+        if values.len() > MAX_JOIN_CODE_LENGTH {
+            return Err(JoinCodeError::TooLong);
+        }
+
+        let (digits, check) = values.split_at(values.len() - 2);
+        let mut id: u64 = 0;
+        for &v in digits {
+            id = id
+                .checked_mul(32)
+                .map(|id| id | v as u64)
+                .ok_or(JoinCodeError::TooLong)?;
+        }
+        let check = (check[0] as u16) << 5 | check[1] as u16;
+        if check != join_code_checksum(id) {
+            return Err(JoinCodeError::ChecksumMismatch);
+        }
+        let id = LobbyId(id);
+        if !id.is_valid() {
+            return Err(JoinCodeError::InvalidLobby);
+        }
+        Ok(id)
+    }
+}
+
+/// Crockford base32 alphabet (no I, L, O or U).
+const JOIN_CODE_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// 13 digits hold a `u64`, plus 2 checksum characters.
+pub const MAX_JOIN_CODE_LENGTH: usize = 15;
+
+/// 10-bit checksum of a lobby ID, spread so neighbouring IDs differ.
+fn join_code_checksum(id: u64) -> u16 {
+    (id.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 54) as u16
+}
+
+fn join_code_value(c: char) -> Option<u8> {
+    match c.to_ascii_uppercase() {
+        'O' => Some(0),
+        'I' | 'L' => Some(1),
+        c => JOIN_CODE_ALPHABET
+            .iter()
+            .position(|&a| a as char == c)
+            .map(|i| i as u8),
+    }
+}
+
+/// Why a join code couldn't be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinCodeError {
+    /// A character outside the base32 alphabet.
+    InvalidCharacter(char),
+    /// Fewer characters than a single digit plus the checksum.
+    TooShort,
+    /// More digits than fit in a lobby ID.
+    TooLong,
+    /// The checksum doesn't match, usually a typo.
+    ChecksumMismatch,
+    /// The code decodes to the invalid lobby ID.
+    InvalidLobby,
+}
+
+impl fmt::Display for JoinCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JoinCodeError::InvalidCharacter(c) => {
+                write!(f, "join code contains invalid character '{}'", c)
+            }
+            JoinCodeError::TooShort => write!(f, "join code is too short"),
+            JoinCodeError::TooLong => write!(f, "join code is too long"),
+            JoinCodeError::ChecksumMismatch => {
+                write!(f, "join code checksum does not match (check for typos)")
+            }
+            JoinCodeError::InvalidLobby => write!(f, "join code does not name a lobby"),
+        }
+    }
 }
 
+impl std::error::Error for JoinCodeError {}
+
 /// A lobby member's data.
 #[derive(Debug, Clone)]
 pub struct LobbyMember {
@@ -157,6 +278,9 @@ pub const MAX_LOBBY_KEY_LENGTH: usize = 255;
 pub const MAX_LOBBY_VALUE_LENGTH: usize = 8192; // 8KB
 pub const MAX_LOBBY_DATA_ENTRIES: usize = 256;
 
+/// Member data key holding a member's ready flag (`"true"`/`"false"`).
+pub const READY_MEMBER_KEY: &str = "ready";
+
 impl Lobby {
     /// Create a new lobby.
     pub fn new(id: LobbyId, owner: SteamId, lobby_type: LobbyType, max_members: u32) -> Self {
@@ -174,6 +298,13 @@ impl Lobby {
         }
     }
 
+    /// Short, shareable code for joining this lobby by ID.
+    ///
+    /// Works for every lobby type, including `Invisible` ones.
+    pub fn to_join_code(&self) -> String {
+        self.id.to_join_code()
+    }
+
     /// Get current member count.
     pub fn member_count(&self) -> u32 {
         self.members.len() as u32
@@ -246,6 +377,17 @@ impl Lobby {
         Ok(())
     }
 
+    /// Set lobby data on behalf of `actor`, who must be the owner.
+    pub fn set_data_as(
+        &mut self,
+        actor: SteamId,
+        key: &str,
+        value: &str,
+    ) -> Result<(), LobbyError> {
+        self.check_owner(actor)?;
+        self.set_data(key, value)
+    }
+
     /// Get lobby data.
     pub fn get_data(&self, key: &str) -> Option<&str> {
         self.data.get(key).map(|s| s.as_str())
@@ -275,6 +417,23 @@ impl Lobby {
         Ok(())
     }
 
+    /// Set member data on behalf of `actor`, who may only change their own.
+    ///
+    /// Fails with `NotPermitted` if `actor` is anyone else, the owner
+    /// included.
+    pub fn set_member_data_as(
+        &mut self,
+        actor: SteamId,
+        steam_id: SteamId,
+        key: &str,
+        value: &str,
+    ) -> Result<(), LobbyError> {
+        if actor != steam_id {
+            return Err(LobbyError::NotPermitted);
+        }
+        self.set_member_data(steam_id, key, value)
+    }
+
     /// Get member data.
     pub fn get_member_data(&self, steam_id: SteamId, key: &str) -> Option<&str> {
         self.members
@@ -283,6 +442,36 @@ impl Lobby {
             .and_then(|m| m.data.get(key).map(|s| s.as_str()))
     }
 
+    /// Set a member's ready flag.
+    pub fn set_ready(&mut self, steam_id: SteamId, ready: bool) -> Result<(), LobbyError> {
+        self.set_member_data(
+            steam_id,
+            READY_MEMBER_KEY,
+            if ready { "true" } else { "false" },
+        )
+    }
+
+    /// Check if a member has flagged themselves ready (`"true"` or `"1"`).
+    pub fn is_member_ready(&self, steam_id: SteamId) -> bool {
+        matches!(
+            self.get_member_data(steam_id, READY_MEMBER_KEY),
+            Some("true" | "1")
+        )
+    }
+
+    /// Number of members flagged ready.
+    pub fn ready_count(&self) -> u32 {
+        self.members
+            .iter()
+            .filter(|m| self.is_member_ready(m.steam_id))
+            .count() as u32
+    }
+
+    /// Check if every member is ready, e.g. before starting the match.
+    pub fn all_members_ready(&self) -> bool {
+        !self.members.is_empty() && self.ready_count() == self.member_count()
+    }
+
     /// Set game server.
     pub fn set_game_server(&mut self, ip: u32, port: u16, server_id: Option<SteamId>) {
         self.game_server = Some(LobbyGameServer {
@@ -292,6 +481,19 @@ impl Lobby {
         });
     }
 
+    /// Set game server on behalf of `actor`, who must be the owner.
+    pub fn set_game_server_as(
+        &mut self,
+        actor: SteamId,
+        ip: u32,
+        port: u16,
+        server_id: Option<SteamId>,
+    ) -> Result<(), LobbyError> {
+        self.check_owner(actor)?;
+        self.set_game_server(ip, port, server_id);
+        Ok(())
+    }
+
     /// Set member limit.
     pub fn set_member_limit(&mut self, limit: u32) -> Result<(), LobbyError> {
         if limit < self.member_count() {
@@ -301,6 +503,12 @@ impl Lobby {
         Ok(())
     }
 
+    /// Set member limit on behalf of `actor`, who must be the owner.
+    pub fn set_member_limit_as(&mut self, actor: SteamId, limit: u32) -> Result<(), LobbyError> {
+        self.check_owner(actor)?;
+        self.set_member_limit(limit)
+    }
+
     /// Transfer ownership.
     pub fn set_owner(&mut self, new_owner: SteamId) -> Result<(), LobbyError> {
         if !self.is_member(new_owner) {
@@ -309,6 +517,19 @@ impl Lobby {
         self.owner = new_owner;
         Ok(())
     }
+
+    /// Transfer ownership on behalf of `actor`, who must be the owner.
+    pub fn set_owner_as(&mut self, actor: SteamId, new_owner: SteamId) -> Result<(), LobbyError> {
+        self.check_owner(actor)?;
+        self.set_owner(new_owner)
+    }
+
+    fn check_owner(&self, actor: SteamId) -> Result<(), LobbyError> {
+        if !self.is_owner(actor) {
+            return Err(LobbyError::NotOwner);
+        }
+        Ok(())
+    }
 }
 
 /// Lobby operation errors.
@@ -319,11 +540,17 @@ pub enum LobbyError {
     NotMember,
     AlreadyMember,
     NotOwner,
+    /// The actor may not act on another member's behalf.
+    NotPermitted,
     KeyTooLong,
     ValueTooLong,
     TooManyEntries,
     LimitTooLow,
     InvalidLobby,
+    /// A join code that couldn't be decoded.
+    InvalidJoinCode(JoinCodeError),
+    /// A join code for a lobby that no longer exists.
+    ExpiredJoinCode,
 }
 
 /// Lobby search filter.
@@ -474,6 +701,20 @@ impl LobbyManager {
         self.lobbies.remove(&id)
     }
 
+    /// Join the lobby named by a join code.
+    ///
+    /// Visibility doesn't matter: holding the code is enough, as with
+    /// joining an `Invisible` lobby by ID.
+    pub fn join_by_code(&mut self, code: &str, who: SteamId) -> Result<LobbyId, LobbyError> {
+        let id = LobbyId::from_join_code(code).map_err(LobbyError::InvalidJoinCode)?;
+        let lobby = self
+            .lobbies
+            .get_mut(&id)
+            .ok_or(LobbyError::ExpiredJoinCode)?;
+        lobby.add_member(who)?;
+        Ok(id)
+    }
+
     /// Search for lobbies matching a filter.
     pub fn search(&self, filter: &LobbySearchFilter) -> Vec<&Lobby> {
         let mut results: Vec<_> = self
@@ -822,6 +1063,44 @@ mod tests {
         assert_eq!(lobby.get_member_data(owner, "ready"), Some("true"));
     }
 
+    #[test]
+    fn lob_data_005_all_members_ready() {
+        let mut manager = LobbyManager::new();
+        let owner = test_steam_id(12345);
+        let joiner = test_steam_id(67890);
+
+        let lobby_id = manager.create_lobby(owner, LobbyType::Public, 8);
+        let lobby = manager.get_lobby_mut(lobby_id).unwrap();
+        lobby.add_member(joiner).unwrap();
+        assert_eq!(lobby.ready_count(), 0);
+        assert!(!lobby.all_members_ready());
+
+        lobby.set_ready(owner, true).unwrap();
+        assert_eq!(lobby.ready_count(), 1);
+        assert!(!lobby.all_members_ready());
+
+        // Raw member data counts too.
+        lobby
+            .set_member_data(joiner, READY_MEMBER_KEY, "1")
+            .unwrap();
+        assert_eq!(lobby.ready_count(), 2);
+        assert!(lobby.all_members_ready());
+
+        lobby.set_ready(joiner, false).unwrap();
+        assert_eq!(lobby.get_member_data(joiner, "ready"), Some("false"));
+        assert!(!lobby.all_members_ready());
+
+        // A newcomer isn't ready yet.
+        lobby.set_ready(joiner, true).unwrap();
+        lobby.add_member(test_steam_id(1)).unwrap();
+        assert!(!lobby.all_members_ready());
+
+        assert_eq!(
+            lobby.set_ready(test_steam_id(2), true),
+            Err(LobbyError::NotMember)
+        );
+    }
+
     // =============================================================================
     // LOB-DATA-006: Game Server Info
     // Reference: https://partner.steamgames.com/doc/api/ISteamMatchmaking#SetLobbyGameServer
@@ -928,4 +1207,207 @@ mod tests {
 
         assert_eq!(results.len(), 3);
     }
+
+    // =============================================================================
+    // LOB-011: Join Codes
+    // =============================================================================
+
+    #[test]
+    fn lob_011_join_code_roundtrip() {
+        for raw in [1, 31, 32, 12345, 0xDEAD_BEEF, u64::MAX] {
+            let id = LobbyId::new(raw);
+            let code = id.to_join_code();
+            assert!(code.len() <= MAX_JOIN_CODE_LENGTH, "{code}");
+            assert_eq!(LobbyId::from_join_code(&code), Ok(id), "{code}");
+        }
+
+        // Short for the IDs a manager hands out.
+        let code = LobbyId::new(12345).to_join_code();
+        assert_eq!(code.len(), 5);
+
+        // Typed in lowercase, grouped, and with look-alike letters.
+        let typed = format!("{}-{}", &code[..2], &code[2..])
+            .to_lowercase()
+            .replace('0', "o")
+            .replace('1', "l");
+        assert_eq!(LobbyId::from_join_code(&typed), Ok(LobbyId::new(12345)));
+    }
+
+    #[test]
+    fn lob_011_join_code_checksum_rejection() {
+        let code = LobbyId::new(12345).to_join_code();
+        let alphabet = "0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+        for i in 0..code.len() {
+            for c in alphabet.chars().filter(|&c| Some(c) != code.chars().nth(i)) {
+                let mut typo = code.clone();
+                typo.replace_range(i..i + 1, &c.to_string());
+                // A changed digit must not decode to the same lobby.
+                assert_ne!(
+                    LobbyId::from_join_code(&typo),
+                    Ok(LobbyId::new(12345)),
+                    "{typo}"
+                );
+            }
+        }
+
+        let mut typo = code.clone();
+        let last = if code.ends_with('0') { "1" } else { "0" };
+        typo.replace_range(code.len() - 1.., last);
+        assert_eq!(
+            LobbyId::from_join_code(&typo),
+            Err(JoinCodeError::ChecksumMismatch)
+        );
+        assert_eq!(
+            LobbyId::from_join_code("AB!CD"),
+            Err(JoinCodeError::InvalidCharacter('!'))
+        );
+        assert_eq!(
+            LobbyId::from_join_code("U1234"),
+            Err(JoinCodeError::InvalidCharacter('U'))
+        );
+        assert_eq!(LobbyId::from_join_code("-A "), Err(JoinCodeError::TooShort));
+        assert_eq!(
+            LobbyId::from_join_code(&"Z".repeat(MAX_JOIN_CODE_LENGTH + 1)),
+            Err(JoinCodeError::TooLong)
+        );
+        assert_eq!(
+            LobbyId::from_join_code(&LobbyId::INVALID.to_join_code()),
+            Err(JoinCodeError::InvalidLobby)
+        );
+    }
+
+    #[test]
+    fn lob_011_join_invisible_lobby_by_code() {
+        let mut manager = LobbyManager::new();
+        let owner = test_steam_id(12345);
+        let friend = test_steam_id(67890);
+
+        let lobby_id = manager.create_lobby(owner, LobbyType::Invisible, 4);
+        let code = manager.get_lobby(lobby_id).unwrap().to_join_code();
+        assert!(manager.search(&LobbySearchFilter::new()).is_empty());
+
+        assert_eq!(manager.join_by_code(&code, friend), Ok(lobby_id));
+        assert!(manager.get_lobby(lobby_id).unwrap().is_member(friend));
+        assert_eq!(
+            manager.join_by_code(&code, friend),
+            Err(LobbyError::AlreadyMember)
+        );
+    }
+
+    #[test]
+    fn lob_011_expired_and_malformed_codes_rejected() {
+        let mut manager = LobbyManager::new();
+        let owner = test_steam_id(12345);
+        let lobby_id = manager.create_lobby(owner, LobbyType::Public, 4);
+        let code = manager.get_lobby(lobby_id).unwrap().to_join_code();
+        manager.remove_lobby(lobby_id);
+
+        assert_eq!(
+            manager.join_by_code(&code, test_steam_id(1)),
+            Err(LobbyError::ExpiredJoinCode)
+        );
+        assert_eq!(
+            manager.join_by_code("not a code!", test_steam_id(1)),
+            Err(LobbyError::InvalidJoinCode(
+                JoinCodeError::InvalidCharacter('!')
+            ))
+        );
+    }
+
+    // =============================================================================
+    // LOB-012: Owner-Only Operations
+    // Reference: https://partner.steamgames.com/doc/api/ISteamMatchmaking#SetLobbyData
+    // =============================================================================
+
+    #[test]
+    fn lob_012_non_owner_cannot_set_lobby_data() {
+        let mut manager = LobbyManager::new();
+        let owner = test_steam_id(12345);
+        let member = test_steam_id(67890);
+        let lobby_id = manager.create_lobby(owner, LobbyType::Public, 8);
+        let lobby = manager.get_lobby_mut(lobby_id).unwrap();
+        lobby.add_member(member).unwrap();
+
+        assert_eq!(
+            lobby.set_data_as(member, "map", "de_nuke"),
+            Err(LobbyError::NotOwner)
+        );
+        assert_eq!(lobby.get_data("map"), None);
+        assert_eq!(lobby.set_data_as(owner, "map", "de_dust2"), Ok(()));
+        assert_eq!(lobby.get_data("map"), Some("de_dust2"));
+
+        // Limits still apply to the owner.
+        let long_key = "k".repeat(MAX_LOBBY_KEY_LENGTH + 1);
+        assert_eq!(
+            lobby.set_data_as(owner, &long_key, "v"),
+            Err(LobbyError::KeyTooLong)
+        );
+    }
+
+    #[test]
+    fn lob_012_owner_only_server_limit_and_transfer() {
+        let mut manager = LobbyManager::new();
+        let owner = test_steam_id(12345);
+        let member = test_steam_id(67890);
+        let lobby_id = manager.create_lobby(owner, LobbyType::Public, 8);
+        let lobby = manager.get_lobby_mut(lobby_id).unwrap();
+        lobby.add_member(member).unwrap();
+
+        assert_eq!(
+            lobby.set_game_server_as(member, 0x7f000001, 27015, None),
+            Err(LobbyError::NotOwner)
+        );
+        assert!(lobby.game_server.is_none());
+        assert_eq!(
+            lobby.set_member_limit_as(member, 16),
+            Err(LobbyError::NotOwner)
+        );
+        assert_eq!(lobby.max_members, 8);
+        assert_eq!(
+            lobby.set_owner_as(member, member),
+            Err(LobbyError::NotOwner)
+        );
+
+        lobby
+            .set_game_server_as(owner, 0x7f000001, 27015, None)
+            .unwrap();
+        assert_eq!(lobby.game_server.as_ref().unwrap().port, 27015);
+        lobby.set_member_limit_as(owner, 16).unwrap();
+        assert_eq!(lobby.max_members, 16);
+
+        // After a transfer the old owner loses the rights.
+        lobby.set_owner_as(owner, member).unwrap();
+        assert_eq!(
+            lobby.set_data_as(owner, "map", "de_inferno"),
+            Err(LobbyError::NotOwner)
+        );
+        assert_eq!(lobby.set_data_as(member, "map", "de_inferno"), Ok(()));
+    }
+
+    #[test]
+    fn lob_012_members_write_only_their_own_data() {
+        let mut manager = LobbyManager::new();
+        let owner = test_steam_id(12345);
+        let member = test_steam_id(67890);
+        let lobby_id = manager.create_lobby(owner, LobbyType::Public, 8);
+        let lobby = manager.get_lobby_mut(lobby_id).unwrap();
+        lobby.add_member(member).unwrap();
+
+        assert_eq!(
+            lobby.set_member_data_as(member, member, "team", "ct"),
+            Ok(())
+        );
+        assert_eq!(lobby.get_member_data(member, "team"), Some("ct"));
+
+        // Not even the owner may write someone else's member data.
+        assert_eq!(
+            lobby.set_member_data_as(owner, member, "team", "t"),
+            Err(LobbyError::NotPermitted)
+        );
+        assert_eq!(
+            lobby.set_member_data_as(member, owner, "team", "t"),
+            Err(LobbyError::NotPermitted)
+        );
+        assert_eq!(lobby.get_member_data(member, "team"), Some("ct"));
+    }
 }
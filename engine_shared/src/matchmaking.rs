@@ -7,15 +7,23 @@
 //!
 //! # Features
 //! - Server browser queries (internet, LAN, favorites, history)
+//! - Favorites and history saved between sessions
 //! - Server filtering with key-value pairs
 //! - A2S protocol queries (INFO, PLAYER, RULES)
 //! - Ping measurement
+//! - Quick join server scoring
+//! - Skill-based matchmaking queue
 
 use std::collections::HashMap;
-use std::net::{Ipv4Addr, SocketAddrV4};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 
+use crate::steam_id::SteamId;
+
 /// Server type for query requests.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ServerType {
@@ -52,11 +60,11 @@ impl MatchMakingKeyValuePair {
     }
 }
 
-/// Server network address.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Server network address (IPv4 or IPv6).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ServerNetAdr {
     /// IP address.
-    pub ip: u32,
+    pub ip: IpAddr,
     /// Connection port.
     pub connection_port: u16,
     /// Query port.
@@ -64,8 +72,13 @@ pub struct ServerNetAdr {
 }
 
 impl ServerNetAdr {
-    /// Create from components.
+    /// Create from a host-order IPv4 address and ports.
     pub fn new(ip: u32, connection_port: u16, query_port: u16) -> Self {
+        Self::from_ip(IpAddr::V4(Ipv4Addr::from(ip)), connection_port, query_port)
+    }
+
+    /// Create from any IP address and ports.
+    pub fn from_ip(ip: IpAddr, connection_port: u16, query_port: u16) -> Self {
         Self {
             ip,
             connection_port,
@@ -74,28 +87,29 @@ impl ServerNetAdr {
     }
 
     /// Create from socket address.
-    pub fn from_socket_addr(addr: SocketAddrV4, query_port: u16) -> Self {
-        let ip = u32::from(*addr.ip());
-        Self {
-            ip,
-            connection_port: addr.port(),
-            query_port,
-        }
+    pub fn from_socket_addr(addr: impl Into<SocketAddr>, query_port: u16) -> Self {
+        let addr = addr.into();
+        Self::from_ip(addr.ip(), addr.port(), query_port)
     }
 
-    /// Get IP as Ipv4Addr.
-    pub fn ip_addr(&self) -> Ipv4Addr {
-        Ipv4Addr::from(self.ip)
+    /// Get the IP address.
+    pub fn ip_addr(&self) -> IpAddr {
+        self.ip
     }
 
-    /// Get connection address string.
+    /// Check if this is an IPv6 address.
+    pub fn is_ipv6(&self) -> bool {
+        self.ip.is_ipv6()
+    }
+
+    /// Get connection address string (IPv6 addresses are bracketed).
     pub fn connection_address(&self) -> String {
-        format!("{}:{}", self.ip_addr(), self.connection_port)
+        SocketAddr::new(self.ip, self.connection_port).to_string()
     }
 
-    /// Get query address string.
+    /// Get query address string (IPv6 addresses are bracketed).
     pub fn query_address(&self) -> String {
-        format!("{}:{}", self.ip_addr(), self.query_port)
+        SocketAddr::new(self.ip, self.query_port).to_string()
     }
 }
 
@@ -250,12 +264,63 @@ pub enum QueryResult {
     RateLimited,
 }
 
+/// Pings at or above this score nothing for quick join.
+pub const QUICK_JOIN_MAX_PING: u32 = 300;
+
+/// How much each factor counts when quick join ranks servers.
+///
+/// Each factor scores between 0 and 1 before weighting, so the weights are
+/// directly comparable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreWeights {
+    /// Lower ping is better, falling to nothing at `QUICK_JOIN_MAX_PING`.
+    pub ping: f32,
+    /// Fuller (but never full) servers are better.
+    pub fill: f32,
+    /// VAC-secured servers are better.
+    pub secure: f32,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            ping: 1.0,
+            fill: 0.5,
+            secure: 1.0,
+        }
+    }
+}
+
+impl ScoreWeights {
+    /// Score a server, or `None` if it can't be joined because it's full.
+    pub fn score(&self, server: &GameServerInfo) -> Option<f32> {
+        if server.players >= server.max_players {
+            return None;
+        }
+        let ping = 1.0 - server.ping.min(QUICK_JOIN_MAX_PING) as f32 / QUICK_JOIN_MAX_PING as f32;
+        let fill = server.players as f32 / server.max_players as f32;
+        let secure = if server.secure { 1.0 } else { 0.0 };
+        Some(self.ping * ping + self.fill * fill + self.secure * secure)
+    }
+}
+
+/// Servers kept in the history list; the oldest are dropped past this.
+pub const MAX_SERVER_HISTORY: usize = 100;
+
+/// The parts of [`ServerBrowser`] saved between sessions.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BrowserState {
+    #[serde(default)]
+    favorites: Vec<ServerNetAdr>,
+    /// Most recent first.
+    #[serde(default)]
+    history: Vec<ServerNetAdr>,
+}
+
 /// Mock server browser for testing.
 ///
 /// In production, this would interface with Steamworks SDK.
 pub struct ServerBrowser {
-    /// App ID to filter.
-    app_id: u32,
     /// Known servers.
     servers: HashMap<ServerNetAdr, GameServerInfo>,
     /// Favorite servers.
@@ -274,9 +339,11 @@ pub struct ServerBrowser {
 
 impl ServerBrowser {
     /// Create a new server browser.
-    pub fn new(app_id: u32) -> Self {
+    ///
+    /// The app ID is accepted for Steamworks parity; filter on it with an
+    /// `appid` filter.
+    pub fn new(_app_id: u32) -> Self {
         Self {
-            app_id,
             servers: HashMap::new(),
             favorites: Vec::new(),
             history: Vec::new(),
@@ -301,7 +368,11 @@ impl ServerBrowser {
     pub fn request_server_list(&self, server_type: ServerType) -> Vec<&GameServerInfo> {
         let addrs: Vec<&ServerNetAdr> = match server_type {
             ServerType::Internet => self.servers.keys().collect(),
-            ServerType::Lan => self.servers.keys().filter(|a| Self::is_lan_addr(*a)).collect(),
+            ServerType::Lan => self
+                .servers
+                .keys()
+                .filter(|a| Self::is_lan_addr(a))
+                .collect(),
             ServerType::Friends => self.friends_servers.iter().collect(),
             ServerType::Favorites => self.favorites.iter().collect(),
             ServerType::History => self.history.iter().collect(),
@@ -316,56 +387,42 @@ impl ServerBrowser {
     }
 
     /// Check if address is LAN.
+    ///
+    /// IPv6 LAN ranges are loopback, unique local (`fc00::/7`) and
+    /// link-local (`fe80::/10`); IPv4-mapped addresses use the IPv4 rules.
     fn is_lan_addr(addr: &ServerNetAdr) -> bool {
-        let ip = Ipv4Addr::from(addr.ip);
-        ip.is_private() || ip.is_loopback()
+        fn is_lan_v4(ip: Ipv4Addr) -> bool {
+            ip.is_private() || ip.is_loopback()
+        }
+        fn is_lan_v6(ip: Ipv6Addr) -> bool {
+            let first = ip.segments()[0];
+            ip.is_loopback() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80
+        }
+
+        match addr.ip {
+            IpAddr::V4(ip) => is_lan_v4(ip),
+            IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+                Some(v4) => is_lan_v4(v4),
+                None => is_lan_v6(ip),
+            },
+        }
     }
 
     /// Check if server matches current filters.
+    #[allow(clippy::collapsible_match)]
     fn matches_filters(&self, server: &GameServerInfo) -> bool {
         for filter in &self.filters {
             match filter.key.as_str() {
-                "appid" => {
-                    if server.app_id.to_string() != filter.value {
-                        return false;
-                    }
-                }
-                "map" => {
-                    if !server.map.contains(&filter.value) {
-                        return false;
-                    }
-                }
-                "gamedir" => {
-                    if server.game_dir != filter.value {
-                        return false;
-                    }
-                }
-                "secure" => {
-                    let want_secure = filter.value == "1";
-                    if server.secure != want_secure {
-                        return false;
-                    }
-                }
-                "notfull" => {
-                    if filter.value == "1" && server.players >= server.max_players {
-                        return false;
-                    }
-                }
-                "hasplayers" => {
-                    if filter.value == "1" && server.players == 0 {
-                        return false;
-                    }
-                }
-                "noplayers" => {
-                    if filter.value == "1" && server.players > 0 {
-                        return false;
-                    }
-                }
-                "gametype" => {
-                    if !server.tags.contains(&filter.value) {
-                        return false;
-                    }
+                "appid" if server.app_id.to_string() != filter.value => return false,
+                "map" if !server.map.contains(&filter.value) => return false,
+                "gamedir" if server.game_dir != filter.value => return false,
+                "secure" if server.secure != (filter.value == "1") => return false,
+                "notfull" if filter.value == "1" && server.players >= server.max_players => {
+                    return false;
                 }
+                "hasplayers" if filter.value == "1" && server.players == 0 => return false,
+                "noplayers" if filter.value == "1" && server.players > 0 => return false,
+                "gametype" if !server.tags.contains(&filter.value) => return false,
                 _ => {}
             }
         }
@@ -401,9 +458,35 @@ impl ServerBrowser {
         // Add to front.
         self.history.insert(0, addr);
         // Limit history size.
-        if self.history.len() > 100 {
-            self.history.truncate(100);
+        self.history.truncate(MAX_SERVER_HISTORY);
+    }
+
+    /// Save favorites and history as JSON.
+    pub fn save_state(&self, path: &Path) -> std::io::Result<()> {
+        let state = BrowserState {
+            favorites: self.favorites.clone(),
+            history: self.history.clone(),
+        };
+        fs::write(path, serde_json::to_string_pretty(&state)?)
+    }
+
+    /// Load favorites and history saved by [`save_state`](Self::save_state).
+    ///
+    /// Merges into the current lists rather than replacing them: servers
+    /// already listed keep their place, and saved ones are added after them.
+    /// Current history is newer than anything saved, so it stays in front.
+    pub fn load_state(&mut self, path: &Path) -> std::io::Result<()> {
+        let state: BrowserState = serde_json::from_str(&fs::read_to_string(path)?)?;
+        for addr in state.favorites {
+            self.add_to_favorites(addr);
         }
+        for addr in state.history {
+            if !self.history.contains(&addr) {
+                self.history.push(addr);
+            }
+        }
+        self.history.truncate(MAX_SERVER_HISTORY);
+        Ok(())
     }
 
     /// Add friend's server.
@@ -425,6 +508,27 @@ impl ServerBrowser {
         }
     }
 
+    /// Pick the best server to quick join among those matching the filters.
+    ///
+    /// Full servers are never picked. Ties go to the lower ping, then the
+    /// address, so the choice is stable.
+    pub fn best_server(
+        &self,
+        server_type: ServerType,
+        weights: ScoreWeights,
+    ) -> Option<&GameServerInfo> {
+        self.request_server_list(server_type)
+            .into_iter()
+            .filter_map(|server| weights.score(server).map(|score| (score, server)))
+            .max_by(|(a_score, a), (b_score, b)| {
+                a_score
+                    .total_cmp(b_score)
+                    .then_with(|| b.ping.cmp(&a.ping))
+                    .then_with(|| b.addr.cmp(&a.addr))
+            })
+            .map(|(_, server)| server)
+    }
+
     /// Ping a server (simulated).
     pub fn ping_server(&self, addr: &ServerNetAdr) -> Option<u32> {
         self.servers.get(addr).map(|s| s.ping)
@@ -468,7 +572,7 @@ impl A2SQuery {
         if packet.len() < 5 {
             return None;
         }
-        if &packet[0..4] != &[0xFF, 0xFF, 0xFF, 0xFF] {
+        if packet[0..4] != [0xFF, 0xFF, 0xFF, 0xFF] {
             return None;
         }
         A2SResponseType::from_byte(packet[4])
@@ -479,7 +583,7 @@ impl A2SQuery {
         if packet.len() < 4 {
             return false;
         }
-        &packet[0..4] == &[0xFE, 0xFF, 0xFF, 0xFF]
+        packet[0..4] == [0xFE, 0xFF, 0xFF, 0xFF]
     }
 
     /// Maximum single packet size.
@@ -528,8 +632,191 @@ impl Default for ServerRules {
     }
 }
 
+/// How far apart in MMR queued players may be matched.
+///
+/// The window starts at `initial` and grows by `growth_per_second` for every
+/// second the longest-waiting player has been queued, up to `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MmrWindow {
+    /// Spread allowed immediately after queueing.
+    pub initial: i32,
+    /// Additional spread per second of waiting.
+    pub growth_per_second: i32,
+    /// Upper bound on the spread.
+    pub max: i32,
+}
+
+impl MmrWindow {
+    /// Allowed spread after waiting for `waited`.
+    pub fn spread(&self, waited: Duration) -> i32 {
+        let growth = self.growth_per_second as i64 * waited.as_secs() as i64;
+        (self.initial as i64 + growth).min(self.max as i64) as i32
+    }
+}
+
+impl Default for MmrWindow {
+    fn default() -> Self {
+        Self {
+            initial: 100,
+            growth_per_second: 25,
+            max: 1000,
+        }
+    }
+}
+
+/// Matchmaking queue error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueError {
+    /// This player is already queued.
+    AlreadyQueued,
+    /// Party size is zero or larger than a match.
+    InvalidPartySize(u32),
+}
+
+/// A match formed by the queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchFound {
+    /// Queued players (party leaders for parties), in queue order.
+    pub players: Vec<SteamId>,
+}
+
+#[derive(Debug, Clone)]
+struct QueueEntry {
+    steam_id: SteamId,
+    mmr: i32,
+    party_size: u32,
+    enqueued_at: Instant,
+}
+
+/// Skill-based matchmaking queue.
+///
+/// Each entry is a player or a party (queued under its leader) that fills
+/// `party_size` slots; parties are never split. [`tick`](Self::tick) forms
+/// matches of exactly `match_size` slots, anchored on the longest-waiting
+/// entry and taking the closest MMRs within its current [`MmrWindow`].
+pub struct MatchmakingQueue {
+    /// Slots per match.
+    match_size: u32,
+    /// MMR spread policy.
+    window: MmrWindow,
+    /// Waiting entries, oldest first.
+    entries: Vec<QueueEntry>,
+}
+
+impl MatchmakingQueue {
+    /// Create a queue forming matches of `match_size` players.
+    pub fn new(match_size: u32) -> Self {
+        Self::with_window(match_size, MmrWindow::default())
+    }
+
+    /// Create a queue with a custom MMR window.
+    pub fn with_window(match_size: u32, window: MmrWindow) -> Self {
+        Self {
+            match_size,
+            window,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Queue a player, or a party of `party_size` under its leader.
+    pub fn enqueue(
+        &mut self,
+        steam_id: SteamId,
+        mmr: i32,
+        party_size: u32,
+    ) -> Result<(), QueueError> {
+        if party_size == 0 || party_size > self.match_size {
+            return Err(QueueError::InvalidPartySize(party_size));
+        }
+        if self.is_queued(steam_id) {
+            return Err(QueueError::AlreadyQueued);
+        }
+        self.entries.push(QueueEntry {
+            steam_id,
+            mmr,
+            party_size,
+            enqueued_at: Instant::now(),
+        });
+        Ok(())
+    }
+
+    /// Leave the queue. Returns whether the player was queued.
+    pub fn dequeue(&mut self, steam_id: SteamId) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.steam_id != steam_id);
+        self.entries.len() != before
+    }
+
+    /// Check if a player is queued.
+    pub fn is_queued(&self, steam_id: SteamId) -> bool {
+        self.entries.iter().any(|e| e.steam_id == steam_id)
+    }
+
+    /// Number of queued entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Form as many matches as possible at time `now`.
+    pub fn tick(&mut self, now: Instant) -> Vec<MatchFound> {
+        let mut matches = Vec::new();
+        let mut anchor = 0;
+        while anchor < self.entries.len() {
+            match self.try_form_match(anchor, now) {
+                Some(picked) => {
+                    let players = picked.iter().map(|&i| self.entries[i].steam_id).collect();
+                    for &i in picked.iter().rev() {
+                        self.entries.remove(i);
+                    }
+                    matches.push(MatchFound { players });
+                }
+                None => anchor += 1,
+            }
+        }
+        matches
+    }
+
+    /// Pick entries filling a match around `anchor`, returned in queue order.
+    fn try_form_match(&self, anchor: usize, now: Instant) -> Option<Vec<usize>> {
+        let base = &self.entries[anchor];
+        let spread = self
+            .window
+            .spread(now.saturating_duration_since(base.enqueued_at));
+
+        let mut candidates: Vec<usize> = (0..self.entries.len())
+            .filter(|&i| i != anchor && (self.entries[i].mmr - base.mmr).abs() <= spread)
+            .collect();
+        candidates.sort_by_key(|&i| (self.entries[i].mmr - base.mmr).abs());
+
+        let mut picked = vec![anchor];
+        let mut slots = base.party_size;
+        for i in candidates {
+            if slots == self.match_size {
+                break;
+            }
+            if slots + self.entries[i].party_size <= self.match_size {
+                slots += self.entries[i].party_size;
+                picked.push(i);
+            }
+        }
+
+        if slots != self.match_size {
+            return None;
+        }
+        picked.sort_unstable();
+        Some(picked)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::net::{SocketAddrV4, SocketAddrV6};
+
     use super::*;
 
     fn create_test_server(name: &str, map: &str, players: u8, max_players: u8) -> GameServerInfo {
@@ -727,6 +1014,47 @@ mod tests {
         assert_eq!(addr.query_port, 27016);
     }
 
+    #[test]
+    fn mm_008_ipv6_server_net_adr() {
+        let ip: Ipv6Addr = "2001:db8::10".parse().unwrap();
+        let socket = SocketAddrV6::new(ip, 27015, 0, 0);
+        let addr = ServerNetAdr::from_socket_addr(socket, 27016);
+
+        assert!(addr.is_ipv6());
+        assert_eq!(addr.ip_addr(), IpAddr::V6(ip));
+        assert_eq!(addr.connection_address(), "[2001:db8::10]:27015");
+        assert_eq!(addr.query_address(), "[2001:db8::10]:27016");
+        assert!(!ServerNetAdr::new(0x0A000001, 27015, 27015).is_ipv6());
+    }
+
+    #[test]
+    fn mm_002_ipv6_lan_detection() {
+        let v6 = |s: &str| ServerNetAdr::from_ip(s.parse().unwrap(), 27015, 27015);
+
+        assert!(ServerBrowser::is_lan_addr(&v6("fe80::1")));
+        assert!(ServerBrowser::is_lan_addr(&v6("febf::1")));
+        assert!(ServerBrowser::is_lan_addr(&v6("fc00::1")));
+        assert!(ServerBrowser::is_lan_addr(&v6("fd12:3456::1")));
+        assert!(ServerBrowser::is_lan_addr(&v6("::1")));
+        assert!(ServerBrowser::is_lan_addr(&v6("::ffff:192.168.1.1")));
+        assert!(!ServerBrowser::is_lan_addr(&v6("fec0::1")));
+        assert!(!ServerBrowser::is_lan_addr(&v6("2001:db8::1")));
+        assert!(!ServerBrowser::is_lan_addr(&v6("::ffff:8.8.8.8")));
+
+        let mut browser = ServerBrowser::new(730);
+        browser.add_server(
+            v6("fd00::5"),
+            create_test_server("LAN v6", "de_dust2", 5, 10),
+        );
+        browser.add_server(
+            v6("2001:db8::5"),
+            create_test_server("WAN v6", "de_dust2", 5, 10),
+        );
+        let servers = browser.request_server_list(ServerType::Lan);
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].server_name, "LAN v6");
+    }
+
     // =============================================================================
     // MM-009: Ping Measurement
     // =============================================================================
@@ -894,4 +1222,232 @@ mod tests {
         assert_eq!(servers.len(), 1);
         assert_eq!(servers[0].server_name, "Active");
     }
+
+    // =============================================================================
+    // Matchmaking queue
+    // =============================================================================
+
+    fn player(n: u32) -> SteamId {
+        SteamId::from_account_id(n)
+    }
+
+    #[test]
+    fn queue_matches_compatible_mmr() {
+        let mut queue = MatchmakingQueue::new(4);
+        queue.enqueue(player(1), 1500, 1).unwrap();
+        queue.enqueue(player(2), 1540, 2).unwrap();
+        queue.enqueue(player(3), 2400, 1).unwrap();
+        queue.enqueue(player(4), 1460, 1).unwrap();
+
+        let matches = queue.tick(Instant::now());
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].players, vec![player(1), player(2), player(4)]);
+        assert_eq!(queue.len(), 1);
+        assert!(queue.is_queued(player(3)));
+    }
+
+    #[test]
+    fn queue_widens_window_over_time() {
+        let window = MmrWindow {
+            initial: 50,
+            growth_per_second: 10,
+            max: 500,
+        };
+        let mut queue = MatchmakingQueue::with_window(2, window);
+        queue.enqueue(player(1), 1000, 1).unwrap();
+        queue.enqueue(player(2), 1200, 1).unwrap();
+
+        let start = Instant::now();
+        assert!(queue.tick(start).is_empty());
+        assert!(queue.tick(start + Duration::from_secs(10)).is_empty());
+
+        // 50 + 10 * 15 = 200 covers the gap.
+        let matches = queue.tick(start + Duration::from_secs(15));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].players, vec![player(1), player(2)]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn queue_keeps_parties_together() {
+        let mut queue = MatchmakingQueue::new(5);
+        queue.enqueue(player(1), 1000, 3).unwrap();
+        queue.enqueue(player(2), 1000, 3).unwrap();
+        assert!(queue.tick(Instant::now()).is_empty());
+
+        queue.enqueue(player(3), 1010, 2).unwrap();
+        let matches = queue.tick(Instant::now());
+        assert_eq!(
+            matches,
+            vec![MatchFound {
+                players: vec![player(1), player(3)]
+            }]
+        );
+        assert!(queue.is_queued(player(2)));
+    }
+
+    #[test]
+    fn queue_rejects_invalid_entries() {
+        let mut queue = MatchmakingQueue::new(5);
+        assert_eq!(
+            queue.enqueue(player(1), 1000, 0),
+            Err(QueueError::InvalidPartySize(0))
+        );
+        assert_eq!(
+            queue.enqueue(player(1), 1000, 6),
+            Err(QueueError::InvalidPartySize(6))
+        );
+        queue.enqueue(player(1), 1000, 1).unwrap();
+        assert_eq!(
+            queue.enqueue(player(1), 1200, 1),
+            Err(QueueError::AlreadyQueued)
+        );
+        assert!(queue.dequeue(player(1)));
+        assert!(!queue.dequeue(player(1)));
+    }
+
+    // =============================================================================
+    // MM-QJ: Quick Join Scoring
+    // =============================================================================
+
+    fn quick_join_browser() -> ServerBrowser {
+        let mut browser = ServerBrowser::new(730);
+        let servers = [
+            // (name, ping, players, max, secure)
+            ("full", 10, 24, 24, true),
+            ("insecure", 15, 12, 24, false),
+            ("near", 30, 12, 24, true),
+            ("busy", 120, 22, 24, true),
+            ("empty", 40, 0, 24, true),
+        ];
+        for (i, (name, ping, players, max_players, secure)) in servers.into_iter().enumerate() {
+            let mut info = create_test_server(name, "de_dust2", players, max_players);
+            info.addr = format!("203.0.113.{}:27015", i + 1);
+            info.ping = ping;
+            info.secure = secure;
+            browser.add_server(
+                ServerNetAdr::new(0xCB00_7101 + i as u32, 27015, 27015),
+                info,
+            );
+        }
+        browser
+    }
+
+    #[test]
+    fn mm_qj_lowest_ping_joinable_secure_server_wins() {
+        let browser = quick_join_browser();
+        let best = browser
+            .best_server(ServerType::Internet, ScoreWeights::default())
+            .unwrap();
+        assert_eq!(best.server_name, "near");
+    }
+
+    #[test]
+    fn mm_qj_weights_change_the_choice() {
+        let browser = quick_join_browser();
+        let no_ping = ScoreWeights {
+            ping: 0.0,
+            ..Default::default()
+        };
+        let best = browser.best_server(ServerType::Internet, no_ping).unwrap();
+        assert_eq!(best.server_name, "busy");
+
+        let ping_only = ScoreWeights {
+            ping: 1.0,
+            fill: 0.0,
+            secure: 0.0,
+        };
+        let best = browser
+            .best_server(ServerType::Internet, ping_only)
+            .unwrap();
+        // The full server is closer, but can't be joined.
+        assert_eq!(best.server_name, "insecure");
+    }
+
+    #[test]
+    fn mm_qj_none_when_nothing_matches() {
+        let mut browser = quick_join_browser();
+        assert!(browser
+            .best_server(ServerType::Favorites, ScoreWeights::default())
+            .is_none());
+
+        browser.add_filter("map", "de_nuke");
+        assert!(browser
+            .best_server(ServerType::Internet, ScoreWeights::default())
+            .is_none());
+    }
+
+    // =============================================================================
+    // MM-PERSIST: Favorites and history across sessions
+    // =============================================================================
+
+    fn state_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("mm_state_{}_{}.json", std::process::id(), name))
+    }
+
+    #[test]
+    fn mm_persist_roundtrip() {
+        let mut browser = ServerBrowser::new(730);
+        let v6 = ServerNetAdr::from_ip("2001:db8::1".parse().unwrap(), 27015, 27016);
+        browser.add_to_favorites(ServerNetAdr::new(0x0A000001, 27015, 27015));
+        browser.add_to_favorites(v6);
+        for i in 1..=3 {
+            browser.add_to_history(ServerNetAdr::new(0xC0A80000 + i, 27015, 27015));
+        }
+
+        let path = state_path("roundtrip");
+        browser.save_state(&path).unwrap();
+        let mut restored = ServerBrowser::new(730);
+        restored.load_state(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(restored.favorites, browser.favorites);
+        assert_eq!(
+            restored.history,
+            vec![
+                ServerNetAdr::new(0xC0A80003, 27015, 27015),
+                ServerNetAdr::new(0xC0A80002, 27015, 27015),
+                ServerNetAdr::new(0xC0A80001, 27015, 27015),
+            ]
+        );
+    }
+
+    #[test]
+    fn mm_persist_load_merges_and_dedupes() {
+        let a = ServerNetAdr::new(0xC0A80001, 27015, 27015);
+        let b = ServerNetAdr::new(0xC0A80002, 27015, 27015);
+        let c = ServerNetAdr::new(0xC0A80003, 27015, 27015);
+
+        let mut saved = ServerBrowser::new(730);
+        saved.add_to_favorites(a);
+        saved.add_to_favorites(b);
+        saved.add_to_history(a);
+        saved.add_to_history(b); // history: b, a
+        let path = state_path("merge");
+        saved.save_state(&path).unwrap();
+
+        let mut browser = ServerBrowser::new(730);
+        browser.add_to_favorites(b);
+        browser.add_to_favorites(c);
+        browser.add_to_history(c);
+        browser.add_to_history(a); // history: a, c
+        browser.load_state(&path).unwrap();
+        // Loading twice changes nothing.
+        browser.load_state(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(browser.favorites, vec![b, c, a]);
+        assert_eq!(browser.history, vec![a, c, b]);
+    }
+
+    #[test]
+    fn mm_persist_load_rejects_bad_file() {
+        let path = state_path("bad");
+        std::fs::write(&path, "not json").unwrap();
+        let mut browser = ServerBrowser::new(730);
+        let err = browser.load_state(&path).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(browser.favorites.is_empty());
+    }
 }
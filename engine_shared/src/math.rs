@@ -5,6 +5,8 @@
 
 use serde::{Deserialize, Serialize};
 
+pub mod det;
+
 /// 3D vector.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
 pub struct Vec3 {
@@ -32,6 +34,20 @@ impl Vec3 {
         self.dot(self)
     }
 
+    /// Length, via [`det::length`].
+    pub fn length(self) -> f32 {
+        det::length(self)
+    }
+
+    /// Unit vector in the same direction, via [`det::normalize`].
+    pub fn normalized(self) -> Self {
+        det::normalize(self)
+    }
+
+    pub fn distance_sq(self, other: Self) -> f32 {
+        Self::new(self.x - other.x, self.y - other.y, self.z - other.z).len_sq()
+    }
+
     pub fn lerp(self, to: Self, t: f32) -> Self {
         let t = t.clamp(0.0, 1.0);
         Self::new(
@@ -42,6 +58,157 @@ impl Vec3 {
     }
 }
 
+/// Axis-aligned bounding box, with inclusive bounds.
+///
+/// A box is empty when `min > max` on any axis; [`Aabb::EMPTY`] is the
+/// identity for [`Aabb::merge`]. A box with `min == max` is a single point
+/// and is not empty.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// Box containing nothing.
+    pub const EMPTY: Self = Self {
+        min: Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        max: Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+    };
+
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Smallest box containing all `points`; empty if there are none.
+    pub fn from_points(points: &[Vec3]) -> Self {
+        points
+            .iter()
+            .fold(Self::EMPTY, |acc, &p| acc.merge(&Self::new(p, p)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// Whether `p` is inside or on the boundary.
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x)
+            && (self.min.y..=self.max.y).contains(&p.y)
+            && (self.min.z..=self.max.z).contains(&p.z)
+    }
+
+    /// Whether the boxes overlap; touching faces count. Empty boxes never
+    /// intersect anything.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+            && self.min.z <= other.max.z
+            && other.min.z <= self.max.z
+    }
+
+    /// Smallest box containing both.
+    pub fn merge(&self, other: &Aabb) -> Aabb {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        Aabb::new(
+            Vec3::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            Vec3::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        )
+    }
+
+    /// Grows the box by `margin` on every side; a negative margin shrinks
+    /// it. Empty boxes stay empty.
+    pub fn expand(&self, margin: f32) -> Aabb {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+        Aabb::new(
+            Vec3::new(
+                self.min.x - margin,
+                self.min.y - margin,
+                self.min.z - margin,
+            ),
+            Vec3::new(
+                self.max.x + margin,
+                self.max.y + margin,
+                self.max.z + margin,
+            ),
+        )
+    }
+
+    pub fn center(&self) -> Vec3 {
+        self.min.lerp(self.max, 0.5)
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+/// Interpolates between two angles in degrees along the shorter arc.
+///
+/// `t` is not clamped, so values outside `[0, 1]` extrapolate.
+pub fn lerp_angle(a: f32, b: f32, t: f32) -> f32 {
+    let delta = (b - a + 180.0).rem_euclid(360.0) - 180.0;
+    a + delta * t
+}
+
+/// Converts Source eye angles (degrees) to forward, right and up vectors.
+///
+/// Z is up; yaw 0 faces +X and yaw 90 faces +Y. Positive pitch looks down.
+/// Built on [`det::sin_cos`], so the result is deterministic.
+pub fn angle_vectors(pitch: f32, yaw: f32, roll: f32) -> (Vec3, Vec3, Vec3) {
+    let (sp, cp) = det::sin_cos(pitch.to_radians());
+    let (sy, cy) = det::sin_cos(yaw.to_radians());
+    let (sr, cr) = det::sin_cos(roll.to_radians());
+
+    let forward = Vec3::new(cp * cy, cp * sy, -sp);
+    let right = Vec3::new(-sr * sp * cy + cr * sy, -sr * sp * sy - cr * cy, -sr * cp);
+    let up = Vec3::new(cr * sp * cy + sr * sy, cr * sp * sy - sr * cy, cr * cp);
+    (forward, right, up)
+}
+
+/// Converts a forward vector to `(pitch, yaw)` in degrees, both in `[0, 360)`.
+///
+/// Straight up gives pitch 270 and straight down pitch 90, with yaw 0.
+/// Uses `f32::atan2`, which isn't covered by the [`det`] guarantees.
+pub fn vector_angles(forward: Vec3) -> (f32, f32) {
+    if forward.x == 0.0 && forward.y == 0.0 {
+        let pitch = if forward.z > 0.0 { 270.0 } else { 90.0 };
+        return (pitch, 0.0);
+    }
+
+    let mut yaw = forward.y.atan2(forward.x).to_degrees();
+    if yaw < 0.0 {
+        yaw += 360.0;
+    }
+    let horizontal = det::sqrt(forward.x * forward.x + forward.y * forward.y);
+    let mut pitch = (-forward.z).atan2(horizontal).to_degrees();
+    if pitch < 0.0 {
+        pitch += 360.0;
+    }
+    (pitch, yaw)
+}
+
 /// Unit quaternion (conceptually). Kept minimal for now.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Quat {
@@ -53,13 +220,61 @@ pub struct Quat {
 
 impl Default for Quat {
     fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Quat {
+    pub const IDENTITY: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+        w: 1.0,
+    };
+
+    /// Rotation of `degrees` about `axis` (counter-clockwise looking down
+    /// the axis).
+    pub fn from_axis_angle(axis: Vec3, degrees: f32) -> Self {
+        let axis = axis.normalized();
+        let (sin, cos) = (degrees.to_radians() * 0.5).sin_cos();
         Self {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-            w: 1.0,
+            x: axis.x * sin,
+            y: axis.y * sin,
+            z: axis.z * sin,
+            w: cos,
         }
     }
+
+    /// Rotation applying `rhs` first, then `self`.
+    pub fn compose(self, rhs: Self) -> Self {
+        Self {
+            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        }
+    }
+
+    /// Rotates a vector.
+    pub fn rotate(self, v: Vec3) -> Vec3 {
+        // v + 2w(q x v) + 2(q x (q x v))
+        let q = Vec3::new(self.x, self.y, self.z);
+        let cross = |a: Vec3, b: Vec3| {
+            Vec3::new(
+                a.y * b.z - a.z * b.y,
+                a.z * b.x - a.x * b.z,
+                a.x * b.y - a.y * b.x,
+            )
+        };
+        let t = cross(q, v);
+        let t = Vec3::new(2.0 * t.x, 2.0 * t.y, 2.0 * t.z);
+        let u = cross(q, t);
+        Vec3::new(
+            v.x + self.w * t.x + u.x,
+            v.y + self.w * t.y + u.y,
+            v.z + self.w * t.z + u.z,
+        )
+    }
 }
 
 /// 4x4 matrix (column-major). Placeholder for transforms.
@@ -92,4 +307,159 @@ mod tests {
         let mid = a.lerp(b, 0.5);
         assert_eq!(mid, Vec3::new(1.0, 2.0, 3.0));
     }
+
+    fn vec_approx(a: Vec3, b: Vec3) -> bool {
+        (a.x - b.x).abs() < 1e-5 && (a.y - b.y).abs() < 1e-5 && (a.z - b.z).abs() < 1e-5
+    }
+
+    #[test]
+    fn angle_vectors_cardinal_yaws() {
+        let (f, r, u) = angle_vectors(0.0, 0.0, 0.0);
+        assert!(vec_approx(f, Vec3::new(1.0, 0.0, 0.0)));
+        assert!(vec_approx(r, Vec3::new(0.0, -1.0, 0.0)));
+        assert!(vec_approx(u, Vec3::new(0.0, 0.0, 1.0)));
+
+        let (f, r, _) = angle_vectors(0.0, 90.0, 0.0);
+        assert!(vec_approx(f, Vec3::new(0.0, 1.0, 0.0)));
+        assert!(vec_approx(r, Vec3::new(1.0, 0.0, 0.0)));
+
+        let (f, r, _) = angle_vectors(0.0, 180.0, 0.0);
+        assert!(vec_approx(f, Vec3::new(-1.0, 0.0, 0.0)));
+        assert!(vec_approx(r, Vec3::new(0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn angle_vectors_pitch_and_roll() {
+        // Looking straight down.
+        let (f, _, u) = angle_vectors(90.0, 0.0, 0.0);
+        assert!(vec_approx(f, Vec3::new(0.0, 0.0, -1.0)));
+        assert!(vec_approx(u, Vec3::new(1.0, 0.0, 0.0)));
+
+        // Rolling 90 degrees tips "up" over to the old right side.
+        let (_, r, u) = angle_vectors(0.0, 0.0, 90.0);
+        assert!(vec_approx(u, Vec3::new(0.0, -1.0, 0.0)));
+        assert!(vec_approx(r, Vec3::new(0.0, 0.0, -1.0)));
+    }
+
+    #[test]
+    fn vector_angles_roundtrip() {
+        for &(pitch, yaw) in &[(0.0, 0.0), (30.0, 45.0), (330.0, 200.0), (80.0, 359.0)] {
+            let (f, _, _) = angle_vectors(pitch, yaw, 0.0);
+            let (p2, y2) = vector_angles(f);
+            assert!((p2 - pitch).abs() < 1e-3, "pitch {pitch} -> {p2}");
+            assert!((y2 - yaw).abs() < 1e-3, "yaw {yaw} -> {y2}");
+        }
+
+        assert_eq!(vector_angles(Vec3::new(0.0, 0.0, 1.0)), (270.0, 0.0));
+        assert_eq!(vector_angles(Vec3::new(0.0, 0.0, -1.0)), (90.0, 0.0));
+    }
+
+    #[test]
+    fn quat_rotate_and_compose() {
+        let yaw90 = Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 90.0);
+        assert!(vec_approx(
+            yaw90.rotate(Vec3::new(1.0, 0.0, 0.0)),
+            Vec3::new(0.0, 1.0, 0.0)
+        ));
+        assert!(vec_approx(
+            yaw90.compose(yaw90).rotate(Vec3::new(1.0, 0.0, 0.0)),
+            Vec3::new(-1.0, 0.0, 0.0)
+        ));
+
+        // Pitch first, then yaw: forward tips down, then swings to +y.
+        let pitch90 = Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 90.0);
+        assert!(vec_approx(
+            yaw90.compose(pitch90).rotate(Vec3::new(1.0, 0.0, 0.0)),
+            Vec3::new(0.0, 0.0, -1.0)
+        ));
+        assert!(vec_approx(
+            pitch90.compose(yaw90).rotate(Vec3::new(1.0, 0.0, 0.0)),
+            Vec3::new(0.0, 1.0, 0.0)
+        ));
+        assert_eq!(
+            Quat::IDENTITY.rotate(Vec3::new(1.0, 2.0, 3.0)),
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+    }
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vec3::ZERO, Vec3::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn aabb_overlap_detection() {
+        let a = unit_box();
+        let overlapping = Aabb::new(Vec3::new(0.5, 0.5, 0.5), Vec3::new(2.0, 2.0, 2.0));
+        let touching = Aabb::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0));
+        let apart = Aabb::new(Vec3::new(1.5, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0));
+
+        assert!(a.intersects(&overlapping));
+        assert!(overlapping.intersects(&a));
+        assert!(a.intersects(&touching));
+        assert!(!a.intersects(&apart));
+        assert!(!a.intersects(&Aabb::EMPTY));
+        assert!(!Aabb::EMPTY.intersects(&Aabb::EMPTY));
+    }
+
+    #[test]
+    fn aabb_contains_point_on_boundaries() {
+        let a = unit_box();
+        assert!(a.contains_point(Vec3::new(0.5, 0.5, 0.5)));
+        assert!(a.contains_point(Vec3::ZERO));
+        assert!(a.contains_point(Vec3::new(1.0, 1.0, 1.0)));
+        assert!(a.contains_point(Vec3::new(1.0, 0.0, 0.5)));
+        assert!(!a.contains_point(Vec3::new(1.0001, 0.5, 0.5)));
+        assert!(!a.contains_point(Vec3::new(0.5, -0.0001, 0.5)));
+        assert!(!Aabb::EMPTY.contains_point(Vec3::ZERO));
+    }
+
+    #[test]
+    fn aabb_merge_disjoint_boxes() {
+        let a = unit_box();
+        let b = Aabb::new(Vec3::new(5.0, -2.0, 3.0), Vec3::new(6.0, -1.0, 4.0));
+        let m = a.merge(&b);
+        assert_eq!(
+            m,
+            Aabb::new(Vec3::new(0.0, -2.0, 0.0), Vec3::new(6.0, 1.0, 4.0))
+        );
+        assert_eq!(b.merge(&a), m);
+        assert_eq!(a.merge(&Aabb::EMPTY), a);
+        assert_eq!(Aabb::EMPTY.merge(&a), a);
+    }
+
+    #[test]
+    fn aabb_from_points_and_expand() {
+        assert!(Aabb::from_points(&[]).is_empty());
+        assert_eq!(Aabb::default(), Aabb::EMPTY);
+
+        let point = Aabb::from_points(&[Vec3::new(2.0, 3.0, 4.0)]);
+        assert!(!point.is_empty());
+        assert!(point.contains_point(Vec3::new(2.0, 3.0, 4.0)));
+
+        let b = Aabb::from_points(&[
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(-2.0, 4.0, 1.0),
+            Vec3::new(0.0, 0.0, -3.0),
+        ]);
+        assert_eq!(
+            b,
+            Aabb::new(Vec3::new(-2.0, -1.0, -3.0), Vec3::new(1.0, 4.0, 1.0))
+        );
+
+        let grown = unit_box().expand(1.0);
+        assert_eq!(
+            grown,
+            Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(2.0, 2.0, 2.0))
+        );
+        assert!(unit_box().expand(-0.6).is_empty());
+        assert!(Aabb::EMPTY.expand(10.0).is_empty());
+    }
+
+    #[test]
+    fn lerp_angle_takes_shorter_arc() {
+        assert_eq!(lerp_angle(10.0, 30.0, 0.5), 20.0);
+        assert_eq!(lerp_angle(350.0, 10.0, 0.5), 360.0);
+        assert_eq!(lerp_angle(10.0, 350.0, 0.5), 0.0);
+        assert_eq!(lerp_angle(0.0, 10.0, 1.5), 15.0);
+    }
 }
@@ -0,0 +1,211 @@
+//! Deterministic float helpers.
+//!
+//! Simulation code that must agree bit-for-bit between client, server and
+//! replays should use these instead of the `f32` transcendental methods.
+//!
+//! # Guarantees
+//! - `+`, `-`, `*`, `/` on `f32` are IEEE 754 correctly rounded. Rust never
+//!   fuses them into FMAs, so they are deterministic on every supported target.
+//! - [`sqrt`] is computed in integer arithmetic and correctly rounded, so it
+//!   matches IEEE `sqrtf` bit-for-bit without relying on the host FPU.
+//! - [`sin`], [`cos`] and [`sin_cos`] use only the operations above (range
+//!   reduction plus fixed polynomials), so their results are identical
+//!   everywhere. They are accurate to a few ULP for `|x| <= 65536`; larger
+//!   inputs stay deterministic but lose precision.
+//! - [`length`] and [`normalize`] are built on [`sqrt`].
+//!
+//! `f32::sin`, `f32::cos`, `f32::powf` and friends call the platform libm and
+//! are **not** guaranteed to match across hosts.
+
+use super::Vec3;
+
+/// Correctly rounded square root.
+///
+/// Negative inputs and NaN give NaN; `-0.0` gives `-0.0`.
+pub fn sqrt(x: f32) -> f32 {
+    let bits = x.to_bits();
+    if x.is_nan() || x < 0.0 {
+        return f32::NAN;
+    }
+    if x == 0.0 || x.is_infinite() {
+        return x;
+    }
+
+    // Split into a 24-bit mantissa `m` and exponent `e` with x = m * 2^e,
+    // normalizing subnormals.
+    let raw_exp = ((bits >> 23) & 0xff) as i32;
+    let (mut m, mut e) = if raw_exp == 0 {
+        (u64::from(bits & 0x7f_ffff), -149)
+    } else {
+        (u64::from(bits & 0x7f_ffff) | 0x80_0000, raw_exp - 150)
+    };
+    while m & 0x80_0000 == 0 {
+        m <<= 1;
+        e -= 1;
+    }
+
+    // Scale m into [2^48, 2^50) with an even remaining exponent, so the
+    // integer root has 24 mantissa bits plus one guard bit.
+    let shift = if (e - 25) % 2 == 0 { 25 } else { 26 };
+    let root = isqrt(m << shift);
+    e = (e - shift) / 2 + 1;
+
+    // The exact root is never a rounding midpoint, so the guard bit alone
+    // decides the rounding direction.
+    let mut mant = (root >> 1) + (root & 1);
+    if mant == 1 << 24 {
+        mant >>= 1;
+        e += 1;
+    }
+
+    let biased = (e + 150) as u32;
+    f32::from_bits((biased << 23) | (mant as u32 & 0x7f_ffff))
+}
+
+/// Integer square root, rounded down.
+fn isqrt(n: u64) -> u64 {
+    let mut rem = n;
+    let mut root = 0u64;
+    let mut bit = 1u64 << 62;
+    while bit > n {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if rem >= root + bit {
+            rem -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+// pi/2 split so k * PIO2_1 and k * PIO2_2 are exact for moderate k.
+const PIO2_1: f32 = 1.570_312_5;
+const PIO2_2: f32 = 4.837_513e-4;
+const PIO2_3: f32 = 7.549_79e-8;
+
+/// Sine of `x` radians.
+pub fn sin(x: f32) -> f32 {
+    sin_cos(x).0
+}
+
+/// Cosine of `x` radians.
+pub fn cos(x: f32) -> f32 {
+    sin_cos(x).1
+}
+
+/// Sine and cosine of `x` radians.
+pub fn sin_cos(x: f32) -> (f32, f32) {
+    if !x.is_finite() {
+        return (f32::NAN, f32::NAN);
+    }
+
+    // Reduce to r in [-pi/4, pi/4] and a quadrant.
+    let k = (x * std::f32::consts::FRAC_2_PI).round();
+    let r = ((x - k * PIO2_1) - k * PIO2_2) - k * PIO2_3;
+    let (s, c) = (sin_poly(r), cos_poly(r));
+
+    match (k as i64).rem_euclid(4) {
+        0 => (s, c),
+        1 => (c, -s),
+        2 => (-s, -c),
+        _ => (-c, s),
+    }
+}
+
+/// Minimax sine on [-pi/4, pi/4].
+fn sin_poly(x: f32) -> f32 {
+    let z = x * x;
+    ((-1.951_529_6e-4 * z + 8.332_161e-3) * z - 1.666_665_5e-1) * z * x + x
+}
+
+/// Minimax cosine on [-pi/4, pi/4].
+fn cos_poly(x: f32) -> f32 {
+    let z = x * x;
+    ((2.443_315_7e-5 * z - 1.388_731_6e-3) * z + 4.166_664_6e-2) * z * z - 0.5 * z + 1.0
+}
+
+/// Length of `v`.
+pub fn length(v: Vec3) -> f32 {
+    sqrt(v.len_sq())
+}
+
+/// `v` scaled to unit length, or zero if `v` has no length.
+pub fn normalize(v: Vec3) -> Vec3 {
+    let len = length(v);
+    if len > 0.0 {
+        Vec3::new(v.x / len, v.y / len, v.z / len)
+    } else {
+        Vec3::ZERO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqrt_known_bit_patterns() {
+        assert_eq!(sqrt(4.0).to_bits(), 0x4000_0000);
+        assert_eq!(sqrt(2.0).to_bits(), 0x3fb5_04f3);
+        assert_eq!(sqrt(0.5).to_bits(), 0x3f35_04f3);
+        assert_eq!(sqrt(3.0).to_bits(), 0x3fdd_b3d7);
+        // Smallest subnormal: sqrt(2^-149) = 2^-74.5.
+        assert_eq!(sqrt(f32::from_bits(1)).to_bits(), 0x1a35_04f3);
+        assert_eq!(sqrt(f32::MAX).to_bits(), 0x5f7f_ffff);
+    }
+
+    #[test]
+    fn sqrt_special_values() {
+        assert!(sqrt(-1.0).is_nan());
+        assert!(sqrt(f32::NAN).is_nan());
+        assert_eq!(sqrt(f32::INFINITY), f32::INFINITY);
+        assert_eq!(sqrt(0.0).to_bits(), 0.0f32.to_bits());
+        assert_eq!(sqrt(-0.0).to_bits(), (-0.0f32).to_bits());
+    }
+
+    #[test]
+    fn sqrt_matches_ieee_for_sampled_inputs() {
+        // Walk the bit space, including subnormals and every exponent.
+        let mut bits = 1u32;
+        while bits < 0x7f80_0000 {
+            let x = f32::from_bits(bits);
+            assert_eq!(sqrt(x).to_bits(), x.sqrt().to_bits(), "sqrt({x:e})");
+            bits += 0x1_2345;
+        }
+    }
+
+    #[test]
+    fn sin_cos_known_bit_patterns() {
+        assert_eq!(sin(0.0).to_bits(), 0);
+        assert_eq!(cos(0.0).to_bits(), 1.0f32.to_bits());
+        assert_eq!(sin(0.5).to_bits(), 0x3ef5_7744);
+        assert_eq!(cos(0.5).to_bits(), 0x3f60_a940);
+        assert_eq!(sin(2.0).to_bits(), 0x3f68_c7b7);
+        assert_eq!(cos(-3.0).to_bits(), 0xbf7d_7026);
+    }
+
+    #[test]
+    fn sin_cos_close_to_libm() {
+        let mut x = -100.0f32;
+        while x < 100.0 {
+            let (s, c) = sin_cos(x);
+            assert!((s - x.sin()).abs() < 2e-6, "sin({x})");
+            assert!((c - x.cos()).abs() < 2e-6, "cos({x})");
+            x += 0.0137;
+        }
+        assert!(sin(f32::INFINITY).is_nan());
+    }
+
+    #[test]
+    fn length_and_normalize() {
+        let v = Vec3::new(3.0, 4.0, 12.0);
+        assert_eq!(length(v), 13.0);
+        let n = normalize(v);
+        assert_eq!(n, Vec3::new(3.0 / 13.0, 4.0 / 13.0, 12.0 / 13.0));
+        assert_eq!(normalize(Vec3::ZERO), Vec3::ZERO);
+    }
+}
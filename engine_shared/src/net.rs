@@ -8,22 +8,58 @@
 //! This is not a full Source-style netcode implementation; it is a scaffold.
 
 use anyhow::Context;
-use bytes::{BufMut, Bytes, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use serde::{Deserialize, Serialize};
 use std::{
-    net::SocketAddr,
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    fmt,
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    str::FromStr,
     sync::atomic::{AtomicU32, Ordering},
+    time::{Duration, Instant},
 };
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
     net::{TcpListener, TcpStream, UdpSocket},
     time,
 };
 
-use crate::{ecs::EntityId, math::Vec3};
+use crate::{
+    chat::ChatMessage,
+    ecs::EntityId,
+    event::GameEvent,
+    math::{lerp_angle, Vec3},
+    steam_id::SteamId,
+};
 
 /// Protocol version for compatibility checks.
-pub const PROTOCOL_VERSION: u32 = 1;
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Largest encoded message accepted from a peer, in bytes.
+pub const MAX_MESSAGE_SIZE: usize = 256 * 1024;
+
+/// Maximum entities in a snapshot (Source's `MAX_EDICTS`).
+pub const MAX_SNAPSHOT_ENTITIES: usize = 2048;
+
+/// Maximum length of any string field, in bytes.
+pub const MAX_STRING_LEN: usize = 4096;
+
+/// Maximum key/value properties on an entity spawn.
+pub const MAX_ENTITY_PROPERTIES: usize = 256;
+
+/// Maximum owned DLC app IDs a client may report in its handshake.
+pub const MAX_OWNED_DLC: usize = 1024;
+
+/// Encoded size above which `compress_encode` deflates the payload.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Bytes in a frame's length prefix.
+pub const FRAME_HEADER_LEN: usize = 4;
+
+/// Header byte: payload is raw encoded message.
+pub const PAYLOAD_UNCOMPRESSED: u8 = 0;
+/// Header byte: payload is deflate-compressed encoded message.
+pub const PAYLOAD_DEFLATE: u8 = 1;
 
 static NEXT_CLIENT_ID: AtomicU32 = AtomicU32::new(1);
 
@@ -37,12 +73,136 @@ impl ClientId {
     }
 }
 
+/// Why the server refused a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RejectReason {
+    /// Every slot is taken (or held for a reserved player).
+    ServerFull,
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RejectReason::ServerFull => write!(f, "server is full"),
+        }
+    }
+}
+
+/// A server address as typed by a user: `host:port`, not yet resolved.
+///
+/// The host is a DNS name, an IPv4 address, or a bracketed IPv6 address
+/// (`[::1]:40000`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HostPort {
+    pub host: String,
+    pub port: u16,
+}
+
+impl HostPort {
+    /// Checks the syntax of `s` without resolving it.
+    pub fn parse(s: &str) -> Result<Self, AddrError> {
+        let s = s.trim();
+        let (host, port) = if let Some(rest) = s.strip_prefix('[') {
+            let (host, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| AddrError::InvalidHost(s.to_string()))?;
+            if host.parse::<Ipv6Addr>().is_err() {
+                return Err(AddrError::InvalidHost(host.to_string()));
+            }
+            let port = rest
+                .strip_prefix(':')
+                .ok_or_else(|| AddrError::MissingPort(s.to_string()))?;
+            (host, port)
+        } else {
+            let (host, port) = s
+                .rsplit_once(':')
+                .ok_or_else(|| AddrError::MissingPort(s.to_string()))?;
+            let valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_');
+            if host.is_empty() {
+                return Err(AddrError::EmptyHost);
+            }
+            if !host.chars().all(valid) {
+                return Err(AddrError::InvalidHost(host.to_string()));
+            }
+            (host, port)
+        };
+        let port = match port.parse::<u16>() {
+            Ok(port) if port != 0 => port,
+            _ => return Err(AddrError::InvalidPort(port.to_string())),
+        };
+        Ok(Self {
+            host: host.to_string(),
+            port,
+        })
+    }
+
+    /// The host as an IP address, if it is one and needs no lookup.
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.host.parse().ok()
+    }
+}
+
+impl FromStr for HostPort {
+    type Err = AddrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for HostPort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Why a `host:port` string was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddrError {
+    /// Nothing before the `:`.
+    EmptyHost,
+    /// No `:port` suffix.
+    MissingPort(String),
+    /// The port isn't a number in 1..=65535.
+    InvalidPort(String),
+    /// The host has characters no name or address can contain.
+    InvalidHost(String),
+}
+
+impl fmt::Display for AddrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddrError::EmptyHost => write!(f, "address has no host"),
+            AddrError::MissingPort(addr) => {
+                write!(f, "'{}' has no port (expected host:port)", addr)
+            }
+            AddrError::InvalidPort(port) => write!(f, "'{}' is not a valid port", port),
+            AddrError::InvalidHost(host) => write!(f, "'{}' is not a valid host", host),
+        }
+    }
+}
+
+impl std::error::Error for AddrError {}
+
 /// High-level message envelope.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum NetMsg {
     // ─── Connection handshake ───
     Hello {
         protocol: u32,
+        /// Steam ID the client claims to be connecting as.
+        #[serde(default)]
+        steam_id: SteamId,
+        /// DLC app IDs the client owns, used to gate DLC maps.
+        #[serde(default)]
+        owned_dlc: Vec<u32>,
+        /// Name shown to other players, e.g. as the sender of chat.
+        #[serde(default)]
+        player_name: String,
     },
     /// Client announces its UDP port to the server.
     UdpHello {
@@ -51,6 +211,10 @@ pub enum NetMsg {
     Welcome {
         client_id: ClientId,
     },
+    /// Server refuses the handshake; sent instead of `Welcome`.
+    Reject {
+        reason: RejectReason,
+    },
 
     // ─── Map loading ───
     /// Server tells client which map to load.
@@ -75,6 +239,12 @@ pub enum NetMsg {
     PlayerCommand(PlayerCommand),
     /// Server -> client: world snapshot for interpolation.
     Snapshot(Snapshot),
+    /// Server -> client: changes relative to an earlier snapshot.
+    DeltaSnapshot(DeltaSnapshot),
+    /// Server -> client: world snapshot with fixed-point entity fields.
+    QuantizedSnapshot(QuantizedSnapshot),
+    /// Server -> client: a gameplay event such as a kill or round end.
+    GameEvent(GameEvent),
 
     // ─── Console/chat ───
     /// Server -> client: print message to console.
@@ -85,6 +255,36 @@ pub enum NetMsg {
     ClientCommand {
         command: String,
     },
+    /// Server -> client: a replicated cvar's new value.
+    CvarUpdate {
+        name: String,
+        value: String,
+    },
+    /// Chat in either direction. The server ignores a client's `timestamp`
+    /// and relays the message to everyone who should see it, sender included.
+    Chat(ChatMessage),
+
+    // ─── Reliable delivery over unreliable transport ───
+    /// Sequenced message that must be acknowledged by the receiver.
+    Reliable {
+        seq: u32,
+        inner: Box<NetMsg>,
+    },
+    /// Acknowledges receipt of a `Reliable` message.
+    Ack {
+        seq: u32,
+    },
+
+    // ─── Keepalive ───
+    /// Server -> client: liveness probe, answered with `Pong`.
+    Ping {
+        seq: u32,
+    },
+    /// Client -> server: reply to `Ping` with the same `seq`.
+    Pong {
+        client_id: ClientId,
+        seq: u32,
+    },
 
     // ─── Disconnect ───
     Disconnect {
@@ -92,6 +292,133 @@ pub enum NetMsg {
     },
 }
 
+impl NetMsg {
+    /// Checks that variable-length fields are within protocol limits.
+    pub fn validate(&self) -> Result<(), NetError> {
+        match self {
+            NetMsg::Hello {
+                owned_dlc,
+                player_name,
+                ..
+            } => {
+                check_len("hello.owned_dlc", owned_dlc.len(), MAX_OWNED_DLC)?;
+                check_str("hello.player_name", player_name)
+            }
+            NetMsg::MapInfo(info) => check_str("map_info.name", &info.name),
+            NetMsg::EntitySpawn(spawn) => {
+                check_str("entity_spawn.classname", &spawn.classname)?;
+                check_len(
+                    "entity_spawn.properties",
+                    spawn.properties.len(),
+                    MAX_ENTITY_PROPERTIES,
+                )?;
+                for (k, v) in &spawn.properties {
+                    check_str("entity_spawn.properties", k)?;
+                    check_str("entity_spawn.properties", v)?;
+                }
+                Ok(())
+            }
+            NetMsg::Snapshot(snap) => check_len(
+                "snapshot.entities",
+                snap.entities.len(),
+                MAX_SNAPSHOT_ENTITIES,
+            ),
+            NetMsg::QuantizedSnapshot(snap) => {
+                if !(snap.position_step.is_finite() && snap.position_step > 0.0) {
+                    return Err(NetError::MalformedField("quantized_snapshot.position_step"));
+                }
+                check_len(
+                    "quantized_snapshot.entities",
+                    snap.entities.len(),
+                    MAX_SNAPSHOT_ENTITIES,
+                )
+            }
+            NetMsg::DeltaSnapshot(delta) => {
+                check_len(
+                    "delta_snapshot.changed",
+                    delta.changed.len(),
+                    MAX_SNAPSHOT_ENTITIES,
+                )?;
+                check_len(
+                    "delta_snapshot.removed",
+                    delta.removed.len(),
+                    MAX_SNAPSHOT_ENTITIES,
+                )
+            }
+            NetMsg::GameEvent(GameEvent::PlayerDeath { weapon, .. }) => {
+                check_str("game_event.weapon", weapon)
+            }
+            NetMsg::ServerPrint { message } => check_str("server_print.message", message),
+            NetMsg::ClientCommand { command } => check_str("client_command.command", command),
+            NetMsg::CvarUpdate { name, value } => {
+                check_str("cvar_update.name", name)?;
+                check_str("cvar_update.value", value)
+            }
+            NetMsg::Chat(msg) => {
+                check_str("chat.sender_name", &msg.sender_name)?;
+                check_str("chat.content", &msg.content)
+            }
+            NetMsg::Disconnect { reason } => check_str("disconnect.reason", reason),
+            NetMsg::Reliable { inner, .. } => match **inner {
+                NetMsg::Reliable { .. } => Err(NetError::MalformedField("reliable.inner")),
+                ref inner => inner.validate(),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+fn check_len(field: &'static str, len: usize, max: usize) -> Result<(), NetError> {
+    if len > max {
+        return Err(NetError::MalformedField(field));
+    }
+    Ok(())
+}
+
+fn check_str(field: &'static str, s: &str) -> Result<(), NetError> {
+    check_len(field, s.len(), MAX_STRING_LEN)
+}
+
+/// Errors produced when decoding untrusted network data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetError {
+    /// Frame exceeds `MAX_MESSAGE_SIZE`.
+    MessageTooLarge { size: usize, max: usize },
+    /// A variable-length field is out of bounds.
+    MalformedField(&'static str),
+    /// Payload is not a valid encoded message.
+    Decode(String),
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::MessageTooLarge { size, max } => {
+                write!(
+                    f,
+                    "message of {} bytes exceeds limit of {} bytes",
+                    size, max
+                )
+            }
+            NetError::MalformedField(field) => write!(f, "field `{}` out of bounds", field),
+            NetError::Decode(e) => write!(f, "failed to decode message: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+/// Rejects a declared frame length before any buffer is allocated for it.
+pub fn check_frame_len(len: usize) -> Result<(), NetError> {
+    if len > MAX_MESSAGE_SIZE {
+        return Err(NetError::MessageTooLarge {
+            size: len,
+            max: MAX_MESSAGE_SIZE,
+        });
+    }
+    Ok(())
+}
+
 /// Map information sent to clients.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct MapInfo {
@@ -120,6 +447,20 @@ pub struct PlayerCommand {
     pub tick: u32,
     /// Wish move/accel in local space (placeholder).
     pub wish: Vec3,
+    /// The `UserCmd` sequence this was built from, echoed back in
+    /// [`CommandAck`]; 0 if the client doesn't predict.
+    #[serde(default)]
+    pub sequence: u32,
+}
+
+/// The server's state for the receiving client's player after its last
+/// processed command, for prediction reconciliation.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct CommandAck {
+    /// Sequence of the last command applied.
+    pub sequence: u32,
+    pub position: Vec3,
+    pub velocity: Vec3,
 }
 
 /// A minimal entity state for replication.
@@ -127,6 +468,12 @@ pub struct PlayerCommand {
 pub struct EntityState {
     pub id: EntityId,
     pub position: Vec3,
+    /// Pitch, yaw and roll in degrees.
+    #[serde(default)]
+    pub angles: Vec3,
+    /// Hit points, for entities that have any.
+    #[serde(default)]
+    pub health: Option<u32>,
 }
 
 /// World snapshot.
@@ -134,145 +481,1865 @@ pub struct EntityState {
 pub struct Snapshot {
     pub tick: u32,
     pub entities: Vec<EntityState>,
+    /// Per-recipient command ack; not part of the shared world state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ack: Option<CommandAck>,
 }
 
-/// Reliable connection over TCP with length-prefixed frames.
-#[derive(Debug)]
-pub struct ReliableConn {
-    stream: TcpStream,
-}
+impl Snapshot {
+    /// Computes the changes needed to turn `base` into `self`.
+    ///
+    /// Entities whose state is identical in both snapshots are omitted.
+    pub fn delta_from(&self, base: &Snapshot) -> DeltaSnapshot {
+        let changed = self
+            .entities
+            .iter()
+            .filter_map(|e| match base.entities.iter().find(|b| b.id == e.id) {
+                Some(b) if b == e => None,
+                Some(b) => Some(EntityDelta {
+                    id: e.id,
+                    position: (b.position != e.position).then_some(e.position),
+                    angles: (b.angles != e.angles).then_some(e.angles),
+                    health: (b.health != e.health).then_some(e.health),
+                }),
+                None => Some(EntityDelta {
+                    id: e.id,
+                    position: Some(e.position),
+                    angles: Some(e.angles),
+                    health: Some(e.health),
+                }),
+            })
+            .collect();
 
-impl ReliableConn {
-    pub fn new(stream: TcpStream) -> Self {
-        Self { stream }
+        let removed = base
+            .entities
+            .iter()
+            .filter(|b| !self.entities.iter().any(|e| e.id == b.id))
+            .map(|b| b.id)
+            .collect();
+
+        DeltaSnapshot {
+            base_tick: base.tick,
+            tick: self.tick,
+            changed,
+            removed,
+        }
     }
 
-    pub async fn send(&mut self, msg: &NetMsg) -> anyhow::Result<()> {
-        let payload = serde_json::to_vec(msg).context("serialize msg")?;
-        let mut buf = BytesMut::with_capacity(4 + payload.len());
-        buf.put_u32(payload.len() as u32);
-        buf.extend_from_slice(&payload);
-        self.stream.write_all(&buf).await.context("tcp write")?;
+    /// Applies a delta in place, advancing this snapshot to `delta.tick`.
+    ///
+    /// Fails if this snapshot is not the delta's base.
+    pub fn apply_delta(&mut self, delta: &DeltaSnapshot) -> anyhow::Result<()> {
+        if self.tick != delta.base_tick {
+            anyhow::bail!(
+                "delta base tick {} does not match snapshot tick {}",
+                delta.base_tick,
+                self.tick
+            );
+        }
+
+        self.entities.retain(|e| !delta.removed.contains(&e.id));
+
+        for change in &delta.changed {
+            match self.entities.iter_mut().find(|e| e.id == change.id) {
+                Some(existing) => {
+                    if let Some(position) = change.position {
+                        existing.position = position;
+                    }
+                    if let Some(angles) = change.angles {
+                        existing.angles = angles;
+                    }
+                    if let Some(health) = change.health {
+                        existing.health = health;
+                    }
+                }
+                None => self.entities.push(EntityState {
+                    id: change.id,
+                    position: change.position.unwrap_or(Vec3::ZERO),
+                    angles: change.angles.unwrap_or(Vec3::ZERO),
+                    health: change.health.flatten(),
+                }),
+            }
+        }
+
+        self.tick = delta.tick;
         Ok(())
     }
+}
 
-    pub async fn recv(&mut self) -> anyhow::Result<NetMsg> {
-        let mut len_buf = [0u8; 4];
-        self.stream
-            .read_exact(&mut len_buf)
-            .await
-            .context("tcp read len")?;
-        let len = u32::from_be_bytes(len_buf) as usize;
-        let mut payload = vec![0u8; len];
-        self.stream
-            .read_exact(&mut payload)
-            .await
-            .context("tcp read payload")?;
-        let msg = serde_json::from_slice(&payload).context("deserialize msg")?;
-        Ok(msg)
+/// Recent snapshots kept by the server to rewind entities for lag
+/// compensation.
+#[derive(Debug, Clone)]
+pub struct SnapshotHistory {
+    snapshots: VecDeque<Snapshot>,
+    window: u32,
+}
+
+impl SnapshotHistory {
+    /// Keeps snapshots up to `window` ticks older than the newest one.
+    pub fn new(window: u32) -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            window,
+        }
     }
 
-    pub fn peer_addr(&self) -> anyhow::Result<SocketAddr> {
-        Ok(self.stream.peer_addr()?)
+    /// Records a snapshot and discards ones that fell out of the window.
+    ///
+    /// Snapshots must arrive in tick order; older or repeated ticks are
+    /// ignored.
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self
+            .newest_tick()
+            .is_some_and(|newest| snapshot.tick <= newest)
+        {
+            return;
+        }
+        let cutoff = snapshot.tick.saturating_sub(self.window);
+        self.snapshots.push_back(snapshot);
+        while self.snapshots.front().is_some_and(|s| s.tick < cutoff) {
+            self.snapshots.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn oldest_tick(&self) -> Option<u32> {
+        self.snapshots.front().map(|s| s.tick)
+    }
+
+    pub fn newest_tick(&self) -> Option<u32> {
+        self.snapshots.back().map(|s| s.tick)
+    }
+
+    /// Gets an entity's state at `tick`, interpolating between the stored
+    /// snapshots around it.
+    ///
+    /// Returns `None` if `tick` is outside the retained range or the entity
+    /// isn't in both surrounding snapshots.
+    pub fn state_at(&self, entity: EntityId, tick: u32) -> Option<EntityState> {
+        let find = |snap: &Snapshot| snap.entities.iter().find(|e| e.id == entity).cloned();
+
+        let after = self.snapshots.iter().position(|s| s.tick >= tick)?;
+        let b = &self.snapshots[after];
+        if b.tick == tick {
+            return find(b);
+        }
+        let a = &self.snapshots[after.checked_sub(1)?];
+        let (sa, sb) = (find(a)?, find(b)?);
+
+        let t = (tick - a.tick) as f32 / (b.tick - a.tick) as f32;
+        Some(EntityState {
+            id: entity,
+            position: sa.position.lerp(sb.position, t),
+            angles: Vec3::new(
+                lerp_angle(sa.angles.x, sb.angles.x, t),
+                lerp_angle(sa.angles.y, sb.angles.y, t),
+                lerp_angle(sa.angles.z, sb.angles.z, t),
+            ),
+            health: if t < 0.5 { sa.health } else { sb.health },
+        })
     }
 }
 
-/// Unreliable channel over UDP.
-#[derive(Debug)]
-pub struct UnreliableConn {
-    socket: UdpSocket,
-    peer: SocketAddr,
+/// Per-entity change carried in a [`DeltaSnapshot`].
+///
+/// Fields are `None` when unchanged from the base snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EntityDelta {
+    pub id: EntityId,
+    pub position: Option<Vec3>,
+    #[serde(default)]
+    pub angles: Option<Vec3>,
+    /// `Some(None)` when the entity lost its health.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "double_option"
+    )]
+    pub health: Option<Option<u32>>,
 }
 
-impl UnreliableConn {
-    pub async fn connect(bind_addr: SocketAddr, peer: SocketAddr) -> anyhow::Result<Self> {
-        let socket = UdpSocket::bind(bind_addr).await.context("udp bind")?;
-        Ok(Self { socket, peer })
+/// Deserializes a present field (even `null`) as `Some`, so a missing
+/// field stays distinguishable from an explicit `None`.
+fn double_option<'de, D, T>(d: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(d).map(Some)
+}
+
+/// Snapshot encoded as changes against an earlier `base_tick` snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeltaSnapshot {
+    pub base_tick: u32,
+    pub tick: u32,
+    /// Entities that are new or changed since the base.
+    pub changed: Vec<EntityDelta>,
+    /// Entities present in the base but no longer in the world.
+    pub removed: Vec<EntityId>,
+}
+
+impl DeltaSnapshot {
+    /// Returns true if nothing changed since the base snapshot.
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
     }
+}
 
-    pub async fn send(&self, msg: &NetMsg) -> anyhow::Result<()> {
-        let payload = serde_json::to_vec(msg).context("serialize udp msg")?;
-        self.socket
-            .send_to(&payload, self.peer)
-            .await
-            .context("udp send")?;
-        Ok(())
+/// Default half-size of the world along each axis, in units.
+pub const DEFAULT_WORLD_EXTENT: f32 = 16384.0;
+
+/// Default fixed-point position resolution, in units.
+pub const DEFAULT_POSITION_STEP: f32 = 1.0 / 32.0;
+
+/// Largest error, in degrees, of an angle after quantizing to a `u16`.
+pub const MAX_ANGLE_ERROR: f32 = 360.0 / 65536.0 / 2.0;
+
+/// How snapshot entity fields are reduced for the wire.
+///
+/// Positions become fixed-point multiples of `position_step`. For
+/// coordinates within `world_extent`, a decoded coordinate is off by at most
+/// [`max_position_error`](Self::max_position_error), give or take `f32`
+/// rounding. Coordinates beyond the extent are clamped to it, so this bound
+/// does not apply to them. Angles become a `u16` fraction of a turn and are
+/// off by at most [`MAX_ANGLE_ERROR`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantizeConfig {
+    world_extent: f32,
+    position_step: f32,
+}
+
+/// Why [`QuantizeConfig::new`] rejected its arguments.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuantizeConfigError {
+    /// `position_step` is not a positive, finite number.
+    InvalidStep(f32),
+    /// `world_extent` is not a non-negative, finite number.
+    InvalidExtent(f32),
+    /// `world_extent / position_step` doesn't fit in an `i32`.
+    ExtentTooLarge {
+        world_extent: f32,
+        position_step: f32,
+    },
+}
+
+impl fmt::Display for QuantizeConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuantizeConfigError::InvalidStep(step) => {
+                write!(f, "position step {} must be positive and finite", step)
+            }
+            QuantizeConfigError::InvalidExtent(extent) => {
+                write!(f, "world extent {} must be non-negative and finite", extent)
+            }
+            QuantizeConfigError::ExtentTooLarge {
+                world_extent,
+                position_step,
+            } => write!(
+                f,
+                "world extent {} is more than i32::MAX steps of {}",
+                world_extent, position_step
+            ),
+        }
     }
+}
 
-    pub async fn recv(&self) -> anyhow::Result<NetMsg> {
-        let mut buf = vec![0u8; 64 * 1024];
-        let (n, _from) = self.socket.recv_from(&mut buf).await.context("udp recv")?;
-        let msg = serde_json::from_slice(&buf[..n]).context("deserialize udp msg")?;
-        Ok(msg)
+impl std::error::Error for QuantizeConfigError {}
+
+impl Default for QuantizeConfig {
+    fn default() -> Self {
+        Self {
+            world_extent: DEFAULT_WORLD_EXTENT,
+            position_step: DEFAULT_POSITION_STEP,
+        }
     }
+}
 
-    /// Receives a datagram within the given timeout.
-    pub async fn recv_timeout(
-        &self,
-        timeout: std::time::Duration,
-    ) -> anyhow::Result<Option<NetMsg>> {
-        let mut buf = vec![0u8; 64 * 1024];
-        match time::timeout(timeout, self.socket.recv_from(&mut buf)).await {
-            Ok(Ok((n, _from))) => {
-                let msg = serde_json::from_slice(&buf[..n]).context("deserialize udp msg")?;
-                Ok(Some(msg))
-            }
-            Ok(Err(e)) => Err(e).context("udp recv")?,
-            Err(_) => Ok(None),
+impl QuantizeConfig {
+    /// Checks that `position_step` is positive and finite, and that
+    /// `world_extent` is at most `i32::MAX` steps.
+    pub fn new(world_extent: f32, position_step: f32) -> Result<Self, QuantizeConfigError> {
+        if !(position_step.is_finite() && position_step > 0.0) {
+            return Err(QuantizeConfigError::InvalidStep(position_step));
+        }
+        if !(world_extent.is_finite() && world_extent >= 0.0) {
+            return Err(QuantizeConfigError::InvalidExtent(world_extent));
         }
+        // `i32::MAX as f32` rounds up to 2^31, which is already too many.
+        if world_extent / position_step >= i32::MAX as f32 {
+            return Err(QuantizeConfigError::ExtentTooLarge {
+                world_extent,
+                position_step,
+            });
+        }
+        Ok(Self {
+            world_extent,
+            position_step,
+        })
     }
 
-    pub fn peer_addr(&self) -> SocketAddr {
-        self.peer
+    pub fn world_extent(&self) -> f32 {
+        self.world_extent
     }
 
-    pub fn local_addr(&self) -> anyhow::Result<SocketAddr> {
-        Ok(self.socket.local_addr()?)
+    pub fn position_step(&self) -> f32 {
+        self.position_step
+    }
+
+    pub fn max_position_error(&self) -> f32 {
+        self.position_step / 2.0
+    }
+
+    pub fn quantize_position(&self, p: Vec3) -> [i32; 3] {
+        let q = |v: f32| {
+            (v.clamp(-self.world_extent, self.world_extent) / self.position_step).round() as i32
+        };
+        [q(p.x), q(p.y), q(p.z)]
+    }
+
+    pub fn dequantize_position(&self, q: [i32; 3]) -> Vec3 {
+        dequantize_position(q, self.position_step)
+    }
+
+    pub fn quantize_entity(&self, e: &EntityState) -> QuantizedEntityState {
+        QuantizedEntityState {
+            id: e.id,
+            position: self.quantize_position(e.position),
+            angles: [
+                quantize_angle(e.angles.x),
+                quantize_angle(e.angles.y),
+                quantize_angle(e.angles.z),
+            ],
+            health: e.health,
+        }
+    }
+
+    pub fn quantize_snapshot(&self, snap: &Snapshot) -> QuantizedSnapshot {
+        QuantizedSnapshot {
+            tick: snap.tick,
+            position_step: self.position_step,
+            entities: snap
+                .entities
+                .iter()
+                .map(|e| self.quantize_entity(e))
+                .collect(),
+            ack: snap.ack,
+        }
     }
 }
 
-/// TCP server listener.
-pub struct ReliableListener {
-    listener: TcpListener,
+fn dequantize_position(q: [i32; 3], step: f32) -> Vec3 {
+    Vec3::new(q[0] as f32 * step, q[1] as f32 * step, q[2] as f32 * step)
 }
 
-impl ReliableListener {
-    pub async fn bind(addr: SocketAddr) -> anyhow::Result<Self> {
-        let listener = TcpListener::bind(addr).await.context("tcp bind")?;
-        Ok(Self { listener })
+/// Maps an angle in degrees to a fraction of a turn. Any multiple of 360
+/// degrees apart gives the same value.
+pub fn quantize_angle(degrees: f32) -> u16 {
+    let turns = degrees.rem_euclid(360.0) / 360.0;
+    // A turn that rounds up to 65536 wraps to 0.
+    ((turns * 65536.0).round() as u32 & 0xffff) as u16
+}
+
+/// Inverse of [`quantize_angle`], giving degrees in `[-180, 180)`.
+pub fn dequantize_angle(q: u16) -> f32 {
+    f32::from(q as i16) * (360.0 / 65536.0)
+}
+
+/// [`EntityState`] with fixed-point position and angles.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuantizedEntityState {
+    pub id: EntityId,
+    pub position: [i32; 3],
+    pub angles: [u16; 3],
+    #[serde(default)]
+    pub health: Option<u32>,
+}
+
+/// [`Snapshot`] as sent with quantization on.
+///
+/// Carries its own `position_step` so a client can decode it without knowing
+/// the server's settings.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuantizedSnapshot {
+    pub tick: u32,
+    pub position_step: f32,
+    pub entities: Vec<QuantizedEntityState>,
+    /// Left at full precision for prediction reconciliation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ack: Option<CommandAck>,
+}
+
+impl QuantizedSnapshot {
+    pub fn dequantize(&self) -> Snapshot {
+        Snapshot {
+            tick: self.tick,
+            entities: self
+                .entities
+                .iter()
+                .map(|e| EntityState {
+                    id: e.id,
+                    position: dequantize_position(e.position, self.position_step),
+                    angles: Vec3::new(
+                        dequantize_angle(e.angles[0]),
+                        dequantize_angle(e.angles[1]),
+                        dequantize_angle(e.angles[2]),
+                    ),
+                    health: e.health,
+                })
+                .collect(),
+            ack: self.ack,
+        }
     }
+}
 
-    pub async fn accept(&self) -> anyhow::Result<(ReliableConn, SocketAddr)> {
-        let (stream, addr) = self.listener.accept().await.context("tcp accept")?;
-        Ok((ReliableConn::new(stream), addr))
+/// Default time to wait for an `Ack` before resending a reliable message.
+pub const DEFAULT_RESEND_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// How far past the lowest missing sequence a receiver will buffer.
+pub const RELIABLE_RECV_WINDOW: u32 = 256;
+
+/// Sequencing, acknowledgement, and resend bookkeeping for reliable messages
+/// carried over an unreliable transport.
+///
+/// The channel does no IO itself: callers send whatever `send`/`resend_due`
+/// return, feed incoming `Ack`s to `on_ack`, and pass incoming `Reliable`
+/// messages through `receive` (replying with `Ack { seq }` whenever
+/// `should_ack` says so, since the sender may have missed an earlier ack).
+///
+/// Sequence numbers wrap, so they are compared with serial-number arithmetic
+/// and only [`RELIABLE_RECV_WINDOW`] sequences past `recv_floor` are tracked.
+#[derive(Debug)]
+pub struct ReliableChannel {
+    next_seq: u32,
+    resend_timeout: Duration,
+    /// Unacknowledged outgoing messages by sequence, with last send time.
+    outstanding: BTreeMap<u32, (NetMsg, Instant)>,
+    /// All sequences below this have been received.
+    recv_floor: u32,
+    /// Received sequences in the window, as offsets from `recv_floor`.
+    recv_ahead: BTreeSet<u32>,
+}
+
+impl ReliableChannel {
+    pub fn new(resend_timeout: Duration) -> Self {
+        Self {
+            next_seq: 0,
+            resend_timeout,
+            outstanding: BTreeMap::new(),
+            recv_floor: 0,
+            recv_ahead: BTreeSet::new(),
+        }
     }
 
-    pub fn local_addr(&self) -> anyhow::Result<SocketAddr> {
-        Ok(self.listener.local_addr()?)
+    /// Wraps `msg` with the next sequence number and tracks it until acked.
+    pub fn send(&mut self, msg: NetMsg) -> NetMsg {
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+        self.outstanding.insert(seq, (msg.clone(), Instant::now()));
+        NetMsg::Reliable {
+            seq,
+            inner: Box::new(msg),
+        }
     }
-}
 
-/// Convenience codec helpers.
-pub fn encode_to_bytes(msg: &NetMsg) -> anyhow::Result<Bytes> {
-    let payload = serde_json::to_vec(msg).context("serialize")?;
-    Ok(Bytes::from(payload))
+    /// Stops tracking an acknowledged message. Unknown sequences are ignored.
+    pub fn on_ack(&mut self, seq: u32) {
+        self.outstanding.remove(&seq);
+    }
+
+    /// Returns messages whose ack has not arrived within the resend timeout,
+    /// resetting their timers.
+    pub fn resend_due(&mut self) -> Vec<NetMsg> {
+        let now = Instant::now();
+        let mut due = Vec::new();
+        for (seq, (msg, sent_at)) in self.outstanding.iter_mut() {
+            if now.duration_since(*sent_at) >= self.resend_timeout {
+                *sent_at = now;
+                due.push(NetMsg::Reliable {
+                    seq: *seq,
+                    inner: Box::new(msg.clone()),
+                });
+            }
+        }
+        due
+    }
+
+    /// Accepts an incoming reliable message, returning its payload the first
+    /// time `seq` is seen and `None` for duplicates. Sequences beyond the
+    /// receive window are dropped unrecorded, so the sender resends them.
+    pub fn receive(&mut self, seq: u32, inner: NetMsg) -> Option<NetMsg> {
+        let offset = self.window_offset(seq)?;
+        if offset >= RELIABLE_RECV_WINDOW || !self.recv_ahead.insert(offset) {
+            return None;
+        }
+        let mut advance = 0;
+        while self.recv_ahead.first() == Some(&advance) {
+            self.recv_ahead.pop_first();
+            advance += 1;
+        }
+        if advance > 0 {
+            self.recv_floor = self.recv_floor.wrapping_add(advance);
+            self.recv_ahead = self.recv_ahead.iter().map(|o| o - advance).collect();
+        }
+        Some(inner)
+    }
+
+    /// Whether `seq` should be acknowledged: it was already received or fits
+    /// in the receive window. Sequences too far ahead must not be acked, or
+    /// the sender would stop resending a message that was dropped.
+    pub fn should_ack(&self, seq: u32) -> bool {
+        self.window_offset(seq)
+            .is_none_or(|offset| offset < RELIABLE_RECV_WINDOW)
+    }
+
+    /// Distance of `seq` past `recv_floor`, or `None` if it is behind it.
+    fn window_offset(&self, seq: u32) -> Option<u32> {
+        let diff = seq.wrapping_sub(self.recv_floor);
+        // Serial-number comparison: the upper half of the space is "behind".
+        (diff < 1 << 31).then_some(diff)
+    }
+
+    /// Number of sent messages still awaiting an ack.
+    pub fn outstanding(&self) -> usize {
+        self.outstanding.len()
+    }
 }
 
-pub fn decode_from_bytes(b: &[u8]) -> anyhow::Result<NetMsg> {
-    serde_json::from_slice(b).context("deserialize")
+impl Default for ReliableChannel {
+    fn default() -> Self {
+        Self::new(DEFAULT_RESEND_TIMEOUT)
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Default per-client outgoing rate cap in bytes per second (`sv_maxrate`).
+pub const DEFAULT_MAX_RATE: u32 = 196_608;
+
+/// Default span over which [`BandwidthTracker`] measures rates.
+pub const DEFAULT_BANDWIDTH_WINDOW: Duration = Duration::from_secs(1);
+
+/// Bytes moved to and from one client within the window.
+#[derive(Debug, Default)]
+struct ClientTraffic {
+    sent: VecDeque<(Instant, usize)>,
+    received: VecDeque<(Instant, usize)>,
+}
+
+/// Per-client byte counts over a sliding window, and the outgoing rate cap
+/// checked against them.
+///
+/// Like [`ReliableChannel`], this does no IO: the server asks `can_send`
+/// before sending a datagram and reports what it sent and received. The
+/// `_at` variants take the current time for tests.
+#[derive(Debug)]
+pub struct BandwidthTracker {
+    /// Outgoing cap in bytes per second; 0 for unlimited.
+    max_rate: u32,
+    window: Duration,
+    clients: HashMap<ClientId, ClientTraffic>,
+}
+
+impl BandwidthTracker {
+    pub fn new(max_rate: u32, window: Duration) -> Self {
+        Self {
+            max_rate,
+            window,
+            clients: HashMap::new(),
+        }
+    }
+
+    /// Outgoing cap in bytes per second; 0 for unlimited.
+    pub fn max_rate(&self) -> u32 {
+        self.max_rate
+    }
+
+    pub fn set_max_rate(&mut self, max_rate: u32) {
+        self.max_rate = max_rate;
+    }
+
+    /// Whether sending `bytes` now keeps `client` within the cap.
+    pub fn can_send(&mut self, client: ClientId, bytes: usize) -> bool {
+        self.can_send_at(client, bytes, Instant::now())
+    }
+
+    pub fn can_send_at(&mut self, client: ClientId, bytes: usize, now: Instant) -> bool {
+        if self.max_rate == 0 {
+            return true;
+        }
+        let budget = (self.max_rate as f64 * self.window.as_secs_f64()) as usize;
+        self.sent_in_window(client, now) + bytes <= budget
+    }
+
+    /// Records a datagram sent to `client`.
+    pub fn record_sent(&mut self, client: ClientId, bytes: usize) {
+        self.record_sent_at(client, bytes, Instant::now());
+    }
+
+    pub fn record_sent_at(&mut self, client: ClientId, bytes: usize, now: Instant) {
+        let traffic = self.clients.entry(client).or_default();
+        traffic.sent.push_back((now, bytes));
+        Self::expire(&mut traffic.sent, self.window, now);
+    }
+
+    /// Records a datagram received from `client`.
+    pub fn record_received(&mut self, client: ClientId, bytes: usize) {
+        self.record_received_at(client, bytes, Instant::now());
+    }
+
+    pub fn record_received_at(&mut self, client: ClientId, bytes: usize, now: Instant) {
+        let traffic = self.clients.entry(client).or_default();
+        traffic.received.push_back((now, bytes));
+        Self::expire(&mut traffic.received, self.window, now);
+    }
+
+    /// Outgoing rate to `client` over the window, in bytes per second.
+    pub fn bytes_per_sec(&mut self, client: ClientId) -> u32 {
+        self.bytes_per_sec_at(client, Instant::now())
+    }
+
+    pub fn bytes_per_sec_at(&mut self, client: ClientId, now: Instant) -> u32 {
+        let sent = self.sent_in_window(client, now);
+        self.rate(sent)
+    }
+
+    /// Incoming rate from `client` over the window, in bytes per second.
+    pub fn received_bytes_per_sec(&mut self, client: ClientId) -> u32 {
+        self.received_bytes_per_sec_at(client, Instant::now())
+    }
+
+    pub fn received_bytes_per_sec_at(&mut self, client: ClientId, now: Instant) -> u32 {
+        let Some(traffic) = self.clients.get_mut(&client) else {
+            return 0;
+        };
+        Self::expire(&mut traffic.received, self.window, now);
+        let received = traffic.received.iter().map(|&(_, n)| n).sum();
+        self.rate(received)
+    }
+
+    /// Forgets a disconnected client.
+    pub fn remove_client(&mut self, client: ClientId) {
+        self.clients.remove(&client);
+    }
+
+    fn sent_in_window(&mut self, client: ClientId, now: Instant) -> usize {
+        let Some(traffic) = self.clients.get_mut(&client) else {
+            return 0;
+        };
+        Self::expire(&mut traffic.sent, self.window, now);
+        traffic.sent.iter().map(|&(_, n)| n).sum()
+    }
+
+    fn rate(&self, bytes: usize) -> u32 {
+        (bytes as f64 / self.window.as_secs_f64()) as u32
+    }
+
+    fn expire(entries: &mut VecDeque<(Instant, usize)>, window: Duration, now: Instant) {
+        while let Some(&(at, _)) = entries.front() {
+            if now.saturating_duration_since(at) < window {
+                break;
+            }
+            entries.pop_front();
+        }
+    }
+}
+
+impl Default for BandwidthTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_RATE, DEFAULT_BANDWIDTH_WINDOW)
+    }
+}
+
+/// In-memory buffer per direction of a loopback `ReliableConn`. Generous,
+/// since a listen server may queue several messages (map info, entity
+/// spawns) before its in-process client gets to read them.
+pub const LOOPBACK_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+/// Byte stream a `ReliableConn` frames messages over.
+trait ByteStream: AsyncRead + AsyncWrite + Unpin + Send + Sync + fmt::Debug {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Sync + fmt::Debug> ByteStream for T {}
+
+/// Reliable connection with length-prefixed frames, over TCP or an
+/// in-memory loopback stream.
+///
+/// Frame payloads carry a compression header byte (see `compress_encode`).
+#[derive(Debug)]
+pub struct ReliableConn {
+    stream: Box<dyn ByteStream>,
+    /// Remote address; `None` for loopback.
+    peer: Option<SocketAddr>,
+    codec: FramedCodec,
+    /// Bytes read that don't yet make up a whole frame.
+    rx_buf: BytesMut,
+}
+
+impl ReliableConn {
+    pub fn new(stream: TcpStream) -> Self {
+        let peer = stream.peer_addr().ok();
+        Self {
+            stream: Box::new(stream),
+            peer,
+            codec: FramedCodec::default(),
+            rx_buf: BytesMut::new(),
+        }
+    }
+
+    /// Two ends of an in-process connection, for a listen server and its
+    /// local client.
+    pub fn loopback_pair() -> (Self, Self) {
+        let (a, b) = tokio::io::duplex(LOOPBACK_BUFFER_SIZE);
+        let wrap = |stream| Self {
+            stream: Box::new(stream),
+            peer: None,
+            codec: FramedCodec::default(),
+            rx_buf: BytesMut::new(),
+        };
+        (wrap(a), wrap(b))
+    }
+
+    /// Whether this is an in-process loopback connection.
+    pub fn is_loopback(&self) -> bool {
+        self.peer.is_none()
+    }
+
+    /// Sets the encoded size above which outgoing messages are compressed.
+    pub fn set_compression_threshold(&mut self, threshold: usize) {
+        self.codec.compression_threshold = threshold;
+    }
+
+    pub async fn send(&mut self, msg: &NetMsg) -> anyhow::Result<()> {
+        write_frame(&mut self.stream, &self.codec, msg).await
+    }
+
+    pub async fn recv(&mut self) -> anyhow::Result<NetMsg> {
+        loop {
+            if let Some(msg) = self.codec.decode(&mut self.rx_buf)? {
+                return Ok(msg);
+            }
+            if self.read_more().await? == 0 {
+                anyhow::bail!("connection closed");
+            }
+        }
+    }
+
+    /// Returns the next message if a whole one has arrived, without waiting
+    /// for more.
+    pub async fn try_recv(&mut self) -> anyhow::Result<Option<NetMsg>> {
+        loop {
+            if let Some(msg) = self.codec.decode(&mut self.rx_buf)? {
+                return Ok(Some(msg));
+            }
+            // Reads are cancel-safe, so giving up on one loses no bytes.
+            match time::timeout(Duration::ZERO, self.read_more()).await {
+                Ok(Ok(0)) => anyhow::bail!("connection closed"),
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => return Err(e),
+                Err(_) => return Ok(None),
+            }
+        }
+    }
+
+    async fn read_more(&mut self) -> anyhow::Result<usize> {
+        self.stream
+            .read_buf(&mut self.rx_buf)
+            .await
+            .context("read frame")
+    }
+
+    pub fn peer_addr(&self) -> anyhow::Result<SocketAddr> {
+        self.peer.context("loopback connection has no peer address")
+    }
+
+    /// Flushes pending writes and closes the sending half, so the peer
+    /// reads everything sent so far and then EOF.
+    pub async fn close(&mut self) -> anyhow::Result<()> {
+        self.stream.shutdown().await.context("stream shutdown")?;
+        Ok(())
+    }
+}
+
+/// Unreliable channel over UDP.
+#[derive(Debug)]
+pub struct UnreliableConn {
+    socket: UdpSocket,
+    peer: SocketAddr,
+}
+
+impl UnreliableConn {
+    pub async fn connect(bind_addr: SocketAddr, peer: SocketAddr) -> anyhow::Result<Self> {
+        let socket = UdpSocket::bind(bind_addr).await.context("udp bind")?;
+        Ok(Self { socket, peer })
+    }
+
+    pub async fn send(&self, msg: &NetMsg) -> anyhow::Result<()> {
+        let payload = serde_json::to_vec(msg).context("serialize udp msg")?;
+        self.socket
+            .send_to(&payload, self.peer)
+            .await
+            .context("udp send")?;
+        Ok(())
+    }
+
+    pub async fn recv(&self) -> anyhow::Result<NetMsg> {
+        let mut buf = vec![0u8; 64 * 1024];
+        let (n, _from) = self.socket.recv_from(&mut buf).await.context("udp recv")?;
+        let msg = decode_from_bytes(&buf[..n]).context("deserialize udp msg")?;
+        Ok(msg)
+    }
+
+    /// Receives a datagram within the given timeout.
+    pub async fn recv_timeout(
+        &self,
+        timeout: std::time::Duration,
+    ) -> anyhow::Result<Option<NetMsg>> {
+        let mut buf = vec![0u8; 64 * 1024];
+        match time::timeout(timeout, self.socket.recv_from(&mut buf)).await {
+            Ok(Ok((n, _from))) => {
+                let msg = decode_from_bytes(&buf[..n]).context("deserialize udp msg")?;
+                Ok(Some(msg))
+            }
+            Ok(Err(e)) => Err(e).context("udp recv")?,
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer
+    }
+
+    pub fn local_addr(&self) -> anyhow::Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+}
+
+/// TCP server listener.
+pub struct ReliableListener {
+    listener: TcpListener,
+}
+
+impl ReliableListener {
+    pub async fn bind(addr: SocketAddr) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr).await.context("tcp bind")?;
+        Ok(Self { listener })
+    }
+
+    pub async fn accept(&self) -> anyhow::Result<(ReliableConn, SocketAddr)> {
+        let (stream, addr) = self.listener.accept().await.context("tcp accept")?;
+        Ok((ReliableConn::new(stream), addr))
+    }
+
+    pub fn local_addr(&self) -> anyhow::Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+}
+
+/// Convenience codec helpers.
+pub fn encode_to_bytes(msg: &NetMsg) -> anyhow::Result<Bytes> {
+    let payload = serde_json::to_vec(msg).context("serialize")?;
+    Ok(Bytes::from(payload))
+}
+
+/// Decodes a message from an untrusted buffer, enforcing size and field limits.
+pub fn decode_from_bytes(b: &[u8]) -> Result<NetMsg, NetError> {
+    check_frame_len(b.len())?;
+    let msg: NetMsg = serde_json::from_slice(b).map_err(|e| NetError::Decode(e.to_string()))?;
+    msg.validate()?;
+    Ok(msg)
+}
+
+/// Encodes a message behind a one-byte compression header.
+///
+/// Messages whose encoding exceeds `threshold` bytes are deflated; smaller
+/// ones (or ones that don't shrink) are sent raw to avoid the overhead.
+pub fn compress_encode(msg: &NetMsg, threshold: usize) -> anyhow::Result<Vec<u8>> {
+    let raw = serde_json::to_vec(msg).context("serialize")?;
+    if raw.len() > threshold {
+        let compressed = miniz_oxide::deflate::compress_to_vec(&raw, 6);
+        if compressed.len() < raw.len() {
+            let mut out = Vec::with_capacity(1 + compressed.len());
+            out.push(PAYLOAD_DEFLATE);
+            out.extend_from_slice(&compressed);
+            return Ok(out);
+        }
+    }
+    let mut out = Vec::with_capacity(1 + raw.len());
+    out.push(PAYLOAD_UNCOMPRESSED);
+    out.extend_from_slice(&raw);
+    Ok(out)
+}
+
+/// Decodes a payload produced by `compress_encode`.
+///
+/// Decompressed output is capped at `MAX_MESSAGE_SIZE`.
+pub fn decompress_decode(b: &[u8]) -> Result<NetMsg, NetError> {
+    let (&flag, payload) = b
+        .split_first()
+        .ok_or_else(|| NetError::Decode("empty payload".to_string()))?;
+    match flag {
+        PAYLOAD_UNCOMPRESSED => decode_from_bytes(payload),
+        PAYLOAD_DEFLATE => {
+            check_frame_len(payload.len())?;
+            let raw = miniz_oxide::inflate::decompress_to_vec_with_limit(payload, MAX_MESSAGE_SIZE)
+                .map_err(|e| match e.status {
+                    miniz_oxide::inflate::TINFLStatus::HasMoreOutput => NetError::MessageTooLarge {
+                        size: e.output.len(),
+                        max: MAX_MESSAGE_SIZE,
+                    },
+                    _ => NetError::Decode(format!("inflate failed: {:?}", e.status)),
+                })?;
+            decode_from_bytes(&raw)
+        }
+        other => Err(NetError::Decode(format!(
+            "unknown compression flag {other}"
+        ))),
+    }
+}
+
+/// Length-prefixed framing for messages on a byte stream.
+///
+/// Each frame is a little-endian `u32` payload length followed by a
+/// [`compress_encode`] payload, so messages that arrive together in one
+/// read (or split across several) can be told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FramedCodec {
+    /// Encoded size above which payloads are deflated.
+    pub compression_threshold: usize,
+}
+
+impl Default for FramedCodec {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPRESSION_THRESHOLD)
+    }
+}
+
+impl FramedCodec {
+    pub fn new(compression_threshold: usize) -> Self {
+        Self {
+            compression_threshold,
+        }
+    }
+
+    /// Encodes `msg` as one frame.
+    pub fn encode_frame(&self, msg: &NetMsg) -> anyhow::Result<Bytes> {
+        let mut buf = BytesMut::new();
+        self.encode(msg, &mut buf)?;
+        Ok(buf.freeze())
+    }
+
+    /// Appends `msg` to `buf` as one frame.
+    pub fn encode(&self, msg: &NetMsg, buf: &mut BytesMut) -> anyhow::Result<()> {
+        let payload = compress_encode(msg, self.compression_threshold)?;
+        check_frame_len(payload.len())?;
+        buf.reserve(FRAME_HEADER_LEN + payload.len());
+        buf.put_u32_le(payload.len() as u32);
+        buf.extend_from_slice(&payload);
+        Ok(())
+    }
+
+    /// Takes the first complete frame off the front of `buf` and decodes it.
+    ///
+    /// Returns `Ok(None)`, consuming nothing, if `buf` holds only part of a
+    /// frame. A frame that fails to decode is still consumed, so the next
+    /// call starts at the following frame.
+    ///
+    /// An oversized length prefix is rejected before its payload arrives.
+    /// There's no finding the next frame after one, so `buf` is cleared and
+    /// the connection should be closed.
+    pub fn decode(&self, buf: &mut BytesMut) -> Result<Option<NetMsg>, NetError> {
+        match Self::next_payload(buf)? {
+            Some(payload) => decompress_decode(&payload).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Splits the first complete frame's payload off `buf`.
+    fn next_payload(buf: &mut BytesMut) -> Result<Option<BytesMut>, NetError> {
+        let Some(header) = buf.get(..FRAME_HEADER_LEN) else {
+            return Ok(None);
+        };
+        let len = u32::from_le_bytes(header.try_into().expect("4-byte header")) as usize;
+        if let Err(e) = check_frame_len(len) {
+            buf.clear();
+            return Err(e);
+        }
+        if buf.len() < FRAME_HEADER_LEN + len {
+            return Ok(None);
+        }
+        buf.advance(FRAME_HEADER_LEN);
+        Ok(Some(buf.split_to(len)))
+    }
+
+    /// Decodes every complete frame in `buf`, leaving any trailing partial
+    /// frame buffered for when the rest arrives.
+    ///
+    /// Stops at the first frame that fails, returning the messages decoded
+    /// before it along with the error.
+    pub fn decode_stream(&self, buf: &mut BytesMut) -> DecodedFrames {
+        let mut decoded = DecodedFrames::default();
+        loop {
+            let payload = match Self::next_payload(buf) {
+                Ok(Some(payload)) => payload,
+                Ok(None) => break,
+                Err(e) => {
+                    decoded.error = Some(e);
+                    decoded.fatal = true;
+                    break;
+                }
+            };
+            match decompress_decode(&payload) {
+                Ok(msg) => decoded.msgs.push(msg),
+                Err(e) => {
+                    decoded.error = Some(e);
+                    break;
+                }
+            }
+        }
+        decoded
+    }
+}
+
+/// Output of [`FramedCodec::decode_stream`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DecodedFrames {
+    /// Messages decoded before any error, in stream order.
+    pub msgs: Vec<NetMsg>,
+    /// The error that stopped decoding, if any.
+    pub error: Option<NetError>,
+    /// Whether `error` came from an oversized length prefix, after which
+    /// the stream can't be read further and the connection should be closed.
+    pub fatal: bool,
+}
+
+/// Writes `msg` to `w` as one frame.
+pub async fn write_frame<W: AsyncWrite + Unpin + ?Sized>(
+    w: &mut W,
+    codec: &FramedCodec,
+    msg: &NetMsg,
+) -> anyhow::Result<()> {
+    let frame = codec.encode_frame(msg)?;
+    w.write_all(&frame).await.context("write frame")?;
+    Ok(())
+}
+
+/// Reads exactly one frame from `r` and decodes it.
+///
+/// The declared length is checked against [`MAX_MESSAGE_SIZE`] before the
+/// payload is allocated.
+pub async fn read_frame<R: AsyncRead + Unpin + ?Sized>(r: &mut R) -> anyhow::Result<NetMsg> {
+    let mut len_buf = [0u8; FRAME_HEADER_LEN];
+    r.read_exact(&mut len_buf)
+        .await
+        .context("read frame length")?;
+    let len = u32::from_le_bytes(len_buf) as usize;
+    check_frame_len(len)?;
+    let mut payload = vec![0u8; len];
+    r.read_exact(&mut payload)
+        .await
+        .context("read frame payload")?;
+    let msg = decompress_decode(&payload).context("deserialize msg")?;
+    Ok(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
     fn netmsg_roundtrip_bytes() {
         let msg = NetMsg::Hello {
             protocol: PROTOCOL_VERSION,
+            steam_id: SteamId::from_account_id(12345),
+            owned_dlc: vec![1001],
+            player_name: "Player".to_string(),
         };
         let bytes = encode_to_bytes(&msg).unwrap();
         let back = decode_from_bytes(&bytes).unwrap();
         assert_eq!(msg, back);
     }
+
+    fn entity(id: u64, x: f32) -> EntityState {
+        EntityState {
+            id: EntityId(id),
+            position: Vec3::new(x, 0.0, 0.0),
+            angles: Vec3::ZERO,
+            health: None,
+        }
+    }
+
+    #[test]
+    fn delta_against_identical_base_is_empty() {
+        let base = Snapshot {
+            tick: 10,
+            entities: vec![entity(1, 1.0), entity(2, 2.0)],
+            ack: None,
+        };
+        let next = Snapshot {
+            tick: 11,
+            ..base.clone()
+        };
+
+        let delta = next.delta_from(&base);
+        assert!(delta.is_empty());
+        assert_eq!(delta.base_tick, 10);
+        assert_eq!(delta.tick, 11);
+    }
+
+    #[test]
+    fn delta_apply_reconstructs_snapshot() {
+        let base = Snapshot {
+            tick: 10,
+            entities: vec![entity(1, 1.0), entity(2, 2.0), entity(3, 3.0)],
+            ack: None,
+        };
+        let next = Snapshot {
+            tick: 12,
+            entities: vec![entity(1, 1.0), entity(3, 5.0), entity(4, 4.0)],
+            ack: None,
+        };
+
+        let delta = next.delta_from(&base);
+        assert_eq!(delta.changed.len(), 2);
+        assert_eq!(delta.removed, vec![EntityId(2)]);
+
+        let mut rebuilt = base.clone();
+        rebuilt.apply_delta(&delta).unwrap();
+        assert_eq!(rebuilt, next);
+    }
+
+    #[test]
+    fn delta_health_removal_survives_json() {
+        let mut hurt = entity(1, 0.0);
+        hurt.health = Some(40);
+        let base = Snapshot {
+            tick: 1,
+            entities: vec![hurt],
+            ack: None,
+        };
+        let next = Snapshot {
+            tick: 2,
+            entities: vec![entity(1, 0.0)],
+            ack: None,
+        };
+
+        let delta = next.delta_from(&base);
+        assert_eq!(delta.changed[0].health, Some(None));
+        let json = serde_json::to_vec(&delta).unwrap();
+        let decoded: DeltaSnapshot = serde_json::from_slice(&json).unwrap();
+        assert_eq!(decoded, delta);
+
+        let mut rebuilt = base.clone();
+        rebuilt.apply_delta(&decoded).unwrap();
+        assert_eq!(rebuilt, next);
+    }
+
+    #[test]
+    fn delta_apply_rejects_wrong_base() {
+        let base = Snapshot {
+            tick: 10,
+            entities: vec![entity(1, 1.0)],
+            ack: None,
+        };
+        let next = Snapshot {
+            tick: 11,
+            entities: vec![entity(1, 2.0)],
+            ack: None,
+        };
+        let delta = next.delta_from(&base);
+
+        let mut stale = Snapshot {
+            tick: 9,
+            entities: vec![entity(1, 1.0)],
+            ack: None,
+        };
+        assert!(stale.apply_delta(&delta).is_err());
+    }
+
+    fn print(message: &str) -> NetMsg {
+        NetMsg::ServerPrint {
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn reliable_dropped_message_is_resent() {
+        let mut sender = ReliableChannel::new(Duration::ZERO);
+        let mut receiver = ReliableChannel::default();
+
+        // First transmission is lost on the wire.
+        let _dropped = sender.send(print("map change"));
+        assert_eq!(sender.outstanding(), 1);
+
+        let resent = sender.resend_due();
+        assert_eq!(resent.len(), 1);
+        let NetMsg::Reliable { seq, inner } = resent[0].clone() else {
+            panic!("expected Reliable, got {:?}", resent[0]);
+        };
+        assert_eq!(seq, 0);
+
+        assert_eq!(receiver.receive(seq, *inner), Some(print("map change")));
+        sender.on_ack(seq);
+        assert_eq!(sender.outstanding(), 0);
+        assert!(sender.resend_due().is_empty());
+    }
+
+    #[test]
+    fn reliable_not_resent_before_timeout() {
+        let mut sender = ReliableChannel::new(Duration::from_secs(60));
+        sender.send(print("hello"));
+        assert!(sender.resend_due().is_empty());
+        assert_eq!(sender.outstanding(), 1);
+    }
+
+    #[test]
+    fn reliable_duplicate_is_ignored() {
+        let mut receiver = ReliableChannel::default();
+
+        assert_eq!(receiver.receive(0, print("a")), Some(print("a")));
+        assert_eq!(receiver.receive(0, print("a")), None);
+
+        // Out-of-order arrival is delivered once, then suppressed.
+        assert_eq!(receiver.receive(2, print("c")), Some(print("c")));
+        assert_eq!(receiver.receive(1, print("b")), Some(print("b")));
+        assert_eq!(receiver.receive(2, print("c")), None);
+        assert_eq!(receiver.receive(1, print("b")), None);
+    }
+
+    #[test]
+    fn reliable_receive_handles_sequence_wraparound() {
+        let mut sender = ReliableChannel {
+            next_seq: u32::MAX - 1,
+            ..Default::default()
+        };
+        let mut receiver = ReliableChannel {
+            recv_floor: u32::MAX - 1,
+            ..Default::default()
+        };
+
+        for text in ["a", "b", "c", "d"] {
+            let NetMsg::Reliable { seq, inner } = sender.send(print(text)) else {
+                unreachable!();
+            };
+            assert_eq!(receiver.receive(seq, *inner), Some(print(text)));
+        }
+        assert_eq!(receiver.recv_floor, 2);
+        assert!(receiver.recv_ahead.is_empty());
+
+        // Pre-wrap sequences are now duplicates, but still acked.
+        assert_eq!(receiver.receive(u32::MAX, print("b")), None);
+        assert!(receiver.should_ack(u32::MAX));
+    }
+
+    #[test]
+    fn reliable_receive_window_is_bounded() {
+        let mut receiver = ReliableChannel::default();
+
+        assert_eq!(
+            receiver.receive(RELIABLE_RECV_WINDOW - 1, print("edge")),
+            Some(print("edge"))
+        );
+        assert!(!receiver.should_ack(RELIABLE_RECV_WINDOW));
+        assert_eq!(receiver.receive(RELIABLE_RECV_WINDOW, print("far")), None);
+        assert_eq!(receiver.recv_ahead.len(), 1);
+
+        // Once the window slides, the dropped sequence is accepted on resend.
+        for seq in 0..RELIABLE_RECV_WINDOW - 1 {
+            receiver.receive(seq, print("fill"));
+        }
+        assert!(receiver.should_ack(RELIABLE_RECV_WINDOW));
+        assert_eq!(
+            receiver.receive(RELIABLE_RECV_WINDOW, print("far")),
+            Some(print("far"))
+        );
+    }
+
+    #[test]
+    fn reliable_wrapper_roundtrip_bytes() {
+        let mut sender = ReliableChannel::default();
+        let msg = sender.send(print("chat"));
+        let back = decode_from_bytes(&encode_to_bytes(&msg).unwrap()).unwrap();
+        assert_eq!(msg, back);
+
+        let ack = NetMsg::Ack { seq: 7 };
+        assert_eq!(
+            decode_from_bytes(&encode_to_bytes(&ack).unwrap()).unwrap(),
+            ack
+        );
+    }
+
+    #[test]
+    fn chat_roundtrip_bytes() {
+        use crate::chat::ChatChannel;
+
+        let global = NetMsg::Chat(ChatMessage::new(
+            SteamId::from_account_id(12345),
+            "alice",
+            ChatChannel::Global,
+            "gl hf",
+            640,
+        ));
+        assert_eq!(
+            decode_from_bytes(&encode_to_bytes(&global).unwrap()).unwrap(),
+            global
+        );
+
+        let private = NetMsg::Chat(ChatMessage::new(
+            SteamId::from_account_id(12345),
+            "alice",
+            ChatChannel::Private(SteamId::from_account_id(67890)),
+            "rematch?",
+            641,
+        ));
+        let back = decode_from_bytes(&encode_to_bytes(&private).unwrap()).unwrap();
+        assert_eq!(back, private);
+        match back {
+            NetMsg::Chat(msg) => assert_eq!(
+                msg.channel,
+                ChatChannel::Private(SteamId::from_account_id(67890))
+            ),
+            other => panic!("expected chat, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn decode_rejects_truncated_snapshot() {
+        let snap = NetMsg::Snapshot(Snapshot {
+            tick: 1,
+            entities: vec![entity(1, 1.0), entity(2, 2.0)],
+            ack: None,
+        });
+        let bytes = encode_to_bytes(&snap).unwrap();
+        let truncated = &bytes[..bytes.len() / 2];
+        assert!(matches!(
+            decode_from_bytes(truncated),
+            Err(NetError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn decode_rejects_oversized_buffer() {
+        let huge = vec![b' '; MAX_MESSAGE_SIZE + 1];
+        assert_eq!(
+            decode_from_bytes(&huge),
+            Err(NetError::MessageTooLarge {
+                size: MAX_MESSAGE_SIZE + 1,
+                max: MAX_MESSAGE_SIZE,
+            })
+        );
+    }
+
+    #[test]
+    fn decode_rejects_too_many_entities() {
+        let entities = (0..=MAX_SNAPSHOT_ENTITIES as u64)
+            .map(|i| entity(i, 0.0))
+            .collect();
+        let snap = NetMsg::Snapshot(Snapshot {
+            tick: 1,
+            entities,
+            ack: None,
+        });
+        let bytes = encode_to_bytes(&snap).unwrap();
+        assert_eq!(
+            decode_from_bytes(&bytes),
+            Err(NetError::MalformedField("snapshot.entities"))
+        );
+    }
+
+    #[test]
+    fn decode_rejects_long_string() {
+        let msg = NetMsg::ClientCommand {
+            command: "x".repeat(MAX_STRING_LEN + 1),
+        };
+        let bytes = encode_to_bytes(&msg).unwrap();
+        assert_eq!(
+            decode_from_bytes(&bytes),
+            Err(NetError::MalformedField("client_command.command"))
+        );
+    }
+
+    #[tokio::test]
+    async fn reliable_recv_rejects_oversized_declared_length() {
+        let listener = ReliableListener::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut attacker = TcpStream::connect(addr).await.unwrap();
+        let (mut conn, _) = listener.accept().await.unwrap();
+
+        // Claim a ~4 GiB payload; recv must bail before allocating it.
+        attacker.write_all(&u32::MAX.to_le_bytes()).await.unwrap();
+        let err = conn.recv().await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<NetError>(),
+            Some(NetError::MessageTooLarge { .. })
+        ));
+    }
+
+    fn snapshot_with(count: u64) -> NetMsg {
+        NetMsg::Snapshot(Snapshot {
+            tick: 1,
+            entities: (0..count).map(|i| entity(i, i as f32)).collect(),
+            ack: None,
+        })
+    }
+
+    #[test]
+    fn compress_small_snapshot_is_uncompressed() {
+        let msg = snapshot_with(1);
+        let bytes = compress_encode(&msg, DEFAULT_COMPRESSION_THRESHOLD).unwrap();
+        assert_eq!(bytes[0], PAYLOAD_UNCOMPRESSED);
+        assert_eq!(decompress_decode(&bytes).unwrap(), msg);
+    }
+
+    #[test]
+    fn compress_large_snapshot_is_compressed() {
+        let msg = snapshot_with(200);
+        let raw_len = encode_to_bytes(&msg).unwrap().len();
+        let bytes = compress_encode(&msg, DEFAULT_COMPRESSION_THRESHOLD).unwrap();
+        assert_eq!(bytes[0], PAYLOAD_DEFLATE);
+        assert!(bytes.len() < raw_len);
+        assert_eq!(decompress_decode(&bytes).unwrap(), msg);
+    }
+
+    #[test]
+    fn compress_threshold_is_configurable() {
+        let msg = snapshot_with(200);
+        let bytes = compress_encode(&msg, usize::MAX).unwrap();
+        assert_eq!(bytes[0], PAYLOAD_UNCOMPRESSED);
+        assert_eq!(decompress_decode(&bytes).unwrap(), msg);
+    }
+
+    #[test]
+    fn decompress_rejects_unknown_flag_and_bomb() {
+        assert!(matches!(
+            decompress_decode(&[7, b'{', b'}']),
+            Err(NetError::Decode(_))
+        ));
+
+        let bomb = miniz_oxide::deflate::compress_to_vec(&vec![b' '; MAX_MESSAGE_SIZE * 2], 6);
+        let mut bytes = vec![PAYLOAD_DEFLATE];
+        bytes.extend_from_slice(&bomb);
+        assert!(matches!(
+            decompress_decode(&bytes),
+            Err(NetError::MessageTooLarge { .. })
+        ));
+    }
+
+    fn snapshot(tick: u32, entities: Vec<EntityState>) -> Snapshot {
+        Snapshot {
+            tick,
+            entities,
+            ack: None,
+        }
+    }
+
+    #[test]
+    fn snapshot_history_interpolates_past_state() {
+        let mut history = SnapshotHistory::new(64);
+        history.push(snapshot(10, vec![entity(1, 0.0), entity(2, 5.0)]));
+        history.push(snapshot(14, vec![entity(1, 40.0), entity(2, 5.0)]));
+        history.push(snapshot(18, vec![entity(1, 80.0)]));
+
+        let state = history.state_at(EntityId(1), 11).unwrap();
+        assert_eq!(state.position, Vec3::new(10.0, 0.0, 0.0));
+        assert_eq!(history.state_at(EntityId(1), 14).unwrap().position.x, 40.0);
+        assert_eq!(history.state_at(EntityId(1), 16).unwrap().position.x, 60.0);
+
+        // Entity 2 is gone by tick 18, so there's nothing to blend with.
+        assert!(history.state_at(EntityId(2), 16).is_none());
+        assert!(history.state_at(EntityId(3), 12).is_none());
+    }
+
+    #[test]
+    fn snapshot_history_outside_window_is_none() {
+        let mut history = SnapshotHistory::new(10);
+        for tick in (0..=40).step_by(5) {
+            history.push(snapshot(tick, vec![entity(1, tick as f32)]));
+        }
+
+        assert_eq!(history.oldest_tick(), Some(30));
+        assert_eq!(history.newest_tick(), Some(40));
+        assert_eq!(history.len(), 3);
+        assert!(history.state_at(EntityId(1), 29).is_none());
+        assert!(history.state_at(EntityId(1), 41).is_none());
+        assert_eq!(history.state_at(EntityId(1), 32).unwrap().position.x, 32.0);
+
+        // Out-of-order snapshots are dropped.
+        history.push(snapshot(35, vec![]));
+        assert_eq!(history.len(), 3);
+    }
+
+    #[test]
+    fn snapshot_ack_is_optional_on_the_wire() {
+        let plain = snapshot(3, vec![entity(1, 1.0)]);
+        let json = serde_json::to_string(&plain).unwrap();
+        assert!(!json.contains("ack"));
+
+        let acked = Snapshot {
+            ack: Some(CommandAck {
+                sequence: 9,
+                position: Vec3::new(1.0, 2.0, 0.0),
+                velocity: Vec3::new(0.5, 0.0, 0.0),
+            }),
+            ..plain.clone()
+        };
+        let back: Snapshot = serde_json::from_str(&serde_json::to_string(&acked).unwrap()).unwrap();
+        assert_eq!(back, acked);
+
+        let cmd: PlayerCommand =
+            serde_json::from_str(r#"{"client_id":1,"tick":4,"wish":{"x":1.0,"y":0.0,"z":0.0}}"#)
+                .unwrap();
+        assert_eq!(cmd.sequence, 0);
+    }
+
+    #[test]
+    fn host_port_parses_names_and_addresses() {
+        let name = HostPort::parse("play.example.com:27015").unwrap();
+        assert_eq!(name.host, "play.example.com");
+        assert_eq!(name.port, 27015);
+        assert_eq!(name.ip(), None);
+
+        let v4: HostPort = " 127.0.0.1:40000 ".parse().unwrap();
+        assert_eq!(v4.ip(), Some(IpAddr::from([127, 0, 0, 1])));
+
+        let v6 = HostPort::parse("[::1]:40000").unwrap();
+        assert_eq!(v6.host, "::1");
+        assert_eq!(v6.ip(), Some(IpAddr::from(Ipv6Addr::LOCALHOST)));
+        assert_eq!(v6.to_string(), "[::1]:40000");
+        assert_eq!(name.to_string(), "play.example.com:27015");
+    }
+
+    #[test]
+    fn host_port_rejects_malformed_input() {
+        assert_eq!(
+            HostPort::parse("localhost"),
+            Err(AddrError::MissingPort("localhost".to_string()))
+        );
+        assert_eq!(HostPort::parse(":40000"), Err(AddrError::EmptyHost));
+        assert_eq!(
+            HostPort::parse("localhost:http"),
+            Err(AddrError::InvalidPort("http".to_string()))
+        );
+        assert_eq!(
+            HostPort::parse("localhost:0"),
+            Err(AddrError::InvalidPort("0".to_string()))
+        );
+        assert_eq!(
+            HostPort::parse("localhost:70000"),
+            Err(AddrError::InvalidPort("70000".to_string()))
+        );
+        // IPv6 must be bracketed so the port is unambiguous.
+        assert_eq!(
+            HostPort::parse("::1:40000"),
+            Err(AddrError::InvalidHost("::1".to_string()))
+        );
+        assert_eq!(
+            HostPort::parse("[nope]:40000"),
+            Err(AddrError::InvalidHost("nope".to_string()))
+        );
+        assert_eq!(
+            HostPort::parse("[::1]"),
+            Err(AddrError::MissingPort("[::1]".to_string()))
+        );
+        assert_eq!(
+            HostPort::parse("bad host:1"),
+            Err(AddrError::InvalidHost("bad host".to_string()))
+        );
+    }
+
+    #[test]
+    fn framed_codec_splits_concatenated_frames() {
+        let codec = FramedCodec::default();
+        let mut buf = BytesMut::new();
+        codec.encode(&print("one"), &mut buf).unwrap();
+        codec.encode(&NetMsg::Ack { seq: 2 }, &mut buf).unwrap();
+
+        let len = u32::from_le_bytes(buf[..4].try_into().unwrap()) as usize;
+        assert_eq!(
+            decompress_decode(&buf[4..4 + len]).unwrap(),
+            print("one"),
+            "little-endian length prefix"
+        );
+
+        let decoded = codec.decode_stream(&mut buf);
+        assert_eq!(decoded.msgs, vec![print("one"), NetMsg::Ack { seq: 2 }]);
+        assert_eq!(decoded.error, None);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn framed_codec_leaves_partial_frame_buffered() {
+        let codec = FramedCodec::default();
+        let first = codec.encode_frame(&print("whole")).unwrap();
+        let second = codec.encode_frame(&print("split")).unwrap();
+
+        // A complete frame, then only part of the next one.
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&first);
+        buf.extend_from_slice(&second[..6]);
+        assert_eq!(codec.decode_stream(&mut buf).msgs, vec![print("whole")]);
+        assert_eq!(&buf[..], &second[..6]);
+
+        // Even a partial length prefix waits for more.
+        let mut header_only = BytesMut::from(&second[..2]);
+        assert_eq!(codec.decode(&mut header_only).unwrap(), None);
+        assert_eq!(header_only.len(), 2);
+
+        buf.extend_from_slice(&second[6..]);
+        assert_eq!(codec.decode_stream(&mut buf).msgs, vec![print("split")]);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn framed_codec_rejects_oversized_length_before_payload() {
+        let codec = FramedCodec::default();
+        let mut buf = BytesMut::from(&u32::MAX.to_le_bytes()[..]);
+        assert!(matches!(
+            codec.decode(&mut buf),
+            Err(NetError::MessageTooLarge { .. })
+        ));
+        // Nothing after the bad prefix can be trusted, so it isn't kept.
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn framed_codec_stream_keeps_messages_before_an_error() {
+        let codec = FramedCodec::default();
+        let mut buf = BytesMut::new();
+        codec.encode(&print("one"), &mut buf).unwrap();
+        buf.put_u32_le(3);
+        buf.extend_from_slice(&[PAYLOAD_UNCOMPRESSED, b'{', b'x']);
+        codec.encode(&print("two"), &mut buf).unwrap();
+
+        // A corrupt frame stops this pass but not the stream.
+        let decoded = codec.decode_stream(&mut buf);
+        assert_eq!(decoded.msgs, vec![print("one")]);
+        assert!(matches!(decoded.error, Some(NetError::Decode(_))));
+        assert!(!decoded.fatal);
+        let decoded = codec.decode_stream(&mut buf);
+        assert_eq!(decoded.msgs, vec![print("two")]);
+        assert_eq!(decoded.error, None);
+
+        // An oversized prefix is fatal.
+        codec.encode(&print("three"), &mut buf).unwrap();
+        buf.put_u32_le(u32::MAX);
+        buf.extend_from_slice(b"junk");
+        let decoded = codec.decode_stream(&mut buf);
+        assert_eq!(decoded.msgs, vec![print("three")]);
+        assert!(matches!(
+            decoded.error,
+            Some(NetError::MessageTooLarge { .. })
+        ));
+        assert!(decoded.fatal);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn framed_codec_skips_a_corrupt_frame() {
+        let codec = FramedCodec::default();
+        let mut buf = BytesMut::new();
+        buf.put_u32_le(3);
+        buf.extend_from_slice(&[PAYLOAD_UNCOMPRESSED, b'{', b'x']);
+        codec.encode(&print("after"), &mut buf).unwrap();
+
+        assert!(matches!(codec.decode(&mut buf), Err(NetError::Decode(_))));
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(print("after")));
+    }
+
+    #[tokio::test]
+    async fn frames_roundtrip_over_a_stream() {
+        let (mut a, mut b) = tokio::io::duplex(1024);
+        let codec = FramedCodec::new(0);
+        let big = print(&"x".repeat(2000));
+        write_frame(&mut a, &codec, &print("hi")).await.unwrap();
+        write_frame(&mut a, &codec, &big).await.unwrap();
+        assert_eq!(read_frame(&mut b).await.unwrap(), print("hi"));
+        assert_eq!(read_frame(&mut b).await.unwrap(), big);
+    }
+
+    #[test]
+    fn bandwidth_cap_blocks_until_window_advances() {
+        let mut tracker = BandwidthTracker::new(1000, Duration::from_secs(1));
+        let client = ClientId(1);
+        let other = ClientId(2);
+        let start = Instant::now();
+
+        assert!(tracker.can_send_at(client, 600, start));
+        tracker.record_sent_at(client, 600, start);
+        let mid = start + Duration::from_millis(400);
+        assert!(tracker.can_send_at(client, 400, mid));
+        tracker.record_sent_at(client, 400, mid);
+
+        // The budget is spent for this client only.
+        assert!(!tracker.can_send_at(client, 1, mid));
+        assert!(tracker.can_send_at(other, 1000, mid));
+        assert_eq!(tracker.bytes_per_sec_at(client, mid), 1000);
+
+        // Once the first send leaves the window, its bytes are available again.
+        let later = start + Duration::from_millis(1000);
+        assert!(!tracker.can_send_at(client, 601, later));
+        assert!(tracker.can_send_at(client, 600, later));
+        assert_eq!(tracker.bytes_per_sec_at(client, later), 400);
+
+        let idle = start + Duration::from_secs(5);
+        assert_eq!(tracker.bytes_per_sec_at(client, idle), 0);
+        assert!(tracker.can_send_at(client, 1000, idle));
+    }
+
+    #[test]
+    fn bandwidth_tracks_received_and_unlimited_rate() {
+        let mut tracker = BandwidthTracker::new(0, Duration::from_millis(500));
+        let client = ClientId(1);
+        let now = Instant::now();
+
+        tracker.record_sent_at(client, 1 << 20, now);
+        assert!(tracker.can_send_at(client, 1 << 20, now));
+
+        tracker.record_received_at(client, 100, now);
+        tracker.record_received_at(client, 150, now + Duration::from_millis(100));
+        assert_eq!(
+            tracker.received_bytes_per_sec_at(client, now + Duration::from_millis(200)),
+            500
+        );
+        assert_eq!(
+            tracker.received_bytes_per_sec_at(client, now + Duration::from_millis(550)),
+            300
+        );
+
+        tracker.remove_client(client);
+        assert_eq!(tracker.bytes_per_sec_at(client, now), 0);
+    }
+
+    fn quantized_state(position: Vec3, angles: Vec3) -> EntityState {
+        EntityState {
+            id: EntityId::new(3, 1),
+            position,
+            angles,
+            health: Some(75),
+        }
+    }
+
+    #[test]
+    fn quantized_position_within_tolerance() {
+        let cfg = QuantizeConfig::default();
+        let tol = cfg.max_position_error() + 1e-3;
+        for p in [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(123.456, -789.012, 64.03),
+            Vec3::new(-16383.99, 16383.99, 0.015),
+        ] {
+            let back = cfg.dequantize_position(cfg.quantize_position(p));
+            assert!((back.x - p.x).abs() <= tol, "{p:?} -> {back:?}");
+            assert!((back.y - p.y).abs() <= tol, "{p:?} -> {back:?}");
+            assert!((back.z - p.z).abs() <= tol, "{p:?} -> {back:?}");
+        }
+
+        // Outside the world extent, coordinates are clamped.
+        let far = cfg.dequantize_position(cfg.quantize_position(Vec3::new(1e9, -1e9, 0.0)));
+        assert_eq!(
+            far,
+            Vec3::new(DEFAULT_WORLD_EXTENT, -DEFAULT_WORLD_EXTENT, 0.0)
+        );
+
+        // A coarser step trades precision for smaller numbers.
+        let coarse = QuantizeConfig::new(4096.0, 1.0).unwrap();
+        assert_eq!(
+            coarse.quantize_position(Vec3::new(10.4, 10.6, -10.6)),
+            [10, 11, -11]
+        );
+        assert_eq!(coarse.max_position_error(), 0.5);
+    }
+
+    #[test]
+    fn quantize_config_rejects_unusable_values() {
+        for step in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(matches!(
+                QuantizeConfig::new(DEFAULT_WORLD_EXTENT, step),
+                Err(QuantizeConfigError::InvalidStep(_))
+            ));
+        }
+        for extent in [-1.0, f32::NAN, f32::INFINITY] {
+            assert!(matches!(
+                QuantizeConfig::new(extent, DEFAULT_POSITION_STEP),
+                Err(QuantizeConfigError::InvalidExtent(_))
+            ));
+        }
+        assert!(matches!(
+            QuantizeConfig::new(1e9, 0.25),
+            Err(QuantizeConfigError::ExtentTooLarge { .. })
+        ));
+        assert_eq!(
+            QuantizeConfig::new(DEFAULT_WORLD_EXTENT, DEFAULT_POSITION_STEP),
+            Ok(QuantizeConfig::default())
+        );
+    }
+
+    #[test]
+    fn quantized_angle_wraps_at_360() {
+        assert_eq!(quantize_angle(0.0), 0);
+        assert_eq!(quantize_angle(360.0), 0);
+        assert_eq!(quantize_angle(720.0), 0);
+        assert_eq!(quantize_angle(-0.0001), 0);
+        assert_eq!(quantize_angle(359.9999), 0);
+        assert_eq!(quantize_angle(90.0), 16384);
+        assert_eq!(quantize_angle(-90.0), quantize_angle(270.0));
+        assert_eq!(quantize_angle(450.0), quantize_angle(90.0));
+
+        assert_eq!(dequantize_angle(quantize_angle(-90.0)), -90.0);
+        assert_eq!(dequantize_angle(quantize_angle(180.0)), -180.0);
+        for deg in [0.0, 12.345, 89.9, -45.5, 179.99, -179.99] {
+            let back = dequantize_angle(quantize_angle(deg));
+            let diff = (back - deg).rem_euclid(360.0);
+            let diff = diff.min(360.0 - diff);
+            assert!(diff <= MAX_ANGLE_ERROR + 1e-4, "{deg} -> {back}");
+        }
+    }
+
+    #[test]
+    fn quantized_snapshot_roundtrips_through_net_msg() {
+        let cfg = QuantizeConfig::default();
+        let snap = Snapshot {
+            tick: 42,
+            entities: vec![quantized_state(
+                Vec3::new(512.3, -20.01, 64.0),
+                Vec3::new(-45.0, 270.0, 0.0),
+            )],
+            ack: Some(CommandAck {
+                sequence: 7,
+                position: Vec3::new(1.234567, 0.0, 0.0),
+                velocity: Vec3::ZERO,
+            }),
+        };
+
+        let msg = NetMsg::QuantizedSnapshot(cfg.quantize_snapshot(&snap));
+        let bytes = encode_to_bytes(&msg).unwrap();
+
+        let NetMsg::QuantizedSnapshot(q) = decode_from_bytes(&bytes).unwrap() else {
+            panic!("expected a quantized snapshot");
+        };
+        let back = q.dequantize();
+        assert_eq!(back.tick, 42);
+        assert_eq!(back.ack, snap.ack);
+        let e = &back.entities[0];
+        assert_eq!(e.id, EntityId::new(3, 1));
+        assert_eq!(e.health, Some(75));
+        assert!((e.position.x - 512.3).abs() <= cfg.max_position_error());
+        assert_eq!(e.angles, Vec3::new(-45.0, -90.0, 0.0));
+
+        let bad = NetMsg::QuantizedSnapshot(QuantizedSnapshot {
+            position_step: 0.0,
+            ..q
+        });
+        assert_eq!(
+            bad.validate(),
+            Err(NetError::MalformedField("quantized_snapshot.position_step"))
+        );
+    }
 }
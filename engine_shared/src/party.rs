@@ -12,7 +12,7 @@
 //! - Party chat
 //! - Cross-game persistence
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
@@ -372,6 +372,7 @@ impl PartyManager {
     }
 
     /// Create a new party.
+    #[allow(unused_variables)]
     pub fn create_party(
         &mut self,
         leader: SteamId,
@@ -379,7 +380,7 @@ impl PartyManager {
         max_size: u32,
     ) -> Result<PartyId, PartyError> {
         // Leave any existing party first
-        if let Some(existing) = self.get_player_party(leader) {
+        if self.get_player_party(leader).is_some() {
             self.leave_party(leader)?;
         }
 
@@ -1,8 +1,16 @@
 //! Physics abstraction.
 //!
-//! Placeholder for a deterministic physics step.
+//! Placeholder for a deterministic physics step, plus the collision
+//! primitives it builds on and trigger volumes.
 
-use crate::{ecs::World, math::Vec3};
+use std::collections::HashSet;
+
+use crate::{
+    bsp::{self, BspMap, Plane},
+    ecs::{EntityId, World},
+    input::InputState,
+    math::{Aabb, Vec3},
+};
 
 /// Physics parameters.
 #[derive(Debug, Clone, Copy)]
@@ -13,7 +21,7 @@ pub struct PhysicsConfig {
 impl Default for PhysicsConfig {
     fn default() -> Self {
         Self {
-            gravity: Vec3::new(0.0, 0.0, -9.81),
+            gravity: Vec3::new(0.0, 0.0, -GRAVITY),
         }
     }
 }
@@ -30,3 +38,627 @@ pub struct NullPhysics;
 impl PhysicsBackend for NullPhysics {
     fn step(&mut self, _world: &mut World, _dt_sec: f32) {}
 }
+
+/// Downward acceleration, in m/s².
+pub const GRAVITY: f32 = 9.81;
+/// Ground friction factor.
+pub const FRICTION: f32 = 4.0;
+/// Below this speed, friction acts as if the player moved this fast, so
+/// slow players come to a stop instead of creeping.
+pub const STOP_SPEED: f32 = 2.5;
+/// Ground acceleration factor.
+pub const ACCELERATE: f32 = 10.0;
+/// Air acceleration factor.
+pub const AIR_ACCELERATE: f32 = 10.0;
+/// Cap on the wish speed used for air acceleration.
+pub const AIR_SPEED_CAP: f32 = 0.75;
+/// Maximum horizontal speed, in m/s.
+pub const MAX_SPEED: f32 = 6.0;
+
+/// Player movement state.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PlayerPhysics {
+    pub position: Vec3,
+    pub velocity: Vec3,
+}
+
+/// What the player is standing on, from a trace below their feet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroundInfo {
+    Airborne,
+    Grounded { normal: Vec3 },
+}
+
+impl GroundInfo {
+    /// Standing on flat ground.
+    pub const FLAT: Self = GroundInfo::Grounded {
+        normal: Vec3::new(0.0, 0.0, 1.0),
+    };
+}
+
+/// Advances player movement by one fixed step.
+///
+/// `input.forward`/`input.right` map to world x/y, like
+/// [`InputState::wish_vector`]; magnitudes above 1 are clamped. Uses only
+/// `f32` arithmetic and `sqrt`, so results are the same on every platform.
+pub fn move_player(state: &mut PlayerPhysics, input: &InputState, dt: f32, ground: GroundInfo) {
+    let wish = Vec3::new(input.forward, input.right, 0.0);
+    let wish_len = wish.len_sq().sqrt();
+    let (wish_dir, wish_speed) = if wish_len > 0.0 {
+        (scale(wish, 1.0 / wish_len), wish_len.min(1.0) * MAX_SPEED)
+    } else {
+        (Vec3::ZERO, 0.0)
+    };
+
+    let v = &mut state.velocity;
+    match ground {
+        GroundInfo::Grounded { normal } => {
+            // Don't keep pushing into the floor.
+            let into = v.dot(normal);
+            if into < 0.0 {
+                *v = add(*v, scale(normal, -into));
+            }
+            apply_friction(v, dt);
+            accelerate(v, wish_dir, wish_speed, ACCELERATE, dt);
+        }
+        GroundInfo::Airborne => {
+            accelerate(
+                v,
+                wish_dir,
+                wish_speed.min(AIR_SPEED_CAP),
+                AIR_ACCELERATE,
+                dt,
+            );
+            v.z -= GRAVITY * dt;
+        }
+    }
+
+    let horizontal = v.x * v.x + v.y * v.y;
+    if horizontal > MAX_SPEED * MAX_SPEED {
+        let s = MAX_SPEED / horizontal.sqrt();
+        v.x *= s;
+        v.y *= s;
+    }
+
+    state.position = add(state.position, scale(*v, dt));
+}
+
+fn apply_friction(v: &mut Vec3, dt: f32) {
+    let speed = v.len_sq().sqrt();
+    if speed <= 0.0 {
+        return;
+    }
+    let drop = speed.max(STOP_SPEED) * FRICTION * dt;
+    let new_speed = (speed - drop).max(0.0);
+    *v = if new_speed > 0.0 {
+        scale(*v, new_speed / speed)
+    } else {
+        Vec3::ZERO
+    };
+}
+
+fn accelerate(v: &mut Vec3, wish_dir: Vec3, wish_speed: f32, accel: f32, dt: f32) {
+    let add_speed = wish_speed - v.dot(wish_dir);
+    if add_speed <= 0.0 {
+        return;
+    }
+    let accel_speed = (accel * wish_speed * dt).min(add_speed);
+    *v = add(*v, scale(wish_dir, accel_speed));
+}
+
+fn add(a: Vec3, b: Vec3) -> Vec3 {
+    Vec3::new(a.x + b.x, a.y + b.y, a.z + b.z)
+}
+
+fn scale(v: Vec3, s: f32) -> Vec3 {
+    Vec3::new(v.x * s, v.y * s, v.z * s)
+}
+
+/// Distance traces stop short of a surface, so the next trace from the end
+/// position doesn't start inside it.
+pub const DIST_EPSILON: f32 = 0.03125;
+
+/// Convex solid for collision: the space behind all of its planes.
+#[derive(Debug, Clone, Default)]
+pub struct Brush {
+    pub planes: Vec<Plane>,
+}
+
+impl Brush {
+    /// Creates an axis-aligned box brush.
+    pub fn from_box(min: Vec3, max: Vec3) -> Self {
+        let plane = |normal: Vec3, dist: f32| Plane {
+            normal,
+            dist,
+            plane_type: 0,
+        };
+        Self {
+            planes: vec![
+                plane(Vec3::new(1.0, 0.0, 0.0), max.x),
+                plane(Vec3::new(-1.0, 0.0, 0.0), -min.x),
+                plane(Vec3::new(0.0, 1.0, 0.0), max.y),
+                plane(Vec3::new(0.0, -1.0, 0.0), -min.y),
+                plane(Vec3::new(0.0, 0.0, 1.0), max.z),
+                plane(Vec3::new(0.0, 0.0, -1.0), -min.z),
+            ],
+        }
+    }
+
+    /// Resolves a map brush's sides to planes.
+    ///
+    /// Returns `None` if the brush references sides or planes the map doesn't have.
+    pub fn from_bsp(map: &BspMap, brush: &bsp::Brush) -> Option<Self> {
+        let first = usize::try_from(brush.first_side).ok()?;
+        let count = usize::try_from(brush.num_sides).ok()?;
+        let sides = map.brush_sides.get(first..first.checked_add(count)?)?;
+        let planes = sides
+            .iter()
+            .map(|side| map.planes.get(usize::from(side.plane_num)).copied())
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self { planes })
+    }
+}
+
+/// Result of sweeping a box through the world.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceResult {
+    /// Fraction of the move completed, in `0.0..=1.0`.
+    pub fraction: f32,
+    /// Position the box reached.
+    pub end_pos: Vec3,
+    /// Normal of the surface hit; zero if nothing was hit.
+    pub normal: Vec3,
+    /// The box started inside a brush.
+    pub start_solid: bool,
+    /// The box never left the brush it started in.
+    pub all_solid: bool,
+}
+
+impl TraceResult {
+    /// Whether the move was cut short.
+    pub fn hit(&self) -> bool {
+        self.fraction < 1.0
+    }
+}
+
+/// Sweeps an axis-aligned box from `start` to `end` against `brushes`.
+///
+/// Each brush plane is pushed out by the box's extent along its normal, so
+/// the box can be traced as a point. The move stops [`DIST_EPSILON`] short
+/// of the first surface hit.
+pub fn sweep_aabb(start: Vec3, end: Vec3, half_extents: Vec3, brushes: &[Brush]) -> TraceResult {
+    let mut trace = TraceResult {
+        fraction: 1.0,
+        end_pos: end,
+        normal: Vec3::ZERO,
+        start_solid: false,
+        all_solid: false,
+    };
+
+    for brush in brushes {
+        clip_box_to_brush(start, end, half_extents, brush, &mut trace);
+        if trace.all_solid {
+            break;
+        }
+    }
+
+    if trace.fraction < 1.0 {
+        trace.end_pos = start.lerp(end, trace.fraction);
+    }
+    trace
+}
+
+fn clip_box_to_brush(
+    start: Vec3,
+    end: Vec3,
+    half_extents: Vec3,
+    brush: &Brush,
+    trace: &mut TraceResult,
+) {
+    if brush.planes.is_empty() {
+        return;
+    }
+
+    let mut enter_frac = -1.0f32;
+    let mut leave_frac = 1.0f32;
+    let mut enter_normal = Vec3::ZERO;
+    let mut starts_out = false;
+    let mut gets_out = false;
+
+    for plane in &brush.planes {
+        let n = plane.normal;
+        let dist = plane.dist
+            + n.x.abs() * half_extents.x
+            + n.y.abs() * half_extents.y
+            + n.z.abs() * half_extents.z;
+        let d1 = start.dot(n) - dist;
+        let d2 = end.dot(n) - dist;
+
+        if d1 > 0.0 {
+            starts_out = true;
+        }
+        if d2 > 0.0 {
+            gets_out = true;
+        }
+
+        // In front of this plane for the whole move, so the brush can't be
+        // hit. This also covers a zero-length move that starts outside.
+        if d1 > 0.0 && d2 >= d1 {
+            return;
+        }
+        // Behind this plane for the whole move; other planes decide.
+        if d1 <= 0.0 && d2 <= 0.0 {
+            continue;
+        }
+
+        if d1 > d2 {
+            let f = (d1 - DIST_EPSILON) / (d1 - d2);
+            if f > enter_frac {
+                enter_frac = f;
+                enter_normal = n;
+            }
+        } else {
+            let f = (d1 + DIST_EPSILON) / (d1 - d2);
+            leave_frac = leave_frac.min(f);
+        }
+    }
+
+    if !starts_out {
+        trace.start_solid = true;
+        if !gets_out {
+            trace.all_solid = true;
+        }
+        trace.fraction = 0.0;
+        return;
+    }
+
+    if enter_frac < leave_frac && enter_frac > -1.0 && enter_frac < trace.fraction {
+        trace.fraction = enter_frac.max(0.0);
+        trace.normal = enter_normal;
+    }
+}
+
+/// A region that fires events as entities enter and leave it, such as a
+/// capture zone or hurt volume.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TriggerVolume {
+    pub id: u32,
+    pub bounds: Aabb,
+}
+
+impl TriggerVolume {
+    pub fn new(id: u32, bounds: Aabb) -> Self {
+        Self { id, bounds }
+    }
+}
+
+/// An entity crossing a trigger's boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerEvent {
+    Enter { trigger: u32, entity: EntityId },
+    Exit { trigger: u32, entity: EntityId },
+}
+
+/// Tracks which entities overlap which triggers between frames.
+#[derive(Debug, Clone, Default)]
+pub struct TriggerTracker {
+    volumes: Vec<TriggerVolume>,
+    /// `(trigger, entity)` pairs overlapping as of the last update.
+    inside: HashSet<(u32, EntityId)>,
+}
+
+impl TriggerTracker {
+    pub fn new(volumes: Vec<TriggerVolume>) -> Self {
+        Self {
+            volumes,
+            inside: HashSet::new(),
+        }
+    }
+
+    pub fn add_volume(&mut self, volume: TriggerVolume) {
+        self.volumes.push(volume);
+    }
+
+    pub fn volumes(&self) -> &[TriggerVolume] {
+        &self.volumes
+    }
+
+    /// Whether `entity` overlapped `trigger` at the last update.
+    pub fn is_inside(&self, trigger: u32, entity: EntityId) -> bool {
+        self.inside.contains(&(trigger, entity))
+    }
+
+    /// Compares this frame's entity bounds against the last frame's overlaps.
+    ///
+    /// Entities missing from `entities` count as having left. Exits come
+    /// before enters, each ordered by trigger then entity, so the result
+    /// doesn't depend on hashing.
+    pub fn update(&mut self, entities: &[(EntityId, Aabb)]) -> Vec<TriggerEvent> {
+        let mut current = HashSet::new();
+        for volume in &self.volumes {
+            for (entity, bounds) in entities {
+                if volume.bounds.intersects(bounds) {
+                    current.insert((volume.id, *entity));
+                }
+            }
+        }
+
+        let mut exits: Vec<_> = self.inside.difference(&current).copied().collect();
+        let mut enters: Vec<_> = current.difference(&self.inside).copied().collect();
+        exits.sort_unstable_by_key(|&(trigger, entity)| (trigger, entity.0));
+        enters.sort_unstable_by_key(|&(trigger, entity)| (trigger, entity.0));
+        self.inside = current;
+
+        exits
+            .into_iter()
+            .map(|(trigger, entity)| TriggerEvent::Exit { trigger, entity })
+            .chain(
+                enters
+                    .into_iter()
+                    .map(|(trigger, entity)| TriggerEvent::Enter { trigger, entity }),
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    /// A wall filling x in 11..12.
+    fn wall() -> Vec<Brush> {
+        vec![Brush::from_box(
+            Vec3::new(11.0, -100.0, -100.0),
+            Vec3::new(12.0, 100.0, 100.0),
+        )]
+    }
+
+    const HALF: Vec3 = Vec3::new(1.0, 1.0, 1.0);
+
+    #[test]
+    fn sweep_clean_move() {
+        let start = Vec3::new(0.0, 0.0, 0.0);
+        let end = Vec3::new(0.0, 20.0, 0.0);
+        let tr = sweep_aabb(start, end, HALF, &wall());
+
+        assert_eq!(tr.fraction, 1.0);
+        assert!(!tr.hit());
+        assert!(!tr.start_solid);
+        assert_eq!(tr.end_pos, end);
+        assert_eq!(tr.normal, Vec3::ZERO);
+    }
+
+    #[test]
+    fn sweep_blocked_halfway() {
+        let start = Vec3::new(0.0, 0.0, 0.0);
+        let end = Vec3::new(20.0, 0.0, 0.0);
+        let tr = sweep_aabb(start, end, HALF, &wall());
+
+        // The box's leading face touches the wall at x = 10.
+        assert!(tr.hit());
+        assert!(!tr.start_solid);
+        assert!(approx(tr.fraction, (10.0 - DIST_EPSILON) / 20.0));
+        assert_eq!(tr.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!(approx(tr.end_pos.x, 10.0 - DIST_EPSILON));
+    }
+
+    #[test]
+    fn sweep_start_solid() {
+        let start = Vec3::new(11.5, 0.0, 0.0);
+        let tr = sweep_aabb(start, Vec3::new(30.0, 0.0, 0.0), HALF, &wall());
+        assert!(tr.start_solid);
+        assert!(!tr.all_solid);
+        assert_eq!(tr.fraction, 0.0);
+
+        let tr = sweep_aabb(start, Vec3::new(11.5, 5.0, 0.0), HALF, &wall());
+        assert!(tr.start_solid);
+        assert!(tr.all_solid);
+    }
+
+    #[test]
+    fn sweep_zero_length_move() {
+        let clear = Vec3::new(0.0, 0.0, 0.0);
+        let tr = sweep_aabb(clear, clear, HALF, &wall());
+        assert_eq!(tr.fraction, 1.0);
+        assert!(!tr.start_solid);
+
+        let inside = Vec3::new(11.5, 0.0, 0.0);
+        let tr = sweep_aabb(inside, inside, HALF, &wall());
+        assert!(tr.start_solid);
+        assert!(tr.all_solid);
+        assert_eq!(tr.fraction, 0.0);
+    }
+
+    #[test]
+    fn brush_from_bsp_resolves_planes() {
+        let map = BspMap {
+            planes: Brush::from_box(Vec3::ZERO, Vec3::new(1.0, 1.0, 1.0)).planes,
+            brush_sides: (0..6)
+                .map(|i| bsp::BrushSide {
+                    plane_num: i,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let brush = bsp::Brush {
+            first_side: 0,
+            num_sides: 6,
+            contents: 1,
+        };
+        let resolved = Brush::from_bsp(&map, &brush).unwrap();
+        assert_eq!(resolved.planes.len(), 6);
+
+        let bad = bsp::Brush {
+            first_side: 4,
+            num_sides: 6,
+            contents: 1,
+        };
+        assert!(Brush::from_bsp(&map, &bad).is_none());
+    }
+
+    const DT: f32 = 1.0 / 64.0;
+
+    #[test]
+    fn grounded_player_decelerates_to_rest() {
+        let mut state = PlayerPhysics {
+            position: Vec3::ZERO,
+            velocity: Vec3::new(5.0, 0.0, 0.0),
+        };
+        let input = InputState::default();
+
+        let mut last_speed = state.velocity.len_sq();
+        for _ in 0..128 {
+            move_player(&mut state, &input, DT, GroundInfo::FLAT);
+            let speed = state.velocity.len_sq();
+            assert!(speed <= last_speed);
+            last_speed = speed;
+        }
+
+        assert_eq!(state.velocity, Vec3::ZERO);
+        assert!(state.position.x > 0.0);
+        assert_eq!(state.position.z, 0.0);
+    }
+
+    #[test]
+    fn airborne_player_falls_under_gravity() {
+        let mut state = PlayerPhysics::default();
+        let input = InputState::default();
+
+        for _ in 0..64 {
+            move_player(&mut state, &input, DT, GroundInfo::Airborne);
+        }
+
+        assert!(approx(state.velocity.z, -GRAVITY));
+        assert!(state.position.z < 0.0);
+        assert_eq!(state.velocity.x, 0.0);
+        assert_eq!(state.velocity.y, 0.0);
+    }
+
+    #[test]
+    fn wish_direction_accelerates_up_to_speed_cap() {
+        let mut state = PlayerPhysics::default();
+        let input = InputState {
+            forward: 1.0,
+            right: 1.0,
+            up: 0.0,
+            ..Default::default()
+        };
+
+        for _ in 0..256 {
+            move_player(&mut state, &input, DT, GroundInfo::FLAT);
+        }
+
+        let v = state.velocity;
+        assert!(v.x > 0.0 && approx(v.x, v.y));
+        assert!((v.x * v.x + v.y * v.y).sqrt() <= MAX_SPEED + 1e-4);
+        assert_eq!(v.z, 0.0);
+    }
+
+    #[test]
+    fn movement_is_deterministic() {
+        let run = || {
+            let mut state = PlayerPhysics {
+                position: Vec3::ZERO,
+                velocity: Vec3::new(1.0, -2.0, 3.0),
+            };
+            let input = InputState {
+                forward: 0.3,
+                right: -0.7,
+                up: 0.0,
+                ..Default::default()
+            };
+            for i in 0..100 {
+                let ground = if i % 3 == 0 {
+                    GroundInfo::Airborne
+                } else {
+                    GroundInfo::FLAT
+                };
+                move_player(&mut state, &input, DT, ground);
+            }
+            state
+        };
+        assert_eq!(run(), run());
+    }
+
+    /// A player-sized box centred on `(x, 0, 0)`.
+    fn player_at(x: f32) -> Aabb {
+        Aabb::new(Vec3::new(x - 0.5, -0.5, 0.0), Vec3::new(x + 0.5, 0.5, 1.8))
+    }
+
+    fn capture_zone() -> TriggerTracker {
+        TriggerTracker::new(vec![TriggerVolume::new(
+            7,
+            Aabb::new(Vec3::new(10.0, -5.0, 0.0), Vec3::new(20.0, 5.0, 4.0)),
+        )])
+    }
+
+    #[test]
+    fn trigger_enter_stay_exit() {
+        let mut tracker = capture_zone();
+        let player = EntityId(1);
+
+        assert!(tracker.update(&[(player, player_at(0.0))]).is_empty());
+
+        let events = tracker.update(&[(player, player_at(9.8))]);
+        assert_eq!(
+            events,
+            vec![TriggerEvent::Enter {
+                trigger: 7,
+                entity: player
+            }]
+        );
+        assert!(tracker.is_inside(7, player));
+
+        // Moving around inside fires nothing.
+        assert!(tracker.update(&[(player, player_at(15.0))]).is_empty());
+        assert!(tracker.update(&[(player, player_at(19.0))]).is_empty());
+
+        let events = tracker.update(&[(player, player_at(25.0))]);
+        assert_eq!(
+            events,
+            vec![TriggerEvent::Exit {
+                trigger: 7,
+                entity: player
+            }]
+        );
+        assert!(tracker.update(&[(player, player_at(25.0))]).is_empty());
+    }
+
+    #[test]
+    fn trigger_exit_when_entity_disappears() {
+        let mut tracker = capture_zone();
+        let a = EntityId(1);
+        let b = EntityId(2);
+
+        let events = tracker.update(&[(b, player_at(12.0)), (a, player_at(14.0))]);
+        assert_eq!(
+            events,
+            vec![
+                TriggerEvent::Enter {
+                    trigger: 7,
+                    entity: a
+                },
+                TriggerEvent::Enter {
+                    trigger: 7,
+                    entity: b
+                },
+            ]
+        );
+
+        // `b` was despawned.
+        let events = tracker.update(&[(a, player_at(14.0))]);
+        assert_eq!(
+            events,
+            vec![TriggerEvent::Exit {
+                trigger: 7,
+                entity: b
+            }]
+        );
+    }
+}
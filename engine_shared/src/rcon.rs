@@ -0,0 +1,547 @@
+//! Remote console (RCON) for server admins.
+//!
+//! # Valve Documentation Reference
+//! - [Source RCON Protocol](https://developer.valvesoftware.com/wiki/Source_RCON_Protocol)
+//!
+//! # Packet Format
+//! Every packet is little-endian: a 32-bit size, then the request id, the
+//! packet type and a null-terminated body followed by an empty string. The
+//! size counts everything after itself.
+//!
+//! # Flow
+//! 1. Admin sends `SERVERDATA_AUTH` with the password
+//! 2. Server answers with an empty `SERVERDATA_RESPONSE_VALUE` and a
+//!    `SERVERDATA_AUTH_RESPONSE` echoing the id, or id -1 on failure
+//! 3. Admin sends `SERVERDATA_EXECCOMMAND` packets
+//! 4. Server runs each through the console and answers with one or more
+//!    `SERVERDATA_RESPONSE_VALUE` packets carrying the output
+//!
+//! An address that fails authentication `max_failures` times in a row is
+//! locked out for the ban penalty, like `sv_rcon_maxfailures` and
+//! `sv_rcon_banpenalty`.
+//!
+//! [`RconServer`] only handles packets; reading and writing them on a
+//! socket is up to the caller.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use crate::console::ConsoleRegistry;
+
+/// RCON packet types.
+///
+/// `EXECCOMMAND` and `AUTH_RESPONSE` share a value; which is meant depends
+/// on the direction.
+pub mod packet_type {
+    pub const SERVERDATA_AUTH: i32 = 3;
+    pub const SERVERDATA_AUTH_RESPONSE: i32 = 2;
+    pub const SERVERDATA_EXECCOMMAND: i32 = 2;
+    pub const SERVERDATA_RESPONSE_VALUE: i32 = 0;
+}
+
+/// Largest response body sent in one packet; longer output is split.
+pub const MAX_RESPONSE_BODY: usize = 4096;
+/// Largest packet size field accepted from a client.
+pub const MAX_PACKET_SIZE: usize = 4096 + 10;
+/// Smallest valid size field: id, type and two empty strings.
+const MIN_PACKET_SIZE: usize = 10;
+
+/// Failed logins allowed before an address is locked out.
+pub const DEFAULT_MAX_FAILURES: u32 = 5;
+/// How long a locked out address is refused.
+pub const DEFAULT_BAN_PENALTY: Duration = Duration::from_secs(30 * 60);
+
+/// Request id sent back for a failed login.
+pub const AUTH_FAILED_ID: i32 = -1;
+
+/// A single RCON packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RconPacket {
+    /// Client-chosen id, echoed in responses.
+    pub id: i32,
+    /// One of the [`packet_type`] constants.
+    pub kind: i32,
+    pub body: String,
+}
+
+impl RconPacket {
+    pub fn new(id: i32, kind: i32, body: impl Into<String>) -> Self {
+        Self {
+            id,
+            kind,
+            body: body.into(),
+        }
+    }
+
+    /// Serialize to wire format.
+    pub fn encode(&self) -> Vec<u8> {
+        let size = 4 + 4 + self.body.len() + 2;
+        let mut out = Vec::with_capacity(4 + size);
+        out.extend_from_slice(&(size as i32).to_le_bytes());
+        out.extend_from_slice(&self.id.to_le_bytes());
+        out.extend_from_slice(&self.kind.to_le_bytes());
+        out.extend_from_slice(self.body.as_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out
+    }
+
+    /// Parse one packet from the front of `buf`.
+    ///
+    /// Returns the packet and the bytes it used, or `None` if `buf` doesn't
+    /// hold a whole packet yet.
+    pub fn decode(buf: &[u8]) -> Result<Option<(RconPacket, usize)>, RconError> {
+        let Some(size) = read_i32(buf, 0) else {
+            return Ok(None);
+        };
+        let size = usize::try_from(size).map_err(|_| RconError::InvalidSize(size))?;
+        if !(MIN_PACKET_SIZE..=MAX_PACKET_SIZE).contains(&size) {
+            return Err(RconError::InvalidSize(size as i32));
+        }
+        let Some(packet) = buf.get(4..4 + size) else {
+            return Ok(None);
+        };
+
+        let id = read_i32(packet, 0).unwrap_or_default();
+        let kind = read_i32(packet, 4).unwrap_or_default();
+        let rest = &packet[8..];
+        let body_len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(RconError::MissingTerminator)?;
+        if rest.len() < body_len + 2 || rest[body_len + 1] != 0 {
+            return Err(RconError::MissingTerminator);
+        }
+        let body = std::str::from_utf8(&rest[..body_len])
+            .map_err(|_| RconError::InvalidBody)?
+            .to_string();
+        Ok(Some((RconPacket { id, kind, body }, 4 + size)))
+    }
+}
+
+fn read_i32(buf: &[u8], at: usize) -> Option<i32> {
+    let bytes = buf.get(at..at + 4)?;
+    Some(i32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Errors from parsing or handling RCON packets.
+///
+/// The caller should close the connection on any of these.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RconError {
+    /// The size field is out of range.
+    InvalidSize(i32),
+    /// The body or trailing empty string isn't null-terminated.
+    MissingTerminator,
+    /// The body isn't valid UTF-8.
+    InvalidBody,
+    /// The packet type isn't one a client sends.
+    UnknownType(i32),
+    /// A command arrived before a successful login.
+    NotAuthenticated,
+    /// The address failed to log in too many times.
+    LockedOut,
+    /// No password is set, so RCON is off.
+    Disabled,
+}
+
+impl fmt::Display for RconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RconError::InvalidSize(size) => write!(f, "invalid rcon packet size {size}"),
+            RconError::MissingTerminator => write!(f, "rcon packet body is not terminated"),
+            RconError::InvalidBody => write!(f, "rcon packet body is not UTF-8"),
+            RconError::UnknownType(kind) => write!(f, "unknown rcon packet type {kind}"),
+            RconError::NotAuthenticated => write!(f, "rcon command before authentication"),
+            RconError::LockedOut => write!(f, "too many failed rcon logins"),
+            RconError::Disabled => write!(f, "rcon is disabled"),
+        }
+    }
+}
+
+impl std::error::Error for RconError {}
+
+/// Failed logins from one address.
+#[derive(Debug, Clone, Copy)]
+struct FailureRecord {
+    count: u32,
+    locked_until: Option<Instant>,
+}
+
+/// Authenticates admins and runs their commands.
+#[derive(Debug)]
+pub struct RconServer {
+    password: String,
+    max_failures: u32,
+    ban_penalty: Duration,
+    /// Connections that have logged in.
+    authenticated: HashSet<SocketAddr>,
+    /// Consecutive failed logins per address.
+    failures: HashMap<IpAddr, FailureRecord>,
+}
+
+impl RconServer {
+    /// Creates a server accepting `password`. An empty password disables
+    /// RCON.
+    pub fn new(password: &str) -> Self {
+        Self {
+            password: password.to_string(),
+            max_failures: DEFAULT_MAX_FAILURES,
+            ban_penalty: DEFAULT_BAN_PENALTY,
+            authenticated: HashSet::new(),
+            failures: HashMap::new(),
+        }
+    }
+
+    /// Changes the password. Existing logins stay valid.
+    pub fn set_password(&mut self, password: &str) {
+        self.password = password.to_string();
+    }
+
+    pub fn set_max_failures(&mut self, max_failures: u32) {
+        self.max_failures = max_failures.max(1);
+    }
+
+    pub fn set_ban_penalty(&mut self, penalty: Duration) {
+        self.ban_penalty = penalty;
+    }
+
+    /// Whether `peer` has logged in.
+    pub fn is_authenticated(&self, peer: SocketAddr) -> bool {
+        self.authenticated.contains(&peer)
+    }
+
+    /// Whether logins from `ip` are currently refused.
+    pub fn is_locked_out(&self, ip: IpAddr) -> bool {
+        self.is_locked_out_at(ip, Instant::now())
+    }
+
+    pub fn is_locked_out_at(&self, ip: IpAddr, now: Instant) -> bool {
+        self.failures
+            .get(&ip)
+            .and_then(|r| r.locked_until)
+            .is_some_and(|until| now < until)
+    }
+
+    /// Forgets a closed connection's login.
+    pub fn disconnect(&mut self, peer: SocketAddr) {
+        self.authenticated.remove(&peer);
+    }
+
+    /// Handles one packet from `peer`, returning the packets to send back.
+    pub fn handle_packet(
+        &mut self,
+        peer: SocketAddr,
+        packet: &RconPacket,
+        console: &ConsoleRegistry,
+    ) -> Result<Vec<RconPacket>, RconError> {
+        self.handle_packet_at(peer, packet, console, Instant::now())
+    }
+
+    pub fn handle_packet_at(
+        &mut self,
+        peer: SocketAddr,
+        packet: &RconPacket,
+        console: &ConsoleRegistry,
+        now: Instant,
+    ) -> Result<Vec<RconPacket>, RconError> {
+        match packet.kind {
+            packet_type::SERVERDATA_AUTH => self.authenticate(peer, packet, now),
+            packet_type::SERVERDATA_EXECCOMMAND => {
+                if !self.is_authenticated(peer) {
+                    return Err(RconError::NotAuthenticated);
+                }
+                Ok(run_command(packet.id, &packet.body, console))
+            }
+            // Clients send an empty response after a command to find where
+            // a split reply ends; mirror it back.
+            packet_type::SERVERDATA_RESPONSE_VALUE => {
+                if !self.is_authenticated(peer) {
+                    return Err(RconError::NotAuthenticated);
+                }
+                Ok(vec![RconPacket::new(
+                    packet.id,
+                    packet_type::SERVERDATA_RESPONSE_VALUE,
+                    "",
+                )])
+            }
+            kind => Err(RconError::UnknownType(kind)),
+        }
+    }
+
+    fn authenticate(
+        &mut self,
+        peer: SocketAddr,
+        packet: &RconPacket,
+        now: Instant,
+    ) -> Result<Vec<RconPacket>, RconError> {
+        if self.password.is_empty() {
+            return Err(RconError::Disabled);
+        }
+        let ip = peer.ip();
+        if self.is_locked_out_at(ip, now) {
+            return Err(RconError::LockedOut);
+        }
+
+        let id = if constant_time_eq(packet.body.as_bytes(), self.password.as_bytes()) {
+            self.failures.remove(&ip);
+            self.authenticated.insert(peer);
+            packet.id
+        } else {
+            self.authenticated.remove(&peer);
+            let record = self.failures.entry(ip).or_insert(FailureRecord {
+                count: 0,
+                locked_until: None,
+            });
+            // A lapsed lockout starts the count over.
+            if record.locked_until.is_some() {
+                *record = FailureRecord {
+                    count: 0,
+                    locked_until: None,
+                };
+            }
+            record.count += 1;
+            if record.count >= self.max_failures {
+                record.locked_until = Some(now + self.ban_penalty);
+            }
+            AUTH_FAILED_ID
+        };
+
+        Ok(vec![
+            RconPacket::new(packet.id, packet_type::SERVERDATA_RESPONSE_VALUE, ""),
+            RconPacket::new(id, packet_type::SERVERDATA_AUTH_RESPONSE, ""),
+        ])
+    }
+}
+
+/// Runs a command line and packs its output into response packets.
+fn run_command(id: i32, line: &str, console: &ConsoleRegistry) -> Vec<RconPacket> {
+    let output = match console.exec(line) {
+        Ok(lines) => lines.join("\n"),
+        Err(e) => e.to_string(),
+    };
+    split_body(&output)
+        .into_iter()
+        .map(|chunk| RconPacket::new(id, packet_type::SERVERDATA_RESPONSE_VALUE, chunk))
+        .collect()
+}
+
+/// Splits `body` into pieces of at most `MAX_RESPONSE_BODY` bytes, on char
+/// boundaries. Always returns at least one (possibly empty) piece.
+fn split_body(mut body: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    while body.len() > MAX_RESPONSE_BODY {
+        let mut end = MAX_RESPONSE_BODY;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, rest) = body.split_at(end);
+        chunks.push(chunk);
+        body = rest;
+    }
+    chunks.push(body);
+    chunks
+}
+
+/// Compares in time that depends only on the lengths, not where the inputs
+/// differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let mut diff = a.len() ^ b.len();
+    for i in 0..a.len().max(b.len()) {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        diff |= usize::from(x ^ y);
+    }
+    diff == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::ConsoleRegistry;
+
+    fn admin() -> SocketAddr {
+        "10.0.0.5:51000".parse().unwrap()
+    }
+
+    fn auth(id: i32, password: &str) -> RconPacket {
+        RconPacket::new(id, packet_type::SERVERDATA_AUTH, password)
+    }
+
+    fn exec(id: i32, line: &str) -> RconPacket {
+        RconPacket::new(id, packet_type::SERVERDATA_EXECCOMMAND, line)
+    }
+
+    #[test]
+    fn packet_roundtrip() {
+        let packet = exec(42, "status");
+        let mut bytes = packet.encode();
+        assert_eq!(bytes.len(), 4 + 4 + 4 + "status".len() + 2);
+        assert_eq!(&bytes[..4], &(bytes.len() as i32 - 4).to_le_bytes());
+
+        // Incomplete until the last byte arrives.
+        assert_eq!(RconPacket::decode(&bytes[..bytes.len() - 1]), Ok(None));
+        bytes.extend_from_slice(&auth(43, "pw").encode());
+        let (back, used) = RconPacket::decode(&bytes).unwrap().unwrap();
+        assert_eq!(back, packet);
+        let (next, _) = RconPacket::decode(&bytes[used..]).unwrap().unwrap();
+        assert_eq!(next, auth(43, "pw"));
+    }
+
+    #[test]
+    fn decode_rejects_malformed() {
+        let mut bytes = exec(1, "x").encode();
+        bytes[..4].copy_from_slice(&(1_000_000i32).to_le_bytes());
+        assert_eq!(
+            RconPacket::decode(&bytes),
+            Err(RconError::InvalidSize(1_000_000))
+        );
+
+        let mut bytes = exec(1, "x").encode();
+        let last = bytes.len() - 1;
+        bytes[last] = b'!';
+        assert_eq!(
+            RconPacket::decode(&bytes),
+            Err(RconError::MissingTerminator)
+        );
+    }
+
+    #[test]
+    fn constant_time_eq_matches_eq() {
+        assert!(constant_time_eq(b"hunter2", b"hunter2"));
+        assert!(!constant_time_eq(b"hunter2", b"hunter3"));
+        assert!(!constant_time_eq(b"hunter2", b"hunter"));
+        assert!(!constant_time_eq(b"", b"x"));
+        assert!(!constant_time_eq(&[0; 256], b""));
+    }
+
+    #[test]
+    fn auth_then_command() {
+        let console = ConsoleRegistry::new();
+        let mut rcon = RconServer::new("hunter2");
+
+        assert_eq!(
+            rcon.handle_packet(admin(), &exec(1, "echo hi"), &console),
+            Err(RconError::NotAuthenticated)
+        );
+
+        let replies = rcon
+            .handle_packet(admin(), &auth(7, "hunter2"), &console)
+            .unwrap();
+        assert_eq!(
+            replies,
+            vec![
+                RconPacket::new(7, packet_type::SERVERDATA_RESPONSE_VALUE, ""),
+                RconPacket::new(7, packet_type::SERVERDATA_AUTH_RESPONSE, ""),
+            ]
+        );
+        assert!(rcon.is_authenticated(admin()));
+
+        let replies = rcon
+            .handle_packet(admin(), &exec(8, "echo hello world"), &console)
+            .unwrap();
+        assert_eq!(
+            replies,
+            vec![RconPacket::new(
+                8,
+                packet_type::SERVERDATA_RESPONSE_VALUE,
+                "hello world"
+            )]
+        );
+
+        // Console errors come back as output rather than ending the session.
+        let replies = rcon
+            .handle_packet(admin(), &exec(9, "echo_typo"), &console)
+            .unwrap();
+        assert_eq!(replies[0].body, "Unknown command: echo_typo");
+        let replies = rcon
+            .handle_packet(admin(), &exec(10, "set"), &console)
+            .unwrap();
+        assert_eq!(replies[0].body, "usage: set <cvar> <value>");
+
+        rcon.disconnect(admin());
+        assert!(!rcon.is_authenticated(admin()));
+    }
+
+    #[test]
+    fn wrong_password_locks_out() {
+        let console = ConsoleRegistry::new();
+        let mut rcon = RconServer::new("hunter2");
+        rcon.set_max_failures(3);
+        rcon.set_ban_penalty(Duration::from_secs(60));
+        let start = Instant::now();
+
+        for attempt in 0..3 {
+            let replies = rcon
+                .handle_packet_at(admin(), &auth(attempt, "guess"), &console, start)
+                .unwrap();
+            assert_eq!(replies[1].id, AUTH_FAILED_ID);
+            assert_eq!(replies[1].kind, packet_type::SERVERDATA_AUTH_RESPONSE);
+        }
+        assert!(rcon.is_locked_out_at(admin().ip(), start));
+
+        // The right password doesn't help while locked out, from any port.
+        let other_port: SocketAddr = "10.0.0.5:51001".parse().unwrap();
+        assert_eq!(
+            rcon.handle_packet_at(other_port, &auth(4, "hunter2"), &console, start),
+            Err(RconError::LockedOut)
+        );
+        assert!(!rcon.is_authenticated(other_port));
+
+        // Other addresses are unaffected.
+        let colleague: SocketAddr = "10.0.0.6:51000".parse().unwrap();
+        rcon.handle_packet_at(colleague, &auth(1, "hunter2"), &console, start)
+            .unwrap();
+        assert!(rcon.is_authenticated(colleague));
+
+        let later = start + Duration::from_secs(61);
+        assert!(!rcon.is_locked_out_at(admin().ip(), later));
+        let replies = rcon
+            .handle_packet_at(admin(), &auth(5, "hunter2"), &console, later)
+            .unwrap();
+        assert_eq!(replies[1].id, 5);
+        assert!(rcon.is_authenticated(admin()));
+    }
+
+    #[test]
+    fn long_output_splits_into_packets() {
+        let mut console = ConsoleRegistry::new();
+        console.register_command("dump", "dump: print a lot", |_| {
+            Ok((0..1000).map(|i| format!("line {i:04}")).collect())
+        });
+        let mut rcon = RconServer::new("hunter2");
+        rcon.handle_packet(admin(), &auth(1, "hunter2"), &console)
+            .unwrap();
+
+        let replies = rcon
+            .handle_packet(admin(), &exec(2, "dump"), &console)
+            .unwrap();
+        assert!(replies.len() > 1);
+        for reply in &replies {
+            assert_eq!(reply.id, 2);
+            assert_eq!(reply.kind, packet_type::SERVERDATA_RESPONSE_VALUE);
+            assert!(reply.body.len() <= MAX_RESPONSE_BODY);
+        }
+        let joined: String = replies.iter().map(|r| r.body.as_str()).collect();
+        let expected: Vec<String> = (0..1000).map(|i| format!("line {i:04}")).collect();
+        assert_eq!(joined, expected.join("\n"));
+
+        // The end-of-response marker is mirrored back after the output.
+        let marker = RconPacket::new(3, packet_type::SERVERDATA_RESPONSE_VALUE, "");
+        assert_eq!(
+            rcon.handle_packet(admin(), &marker, &console).unwrap(),
+            vec![marker]
+        );
+    }
+
+    #[test]
+    fn empty_password_disables_rcon() {
+        let console = ConsoleRegistry::new();
+        let mut rcon = RconServer::new("");
+        assert_eq!(
+            rcon.handle_packet(admin(), &auth(1, ""), &console),
+            Err(RconError::Disabled)
+        );
+    }
+}
@@ -3,7 +3,9 @@
 //! This crate intentionally does not depend on a graphics backend.
 //! Define traits that a renderer implementation would satisfy.
 
-use crate::math::{Mat4, Vec3};
+use std::collections::HashMap;
+
+use crate::math::{det, Aabb, Mat4, Vec3};
 
 /// A minimal rendering API.
 pub trait RenderBackend: Send + Sync {
@@ -23,3 +25,554 @@ impl RenderBackend for NullRenderer {
     fn set_view_proj(&mut self, _view_proj: Mat4) {}
     fn end_frame(&mut self) {}
 }
+
+/// A backend-agnostic draw instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderCommand {
+    /// Restricts drawing to a rectangle of the render target, in pixels.
+    SetViewport {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    /// Sets the camera for subsequent draws.
+    SetCamera { view_proj: Mat4, position: Vec3 },
+    /// Draws a mesh with a material at a world transform.
+    DrawMesh {
+        mesh: u32,
+        material: u32,
+        transform: Mat4,
+    },
+    /// Draws screen-space text at a pixel position.
+    DrawText {
+        text: String,
+        x: f32,
+        y: f32,
+        color: [u8; 4],
+    },
+}
+
+/// Sort key for batching: layer first, then material.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SortKey(pub u64);
+
+impl SortKey {
+    pub fn new(layer: u16, material: u32) -> Self {
+        Self((u64::from(layer) << 32) | u64::from(material))
+    }
+
+    pub fn layer(self) -> u16 {
+        (self.0 >> 32) as u16
+    }
+
+    pub fn material(self) -> u32 {
+        self.0 as u32
+    }
+}
+
+/// Queue of render commands built during a frame and consumed by a backend.
+#[derive(Debug, Clone, Default)]
+pub struct RenderList {
+    commands: Vec<(SortKey, RenderCommand)>,
+}
+
+impl RenderList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a command under `key`.
+    pub fn push(&mut self, key: SortKey, command: RenderCommand) {
+        self.commands.push((key, command));
+    }
+
+    /// Orders commands by key. The sort is stable, so commands with equal
+    /// keys keep their insertion order.
+    pub fn sort(&mut self) {
+        self.commands.sort_by_key(|(key, _)| *key);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RenderCommand> {
+        self.commands.iter().map(|(_, command)| command)
+    }
+
+    /// Iterates commands together with their keys.
+    pub fn iter_keyed(&self) -> impl Iterator<Item = (SortKey, &RenderCommand)> {
+        self.commands.iter().map(|(key, command)| (*key, command))
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Removes all commands, keeping the allocation for the next frame.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+}
+
+/// A frustum plane; points with `normal.dot(p) + d >= 0` are on the inside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipPlane {
+    pub normal: Vec3,
+    pub d: f32,
+}
+
+impl ClipPlane {
+    pub fn distance(&self, p: Vec3) -> f32 {
+        self.normal.dot(p) + self.d
+    }
+}
+
+/// How much of a volume a frustum sees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Inside,
+    Intersecting,
+    Outside,
+}
+
+/// View frustum, as six inward-facing planes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near, far.
+    pub planes: [ClipPlane; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a column-major view-projection matrix.
+    ///
+    /// Assumes OpenGL-style clip space, where visible points have
+    /// `-w <= x, y, z <= w`.
+    pub fn from_view_proj(matrix: Mat4) -> Self {
+        let m = matrix.m;
+        let row = |i: usize| [m[0][i], m[1][i], m[2][i], m[3][i]];
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+
+        let plane = |a: [f32; 4], b: [f32; 4], sign: f32| {
+            let normal = Vec3::new(a[0] + sign * b[0], a[1] + sign * b[1], a[2] + sign * b[2]);
+            let d = a[3] + sign * b[3];
+            let len = det::length(normal);
+            if len > 0.0 {
+                ClipPlane {
+                    normal: Vec3::new(normal.x / len, normal.y / len, normal.z / len),
+                    d: d / len,
+                }
+            } else {
+                ClipPlane { normal, d }
+            }
+        };
+
+        Self {
+            planes: [
+                plane(r3, r0, 1.0),
+                plane(r3, r0, -1.0),
+                plane(r3, r1, 1.0),
+                plane(r3, r1, -1.0),
+                plane(r3, r2, 1.0),
+                plane(r3, r2, -1.0),
+            ],
+        }
+    }
+
+    /// Classifies a box against the frustum. Empty boxes are `Outside`.
+    ///
+    /// Conservative: a box near a frustum corner may be reported as
+    /// `Intersecting` while actually outside, but never the other way round.
+    pub fn contains_aabb(&self, aabb: &Aabb) -> Visibility {
+        if aabb.is_empty() {
+            return Visibility::Outside;
+        }
+
+        let mut result = Visibility::Inside;
+        for plane in &self.planes {
+            let n = plane.normal;
+            // Corners furthest along and against the plane normal.
+            let pick = |pos: bool, min: f32, max: f32| if pos { max } else { min };
+            let far = Vec3::new(
+                pick(n.x >= 0.0, aabb.min.x, aabb.max.x),
+                pick(n.y >= 0.0, aabb.min.y, aabb.max.y),
+                pick(n.z >= 0.0, aabb.min.z, aabb.max.z),
+            );
+            let near = Vec3::new(
+                pick(n.x < 0.0, aabb.min.x, aabb.max.x),
+                pick(n.y < 0.0, aabb.min.y, aabb.max.y),
+                pick(n.z < 0.0, aabb.min.z, aabb.max.z),
+            );
+
+            if plane.distance(far) < 0.0 {
+                return Visibility::Outside;
+            }
+            if plane.distance(near) < 0.0 {
+                result = Visibility::Intersecting;
+            }
+        }
+        result
+    }
+}
+
+/// Per-character advance widths for a font, in pixels.
+///
+/// Supplied by whatever loads the font; characters without an entry use the
+/// default advance, so a monospace font needs no table at all.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontMetrics {
+    pub line_height: f32,
+    pub default_advance: f32,
+    advances: HashMap<char, f32>,
+}
+
+impl FontMetrics {
+    /// Metrics where every character is `advance` wide.
+    pub fn monospace(advance: f32, line_height: f32) -> Self {
+        Self {
+            line_height,
+            default_advance: advance,
+            advances: HashMap::new(),
+        }
+    }
+
+    /// Overrides the advance of one character.
+    pub fn set_advance(&mut self, ch: char, advance: f32) {
+        self.advances.insert(ch, advance);
+    }
+
+    pub fn advance(&self, ch: char) -> f32 {
+        self.advances
+            .get(&ch)
+            .copied()
+            .unwrap_or(self.default_advance)
+    }
+}
+
+/// One line of laid-out text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    pub text: String,
+    /// Byte offset of the line in the source string.
+    pub start: usize,
+    pub width: f32,
+}
+
+/// Measures and wraps text for the HUD and console.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    metrics: FontMetrics,
+}
+
+impl TextLayout {
+    pub fn new(metrics: FontMetrics) -> Self {
+        Self { metrics }
+    }
+
+    pub fn metrics(&self) -> &FontMetrics {
+        &self.metrics
+    }
+
+    /// Width of a single line, ignoring newlines.
+    pub fn line_width(&self, text: &str) -> f32 {
+        text.chars()
+            .filter(|&ch| ch != '\n')
+            .map(|ch| self.metrics.advance(ch))
+            .sum()
+    }
+
+    /// Width and height of `text` without wrapping. Each `\n` starts a new
+    /// line; the width is that of the widest line.
+    pub fn measure(&self, text: &str) -> (f32, f32) {
+        let mut width = 0.0f32;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            width = width.max(self.line_width(line));
+            lines += 1;
+        }
+        (width, lines as f32 * self.metrics.line_height)
+    }
+
+    /// Breaks `text` into lines no wider than `max_width`.
+    ///
+    /// Lines break at whitespace, which is dropped from the end of the line
+    /// it breaks; a word too long for a line on its own is split between
+    /// characters. Each `\n` forces a break. Every line holds at least one
+    /// character, so a tiny `max_width` still makes progress.
+    pub fn wrap(&self, text: &str, max_width: f32) -> Vec<TextLine> {
+        let mut lines = Vec::new();
+        let mut base = 0;
+        for paragraph in text.split('\n') {
+            self.wrap_paragraph(paragraph, base, max_width, &mut lines);
+            base += paragraph.len() + 1;
+        }
+        lines
+    }
+
+    /// Height of `lines` when drawn.
+    pub fn height(&self, lines: &[TextLine]) -> f32 {
+        lines.len() as f32 * self.metrics.line_height
+    }
+
+    fn wrap_paragraph(&self, text: &str, base: usize, max_width: f32, out: &mut Vec<TextLine>) {
+        let mut line_start = 0;
+        let mut width = 0.0;
+        let mut seen_word = false;
+        // End of the line's text and start of the next, if we break at the
+        // last whitespace seen.
+        let mut last_break: Option<(usize, usize)> = None;
+
+        for (i, ch) in text.char_indices() {
+            let advance = self.metrics.advance(ch);
+            if ch.is_whitespace() {
+                if seen_word {
+                    last_break = Some((i, i + ch.len_utf8()));
+                }
+                // Trailing whitespace may hang past the edge.
+                width += advance;
+                continue;
+            }
+            if width + advance > max_width && i > line_start {
+                let (end, mut next) = last_break.unwrap_or((i, i));
+                self.push_line(text, base, line_start, end, out);
+                while let Some(c) = text[next..i].chars().next().filter(|c| c.is_whitespace()) {
+                    next += c.len_utf8();
+                }
+                line_start = next;
+                width = self.line_width(&text[line_start..i]);
+                last_break = None;
+            }
+            seen_word = true;
+            width += advance;
+        }
+        self.push_line(text, base, line_start, text.len(), out);
+    }
+
+    fn push_line(
+        &self,
+        text: &str,
+        base: usize,
+        start: usize,
+        end: usize,
+        out: &mut Vec<TextLine>,
+    ) {
+        let line = text[start..end].trim_end();
+        out.push(TextLine {
+            text: line.to_string(),
+            start: base + start,
+            width: self.line_width(line),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Orthographic view-projection seeing x in [-5, 15] and y, z in [-10, 10].
+    fn ortho() -> Frustum {
+        let mut m = Mat4::default();
+        m.m[0][0] = 0.1;
+        m.m[1][1] = 0.1;
+        m.m[2][2] = 0.1;
+        m.m[3][0] = -0.5;
+        Frustum::from_view_proj(m)
+    }
+
+    fn cube(center: Vec3, half: f32) -> Aabb {
+        Aabb::new(
+            Vec3::new(center.x - half, center.y - half, center.z - half),
+            Vec3::new(center.x + half, center.y + half, center.z + half),
+        )
+    }
+
+    #[test]
+    fn frustum_box_inside() {
+        let f = ortho();
+        assert_eq!(
+            f.contains_aabb(&cube(Vec3::new(5.0, 0.0, 0.0), 2.0)),
+            Visibility::Inside
+        );
+        assert_eq!(
+            f.contains_aabb(&cube(Vec3::new(5.0, 0.0, 0.0), 0.0)),
+            Visibility::Inside
+        );
+    }
+
+    #[test]
+    fn frustum_box_outside() {
+        let f = ortho();
+        assert_eq!(
+            f.contains_aabb(&cube(Vec3::new(-8.0, 0.0, 0.0), 2.0)),
+            Visibility::Outside
+        );
+        assert_eq!(
+            f.contains_aabb(&cube(Vec3::new(5.0, 0.0, 30.0), 2.0)),
+            Visibility::Outside
+        );
+        assert_eq!(f.contains_aabb(&Aabb::EMPTY), Visibility::Outside);
+    }
+
+    #[test]
+    fn frustum_box_straddling_plane() {
+        let f = ortho();
+        // Crosses the left plane at x = -5.
+        assert_eq!(
+            f.contains_aabb(&cube(Vec3::new(-5.0, 0.0, 0.0), 1.0)),
+            Visibility::Intersecting
+        );
+        // Crosses the top plane at y = 10.
+        assert_eq!(
+            f.contains_aabb(&cube(Vec3::new(0.0, 10.5, 0.0), 1.0)),
+            Visibility::Intersecting
+        );
+        // Contains the whole frustum.
+        assert_eq!(
+            f.contains_aabb(&cube(Vec3::new(5.0, 0.0, 0.0), 100.0)),
+            Visibility::Intersecting
+        );
+    }
+
+    fn text(s: &str) -> RenderCommand {
+        RenderCommand::DrawText {
+            text: s.to_string(),
+            x: 0.0,
+            y: 0.0,
+            color: [255; 4],
+        }
+    }
+
+    fn mesh(mesh: u32, material: u32) -> RenderCommand {
+        RenderCommand::DrawMesh {
+            mesh,
+            material,
+            transform: Mat4::default(),
+        }
+    }
+
+    #[test]
+    fn sort_key_packs_layer_and_material() {
+        let key = SortKey::new(3, 0xdead_beef);
+        assert_eq!(key.layer(), 3);
+        assert_eq!(key.material(), 0xdead_beef);
+        assert!(SortKey::new(1, u32::MAX) < SortKey::new(2, 0));
+    }
+
+    #[test]
+    fn render_list_preserves_order_within_layer() {
+        let mut list = RenderList::new();
+        let hud = SortKey::new(5, 0);
+        list.push(hud, text("a"));
+        list.push(hud, text("b"));
+        list.push(hud, text("c"));
+        list.sort();
+
+        let order: Vec<_> = list.iter().cloned().collect();
+        assert_eq!(order, vec![text("a"), text("b"), text("c")]);
+    }
+
+    #[test]
+    fn render_list_sorts_across_layers_and_materials() {
+        let mut list = RenderList::new();
+        list.push(SortKey::new(2, 0), text("hud"));
+        list.push(SortKey::new(1, 7), mesh(1, 7));
+        list.push(
+            SortKey::new(0, 0),
+            RenderCommand::SetViewport {
+                x: 0,
+                y: 0,
+                width: 640,
+                height: 480,
+            },
+        );
+        list.push(SortKey::new(1, 3), mesh(2, 3));
+        list.push(SortKey::new(1, 7), mesh(3, 7));
+        assert_eq!(list.len(), 5);
+        list.sort();
+
+        let keys: Vec<_> = list
+            .iter_keyed()
+            .map(|(k, _)| (k.layer(), k.material()))
+            .collect();
+        assert_eq!(keys, vec![(0, 0), (1, 3), (1, 7), (1, 7), (2, 0)]);
+        let meshes: Vec<_> = list
+            .iter()
+            .filter_map(|c| match c {
+                RenderCommand::DrawMesh { mesh, .. } => Some(*mesh),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(meshes, vec![2, 1, 3]);
+
+        list.clear();
+        assert!(list.is_empty());
+    }
+
+    /// 8px monospace with a few narrow and wide glyphs.
+    fn proportional() -> TextLayout {
+        let mut metrics = FontMetrics::monospace(8.0, 16.0);
+        metrics.set_advance('i', 3.0);
+        metrics.set_advance('l', 3.0);
+        metrics.set_advance(' ', 4.0);
+        metrics.set_advance('W', 12.0);
+        TextLayout::new(metrics)
+    }
+
+    #[test]
+    fn text_measure_known_string() {
+        let mono = TextLayout::new(FontMetrics::monospace(8.0, 16.0));
+        assert_eq!(mono.measure("status"), (48.0, 16.0));
+        assert_eq!(mono.measure(""), (0.0, 16.0));
+        assert_eq!(mono.measure("ab\nabcd\nc"), (32.0, 48.0));
+
+        let font = proportional();
+        // W + i + l + l + space + o
+        assert_eq!(
+            font.line_width("Will o"),
+            12.0 + 3.0 + 3.0 + 3.0 + 4.0 + 8.0
+        );
+        assert_eq!(font.measure("Will o").1, 16.0);
+    }
+
+    #[test]
+    fn text_wrap_at_word_boundary() {
+        let mono = TextLayout::new(FontMetrics::monospace(8.0, 16.0));
+        // Ten characters per line.
+        let lines = mono.wrap("connect to the server now", 80.0);
+        let texts: Vec<_> = lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, vec!["connect to", "the server", "now"]);
+        assert_eq!(lines[0].width, 80.0);
+        assert_eq!(lines[1].start, 11);
+        assert_eq!(lines[2].start, 22);
+        assert_eq!(mono.height(&lines), 48.0);
+
+        // Exactly fitting doesn't wrap, and extra spaces are swallowed.
+        let lines = mono.wrap("abcde     fghij", 40.0);
+        let texts: Vec<_> = lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, vec!["abcde", "fghij"]);
+        assert_eq!(lines[1].start, 10);
+    }
+
+    #[test]
+    fn text_wrap_splits_long_words_and_newlines() {
+        let mono = TextLayout::new(FontMetrics::monospace(8.0, 16.0));
+        let lines = mono.wrap("abcdefghij\n\nxy", 32.0);
+        let texts: Vec<_> = lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, vec!["abcd", "efgh", "ij", "", "xy"]);
+        assert_eq!(lines[4].start, 12);
+
+        // Never stalls, even if nothing fits.
+        let lines = mono.wrap("abc", 1.0);
+        assert_eq!(lines.len(), 3);
+
+        // Proportional glyphs fit more narrow characters per line.
+        let font = proportional();
+        let texts: Vec<_> = font
+            .wrap("illi Wow", 20.0)
+            .into_iter()
+            .map(|l| l.text)
+            .collect();
+        assert_eq!(texts, vec!["illi", "Wo", "w"]);
+    }
+}
@@ -1,14 +1,28 @@
 //! Resource management system.
 //!
-//! This provides a `ResourceManager` and simple typed handles.
-//! In a real engine you'd integrate hot-reload, async loading, streaming, etc.
+//! This provides a `ResourceManager` for shared assets (maps, textures,
+//! sounds). Assets are loaded from disk through a pluggable
+//! [`ResourceLoader`], deduplicated by path, and freed once the last
+//! [`ResourceHandle`] to them is dropped, or, with a memory budget set, kept
+//! cached until the budget forces least-recently-used eviction. Files are
+//! polled for changes with [`ResourceManager::poll_reload`] so edited assets
+//! hot-reload in place.
+//! There are also simple typed handles for values inserted directly.
 
 use std::{
     any::{Any, TypeId},
     collections::HashMap,
-    sync::Arc,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock, Weak,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
+use crate::bsp::BspMap;
+
 /// Typed resource handle.
 #[derive(Debug, Clone)]
 pub struct Handle<T> {
@@ -16,14 +30,241 @@ pub struct Handle<T> {
     _phantom: std::marker::PhantomData<T>,
 }
 
+/// An asset type the manager can load from disk.
+pub trait Resource: Send + Sync + 'static {
+    /// Approximate memory used, counted against the manager's budget.
+    fn size_bytes(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
+}
+
+impl Resource for BspMap {
+    fn size_bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.name.len()
+            + std::mem::size_of_val(self.entities.as_slice())
+            + std::mem::size_of_val(self.planes.as_slice())
+            + std::mem::size_of_val(self.vertices.as_slice())
+            + std::mem::size_of_val(self.edges.as_slice())
+            + std::mem::size_of_val(self.surf_edges.as_slice())
+            + std::mem::size_of_val(self.faces.as_slice())
+            + std::mem::size_of_val(self.brushes.as_slice())
+            + std::mem::size_of_val(self.brush_sides.as_slice())
+            + std::mem::size_of_val(self.models.as_slice())
+    }
+}
+
+/// Loads one resource type from a file.
+pub trait ResourceLoader: Send + Sync + 'static {
+    type Output: Resource;
+
+    fn load(&self, path: &Path) -> Result<Self::Output, ResourceError>;
+}
+
+/// Loads `.bsp` maps.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BspLoader;
+
+impl ResourceLoader for BspLoader {
+    type Output = BspMap;
+
+    fn load(&self, path: &Path) -> Result<BspMap, ResourceError> {
+        BspMap::load(path).map_err(|e| ResourceError::Load {
+            path: path.to_path_buf(),
+            message: format!("{e:#}"),
+        })
+    }
+}
+
+/// Where the manager reads file modification times from.
+pub trait MtimeSource: Send + Sync {
+    /// Modification time of `path`, or `None` if it can't be read.
+    fn modified(&self, path: &Path) -> Option<SystemTime>;
+}
+
+/// Reads modification times from the filesystem.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsMtime;
+
+impl MtimeSource for FsMtime {
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
+
+/// Default time a changed file must stay unchanged before it's reloaded.
+pub const DEFAULT_RELOAD_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// A resource reloaded by [`ResourceManager::poll_reload`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReloadedPath {
+    pub path: PathBuf,
+    pub type_name: &'static str,
+    /// Set if the loader failed; handles keep the previous value.
+    pub error: Option<ResourceError>,
+}
+
+/// Errors from loading a resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceError {
+    /// No loader is registered for the requested type.
+    NoLoader { type_name: &'static str },
+    /// The loader failed.
+    Load { path: PathBuf, message: String },
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceError::NoLoader { type_name } => {
+                write!(f, "no loader registered for {type_name}")
+            }
+            ResourceError::Load { path, message } => {
+                write!(f, "failed to load {}: {message}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResourceError {}
+
+/// Shared state behind every handle to one loaded resource.
+struct Slot<T> {
+    path: PathBuf,
+    value: RwLock<Arc<T>>,
+    /// The manager's use counter, shared so handle access can stamp uses.
+    clock: Arc<AtomicU64>,
+    /// Counter value at the last `load` or `get` of this resource.
+    last_used: Arc<AtomicU64>,
+}
+
+impl<T> Slot<T> {
+    fn touch(&self) {
+        let now = self.clock.fetch_add(1, Ordering::Relaxed) + 1;
+        self.last_used.store(now, Ordering::Relaxed);
+    }
+}
+
+/// Ref-counted handle to a loaded resource.
+///
+/// Clones share the resource; it's unloaded when the last one drops.
+pub struct ResourceHandle<T> {
+    slot: Arc<Slot<T>>,
+}
+
+impl<T> Clone for ResourceHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            slot: Arc::clone(&self.slot),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ResourceHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResourceHandle")
+            .field("path", &self.slot.path)
+            .finish()
+    }
+}
+
+impl<T> ResourceHandle<T> {
+    pub fn path(&self) -> &Path {
+        &self.slot.path
+    }
+
+    /// The current value of the resource.
+    pub fn get(&self) -> Arc<T> {
+        self.slot.touch();
+        Arc::clone(&self.slot.value.read().expect("resource lock poisoned"))
+    }
+
+    /// Whether both handles refer to the same loaded resource.
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.slot, &b.slot)
+    }
+}
+
+/// Reloads an erased `Slot<T>` through the manager's loader for `T`,
+/// returning the new size.
+type ReloadFn = fn(&ResourceManager, &Arc<dyn Any + Send + Sync>) -> Result<usize, ResourceError>;
+
+fn reload_slot<T: Resource>(
+    rm: &ResourceManager,
+    slot: &Arc<dyn Any + Send + Sync>,
+) -> Result<usize, ResourceError> {
+    let slot = slot
+        .downcast_ref::<Slot<T>>()
+        .expect("resource type mismatch");
+    let value = rm.loader::<T>()?.load(&slot.path)?;
+    let size = value.size_bytes();
+    *slot.value.write().expect("resource lock poisoned") = Arc::new(value);
+    Ok(size)
+}
+
+/// Bookkeeping for one loaded resource.
+struct Tracked {
+    /// Dead once all handles drop, unless `cached` keeps it alive.
+    slot: Weak<dyn Any + Send + Sync>,
+    /// The manager's own reference, held while a budget is set so the
+    /// resource outlives its handles until evicted.
+    cached: Option<Arc<dyn Any + Send + Sync>>,
+    size: usize,
+    /// Shared with the slot; see `Slot::last_used`.
+    last_used: Arc<AtomicU64>,
+    type_name: &'static str,
+    reload: ReloadFn,
+    /// Modification time the current value was loaded from.
+    mtime: Option<SystemTime>,
+    /// A newer modification time and when it was first seen.
+    pending: Option<(Option<SystemTime>, Instant)>,
+}
+
 /// In-memory resource manager.
-#[derive(Default)]
 pub struct ResourceManager {
     next_id: u64,
     by_type: HashMap<TypeId, HashMap<u64, Arc<dyn Any + Send + Sync>>>,
+    /// `Arc<dyn ResourceLoader<Output = T>>` by `TypeId` of `T`.
+    loaders: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    /// Loaded resources by type and path.
+    loaded: HashMap<(TypeId, PathBuf), Tracked>,
+    mtimes: Box<dyn MtimeSource>,
+    debounce: Duration,
+    budget: Option<usize>,
+    use_counter: Arc<AtomicU64>,
+    evictions: u64,
+}
+
+impl Default for ResourceManager {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            by_type: HashMap::new(),
+            loaders: HashMap::new(),
+            loaded: HashMap::new(),
+            mtimes: Box::new(FsMtime),
+            debounce: DEFAULT_RELOAD_DEBOUNCE,
+            budget: None,
+            use_counter: Arc::new(AtomicU64::new(0)),
+            evictions: 0,
+        }
+    }
 }
 
 impl ResourceManager {
+    /// Reads modification times from `source` instead of the filesystem.
+    pub fn with_mtime_source(mut self, source: impl MtimeSource + 'static) -> Self {
+        self.mtimes = Box::new(source);
+        self
+    }
+
+    /// Sets how long a changed file must stay unchanged before reloading,
+    /// so an editor's burst of writes causes a single reload.
+    pub fn with_reload_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
     /// Inserts a resource and returns a handle.
     pub fn insert<T: 'static + Send + Sync>(&mut self, value: T) -> Handle<T> {
         let id = self.next_id;
@@ -43,4 +284,464 @@ impl ResourceManager {
             .and_then(|map| map.get(&h.id))
             .and_then(|arc_any| arc_any.clone().downcast::<T>().ok())
     }
+
+    /// Registers the loader for `L::Output`, replacing any previous one.
+    pub fn register_loader<L: ResourceLoader>(&mut self, loader: L) {
+        let loader: Arc<dyn ResourceLoader<Output = L::Output>> = Arc::new(loader);
+        self.loaders
+            .insert(TypeId::of::<L::Output>(), Box::new(loader));
+    }
+
+    fn loader<T: Resource>(&self) -> Result<Arc<dyn ResourceLoader<Output = T>>, ResourceError> {
+        self.loaders
+            .get(&TypeId::of::<T>())
+            .and_then(|l| l.downcast_ref::<Arc<dyn ResourceLoader<Output = T>>>())
+            .cloned()
+            .ok_or(ResourceError::NoLoader {
+                type_name: std::any::type_name::<T>(),
+            })
+    }
+
+    /// Loads `path` as a `T`, or shares the already-loaded copy.
+    pub fn load<T: Resource>(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<ResourceHandle<T>, ResourceError> {
+        let path = path.as_ref().to_path_buf();
+        let key = (TypeId::of::<T>(), path);
+        if let Some(tracked) = self.loaded.get(&key) {
+            if let Some(slot) = tracked.slot.upgrade() {
+                let slot = slot.downcast::<Slot<T>>().expect("resource type mismatch");
+                slot.touch();
+                return Ok(ResourceHandle { slot });
+            }
+        }
+
+        let mtime = self.mtimes.modified(&key.1);
+        let value = self.loader::<T>()?.load(&key.1)?;
+        let size = value.size_bytes();
+        let slot = Arc::new(Slot {
+            path: key.1.clone(),
+            value: RwLock::new(Arc::new(value)),
+            clock: self.use_counter.clone(),
+            last_used: Arc::new(AtomicU64::new(0)),
+        });
+        slot.touch();
+        let erased: Arc<dyn Any + Send + Sync> = slot.clone();
+        self.loaded.retain(|_, t| t.slot.strong_count() > 0);
+        self.loaded.insert(
+            key,
+            Tracked {
+                slot: Arc::downgrade(&erased),
+                cached: self.budget.is_some().then_some(erased.clone()),
+                size,
+                last_used: slot.last_used.clone(),
+                type_name: std::any::type_name::<T>(),
+                reload: reload_slot::<T>,
+                mtime,
+                pending: None,
+            },
+        );
+        self.enforce_budget();
+        Ok(ResourceHandle { slot })
+    }
+
+    /// Whether a `T` loaded from `path` is still held by some handle.
+    pub fn is_loaded<T: Resource>(&self, path: impl AsRef<Path>) -> bool {
+        self.loaded
+            .get(&(TypeId::of::<T>(), path.as_ref().to_path_buf()))
+            .is_some_and(|t| t.slot.strong_count() > 0)
+    }
+
+    /// Number of loaded resources of all types.
+    pub fn loaded_count(&self) -> usize {
+        self.loaded
+            .values()
+            .filter(|t| t.slot.strong_count() > 0)
+            .count()
+    }
+
+    /// Sets the memory budget in bytes, or `None` for no caching.
+    ///
+    /// With a budget, resources stay loaded after their last handle drops
+    /// and are evicted least-recently-used first (a use being a `load` or
+    /// a [`ResourceHandle::get`]) once usage exceeds the
+    /// budget. Resources with live handles are never evicted, so usage can
+    /// still exceed the budget. Without one, resources unload as soon as
+    /// their last handle drops.
+    pub fn set_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+        for tracked in self.loaded.values_mut() {
+            tracked.cached = match budget {
+                Some(_) => tracked.slot.upgrade(),
+                None => None,
+            };
+        }
+        self.loaded.retain(|_, t| t.slot.strong_count() > 0);
+        self.enforce_budget();
+    }
+
+    pub fn budget(&self) -> Option<usize> {
+        self.budget
+    }
+
+    /// Bytes used by loaded resources, per [`Resource::size_bytes`].
+    pub fn current_usage(&self) -> usize {
+        self.loaded
+            .values()
+            .filter(|t| t.slot.strong_count() > 0)
+            .map(|t| t.size)
+            .sum()
+    }
+
+    /// Number of resources evicted to stay within the budget.
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    fn enforce_budget(&mut self) {
+        let Some(budget) = self.budget else {
+            return;
+        };
+        let mut usage = self.current_usage();
+        if usage <= budget {
+            return;
+        }
+        // Only the manager's cached reference left: nothing holds a handle.
+        let mut unpinned: Vec<_> = self
+            .loaded
+            .iter()
+            .filter(|(_, t)| t.slot.strong_count() == 1 && t.cached.is_some())
+            .map(|(key, t)| (t.last_used.load(Ordering::Relaxed), key.clone()))
+            .collect();
+        unpinned.sort();
+        for (_, key) in unpinned {
+            if usage <= budget {
+                break;
+            }
+            if let Some(tracked) = self.loaded.remove(&key) {
+                usage -= tracked.size;
+                self.evictions += 1;
+            }
+        }
+    }
+
+    /// Reloads resources whose files changed on disk.
+    pub fn poll_reload(&mut self) -> Vec<ReloadedPath> {
+        self.poll_reload_at(Instant::now())
+    }
+
+    /// Reloads resources whose modification time changed and has then
+    /// stayed the same for the debounce period as of `now`.
+    ///
+    /// Returned in path order. A failed reload keeps the old value and
+    /// isn't retried until the file changes again.
+    pub fn poll_reload_at(&mut self, now: Instant) -> Vec<ReloadedPath> {
+        let mut due = Vec::new();
+        for (key, tracked) in self.loaded.iter_mut() {
+            let Some(slot) = tracked.slot.upgrade() else {
+                continue;
+            };
+            let mtime = self.mtimes.modified(&key.1);
+            if mtime == tracked.mtime {
+                tracked.pending = None;
+                continue;
+            }
+            match tracked.pending {
+                Some((seen, since)) if seen == mtime => {
+                    if now.duration_since(since) >= self.debounce {
+                        tracked.mtime = mtime;
+                        tracked.pending = None;
+                        due.push((key.clone(), tracked.type_name, tracked.reload, slot));
+                    }
+                }
+                // First sighting of this modification time.
+                _ => {
+                    if self.debounce.is_zero() {
+                        tracked.mtime = mtime;
+                        due.push((key.clone(), tracked.type_name, tracked.reload, slot));
+                    } else {
+                        tracked.pending = Some((mtime, now));
+                    }
+                }
+            }
+        }
+
+        due.sort_by(|a, b| a.0 .1.cmp(&b.0 .1));
+        let reloaded: Vec<_> = due
+            .into_iter()
+            .map(|(key, type_name, reload, slot)| {
+                let result = reload(self, &slot);
+                if let (Ok(size), Some(tracked)) = (&result, self.loaded.get_mut(&key)) {
+                    tracked.size = *size;
+                }
+                ReloadedPath {
+                    path: key.1,
+                    type_name,
+                    error: result.err(),
+                }
+            })
+            .collect();
+        self.enforce_budget();
+        reloaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Fake sound: records its own drop.
+    struct Sound {
+        path: PathBuf,
+        bytes: usize,
+        dropped: Arc<AtomicUsize>,
+    }
+
+    impl Drop for Sound {
+        fn drop(&mut self) {
+            self.dropped.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Resource for Sound {
+        fn size_bytes(&self) -> usize {
+            self.bytes
+        }
+    }
+
+    #[derive(Default)]
+    struct SoundLoader {
+        loads: Arc<AtomicUsize>,
+        dropped: Arc<AtomicUsize>,
+    }
+
+    impl ResourceLoader for SoundLoader {
+        type Output = Sound;
+
+        fn load(&self, path: &Path) -> Result<Sound, ResourceError> {
+            if path.extension().is_none_or(|e| e != "wav") {
+                return Err(ResourceError::Load {
+                    path: path.to_path_buf(),
+                    message: "not a wav".into(),
+                });
+            }
+            self.loads.fetch_add(1, Ordering::SeqCst);
+            Ok(Sound {
+                path: path.to_path_buf(),
+                bytes: 100,
+                dropped: Arc::clone(&self.dropped),
+            })
+        }
+    }
+
+    fn manager() -> (ResourceManager, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        let loader = SoundLoader::default();
+        let (loads, dropped) = (Arc::clone(&loader.loads), Arc::clone(&loader.dropped));
+        let mut rm = ResourceManager::default();
+        rm.register_loader(loader);
+        (rm, loads, dropped)
+    }
+
+    #[test]
+    fn load_same_path_twice_shares_one_resource() {
+        let (mut rm, loads, _) = manager();
+        let a = rm.load::<Sound>("sound/step.wav").unwrap();
+        let b = rm.load::<Sound>("sound/step.wav").unwrap();
+        let c = rm.load::<Sound>("sound/jump.wav").unwrap();
+
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+        assert!(ResourceHandle::ptr_eq(&a, &b));
+        assert!(Arc::ptr_eq(&a.get(), &b.get()));
+        assert!(!ResourceHandle::ptr_eq(&a, &c));
+        assert_eq!(b.get().path, Path::new("sound/step.wav"));
+        assert_eq!(rm.loaded_count(), 2);
+    }
+
+    #[test]
+    fn dropping_all_handles_unloads() {
+        let (mut rm, loads, dropped) = manager();
+        let a = rm.load::<Sound>("sound/step.wav").unwrap();
+        let b = a.clone();
+
+        drop(a);
+        assert!(rm.is_loaded::<Sound>("sound/step.wav"));
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+
+        drop(b);
+        assert!(!rm.is_loaded::<Sound>("sound/step.wav"));
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        assert_eq!(rm.loaded_count(), 0);
+
+        // Loading again goes back to the loader.
+        let _again = rm.load::<Sound>("sound/step.wav").unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn load_errors() {
+        let (mut rm, _, _) = manager();
+        assert!(matches!(
+            rm.load::<Sound>("sound/step.mp3"),
+            Err(ResourceError::Load { .. })
+        ));
+        assert!(!rm.is_loaded::<Sound>("sound/step.mp3"));
+        assert!(matches!(
+            rm.load::<BspMap>("maps/de_dust2.bsp"),
+            Err(ResourceError::NoLoader { .. })
+        ));
+    }
+
+    /// Modification times set by the test.
+    #[derive(Clone, Default)]
+    struct FakeMtimes(Arc<RwLock<HashMap<PathBuf, SystemTime>>>);
+
+    impl FakeMtimes {
+        fn touch(&self, path: &str, secs: u64) {
+            self.0.write().unwrap().insert(
+                PathBuf::from(path),
+                SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+            );
+        }
+    }
+
+    impl MtimeSource for FakeMtimes {
+        fn modified(&self, path: &Path) -> Option<SystemTime> {
+            self.0.read().unwrap().get(path).copied()
+        }
+    }
+
+    fn reloading_manager() -> (ResourceManager, FakeMtimes, Arc<AtomicUsize>) {
+        let mtimes = FakeMtimes::default();
+        let (rm, loads, _) = manager();
+        let rm = rm
+            .with_mtime_source(mtimes.clone())
+            .with_reload_debounce(Duration::from_millis(100));
+        (rm, mtimes, loads)
+    }
+
+    #[test]
+    fn unchanged_file_does_not_reload() {
+        let (mut rm, mtimes, loads) = reloading_manager();
+        mtimes.touch("sound/step.wav", 1);
+        let _h = rm.load::<Sound>("sound/step.wav").unwrap();
+
+        let t0 = Instant::now();
+        for ms in [0, 100, 1000] {
+            assert!(rm.poll_reload_at(t0 + Duration::from_millis(ms)).is_empty());
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn bumped_mtime_reloads_once_after_debounce() {
+        let (mut rm, mtimes, loads) = reloading_manager();
+        mtimes.touch("sound/step.wav", 1);
+        let h = rm.load::<Sound>("sound/step.wav").unwrap();
+        let before = h.get();
+
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        mtimes.touch("sound/step.wav", 2);
+        assert!(rm.poll_reload_at(at(0)).is_empty());
+        // Another write inside the window restarts it.
+        mtimes.touch("sound/step.wav", 3);
+        assert!(rm.poll_reload_at(at(50)).is_empty());
+        assert!(rm.poll_reload_at(at(120)).is_empty());
+
+        let reloaded = rm.poll_reload_at(at(150));
+        assert_eq!(
+            reloaded,
+            vec![ReloadedPath {
+                path: PathBuf::from("sound/step.wav"),
+                type_name: std::any::type_name::<Sound>(),
+                error: None,
+            }]
+        );
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+        assert!(!Arc::ptr_eq(&before, &h.get()));
+
+        assert!(rm.poll_reload_at(at(1000)).is_empty());
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn dropped_resources_are_not_reloaded() {
+        let (mut rm, mtimes, loads) = reloading_manager();
+        mtimes.touch("sound/step.wav", 1);
+        drop(rm.load::<Sound>("sound/step.wav").unwrap());
+
+        mtimes.touch("sound/step.wav", 2);
+        let t0 = Instant::now();
+        rm.poll_reload_at(t0);
+        assert!(rm.poll_reload_at(t0 + Duration::from_secs(1)).is_empty());
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn over_budget_evicts_least_recently_used_unpinned() {
+        let (mut rm, loads, dropped) = manager();
+        rm.set_budget(Some(250));
+
+        let a = rm.load::<Sound>("a.wav").unwrap();
+        drop(rm.load::<Sound>("b.wav").unwrap());
+        // Under budget: a dropped resource stays cached.
+        assert!(rm.is_loaded::<Sound>("b.wav"));
+        assert_eq!(rm.current_usage(), 200);
+
+        // Over budget: `a` is older but pinned, so `b` goes.
+        drop(rm.load::<Sound>("c.wav").unwrap());
+        assert_eq!(rm.evictions(), 1);
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        assert!(rm.is_loaded::<Sound>("a.wav"));
+        assert!(!rm.is_loaded::<Sound>("b.wav"));
+        assert!(rm.is_loaded::<Sound>("c.wav"));
+        assert_eq!(rm.current_usage(), 200);
+
+        // Once unpinned, `a` is the least recently used.
+        drop(a);
+        drop(rm.load::<Sound>("c.wav").unwrap());
+        drop(rm.load::<Sound>("d.wav").unwrap());
+        assert_eq!(rm.evictions(), 2);
+        assert!(!rm.is_loaded::<Sound>("a.wav"));
+        assert!(rm.is_loaded::<Sound>("c.wav"));
+        assert!(rm.is_loaded::<Sound>("d.wav"));
+        assert_eq!(loads.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn handle_access_counts_as_a_use() {
+        let (mut rm, _, _) = manager();
+        rm.set_budget(Some(250));
+
+        let a = rm.load::<Sound>("a.wav").unwrap();
+        let b = rm.load::<Sound>("b.wav").unwrap();
+        a.get();
+        drop((a, b));
+
+        // `a` was loaded first but read last, so `b` goes.
+        drop(rm.load::<Sound>("c.wav").unwrap());
+        assert_eq!(rm.evictions(), 1);
+        assert!(rm.is_loaded::<Sound>("a.wav"));
+        assert!(!rm.is_loaded::<Sound>("b.wav"));
+    }
+
+    #[test]
+    fn pinned_resources_are_never_evicted() {
+        let (mut rm, _, _) = manager();
+        let a = rm.load::<Sound>("a.wav").unwrap();
+        let b = rm.load::<Sound>("b.wav").unwrap();
+
+        rm.set_budget(Some(50));
+        assert_eq!(rm.evictions(), 0);
+        assert_eq!(rm.current_usage(), 200);
+
+        drop(b);
+        let _c = rm.load::<Sound>("c.wav").unwrap();
+        assert_eq!(rm.evictions(), 1);
+        assert!(!rm.is_loaded::<Sound>("b.wav"));
+        assert_eq!(a.get().bytes, 100);
+        assert_eq!(rm.current_usage(), 200);
+    }
 }
@@ -27,6 +27,9 @@ pub const MAX_KEY_LENGTH: usize = 64;
 /// Maximum length of a rich presence value.
 pub const MAX_VALUE_LENGTH: usize = 256;
 
+/// Maximum combined length of all keys and values.
+pub const MAX_TOTAL_LENGTH: usize = 4096;
+
 /// Standard rich presence keys.
 pub mod keys {
     /// Display status in friends list.
@@ -41,32 +44,66 @@ pub mod keys {
     pub const STEAM_PLAYER_GROUP_SIZE: &str = "steam_player_group_size";
 }
 
-/// Rich presence data for a player.
+/// Check that `token` is a localization token of the form `#Token_Name`.
+pub fn is_valid_token(token: &str) -> bool {
+    match token.strip_prefix('#') {
+        Some(name) => {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+/// Rich presence key/value store for a player, enforcing Steam's limits.
 #[derive(Debug, Clone, Default)]
-pub struct RichPresenceData {
+pub struct RichPresence {
     /// Key-value pairs for rich presence.
     data: HashMap<String, String>,
     /// Last update time.
     last_update: Option<Instant>,
 }
 
-impl RichPresenceData {
+/// Former name of [`RichPresence`].
+pub type RichPresenceData = RichPresence;
+
+impl RichPresence {
     /// Create new empty rich presence data.
     pub fn new() -> Self {
         Self::default()
     }
 
     /// Set a rich presence key-value pair.
+    ///
+    /// `steam_display` must be a localization token (`#Token_Name`) and
+    /// `steam_player_group_size` a number.
     pub fn set(&mut self, key: &str, value: &str) -> Result<(), RichPresenceError> {
+        if key.is_empty() {
+            return Err(RichPresenceError::InvalidKey);
+        }
         if key.len() > MAX_KEY_LENGTH {
             return Err(RichPresenceError::KeyTooLong);
         }
         if value.len() > MAX_VALUE_LENGTH {
             return Err(RichPresenceError::ValueTooLong);
         }
-        if self.data.len() >= MAX_RICH_PRESENCE_KEYS && !self.data.contains_key(key) {
+        match key {
+            keys::STEAM_DISPLAY if !is_valid_token(value) => {
+                return Err(RichPresenceError::InvalidToken);
+            }
+            keys::STEAM_PLAYER_GROUP_SIZE if value.parse::<u32>().is_err() => {
+                return Err(RichPresenceError::InvalidValue);
+            }
+            _ => {}
+        }
+
+        let existing = self.data.get(key);
+        if existing.is_none() && self.data.len() >= MAX_RICH_PRESENCE_KEYS {
             return Err(RichPresenceError::TooManyKeys);
         }
+        let replaced = existing.map_or(0, |old| key.len() + old.len());
+        if self.total_length() - replaced + key.len() + value.len() > MAX_TOTAL_LENGTH {
+            return Err(RichPresenceError::TotalTooLong);
+        }
 
         self.data.insert(key.to_string(), value.to_string());
         self.last_update = Some(Instant::now());
@@ -78,6 +115,53 @@ impl RichPresenceData {
         self.data.get(key).map(|s| s.as_str())
     }
 
+    /// Combined length of all keys and values in bytes.
+    pub fn total_length(&self) -> usize {
+        self.data.iter().map(|(k, v)| k.len() + v.len()).sum()
+    }
+
+    /// Get the display token.
+    pub fn display(&self) -> Option<&str> {
+        self.get(keys::STEAM_DISPLAY)
+    }
+
+    /// Set the display token.
+    pub fn set_display(&mut self, token: &str) -> Result<(), RichPresenceError> {
+        self.set(keys::STEAM_DISPLAY, token)
+    }
+
+    /// Render the display string for the current `steam_display` token.
+    ///
+    /// `tokens` maps localization tokens (including the leading `#`) to
+    /// templates. Each `%key%` placeholder in the template is replaced with
+    /// the current value of `key`, or nothing if it is unset. Returns an empty
+    /// string if no display token is set or it has no template.
+    pub fn render(&self, tokens: &HashMap<String, String>) -> String {
+        let Some(template) = self.display().and_then(|token| tokens.get(token)) else {
+            return String::new();
+        };
+
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('%') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            match after.find('%') {
+                Some(end) => {
+                    out.push_str(self.get(&after[..end]).unwrap_or_default());
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    // Unterminated placeholder: keep the text as written.
+                    out.push_str(&rest[start..]);
+                    rest = "";
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
     /// Get the number of keys.
     pub fn key_count(&self) -> usize {
         self.data.len()
@@ -141,7 +225,13 @@ pub enum RichPresenceError {
     KeyTooLong,
     ValueTooLong,
     TooManyKeys,
+    /// Keys and values together exceed [`MAX_TOTAL_LENGTH`].
+    TotalTooLong,
     InvalidKey,
+    /// `steam_display` value is not a `#Token_Name` localization token.
+    InvalidToken,
+    /// Value is not valid for a special key.
+    InvalidValue,
     NotFound,
 }
 
@@ -212,9 +302,9 @@ impl Default for RichPresenceRateLimiter {
 #[derive(Default)]
 pub struct RichPresenceManager {
     /// Local player's rich presence.
-    local_presence: RichPresenceData,
+    local_presence: RichPresence,
     /// Friend rich presence data.
-    friend_presence: HashMap<SteamId, RichPresenceData>,
+    friend_presence: HashMap<SteamId, RichPresence>,
     /// Rate limiter for updates.
     rate_limiter: RichPresenceRateLimiter,
     /// Pending callbacks.
@@ -267,12 +357,7 @@ impl RichPresenceManager {
     }
 
     /// Update friend's rich presence (called when receiving callback).
-    pub fn update_friend_presence(
-        &mut self,
-        friend: SteamId,
-        app_id: u32,
-        data: RichPresenceData,
-    ) {
+    pub fn update_friend_presence(&mut self, friend: SteamId, app_id: u32, data: RichPresence) {
         self.friend_presence.insert(friend, data);
         self.pending_callbacks.push(FriendRichPresenceUpdate {
             steam_id: friend,
@@ -524,4 +609,100 @@ mod tests {
         data.set("key0", "new_value").unwrap();
         assert_eq!(data.get("key0"), Some("new_value"));
     }
+
+    #[test]
+    fn empty_key_rejected() {
+        let mut data = RichPresence::new();
+        assert_eq!(data.set("", "value"), Err(RichPresenceError::InvalidKey));
+    }
+
+    #[test]
+    fn total_length_limit() {
+        let mut data = RichPresence::new();
+        let value = "v".repeat(MAX_VALUE_LENGTH);
+
+        // 15 keys of 5 + 256 bytes = 3915 bytes.
+        for i in 0..15 {
+            data.set(&format!("key{:02}", i), &value).unwrap();
+        }
+        assert_eq!(data.total_length(), 3915);
+        assert_eq!(
+            data.set("key15", &value),
+            Err(RichPresenceError::TotalTooLong)
+        );
+
+        // Replacing a value only counts the difference.
+        data.set("key00", &"w".repeat(MAX_VALUE_LENGTH)).unwrap();
+        data.set("key15", "short").unwrap();
+        assert!(data.total_length() <= MAX_TOTAL_LENGTH);
+    }
+
+    #[test]
+    fn steam_display_token() {
+        let mut data = RichPresence::new();
+        data.set_display("#Status_Competitive").unwrap();
+        assert_eq!(data.display(), Some("#Status_Competitive"));
+
+        for bad in ["Status_Competitive", "#", "#Has Space", "#Dash-Token"] {
+            assert_eq!(
+                data.set(keys::STEAM_DISPLAY, bad),
+                Err(RichPresenceError::InvalidToken),
+                "{bad:?}"
+            );
+        }
+        assert_eq!(data.display(), Some("#Status_Competitive"));
+    }
+
+    #[test]
+    fn player_group_size_must_be_numeric() {
+        let mut data = RichPresence::new();
+        assert_eq!(
+            data.set(keys::STEAM_PLAYER_GROUP_SIZE, "four"),
+            Err(RichPresenceError::InvalidValue)
+        );
+        data.set(keys::STEAM_PLAYER_GROUP_SIZE, "4").unwrap();
+    }
+
+    fn display_tokens() -> HashMap<String, String> {
+        HashMap::from([(
+            "#Status_Competitive".to_string(),
+            "Competitive - %map% (%players% of %max_players%)".to_string(),
+        )])
+    }
+
+    #[test]
+    fn render_substitutes_placeholders() {
+        let mut data = RichPresence::new();
+        data.set_display("#Status_Competitive").unwrap();
+        data.set("map", "de_dust2").unwrap();
+        data.set("players", "5").unwrap();
+        data.set("max_players", "10").unwrap();
+
+        assert_eq!(
+            data.render(&display_tokens()),
+            "Competitive - de_dust2 (5 of 10)"
+        );
+    }
+
+    #[test]
+    fn render_missing_key_is_empty() {
+        let mut data = RichPresence::new();
+        data.set_display("#Status_Competitive").unwrap();
+        data.set("map", "de_inferno").unwrap();
+        data.set("players", "3").unwrap();
+
+        assert_eq!(
+            data.render(&display_tokens()),
+            "Competitive - de_inferno (3 of )"
+        );
+    }
+
+    #[test]
+    fn render_without_template_is_empty() {
+        let mut data = RichPresence::new();
+        assert_eq!(data.render(&display_tokens()), "");
+
+        data.set_display("#Status_Unknown").unwrap();
+        assert_eq!(data.render(&display_tokens()), "");
+    }
 }
@@ -10,6 +10,7 @@
 //! - Persona states and rich presence
 //! - Clan/group integration
 //! - Game invites
+//! - Persona change notifications
 
 use std::collections::HashMap;
 
@@ -92,7 +93,7 @@ impl FriendFlags {
 }
 
 /// Game info for a friend.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FriendGameInfo {
     /// Game App ID (0 if not in game).
     pub app_id: u32,
@@ -106,18 +107,6 @@ pub struct FriendGameInfo {
     pub lobby_id: u64,
 }
 
-impl Default for FriendGameInfo {
-    fn default() -> Self {
-        Self {
-            app_id: 0,
-            game_ip: 0,
-            game_port: 0,
-            query_port: 0,
-            lobby_id: 0,
-        }
-    }
-}
-
 /// Friend data.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Friend {
@@ -221,12 +210,38 @@ pub enum InviteResult {
     Failed,
 }
 
+/// A change to a friend's persona, as reported by
+/// [`FriendsManager::poll_changes`].
+///
+/// Reference: <https://partner.steamgames.com/doc/api/ISteamFriends#PersonaStateChange_t>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FriendChange {
+    /// The friend went from offline (or invisible) to online.
+    CameOnline { steam_id: u64 },
+    /// The friend went offline.
+    WentOffline { steam_id: u64 },
+    /// The friend started playing a game.
+    StartedGame { steam_id: u64, app_id: u32 },
+    /// The friend changed their persona name.
+    ChangedName {
+        steam_id: u64,
+        old: String,
+        new: String,
+    },
+}
+
+/// What `poll_changes` last reported for a friend.
+#[derive(Debug, Clone)]
+struct PersonaSnapshot {
+    online: bool,
+    app_id: u32,
+    name: String,
+}
+
 /// Mock Friends manager for testing.
 ///
 /// In production, this would interface with Steamworks SDK.
 pub struct FriendsManager {
-    /// Local user Steam ID.
-    local_user: u64,
     /// Friends list.
     friends: HashMap<u64, Friend>,
     /// Clans.
@@ -235,20 +250,21 @@ pub struct FriendsManager {
     coplay: Vec<CoplayFriend>,
     /// Invite cooldown tracking.
     invite_timestamps: HashMap<u64, u64>,
-    /// Current app ID.
-    app_id: u32,
+    /// Friend personas as of the last `poll_changes`.
+    persona_snapshot: HashMap<u64, PersonaSnapshot>,
 }
 
 impl FriendsManager {
     /// Create a new friends manager.
-    pub fn new(local_user: u64, app_id: u32) -> Self {
+    ///
+    /// The mock keeps no per-user or per-app state, so both are ignored.
+    pub fn new(_local_user: u64, _app_id: u32) -> Self {
         Self {
-            local_user,
             friends: HashMap::new(),
             clans: Vec::new(),
             coplay: Vec::new(),
             invite_timestamps: HashMap::new(),
-            app_id,
+            persona_snapshot: HashMap::new(),
         }
     }
 
@@ -444,6 +460,65 @@ impl FriendsManager {
         }
     }
 
+    /// Set friend persona name (for testing).
+    pub fn set_friend_name(&mut self, steam_id: u64, name: &str) {
+        if let Some(friend) = self.friends.get_mut(&steam_id) {
+            friend.persona_name = name.to_string();
+        }
+    }
+
+    /// Report what changed for each friend since the last poll.
+    ///
+    /// Friends not seen by a previous poll are compared against being
+    /// offline and out of game, so one already playing reports both. Changes
+    /// are ordered by Steam ID.
+    pub fn poll_changes(&mut self) -> Vec<FriendChange> {
+        let mut ids: Vec<u64> = self.friends.keys().copied().collect();
+        ids.sort_unstable();
+
+        let mut changes = Vec::new();
+        for steam_id in ids {
+            let friend = &self.friends[&steam_id];
+            let current = PersonaSnapshot {
+                online: friend.persona_state.is_online(),
+                app_id: friend.game_info.app_id,
+                name: friend.persona_name.clone(),
+            };
+            let previous = self
+                .persona_snapshot
+                .insert(steam_id, current.clone())
+                .unwrap_or(PersonaSnapshot {
+                    online: false,
+                    app_id: 0,
+                    name: current.name.clone(),
+                });
+
+            if current.name != previous.name {
+                changes.push(FriendChange::ChangedName {
+                    steam_id,
+                    old: previous.name,
+                    new: current.name,
+                });
+            }
+            match (previous.online, current.online) {
+                (false, true) => changes.push(FriendChange::CameOnline { steam_id }),
+                (true, false) => changes.push(FriendChange::WentOffline { steam_id }),
+                _ => {}
+            }
+            if current.app_id != 0 && current.app_id != previous.app_id {
+                changes.push(FriendChange::StartedGame {
+                    steam_id,
+                    app_id: current.app_id,
+                });
+            }
+        }
+
+        let friends = &self.friends;
+        self.persona_snapshot
+            .retain(|steam_id, _| friends.contains_key(steam_id));
+        changes
+    }
+
     /// Set friend rich presence (for testing).
     pub fn set_friend_rich_presence(&mut self, steam_id: u64, key: &str, value: &str) {
         if let Some(friend) = self.friends.get_mut(&steam_id) {
@@ -868,4 +943,82 @@ mod tests {
         assert!(friend.is_playing_game(730));
         assert!(!friend.is_playing_game(440));
     }
+
+    // =============================================================================
+    // SOC-PSC: Persona State Change Notifications
+    // Reference: https://partner.steamgames.com/doc/api/ISteamFriends#PersonaStateChange_t
+    // =============================================================================
+
+    #[test]
+    fn soc_psc_state_changes_are_reported_once() {
+        let mut mgr = FriendsManager::new(12345, 730);
+        mgr.add_friend(Friend::new(111, "Alice"));
+        assert!(mgr.poll_changes().is_empty());
+
+        mgr.set_friend_state(111, PersonaState::Online);
+        assert_eq!(
+            mgr.poll_changes(),
+            vec![FriendChange::CameOnline { steam_id: 111 }]
+        );
+        assert!(mgr.poll_changes().is_empty());
+
+        mgr.set_friend_game(111, 730);
+        assert_eq!(
+            mgr.poll_changes(),
+            vec![FriendChange::StartedGame {
+                steam_id: 111,
+                app_id: 730
+            }]
+        );
+
+        // Online to Away is still online.
+        mgr.set_friend_state(111, PersonaState::Away);
+        assert!(mgr.poll_changes().is_empty());
+
+        mgr.set_friend_state(111, PersonaState::Offline);
+        assert_eq!(
+            mgr.poll_changes(),
+            vec![FriendChange::WentOffline { steam_id: 111 }]
+        );
+        assert!(mgr.poll_changes().is_empty());
+    }
+
+    #[test]
+    fn soc_psc_name_change() {
+        let mut mgr = FriendsManager::new(12345, 730);
+        mgr.add_friend(Friend::new(111, "Alice"));
+        mgr.poll_changes();
+
+        mgr.set_friend_name(111, "Alicia");
+        assert_eq!(
+            mgr.poll_changes(),
+            vec![FriendChange::ChangedName {
+                steam_id: 111,
+                old: "Alice".to_string(),
+                new: "Alicia".to_string(),
+            }]
+        );
+        assert!(mgr.poll_changes().is_empty());
+    }
+
+    #[test]
+    fn soc_psc_new_friend_already_in_game() {
+        let mut mgr = FriendsManager::new(12345, 730);
+        let mut friend = Friend::new(222, "Bob");
+        friend.persona_state = PersonaState::Online;
+        friend.game_info.app_id = 440;
+        mgr.add_friend(friend);
+        mgr.add_friend(Friend::new(111, "Alice"));
+
+        assert_eq!(
+            mgr.poll_changes(),
+            vec![
+                FriendChange::CameOnline { steam_id: 222 },
+                FriendChange::StartedGame {
+                    steam_id: 222,
+                    app_id: 440
+                },
+            ]
+        );
+    }
 }
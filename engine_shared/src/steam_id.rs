@@ -18,6 +18,7 @@
 //! └─────────────────────────────────────────────────────────────────┘
 //! ```
 
+use std::collections::HashSet;
 use std::fmt;
 use std::str::FromStr;
 
@@ -133,6 +134,18 @@ pub enum Instance {
     Web = 4,
 }
 
+/// Instance flags carried by `Chat` IDs, in the top bits of the instance.
+///
+/// Reference: `EChatSteamIDInstanceFlags` in the Steamworks `steamclientpublic.h`
+pub mod chat_instance {
+    /// The chat room belongs to a clan (SteamID3 type `c`).
+    pub const CLAN: u32 = 0x80000;
+    /// The chat room is a lobby (SteamID3 type `L`).
+    pub const LOBBY: u32 = 0x40000;
+    /// The chat room is a matchmaking-service lobby.
+    pub const MMS_LOBBY: u32 = 0x20000;
+}
+
 /// A 64-bit Steam ID.
 ///
 /// # Format Support
@@ -200,6 +213,49 @@ impl SteamId {
         self.account_type() == AccountType::Individual
     }
 
+    /// Check if this is an individual user account in the Public universe.
+    ///
+    /// This is the only kind of ID a retail client should ever present when
+    /// joining a game server.
+    pub fn is_individual_on_public(&self) -> bool {
+        self.is_individual() && self.universe() == Universe::Public
+    }
+
+    /// Validate an ID claimed by a connecting player.
+    ///
+    /// `is_valid` only rejects nil/undecodable IDs; IDs received from untrusted
+    /// clients must additionally be individual accounts in the Public universe,
+    /// so that e.g. a game server or clan ID cannot be used to join as a player.
+    pub fn validate_for_player_connection(&self) -> Result<(), SteamIdError> {
+        if self.account_id() == 0 {
+            return Err(SteamIdError::NilAccount);
+        }
+        if !self.is_individual() {
+            return Err(SteamIdError::NotIndividual(self.account_type()));
+        }
+        if self.universe() != Universe::Public {
+            return Err(SteamIdError::WrongUniverse(self.universe()));
+        }
+        Ok(())
+    }
+
+    /// Validate an ID claimed by a game server.
+    ///
+    /// Only persistent `GameServer` accounts have an identity worth checking;
+    /// anonymous servers get a fresh ID every time they log on.
+    pub fn validate_for_game_server(&self) -> Result<(), SteamIdError> {
+        if self.account_id() == 0 {
+            return Err(SteamIdError::NilAccount);
+        }
+        if self.account_type() != AccountType::GameServer {
+            return Err(SteamIdError::NotGameServer(self.account_type()));
+        }
+        if self.universe() != Universe::Public {
+            return Err(SteamIdError::WrongUniverse(self.universe()));
+        }
+        Ok(())
+    }
+
     /// Check if this represents a game server.
     pub fn is_game_server(&self) -> bool {
         matches!(
@@ -213,9 +269,14 @@ impl SteamId {
         self.account_type() == AccountType::Clan
     }
 
+    /// Check if this represents a chat room (including lobbies).
+    pub fn is_chat(&self) -> bool {
+        self.account_type() == AccountType::Chat
+    }
+
     /// Check if this represents a lobby.
     pub fn is_lobby(&self) -> bool {
-        self.account_type() == AccountType::Chat && (self.instance() & 0x40000) != 0
+        self.is_chat() && (self.instance() & chat_instance::LOBBY) != 0
     }
 
     /// Construct a Steam ID from components.
@@ -243,6 +304,40 @@ impl SteamId {
         )
     }
 
+    /// Create a Steam group/clan ID.
+    ///
+    /// Clans have no instance, as in Source's `CSteamID` constructor.
+    pub fn clan(account_id: u32) -> Self {
+        Self::from_parts(
+            account_id,
+            Instance::All as u32,
+            AccountType::Clan,
+            Universe::Public,
+        )
+    }
+
+    /// Create a persistent game server ID.
+    ///
+    /// Game servers have no instance, as in Source's `CSteamID` constructor.
+    pub fn game_server(account_id: u32) -> Self {
+        Self::from_parts(
+            account_id,
+            Instance::All as u32,
+            AccountType::GameServer,
+            Universe::Public,
+        )
+    }
+
+    /// Create a plain chat room ID, with no [`chat_instance`] flags set.
+    pub fn chat(account_id: u32) -> Self {
+        Self::from_parts(
+            account_id,
+            Instance::All as u32,
+            AccountType::Chat,
+            Universe::Public,
+        )
+    }
+
     /// Format as SteamID2 (legacy format): STEAM_X:Y:Z
     ///
     /// Reference: <https://developer.valvesoftware.com/wiki/SteamID#As_Represented_in_Computer_Programs>
@@ -377,6 +472,33 @@ impl FromStr for SteamId {
     }
 }
 
+/// Parses a pasted list of Steam IDs, one per line, in any format
+/// [`FromStr`] accepts.
+///
+/// Blank lines and `//` comments (whole-line or trailing) are skipped. Returns
+/// the valid IDs in first-seen order without duplicates, and the 1-based line
+/// number and trimmed text of every line that isn't a valid ID.
+pub fn parse_id_list(text: &str) -> (Vec<SteamId>, Vec<(usize, String)>) {
+    let mut ids = Vec::new();
+    let mut seen = HashSet::new();
+    let mut bad = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let entry = line.split("//").next().unwrap_or_default().trim();
+        if entry.is_empty() {
+            continue;
+        }
+        match entry.parse::<SteamId>() {
+            Ok(id) if id.is_valid() => {
+                if seen.insert(id) {
+                    ids.push(id);
+                }
+            }
+            _ => bad.push((i + 1, line.trim().to_string())),
+        }
+    }
+    (ids, bad)
+}
+
 /// Error type for Steam ID parsing.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SteamIdParseError {
@@ -397,6 +519,36 @@ impl fmt::Display for SteamIdParseError {
 
 impl std::error::Error for SteamIdParseError {}
 
+/// Error type for Steam ID validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SteamIdError {
+    /// The account ID portion is zero.
+    NilAccount,
+    /// The account type is not `Individual`.
+    NotIndividual(AccountType),
+    /// The account type is not `GameServer`.
+    NotGameServer(AccountType),
+    /// The universe is not `Public`.
+    WrongUniverse(Universe),
+}
+
+impl fmt::Display for SteamIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SteamIdError::NilAccount => write!(f, "nil account ID"),
+            SteamIdError::NotIndividual(t) => {
+                write!(f, "account type {:?} is not an individual account", t)
+            }
+            SteamIdError::NotGameServer(t) => {
+                write!(f, "account type {:?} is not a game server account", t)
+            }
+            SteamIdError::WrongUniverse(u) => write!(f, "universe {:?} is not Public", u),
+        }
+    }
+}
+
+impl std::error::Error for SteamIdError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -572,6 +724,27 @@ mod tests {
         assert!(anon_gs.is_game_server());
     }
 
+    #[test]
+    fn sid_005_game_server_validation() {
+        let gs = SteamId::from_parts(12345, 1, AccountType::GameServer, Universe::Public);
+        assert_eq!(gs.validate_for_game_server(), Ok(()));
+
+        let anon_gs = SteamId::from_parts(12345, 1, AccountType::AnonGameServer, Universe::Public);
+        assert_eq!(
+            anon_gs.validate_for_game_server(),
+            Err(SteamIdError::NotGameServer(AccountType::AnonGameServer))
+        );
+        assert_eq!(
+            SteamId::from_account_id(12345).validate_for_game_server(),
+            Err(SteamIdError::NotGameServer(AccountType::Individual))
+        );
+        assert_eq!(
+            SteamId::from_parts(0, 1, AccountType::GameServer, Universe::Public)
+                .validate_for_game_server(),
+            Err(SteamIdError::NilAccount)
+        );
+    }
+
     #[test]
     fn sid_005_clan_account() {
         let clan = SteamId::from_parts(12345, 0, AccountType::Clan, Universe::Public);
@@ -579,6 +752,55 @@ mod tests {
         assert!(!clan.is_individual());
     }
 
+    #[test]
+    fn sid_005_non_individual_constructors() {
+        let cases = [
+            (
+                SteamId::clan(4777282),
+                AccountType::Clan,
+                103582791434298690,
+            ),
+            (
+                SteamId::game_server(1234),
+                AccountType::GameServer,
+                85568392920040658,
+            ),
+            (SteamId::chat(55), AccountType::Chat, 108086391056891959),
+        ];
+        for (id, account_type, raw) in cases {
+            assert_eq!(id.as_u64(), raw, "{:?}", account_type);
+            let parsed = SteamId::from_u64(raw);
+            assert_eq!(parsed, id);
+            assert_eq!(parsed.account_type(), account_type);
+            assert_eq!(parsed.universe(), Universe::Public);
+            assert_eq!(parsed.instance(), Instance::All as u32);
+            assert!(parsed.is_valid());
+        }
+        assert_eq!(SteamId::clan(4777282).account_id(), 4777282);
+        assert_eq!(SteamId::clan(4777282).to_steam3(), "[g:1:4777282]");
+        assert_eq!(SteamId::game_server(1234).to_steam3(), "[G:1:1234]");
+    }
+
+    #[test]
+    fn sid_005_constructor_predicates() {
+        let clan = SteamId::clan(1);
+        assert!(clan.is_clan());
+        assert!(!clan.is_chat() && !clan.is_game_server() && !clan.is_individual());
+
+        let server = SteamId::game_server(1);
+        assert!(server.is_game_server());
+        assert!(!server.is_clan() && !server.is_chat());
+        assert_eq!(server.validate_for_game_server(), Ok(()));
+
+        let chat = SteamId::chat(1);
+        assert!(chat.is_chat());
+        assert!(!chat.is_lobby() && !chat.is_clan() && !chat.is_game_server());
+
+        let lobby =
+            SteamId::from_parts(1, chat_instance::LOBBY, AccountType::Chat, Universe::Public);
+        assert!(lobby.is_chat() && lobby.is_lobby());
+    }
+
     // =============================================================================
     // SID-006: Universe Detection
     // Reference: https://developer.valvesoftware.com/wiki/SteamID#Universes_Available_for_Steam_Accounts
@@ -698,4 +920,94 @@ mod tests {
             SteamId::from_parts(1, 0xFFFFF, AccountType::Individual, Universe::Public);
         assert_eq!(max_instance.instance(), 0xFFFFF);
     }
+
+    // =============================================================================
+    // SID-011: Player Connection Validation
+    // =============================================================================
+
+    #[test]
+    fn sid_011_individual_public_accepted() {
+        let id = SteamId::from_account_id(52079950);
+        assert!(id.is_individual_on_public());
+        assert_eq!(id.validate_for_player_connection(), Ok(()));
+    }
+
+    #[test]
+    fn sid_011_game_server_rejected_as_player() {
+        let gs = SteamId::from_parts(12345, 1, AccountType::GameServer, Universe::Public);
+        assert!(gs.is_valid());
+        assert!(!gs.is_individual_on_public());
+        assert_eq!(
+            gs.validate_for_player_connection(),
+            Err(SteamIdError::NotIndividual(AccountType::GameServer))
+        );
+
+        let anon_gs = SteamId::from_parts(12345, 0, AccountType::AnonGameServer, Universe::Public);
+        assert!(anon_gs.validate_for_player_connection().is_err());
+    }
+
+    #[test]
+    fn sid_011_wrong_universe_rejected() {
+        let beta = SteamId::from_parts(12345, 1, AccountType::Individual, Universe::Beta);
+        assert!(beta.is_valid());
+        assert!(!beta.is_individual_on_public());
+        assert_eq!(
+            beta.validate_for_player_connection(),
+            Err(SteamIdError::WrongUniverse(Universe::Beta))
+        );
+    }
+
+    #[test]
+    fn sid_011_nil_rejected() {
+        assert_eq!(
+            SteamId::NIL.validate_for_player_connection(),
+            Err(SteamIdError::NilAccount)
+        );
+        let zero_account = SteamId::from_parts(0, 1, AccountType::Individual, Universe::Public);
+        assert_eq!(
+            zero_account.validate_for_player_connection(),
+            Err(SteamIdError::NilAccount)
+        );
+    }
+
+    // =============================================================================
+    // SID-012: Admin ID Lists
+    // =============================================================================
+
+    #[test]
+    fn sid_012_parse_mixed_format_list() {
+        let list = "\
+// banned for cheating
+76561197960287930
+STEAM_0:0:11101
+
+[U:1:22202] // alt account
+STEAM_0:1:11101  
+   // indented comment
+not a steam id
+[U:1:22202]
+0
+";
+        let (ids, bad) = parse_id_list(list);
+        assert_eq!(
+            ids,
+            vec![
+                SteamId::from_u64(76561197960287930),
+                SteamId::from_account_id(22203),
+            ]
+        );
+        assert_eq!(
+            bad,
+            vec![(8, "not a steam id".to_string()), (10, "0".to_string())]
+        );
+    }
+
+    #[test]
+    fn sid_012_parse_empty_list() {
+        assert_eq!(parse_id_list(""), (Vec::new(), Vec::new()));
+        assert_eq!(
+            parse_id_list("\n  \n// nothing\n"),
+            (Vec::new(), Vec::new())
+        );
+    }
 }
@@ -21,6 +21,8 @@ pub enum TestStatus {
     Failed,
     Skipped,
     Pending,
+    /// Passed, but only after one or more retries.
+    Flaky,
 }
 
 impl TestStatus {
@@ -30,6 +32,7 @@ impl TestStatus {
             TestStatus::Failed => "failed",
             TestStatus::Skipped => "skipped",
             TestStatus::Pending => "pending",
+            TestStatus::Flaky => "flaky",
         }
     }
 
@@ -39,6 +42,7 @@ impl TestStatus {
             TestStatus::Failed => "✗",
             TestStatus::Skipped => "○",
             TestStatus::Pending => "◐",
+            TestStatus::Flaky => "↻",
         }
     }
 }
@@ -99,6 +103,9 @@ pub struct TestResult {
     pub source_file: Option<String>,
     /// Line number.
     pub line_number: Option<u32>,
+    /// Number of retries before the final outcome.
+    #[serde(default)]
+    pub retries: u32,
 }
 
 impl TestResult {
@@ -116,6 +123,7 @@ impl TestResult {
             doc_reference: None,
             source_file: None,
             line_number: None,
+            retries: 0,
         }
     }
 
@@ -153,6 +161,14 @@ impl TestResult {
         self
     }
 
+    /// Mark as passed after `retries` failed attempts.
+    pub fn flaky(mut self, duration: Duration, retries: u32) -> Self {
+        self.status = TestStatus::Flaky;
+        self.duration = duration;
+        self.retries = retries;
+        self
+    }
+
     pub fn skip(mut self, reason: &str) -> Self {
         self.status = TestStatus::Skipped;
         self.error_message = Some(reason.to_string());
@@ -168,15 +184,19 @@ pub struct CategoryStats {
     pub failed: u32,
     pub skipped: u32,
     pub pending: u32,
+    /// Passed after retries; not included in `passed`.
+    #[serde(default)]
+    pub flaky: u32,
     pub total_duration: Duration,
 }
 
 impl CategoryStats {
+    /// Percentage of tests that eventually passed, flaky ones included.
     pub fn pass_rate(&self) -> f64 {
         if self.total == 0 {
             return 0.0;
         }
-        (self.passed as f64 / self.total as f64) * 100.0
+        ((self.passed + self.flaky) as f64 / self.total as f64) * 100.0
     }
 
     pub fn add_result(&mut self, result: &TestResult) {
@@ -187,10 +207,21 @@ impl CategoryStats {
             TestStatus::Failed => self.failed += 1,
             TestStatus::Skipped => self.skipped += 1,
             TestStatus::Pending => self.pending += 1,
+            TestStatus::Flaky => self.flaky += 1,
         }
     }
 }
 
+/// Metadata key listing test IDs seen in more than one merged report.
+pub const DUPLICATE_IDS_METADATA_KEY: &str = "duplicate_test_ids";
+
+/// Coverage below this percentage is shown as failing by default.
+pub const DEFAULT_COVERAGE_THRESHOLD: f64 = 70.0;
+
+fn default_coverage_threshold() -> f64 {
+    DEFAULT_COVERAGE_THRESHOLD
+}
+
 /// Full test report.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestReport {
@@ -210,6 +241,9 @@ pub struct TestReport {
     pub results: Vec<TestResult>,
     /// Coverage percentage (if available).
     pub coverage_percent: Option<f64>,
+    /// Coverage below this percentage is flagged.
+    #[serde(default = "default_coverage_threshold")]
+    pub coverage_threshold: f64,
     /// Additional metadata.
     pub metadata: HashMap<String, String>,
 }
@@ -230,6 +264,7 @@ impl TestReport {
             build_number: None,
             results: Vec::new(),
             coverage_percent: None,
+            coverage_threshold: DEFAULT_COVERAGE_THRESHOLD,
             metadata: HashMap::new(),
         }
     }
@@ -239,6 +274,59 @@ impl TestReport {
         self.results.push(result);
     }
 
+    /// Merge another report (e.g. a parallel shard) into this one.
+    ///
+    /// Results are appended. Git/build info and coverage are taken from
+    /// `other` only where this report has none, and existing metadata keys
+    /// win. Test IDs present in both reports are kept and listed under the
+    /// [`DUPLICATE_IDS_METADATA_KEY`] metadata key.
+    pub fn merge(&mut self, other: TestReport) {
+        fn fill(mine: &mut Option<String>, theirs: Option<String>) {
+            if mine.as_deref().is_none_or(str::is_empty) {
+                if let Some(value) = theirs.filter(|v| !v.is_empty()) {
+                    *mine = Some(value);
+                }
+            }
+        }
+
+        fill(&mut self.git_commit, other.git_commit);
+        fill(&mut self.git_branch, other.git_branch);
+        fill(&mut self.build_number, other.build_number);
+        if self.coverage_percent.is_none() {
+            self.coverage_percent = other.coverage_percent;
+        }
+        if self.timestamp == 0 || (other.timestamp != 0 && other.timestamp < self.timestamp) {
+            self.timestamp = other.timestamp;
+        }
+        for (key, value) in other.metadata {
+            self.metadata.entry(key).or_insert(value);
+        }
+
+        let mut duplicates: Vec<String> = self
+            .metadata
+            .get(DUPLICATE_IDS_METADATA_KEY)
+            .map(|ids| ids.split(',').map(str::to_string).collect())
+            .unwrap_or_default();
+        for result in other.results {
+            if self.results.iter().any(|r| r.id == result.id) && !duplicates.contains(&result.id) {
+                duplicates.push(result.id.clone());
+            }
+            self.results.push(result);
+        }
+        if !duplicates.is_empty() {
+            self.metadata
+                .insert(DUPLICATE_IDS_METADATA_KEY.to_string(), duplicates.join(","));
+        }
+    }
+
+    /// Test IDs that appeared more than once when merging reports.
+    pub fn duplicate_ids(&self) -> Vec<&str> {
+        self.metadata
+            .get(DUPLICATE_IDS_METADATA_KEY)
+            .map(|ids| ids.split(',').collect())
+            .unwrap_or_default()
+    }
+
     /// Get overall stats.
     pub fn overall_stats(&self) -> CategoryStats {
         let mut stats = CategoryStats::default();
@@ -268,9 +356,25 @@ impl TestReport {
         map
     }
 
-    /// Check if all tests passed.
+    /// Check if all tests passed, counting flaky tests as passed.
     pub fn all_passed(&self) -> bool {
-        self.results.iter().all(|r| r.status == TestStatus::Passed)
+        self.results
+            .iter()
+            .all(|r| matches!(r.status, TestStatus::Passed | TestStatus::Flaky))
+    }
+
+    /// Copy of this report containing only non-passing results. Flaky
+    /// results passed in the end, so they are left out.
+    pub fn failures_only(&self) -> TestReport {
+        TestReport {
+            results: self
+                .results
+                .iter()
+                .filter(|r| !matches!(r.status, TestStatus::Passed | TestStatus::Flaky))
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
     }
 
     /// Generate HTML report.
@@ -305,6 +409,7 @@ impl TestReport {
             --steam-green: #5ba32b;
             --steam-red: #c74545;
             --steam-yellow: #ffc82c;
+            --steam-orange: #e08a2e;
             --steam-gray: #8f98a0;
             --steam-light: #c7d5e0;
         }}
@@ -388,6 +493,7 @@ impl TestReport {
         .card .value.passed {{ color: var(--steam-green); }}
         .card .value.failed {{ color: var(--steam-red); }}
         .card .value.skipped {{ color: var(--steam-yellow); }}
+        .card .value.flaky {{ color: var(--steam-orange); }}
         .card .value.total {{ color: var(--steam-blue); }}
         
         .progress-bar {{
@@ -404,6 +510,10 @@ impl TestReport {
             transition: width 0.3s ease;
         }}
         
+        .progress-fill.low {{
+            background: var(--steam-red);
+        }}
+        
         .category-section {{
             margin-bottom: 40px;
         }}
@@ -418,6 +528,16 @@ impl TestReport {
             border: 1px solid rgba(255,255,255,0.1);
             border-bottom: none;
             cursor: pointer;
+            list-style: none;
+        }}
+        
+        .category-header::-webkit-details-marker {{
+            display: none;
+        }}
+        
+        .category-section:not([open]) .category-header {{
+            border-bottom: 1px solid rgba(255,255,255,0.1);
+            border-radius: 4px;
         }}
         
         .category-header:hover {{
@@ -445,6 +565,7 @@ impl TestReport {
         .stat.passed {{ color: var(--steam-green); }}
         .stat.failed {{ color: var(--steam-red); }}
         .stat.skipped {{ color: var(--steam-yellow); }}
+        .stat.flaky {{ color: var(--steam-orange); }}
         
         .test-list {{
             border: 1px solid rgba(255,255,255,0.1);
@@ -482,6 +603,7 @@ impl TestReport {
         .test-status.failed {{ background: var(--steam-red); color: white; }}
         .test-status.skipped {{ background: var(--steam-yellow); color: black; }}
         .test-status.pending {{ background: var(--steam-gray); color: white; }}
+        .test-status.flaky {{ background: var(--steam-orange); color: white; }}
         
         .test-id {{
             font-family: monospace;
@@ -499,6 +621,12 @@ impl TestReport {
             margin-top: 3px;
         }}
         
+        .test-retries {{
+            font-size: 0.8em;
+            color: var(--steam-orange);
+            margin-top: 3px;
+        }}
+        
         .test-priority {{
             font-size: 0.75em;
             padding: 3px 8px;
@@ -572,26 +700,49 @@ impl TestReport {
     </header>
     <div class="container">
 "#,
-            self.title,
-            self.title,
-            self.subtitle,
+            html_escape(&self.title),
+            html_escape(&self.title),
+            html_escape(&self.subtitle),
             chrono_format(self.timestamp),
             self.git_commit
                 .as_ref()
-                .map(|c| format!("<span>Commit: {}</span>", &c[..7.min(c.len())]))
+                .map(|c| {
+                    let short: String = c.chars().take(7).collect();
+                    format!("<span>Commit: {}</span>", html_escape(&short))
+                })
                 .unwrap_or_default(),
             self.git_branch
                 .as_ref()
-                .map(|b| format!("<span>Branch: {}</span>", b))
+                .map(|b| format!("<span>Branch: {}</span>", html_escape(b)))
                 .unwrap_or_default(),
             self.build_number
                 .as_ref()
-                .map(|n| format!("<span>Build: {}</span>", n))
+                .map(|n| format!("<span>Build: {}</span>", html_escape(n)))
                 .unwrap_or_default(),
         )
     }
 
     fn html_summary(&self, stats: &CategoryStats) -> String {
+        let coverage = self
+            .coverage_percent
+            .map(|percent| {
+                let low = percent < self.coverage_threshold;
+                format!(
+                    r#"
+            <div class="card">
+                <h3>Coverage</h3>
+                <div class="value {}">{:.1}%</div>
+                <div class="progress-bar">
+                    <div class="progress-fill{}" style="width: {:.1}%"></div>
+                </div>
+            </div>"#,
+                    if low { "failed" } else { "passed" },
+                    percent,
+                    if low { " low" } else { "" },
+                    percent.clamp(0.0, 100.0),
+                )
+            })
+            .unwrap_or_default();
         format!(
             r#"
         <div class="summary-cards">
@@ -607,6 +758,10 @@ impl TestReport {
                 <h3>Failed</h3>
                 <div class="value failed">{}</div>
             </div>
+            <div class="card">
+                <h3>Flaky</h3>
+                <div class="value flaky">{}</div>
+            </div>
             <div class="card">
                 <h3>Skipped</h3>
                 <div class="value skipped">{}</div>
@@ -621,16 +776,18 @@ impl TestReport {
             <div class="card">
                 <h3>Duration</h3>
                 <div class="value total">{:.2}s</div>
-            </div>
+            </div>{}
         </div>
 "#,
             stats.total,
             stats.passed,
             stats.failed,
+            stats.flaky,
             stats.skipped,
             stats.pass_rate(),
             stats.pass_rate(),
             stats.total_duration.as_secs_f64(),
+            coverage,
         )
     }
 
@@ -653,7 +810,7 @@ impl TestReport {
                 </div>
             </div>
 "#,
-                name,
+                html_escape(name),
                 if stats.failed > 0 { "failed" } else { "passed" },
                 stats.passed,
                 stats.total,
@@ -685,28 +842,39 @@ impl TestReport {
                 .iter()
                 .filter(|r| r.status == TestStatus::Skipped)
                 .count();
+            let flaky = results
+                .iter()
+                .filter(|r| r.status == TestStatus::Flaky)
+                .count();
 
             html.push_str(&format!(
                 r#"
-        <div class="category-section">
-            <div class="category-header">
+        <details class="category-section"{}>
+            <summary class="category-header">
                 <h2>{}</h2>
                 <div class="category-stats">
                     <span class="stat passed">{} passed</span>
                     <span class="stat failed">{} failed</span>
+                    <span class="stat flaky">{} flaky</span>
                     <span class="stat skipped">{} skipped</span>
                 </div>
-            </div>
+            </summary>
             <div class="test-list">
 "#,
-                category, passed, failed, skipped
+                // Only categories with failures start expanded.
+                if failed > 0 { " open" } else { "" },
+                html_escape(category),
+                passed,
+                failed,
+                flaky,
+                skipped
             ));
 
             for result in results {
                 html.push_str(&self.html_test_item(result));
             }
 
-            html.push_str("</div></div>");
+            html.push_str("</div></details>");
         }
 
         html
@@ -721,22 +889,30 @@ impl TestReport {
                     <div class="test-info">
                         <div class="test-name">{}</div>
                         <div class="test-description">{}</div>
-                        {}
+                        {}{}
                     </div>
                     <div class="test-priority {}">{}</div>
                     <div class="test-duration">{:.3}ms</div>
 "#,
             result.status.css_class(),
             result.status.icon(),
-            result.id,
-            result.name,
-            result.description,
+            html_escape(&result.id),
+            html_escape(&result.name),
+            html_escape(&result.description),
+            if result.retries > 0 {
+                format!(
+                    r#"<div class="test-retries">{}</div>"#,
+                    retry_count(result.retries)
+                )
+            } else {
+                String::new()
+            },
             result
                 .doc_reference
                 .as_ref()
                 .map(|url| format!(
                     r#"<a href="{}" class="doc-link" target="_blank">📖 Valve Docs</a>"#,
-                    url
+                    html_escape(url)
                 ))
                 .unwrap_or_default(),
             result.priority.css_class(),
@@ -745,7 +921,10 @@ impl TestReport {
         );
 
         if let Some(ref error) = result.error_message {
-            html.push_str(&format!(r#"<div class="test-error">{}</div>"#, error));
+            html.push_str(&format!(
+                r#"<div class="test-error">{}</div>"#,
+                html_escape(error)
+            ));
         }
 
         html.push_str("</div>");
@@ -771,6 +950,88 @@ impl TestReport {
         fs::write(path, html)
     }
 
+    /// Generate a JUnit XML report, one `<testsuite>` per category.
+    ///
+    /// Pending tests are reported as skipped and flaky tests as passed, since
+    /// JUnit has no equivalent; the retry count goes to `<system-out>`.
+    pub fn to_junit_xml(&self) -> String {
+        let stats = self.overall_stats();
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+            xml_escape(&self.title),
+            stats.total,
+            stats.failed,
+            stats.skipped + stats.pending,
+            stats.total_duration.as_secs_f64(),
+        ));
+        if let Some(percent) = self.coverage_percent {
+            xml.push_str(&format!(
+                "  <properties>\n    <property name=\"coverage_percent\" value=\"{:.1}\"/>\n    <property name=\"coverage_threshold\" value=\"{:.1}\"/>\n  </properties>\n",
+                percent, self.coverage_threshold,
+            ));
+        }
+
+        let by_category = self.results_by_category();
+        let mut categories: Vec<_> = by_category.iter().collect();
+        categories.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (category, results) in categories {
+            let mut cat_stats = CategoryStats::default();
+            for result in results {
+                cat_stats.add_result(result);
+            }
+
+            xml.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+                xml_escape(category),
+                cat_stats.total,
+                cat_stats.failed,
+                cat_stats.skipped + cat_stats.pending,
+                cat_stats.total_duration.as_secs_f64(),
+            ));
+
+            for result in results {
+                xml.push_str(&format!(
+                    "    <testcase name=\"{}: {}\" classname=\"{}\" time=\"{:.3}\"",
+                    xml_escape(&result.id),
+                    xml_escape(&result.name),
+                    xml_escape(category),
+                    result.duration.as_secs_f64(),
+                ));
+
+                let message = result.error_message.as_deref().unwrap_or_default();
+                match result.status {
+                    TestStatus::Passed => xml.push_str("/>\n"),
+                    TestStatus::Flaky => xml.push_str(&format!(
+                        ">\n      <system-out>flaky: passed after {}</system-out>\n    </testcase>\n",
+                        retry_count(result.retries),
+                    )),
+                    TestStatus::Failed => xml.push_str(&format!(
+                        ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                        xml_escape(message),
+                        xml_escape(message),
+                    )),
+                    TestStatus::Skipped | TestStatus::Pending => xml.push_str(&format!(
+                        ">\n      <skipped message=\"{}\"/>\n    </testcase>\n",
+                        xml_escape(message),
+                    )),
+                }
+            }
+
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+
+    /// Save report as JUnit XML.
+    pub fn save_junit(&self, path: &Path) -> std::io::Result<()> {
+        fs::write(path, self.to_junit_xml())
+    }
+
     /// Save report as JSON with computed statistics.
     /// This produces a richer JSON format suitable for CI/CD pipelines.
     pub fn save_json(&self, path: &Path) -> std::io::Result<()> {
@@ -793,6 +1054,7 @@ impl TestReport {
                 failed: stats.failed,
                 skipped: stats.skipped,
                 pending: stats.pending,
+                flaky: stats.flaky,
                 pass_rate: stats.pass_rate(),
                 total_duration_secs: stats.total_duration.as_secs_f64(),
             })
@@ -806,12 +1068,14 @@ impl TestReport {
             git_branch: self.git_branch.clone(),
             build_number: self.build_number.clone(),
             coverage_percent: self.coverage_percent,
+            coverage_threshold: self.coverage_threshold,
             overall_stats: JsonOverallStats {
                 total: overall.total,
                 passed: overall.passed,
                 failed: overall.failed,
                 skipped: overall.skipped,
                 pending: overall.pending,
+                flaky: overall.flaky,
                 pass_rate: overall.pass_rate(),
                 total_duration_secs: overall.total_duration.as_secs_f64(),
             },
@@ -826,6 +1090,7 @@ impl TestReport {
                 duration_secs: r.duration.as_secs_f64(),
                 error_message: r.error_message.clone(),
                 doc_reference: r.doc_reference.clone(),
+                retries: r.retries,
             }).collect(),
             metadata: self.metadata.clone(),
         }
@@ -842,6 +1107,8 @@ pub struct JsonReport {
     pub git_branch: Option<String>,
     pub build_number: Option<String>,
     pub coverage_percent: Option<f64>,
+    #[serde(default = "default_coverage_threshold")]
+    pub coverage_threshold: f64,
     pub overall_stats: JsonOverallStats,
     pub categories: Vec<JsonCategoryStats>,
     pub results: Vec<JsonTestResult>,
@@ -856,6 +1123,8 @@ pub struct JsonOverallStats {
     pub failed: u32,
     pub skipped: u32,
     pub pending: u32,
+    #[serde(default)]
+    pub flaky: u32,
     pub pass_rate: f64,
     pub total_duration_secs: f64,
 }
@@ -869,6 +1138,8 @@ pub struct JsonCategoryStats {
     pub failed: u32,
     pub skipped: u32,
     pub pending: u32,
+    #[serde(default)]
+    pub flaky: u32,
     pub pass_rate: f64,
     pub total_duration_secs: f64,
 }
@@ -885,29 +1156,77 @@ pub struct JsonTestResult {
     pub duration_secs: f64,
     pub error_message: Option<String>,
     pub doc_reference: Option<String>,
+    #[serde(default)]
+    pub retries: u32,
+}
+
+/// Escapes text for safe inclusion in HTML element content and quoted attributes.
+fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escapes text for XML content and attributes, dropping characters that are
+/// not allowed in XML 1.0 documents (most C0 control characters).
+fn xml_escape(s: &str) -> String {
+    let allowed: String = s
+        .chars()
+        .filter(|&c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    html_escape(&allowed)
+}
+
+/// Formats a retry count as "1 retry" or "N retries".
+fn retry_count(retries: u32) -> String {
+    match retries {
+        1 => "1 retry".to_string(),
+        n => format!("{n} retries"),
+    }
 }
 
+/// Formats a Unix timestamp as `YYYY-MM-DD HH:MM:SS UTC`.
 fn chrono_format(timestamp: u64) -> String {
-    // Simple formatting without external chrono crate
-    let secs = timestamp;
-    let days_since_epoch = secs / 86400;
-    let remaining = secs % 86400;
+    let days_since_epoch = (timestamp / 86400) as i64;
+    let remaining = timestamp % 86400;
     let hours = remaining / 3600;
     let minutes = (remaining % 3600) / 60;
     let seconds = remaining % 60;
 
-    // Approximate date (not accurate but works for display)
-    let years = 1970 + (days_since_epoch / 365);
-    let days = days_since_epoch % 365;
-    let month = (days / 30) + 1;
-    let day = (days % 30) + 1;
+    let (year, month, day) = civil_from_days(days_since_epoch);
 
     format!(
         "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
-        years, month, day, hours, minutes, seconds
+        year, month, day, hours, minutes, seconds
     )
 }
 
+/// Converts days since 1970-01-01 to a proleptic Gregorian (year, month, day).
+///
+/// Howard Hinnant's `civil_from_days`:
+/// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097); // [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365; // [0, 399]
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // [0, 365]
+    let mp = (5 * doy + 2) / 153; // [0, 11], March-based
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32; // [1, 31]
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32; // [1, 12]
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
 /// Test report builder with fluent API.
 pub struct ReportBuilder {
     report: TestReport,
@@ -920,6 +1239,18 @@ impl ReportBuilder {
         }
     }
 
+    /// Start from several reports (e.g. parallel shards) merged into one.
+    ///
+    /// The first report's title and subtitle are kept.
+    pub fn from_reports(reports: Vec<TestReport>) -> Self {
+        let mut reports = reports.into_iter();
+        let mut report = reports.next().unwrap_or_else(|| TestReport::new("", ""));
+        for other in reports {
+            report.merge(other);
+        }
+        ReportBuilder { report }
+    }
+
     pub fn subtitle(mut self, subtitle: &str) -> Self {
         self.report.subtitle = subtitle.to_string();
         self
@@ -941,6 +1272,12 @@ impl ReportBuilder {
         self
     }
 
+    /// Coverage below `percent` is shown in red.
+    pub fn coverage_threshold(mut self, percent: f64) -> Self {
+        self.report.coverage_threshold = percent;
+        self
+    }
+
     pub fn metadata(mut self, key: &str, value: &str) -> Self {
         self.report
             .metadata
@@ -1050,4 +1387,286 @@ mod tests {
         assert_eq!(by_cat["Category B"].passed, 1);
         assert_eq!(by_cat["Category B"].failed, 1);
     }
+
+    #[test]
+    fn timestamp_epoch() {
+        assert_eq!(chrono_format(0), "1970-01-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn timestamp_leap_day() {
+        // 2024-02-29 12:34:56 UTC
+        assert_eq!(chrono_format(1_709_210_096), "2024-02-29 12:34:56 UTC");
+        // 2000 is a leap year (divisible by 400).
+        assert_eq!(chrono_format(951_782_400), "2000-02-29 00:00:00 UTC");
+    }
+
+    #[test]
+    fn timestamp_year_boundary() {
+        // 2023-12-31 23:59:59 UTC, then one second later.
+        assert_eq!(chrono_format(1_704_067_199), "2023-12-31 23:59:59 UTC");
+        assert_eq!(chrono_format(1_704_067_200), "2024-01-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn timestamp_non_leap_century() {
+        // 2100 is not a leap year: Feb 28 is followed by Mar 1.
+        assert_eq!(chrono_format(4_107_456_000), "2100-02-28 00:00:00 UTC");
+        assert_eq!(chrono_format(4_107_542_400), "2100-03-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn html_escapes_user_strings() {
+        let report = ReportBuilder::new("Report <b>")
+            .subtitle("a & b")
+            .add_test(
+                TestResult::new("ESC-001", "Vec<u8> compare", "<Generics>").fail(
+                    Duration::ZERO,
+                    "<script>alert('x')</script> left: Vec<u8> != right: &[u8]",
+                ),
+            )
+            .build();
+
+        let html = report.to_html();
+
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"));
+        assert!(html.contains("Vec&lt;u8&gt; compare"));
+        assert!(html.contains("&lt;Generics&gt;"));
+        assert!(html.contains("Report &lt;b&gt;"));
+        assert!(html.contains("a &amp; b"));
+    }
+
+    #[test]
+    fn junit_xml_structure() {
+        let report = ReportBuilder::new("CI Suite")
+            .add_test(TestResult::new("A-001", "Passes", "Alpha").pass(Duration::from_millis(5)))
+            .add_test(
+                TestResult::new("A-002", "Fails", "Alpha")
+                    .fail(Duration::from_millis(2), "expected <1> & got 2"),
+            )
+            .add_test(TestResult::new("B-001", "Skipped", "Beta").skip("not on CI"))
+            .build();
+
+        let xml = report.to_junit_xml();
+
+        assert!(xml.starts_with("<?xml version=\"1.0\""));
+        assert!(
+            xml.contains("<testsuites name=\"CI Suite\" tests=\"3\" failures=\"1\" skipped=\"1\"")
+        );
+        assert!(xml.contains("<testsuite name=\"Alpha\" tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<testsuite name=\"Beta\" tests=\"1\" failures=\"0\" skipped=\"1\""));
+        assert!(
+            xml.contains("<testcase name=\"A-001: Passes\" classname=\"Alpha\" time=\"0.005\"/>")
+        );
+        assert!(xml.contains(
+            "<failure message=\"expected &lt;1&gt; &amp; got 2\">expected &lt;1&gt; &amp; got 2</failure>"
+        ));
+        assert!(xml.contains("<skipped message=\"not on CI\"/>"));
+        assert_eq!(xml.matches("<testcase ").count(), 3);
+        assert!(xml.trim_end().ends_with("</testsuites>"));
+    }
+
+    #[test]
+    fn merge_shards() {
+        let shard_a = ReportBuilder::new("Suite")
+            .git_info(None, Some("main"))
+            .add_test(TestResult::new("A-001", "A1", "Alpha").pass(Duration::from_millis(1)))
+            .add_test(TestResult::new("A-002", "A2", "Alpha").fail(Duration::ZERO, "boom"))
+            .build();
+        let shard_b = ReportBuilder::new("Suite shard 2")
+            .git_info(Some("0123456789abcdef"), Some("feature"))
+            .build_number("42")
+            .add_test(TestResult::new("B-001", "B1", "Beta").pass(Duration::from_millis(2)))
+            .add_test(TestResult::new("B-002", "B2", "Beta").skip("later"))
+            .build();
+
+        let merged = ReportBuilder::from_reports(vec![shard_a, shard_b]).build();
+
+        assert_eq!(merged.title, "Suite");
+        assert_eq!(merged.git_commit.as_deref(), Some("0123456789abcdef"));
+        assert_eq!(merged.git_branch.as_deref(), Some("main"));
+        assert_eq!(merged.build_number.as_deref(), Some("42"));
+
+        let stats = merged.overall_stats();
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.passed, 2);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.skipped, 1);
+        assert_eq!(stats.total_duration, Duration::from_millis(3));
+        assert!(merged.duplicate_ids().is_empty());
+    }
+
+    #[test]
+    fn merge_flags_duplicate_ids() {
+        let mut a = ReportBuilder::new("Suite")
+            .add_test(TestResult::new("DUP-001", "Dup", "Tests").pass(Duration::ZERO))
+            .build();
+        let b = ReportBuilder::new("Suite")
+            .add_test(TestResult::new("DUP-001", "Dup", "Tests").fail(Duration::ZERO, "x"))
+            .add_test(TestResult::new("UNIQ-001", "Uniq", "Tests").pass(Duration::ZERO))
+            .build();
+
+        a.merge(b);
+
+        assert_eq!(a.results.len(), 3);
+        assert_eq!(a.duplicate_ids(), vec!["DUP-001"]);
+    }
+
+    #[test]
+    fn failures_only_on_all_pass_report_is_empty() {
+        let report = ReportBuilder::new("Suite")
+            .git_info(Some("abc123"), Some("main"))
+            .add_test(TestResult::new("OK-001", "One", "Tests").pass(Duration::ZERO))
+            .add_test(TestResult::new("OK-002", "Two", "Tests").pass(Duration::ZERO))
+            .build();
+
+        let failures = report.failures_only();
+        assert!(failures.results.is_empty());
+        assert_eq!(failures.title, "Suite");
+        assert_eq!(failures.git_commit.as_deref(), Some("abc123"));
+        assert_eq!(report.results.len(), 2);
+    }
+
+    #[test]
+    fn failures_only_keeps_non_passing_results() {
+        let report = ReportBuilder::new("Suite")
+            .add_test(TestResult::new("T-001", "Pass", "A").pass(Duration::ZERO))
+            .add_test(TestResult::new("T-002", "Fail", "A").fail(Duration::ZERO, "boom"))
+            .add_test(TestResult::new("T-003", "Skip", "B").skip("not supported"))
+            .add_test(TestResult::new("T-004", "Pending", "B"))
+            .build();
+
+        let ids: Vec<_> = report
+            .failures_only()
+            .results
+            .iter()
+            .map(|r| r.id.clone())
+            .collect();
+        assert_eq!(ids, vec!["T-002", "T-003", "T-004"]);
+    }
+
+    #[test]
+    fn failures_only_leaves_out_flaky_results() {
+        let report = ReportBuilder::new("Suite")
+            .add_test(TestResult::new("T-001", "Wobbly", "A").flaky(Duration::ZERO, 1))
+            .add_test(TestResult::new("T-002", "Fail", "A").fail(Duration::ZERO, "boom"))
+            .build();
+
+        let ids: Vec<_> = report
+            .failures_only()
+            .results
+            .iter()
+            .map(|r| r.id.clone())
+            .collect();
+        assert_eq!(ids, vec!["T-002"]);
+        assert!(report
+            .to_junit_xml()
+            .contains("<system-out>flaky: passed after 1 retry</system-out>"));
+    }
+
+    #[test]
+    fn html_expands_only_failing_categories() {
+        let report = ReportBuilder::new("Suite")
+            .add_test(TestResult::new("A-001", "Pass", "Alpha").pass(Duration::ZERO))
+            .add_test(TestResult::new("B-001", "Fail", "Beta").fail(Duration::ZERO, "boom"))
+            .build();
+
+        const OPEN: &str = r#"<details class="category-section" open>"#;
+        const CLOSED: &str = r#"<details class="category-section">"#;
+        let html = report.to_html();
+        assert_eq!(html.matches(OPEN).count(), 1);
+        assert_eq!(html.matches(CLOSED).count(), 1);
+        let open = html.find(OPEN).unwrap();
+        let beta = html.find("<h2>Beta</h2>").unwrap();
+        let alpha = html.find("<h2>Alpha</h2>").unwrap();
+        assert!(alpha < open && open < beta);
+    }
+
+    #[test]
+    fn flaky_counted_separately_from_passed() {
+        let report = ReportBuilder::new("Suite")
+            .add_test(TestResult::new("F-001", "Stable", "Net").pass(Duration::ZERO))
+            .add_test(TestResult::new("F-002", "Wobbly", "Net").flaky(Duration::ZERO, 2))
+            .build();
+
+        let stats = report.overall_stats();
+        assert_eq!(stats.passed, 1);
+        assert_eq!(stats.flaky, 1);
+        assert_eq!(stats.failed, 0);
+        assert_eq!(stats.pass_rate(), 100.0);
+        assert!(report.all_passed());
+        assert_eq!(report.results[1].retries, 2);
+        assert_eq!(report.to_json_report().overall_stats.flaky, 1);
+    }
+
+    #[test]
+    fn html_shows_flaky_retry_count() {
+        let report = ReportBuilder::new("Suite")
+            .add_test(TestResult::new("F-001", "Wobbly", "Net").flaky(Duration::ZERO, 3))
+            .build();
+
+        let html = report.to_html();
+        assert!(html.contains(r#"<div class="test-status flaky">↻</div>"#));
+        assert!(html.contains(r#"<div class="test-retries">3 retries</div>"#));
+        assert!(html.contains(r#"<span class="stat flaky">1 flaky</span>"#));
+        assert!(report
+            .to_junit_xml()
+            .contains("<system-out>flaky: passed after 3 retries</system-out>"));
+    }
+
+    #[test]
+    fn html_coverage_card_only_when_set() {
+        let test = TestResult::new("T-001", "Test", "Cat").pass(Duration::from_millis(1));
+        let covered = ReportBuilder::new("Covered")
+            .coverage(82.5)
+            .add_test(test.clone())
+            .build()
+            .to_html();
+        assert!(covered.contains("<h3>Coverage</h3>"));
+        assert!(covered.contains(r#"<div class="value passed">82.5%</div>"#));
+        assert!(covered.contains(r#"class="progress-fill" style="width: 82.5%""#));
+
+        let uncovered = ReportBuilder::new("Uncovered").add_test(test).build();
+        assert!(!uncovered.to_html().contains("<h3>Coverage</h3>"));
+        assert!(!uncovered.to_junit_xml().contains("coverage_percent"));
+    }
+
+    #[test]
+    fn coverage_below_threshold_is_flagged() {
+        let report = ReportBuilder::new("Low").coverage(55.0).build();
+        let html = report.to_html();
+        assert!(html.contains(r#"<div class="value failed">55.0%</div>"#));
+        assert!(html.contains(r#"class="progress-fill low" style="width: 55.0%""#));
+
+        // The same coverage passes a lower bar.
+        let html = ReportBuilder::new("Lenient")
+            .coverage(55.0)
+            .coverage_threshold(50.0)
+            .build()
+            .to_html();
+        assert!(html.contains(r#"<div class="value passed">55.0%</div>"#));
+    }
+
+    #[test]
+    fn coverage_in_json_and_junit() {
+        let report = ReportBuilder::new("Exports")
+            .coverage(91.2)
+            .coverage_threshold(80.0)
+            .build();
+
+        let json = report.to_json_report();
+        assert_eq!(json.coverage_percent, Some(91.2));
+        assert_eq!(json.coverage_threshold, 80.0);
+
+        let xml = report.to_junit_xml();
+        assert!(xml.contains(r#"<property name="coverage_percent" value="91.2"/>"#));
+        assert!(xml.contains(r#"<property name="coverage_threshold" value="80.0"/>"#));
+
+        // Reports saved before the threshold existed load with the default.
+        let mut value = serde_json::to_value(&report).unwrap();
+        value.as_object_mut().unwrap().remove("coverage_threshold");
+        let loaded: TestReport = serde_json::from_value(value).unwrap();
+        assert_eq!(loaded.coverage_threshold, DEFAULT_COVERAGE_THRESHOLD);
+    }
 }
@@ -0,0 +1,223 @@
+//! Unreliable-channel transports.
+//!
+//! Snapshots, commands and pings travel over a [`Transport`]: UDP
+//! ([`UnreliableConn`]) between processes, or an in-memory
+//! [`LoopbackTransport`] when a listen server and its local client share one
+//! process. Loopback datagrams are still encoded, so both paths see exactly
+//! the same bytes.
+//!
+//! A loopback connection is opened with a [`LoopbackConnector`], which hands
+//! the server's ends of a [`ReliableConn::loopback_pair`] and a
+//! [`LoopbackTransport::pair`] to the matching [`LoopbackListener`].
+
+use std::{fmt, time::Duration};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use tokio::{
+    sync::{mpsc, Mutex},
+    time,
+};
+
+use crate::net::{decode_from_bytes, encode_to_bytes, NetMsg, ReliableConn, UnreliableConn};
+
+/// Sends and receives unreliable messages to a single peer.
+#[async_trait]
+pub trait Transport: Send + Sync + fmt::Debug {
+    /// Sends `msg` to the peer. Delivery isn't guaranteed.
+    async fn send(&self, msg: &NetMsg) -> anyhow::Result<()>;
+
+    /// Waits up to `timeout` for the next message.
+    async fn recv_timeout(&self, timeout: Duration) -> anyhow::Result<Option<NetMsg>>;
+}
+
+#[async_trait]
+impl Transport for UnreliableConn {
+    async fn send(&self, msg: &NetMsg) -> anyhow::Result<()> {
+        UnreliableConn::send(self, msg).await
+    }
+
+    async fn recv_timeout(&self, timeout: Duration) -> anyhow::Result<Option<NetMsg>> {
+        UnreliableConn::recv_timeout(self, timeout).await
+    }
+}
+
+/// One end of an in-process datagram channel.
+#[derive(Debug)]
+pub struct LoopbackTransport {
+    tx: mpsc::UnboundedSender<Vec<u8>>,
+    rx: Mutex<mpsc::UnboundedReceiver<Vec<u8>>>,
+}
+
+impl LoopbackTransport {
+    /// Two connected ends; what one sends, the other receives.
+    pub fn pair() -> (Self, Self) {
+        let (a_tx, a_rx) = mpsc::unbounded_channel();
+        let (b_tx, b_rx) = mpsc::unbounded_channel();
+        (
+            Self {
+                tx: a_tx,
+                rx: Mutex::new(b_rx),
+            },
+            Self {
+                tx: b_tx,
+                rx: Mutex::new(a_rx),
+            },
+        )
+    }
+
+    /// Sends an already-encoded message, as a server does with a payload it
+    /// shares between clients.
+    pub fn send_bytes(&self, payload: &[u8]) -> anyhow::Result<()> {
+        self.tx
+            .send(payload.to_vec())
+            .map_err(|_| anyhow::anyhow!("loopback peer closed"))
+    }
+
+    /// Takes the next message if one is already waiting.
+    pub fn try_recv(&self) -> anyhow::Result<Option<NetMsg>> {
+        let Ok(mut rx) = self.rx.try_lock() else {
+            // Someone else is mid-receive.
+            return Ok(None);
+        };
+        match rx.try_recv() {
+            Ok(bytes) => decode_from_bytes(&bytes)
+                .context("deserialize loopback msg")
+                .map(Some),
+            Err(mpsc::error::TryRecvError::Empty) => Ok(None),
+            Err(mpsc::error::TryRecvError::Disconnected) => {
+                anyhow::bail!("loopback peer closed")
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for LoopbackTransport {
+    async fn send(&self, msg: &NetMsg) -> anyhow::Result<()> {
+        self.send_bytes(&encode_to_bytes(msg)?)
+    }
+
+    async fn recv_timeout(&self, timeout: Duration) -> anyhow::Result<Option<NetMsg>> {
+        let mut rx = self.rx.lock().await;
+        match time::timeout(timeout, rx.recv()).await {
+            Ok(Some(bytes)) => Ok(Some(
+                decode_from_bytes(&bytes).context("deserialize loopback msg")?,
+            )),
+            Ok(None) => anyhow::bail!("loopback peer closed"),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// The server's ends of a new loopback connection.
+pub type LoopbackConnection = (ReliableConn, LoopbackTransport);
+
+/// Creates a connected connector/listener pair.
+pub fn loopback_listener() -> (LoopbackConnector, LoopbackListener) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (LoopbackConnector { tx }, LoopbackListener { rx })
+}
+
+/// Opens in-process connections to a [`LoopbackListener`].
+#[derive(Debug, Clone)]
+pub struct LoopbackConnector {
+    tx: mpsc::UnboundedSender<LoopbackConnection>,
+}
+
+impl LoopbackConnector {
+    /// Queues a new connection on the listener and returns the client's
+    /// ends. The server side only sees it once it accepts.
+    pub fn connect(&self) -> anyhow::Result<(ReliableConn, LoopbackTransport)> {
+        let (client_conn, server_conn) = ReliableConn::loopback_pair();
+        let (client_transport, server_transport) = LoopbackTransport::pair();
+        self.tx
+            .send((server_conn, server_transport))
+            .map_err(|_| anyhow::anyhow!("loopback listener closed"))?;
+        Ok((client_conn, client_transport))
+    }
+}
+
+/// Accepts connections opened through a [`LoopbackConnector`].
+#[derive(Debug)]
+pub struct LoopbackListener {
+    rx: mpsc::UnboundedReceiver<LoopbackConnection>,
+}
+
+impl LoopbackListener {
+    /// Waits for the next connection.
+    pub async fn accept(&mut self) -> anyhow::Result<LoopbackConnection> {
+        self.rx
+            .recv()
+            .await
+            .context("every loopback connector was dropped")
+    }
+
+    /// Takes a pending connection without waiting.
+    pub fn try_accept(&mut self) -> Option<LoopbackConnection> {
+        self.rx.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::ClientId;
+
+    #[tokio::test]
+    async fn loopback_transport_delivers_both_ways() {
+        let (a, b) = LoopbackTransport::pair();
+        let ping = NetMsg::Ping { seq: 3 };
+        a.send(&ping).await.unwrap();
+        assert_eq!(b.try_recv().unwrap(), Some(ping));
+        assert_eq!(b.try_recv().unwrap(), None);
+
+        let pong = NetMsg::Pong {
+            client_id: ClientId(1),
+            seq: 3,
+        };
+        b.send(&pong).await.unwrap();
+        assert_eq!(
+            a.recv_timeout(Duration::from_millis(10)).await.unwrap(),
+            Some(pong)
+        );
+        assert_eq!(
+            a.recv_timeout(Duration::from_millis(10)).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn loopback_transport_reports_a_closed_peer() {
+        let (a, b) = LoopbackTransport::pair();
+        drop(b);
+        assert!(a.send(&NetMsg::Ping { seq: 0 }).await.is_err());
+        assert!(a.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn connector_hands_server_ends_to_listener() {
+        let (connector, mut listener) = loopback_listener();
+        assert!(listener.try_accept().is_none());
+
+        let (mut client_conn, client_transport) = connector.connect().unwrap();
+        let (mut server_conn, server_transport) = listener.accept().await.unwrap();
+        assert!(client_conn.is_loopback());
+        assert!(server_conn.peer_addr().is_err());
+
+        let hello = NetMsg::ServerPrint {
+            message: "hi".to_string(),
+        };
+        server_conn.send(&hello).await.unwrap();
+        assert_eq!(client_conn.recv().await.unwrap(), hello);
+
+        client_transport
+            .send(&NetMsg::Ping { seq: 1 })
+            .await
+            .unwrap();
+        assert_eq!(
+            server_transport.try_recv().unwrap(),
+            Some(NetMsg::Ping { seq: 1 })
+        );
+    }
+}
@@ -7,14 +7,18 @@
 //! # Features
 //! - Voice recording control
 //! - Voice data compression/decompression
+//! - Pluggable codecs: raw PCM or IMA ADPCM (4:1)
 //! - Push-to-talk support
 //! - Voice activity detection
+//! - Mute-aware and proximity voice routing
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 
+use crate::chat::ChatManager;
+use crate::math::Vec3;
 use crate::steam_id::SteamId;
 
 /// Voice recording state.
@@ -76,6 +80,221 @@ impl VoiceQuality {
     }
 }
 
+/// Converts PCM frames to and from the bytes carried in a [`VoicePacket`].
+pub trait VoiceCodec: Send {
+    fn encode(&mut self, pcm: &[i16]) -> Vec<u8>;
+    fn decode(&mut self, data: &[u8]) -> Vec<i16>;
+}
+
+/// Uncompressed little-endian 16-bit PCM.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PcmPassthrough;
+
+impl VoiceCodec for PcmPassthrough {
+    fn encode(&mut self, pcm: &[i16]) -> Vec<u8> {
+        pcm.iter().flat_map(|s| s.to_le_bytes()).collect()
+    }
+
+    /// A trailing odd byte is dropped.
+    fn decode(&mut self, data: &[u8]) -> Vec<i16> {
+        data.chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect()
+    }
+}
+
+/// IMA ADPCM step index adjustment per 4-bit code.
+const ADPCM_INDEX_TABLE: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+/// IMA ADPCM quantizer step sizes.
+const ADPCM_STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+/// Bytes before the nibbles: sample count (u16), predictor (i16), step index.
+const ADPCM_HEADER_LEN: usize = 5;
+
+/// Lossy IMA ADPCM: 4 bits per sample after a small header.
+///
+/// Each frame's header carries the predictor state it starts from, so a
+/// frame decodes on its own even if the one before it was lost. Frames hold
+/// at most `u16::MAX` samples; longer input is encoded as several frames
+/// back to back.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AdpcmCodec {
+    predictor: i32,
+    step_index: usize,
+}
+
+impl AdpcmCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Quantizes `sample` against the current prediction, advancing the state.
+    fn encode_sample(&mut self, sample: i16) -> u8 {
+        let step = ADPCM_STEP_TABLE[self.step_index];
+        let mut diff = i32::from(sample) - self.predictor;
+        let mut code = 0u8;
+        if diff < 0 {
+            code = 8;
+            diff = -diff;
+        }
+        if diff >= step {
+            code |= 4;
+            diff -= step;
+        }
+        if diff >= step >> 1 {
+            code |= 2;
+            diff -= step >> 1;
+        }
+        if diff >= step >> 2 {
+            code |= 1;
+        }
+        self.decode_sample(code);
+        code
+    }
+
+    /// Applies a 4-bit code to the state, returning the new prediction.
+    fn decode_sample(&mut self, code: u8) -> i16 {
+        let step = ADPCM_STEP_TABLE[self.step_index];
+        let mut delta = step >> 3;
+        if code & 4 != 0 {
+            delta += step;
+        }
+        if code & 2 != 0 {
+            delta += step >> 1;
+        }
+        if code & 1 != 0 {
+            delta += step >> 2;
+        }
+        if code & 8 != 0 {
+            delta = -delta;
+        }
+        self.predictor = (self.predictor + delta).clamp(i16::MIN.into(), i16::MAX.into());
+        self.step_index = (self.step_index as i32 + ADPCM_INDEX_TABLE[usize::from(code)])
+            .clamp(0, ADPCM_STEP_TABLE.len() as i32 - 1) as usize;
+        self.predictor as i16
+    }
+}
+
+impl VoiceCodec for AdpcmCodec {
+    fn encode(&mut self, pcm: &[i16]) -> Vec<u8> {
+        let frames = pcm.len().div_ceil(usize::from(u16::MAX));
+        let mut out = Vec::with_capacity(frames * ADPCM_HEADER_LEN + pcm.len().div_ceil(2));
+        for frame in pcm.chunks(usize::from(u16::MAX)) {
+            out.extend_from_slice(&(frame.len() as u16).to_le_bytes());
+            out.extend_from_slice(&(self.predictor as i16).to_le_bytes());
+            out.push(self.step_index as u8);
+            for pair in frame.chunks(2) {
+                let low = self.encode_sample(pair[0]);
+                let high = pair.get(1).map_or(0, |&s| self.encode_sample(s));
+                out.push(low | (high << 4));
+            }
+        }
+        out
+    }
+
+    /// Malformed or truncated data decodes to as many samples as it holds.
+    fn decode(&mut self, mut data: &[u8]) -> Vec<i16> {
+        let mut out = Vec::new();
+        while let Some((header, rest)) = data.split_first_chunk::<ADPCM_HEADER_LEN>() {
+            let count = usize::from(u16::from_le_bytes([header[0], header[1]]));
+            let (body, next) = rest.split_at(count.div_ceil(2).min(rest.len()));
+            self.predictor = i16::from_le_bytes([header[2], header[3]]).into();
+            self.step_index = usize::from(header[4]).min(ADPCM_STEP_TABLE.len() - 1);
+
+            out.extend(
+                body.iter()
+                    .flat_map(|&byte| [byte & 0x0f, byte >> 4])
+                    .take(count)
+                    .map(|code| self.decode_sample(code)),
+            );
+            data = next;
+        }
+        out
+    }
+}
+
+/// RMS amplitude of a PCM frame.
+pub fn rms(frame: &[i16]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+    let sum_sq: f64 = frame.iter().map(|&s| f64::from(s) * f64::from(s)).sum();
+    (sum_sq / frame.len() as f64).sqrt() as f32
+}
+
+/// Whether a PCM frame's RMS amplitude reaches `threshold`.
+///
+/// RMS is used rather than peak so single-sample clicks don't count as
+/// speech.
+pub fn is_active(frame: &[i16], threshold: i16) -> bool {
+    rms(frame) >= f32::from(threshold)
+}
+
+/// Voice activity gate with hysteresis.
+///
+/// Opens when a frame reaches `open_threshold` and stays open through up to
+/// `hold_frames` consecutive frames below `close_threshold`, so quiet speech
+/// tails aren't clipped.
+#[derive(Debug, Clone)]
+pub struct VoiceGate {
+    open_threshold: i16,
+    close_threshold: i16,
+    hold_frames: u32,
+    open: bool,
+    quiet_frames: u32,
+}
+
+impl VoiceGate {
+    /// Create a closed gate.
+    pub fn new(open_threshold: i16, close_threshold: i16, hold_frames: u32) -> Self {
+        VoiceGate {
+            open_threshold,
+            close_threshold,
+            hold_frames,
+            open: false,
+            quiet_frames: 0,
+        }
+    }
+
+    /// Feed a frame and return whether it should be transmitted.
+    pub fn process(&mut self, frame: &[i16]) -> bool {
+        if is_active(frame, self.open_threshold) {
+            self.open = true;
+            self.quiet_frames = 0;
+        } else if self.open {
+            if is_active(frame, self.close_threshold) {
+                self.quiet_frames = 0;
+            } else {
+                self.quiet_frames += 1;
+                if self.quiet_frames > self.hold_frames {
+                    self.open = false;
+                    self.quiet_frames = 0;
+                }
+            }
+        }
+        self.open
+    }
+
+    /// Check if the gate is open.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+}
+
+impl Default for VoiceGate {
+    /// Thresholds for 16-bit PCM; the hold covers 200ms of 20ms frames.
+    fn default() -> Self {
+        Self::new(1000, 500, 10)
+    }
+}
+
 /// Voice recording manager.
 pub struct VoiceRecorder {
     /// Current state.
@@ -96,10 +315,14 @@ pub struct VoiceRecorder {
     ptt_active: bool,
     /// Voice activity detection enabled.
     vad_enabled: bool,
+    /// Gate applied to PCM frames when VAD is enabled.
+    gate: VoiceGate,
     /// Last voice activity time.
     last_activity: Option<Instant>,
     /// Microphone muted.
     muted: bool,
+    /// Encodes PCM frames into packet data.
+    codec: Box<dyn VoiceCodec>,
 }
 
 impl VoiceRecorder {
@@ -115,11 +338,20 @@ impl VoiceRecorder {
             quality: VoiceQuality::Normal,
             ptt_active: false,
             vad_enabled: true,
+            gate: VoiceGate::default(),
             last_activity: None,
             muted: false,
+            codec: Box::new(PcmPassthrough),
         }
     }
 
+    /// Replace the codec PCM frames are encoded with; raw PCM by default.
+    ///
+    /// Listeners must decode with the same codec.
+    pub fn set_codec(&mut self, codec: Box<dyn VoiceCodec>) {
+        self.codec = codec;
+    }
+
     /// Check if initialized.
     pub fn is_initialized(&self) -> bool {
         self.initialized
@@ -246,6 +478,11 @@ impl VoiceRecorder {
         self.vad_enabled
     }
 
+    /// Replace the voice activity gate.
+    pub fn set_voice_gate(&mut self, gate: VoiceGate) {
+        self.gate = gate;
+    }
+
     /// Mute microphone.
     pub fn set_muted(&mut self, muted: bool) {
         self.muted = muted;
@@ -269,6 +506,22 @@ impl VoiceRecorder {
         }
     }
 
+    /// Encode a captured PCM frame with the recorder's codec and buffer it.
+    ///
+    /// With VAD enabled, frames the [`VoiceGate`] rejects are dropped.
+    /// Returns whether the frame was buffered.
+    pub fn add_pcm_frame(&mut self, frame: &[i16]) -> bool {
+        if self.state != VoiceRecordingState::Recording || self.muted {
+            return false;
+        }
+        if self.vad_enabled && !self.gate.process(frame) {
+            return false;
+        }
+        let data = self.codec.encode(frame);
+        self.add_voice_data(data);
+        true
+    }
+
     /// Get recording duration.
     pub fn recording_duration(&self) -> Option<Duration> {
         self.recording_start.map(|start| start.elapsed())
@@ -309,8 +562,8 @@ pub struct VoiceDecompressor {
     jitter_buffer: VecDeque<VoicePacket>,
     /// Max jitter buffer size.
     max_jitter_buffer: usize,
-    /// Expected next sequence.
-    expected_sequence: Option<u32>,
+    /// Decodes packet data back into PCM frames.
+    codec: Box<dyn VoiceCodec>,
 }
 
 impl VoiceDecompressor {
@@ -321,10 +574,23 @@ impl VoiceDecompressor {
             output_sample_rate: sample_rate,
             jitter_buffer: VecDeque::new(),
             max_jitter_buffer: 20,
-            expected_sequence: None,
+            codec: Box::new(PcmPassthrough),
         }
     }
 
+    /// Replace the codec packets are decoded with; raw PCM by default.
+    ///
+    /// Must match the codec the speaker's recorder encodes with.
+    pub fn set_codec(&mut self, codec: Box<dyn VoiceCodec>) {
+        self.codec = codec;
+    }
+
+    /// Decode the next packet in the jitter buffer to PCM.
+    pub fn decode_next(&mut self) -> Option<Vec<i16>> {
+        let packet = self.get_next_packet()?;
+        Some(self.codec.decode(&packet.data))
+    }
+
     /// Decompress voice data.
     /// Reference: <https://partner.steamgames.com/doc/api/ISteamUser#DecompressVoice>
     pub fn decompress(&self, compressed: &[u8], output: &mut [u8]) -> (VoiceResult, usize) {
@@ -387,6 +653,84 @@ impl Default for VoiceDecompressor {
     }
 }
 
+/// Server-side voice routing.
+///
+/// Honors the same per-player mutes and server mutes as text chat, and can
+/// optionally limit voice to listeners within a radius of the speaker.
+#[derive(Debug, Default)]
+pub struct VoiceManager {
+    /// Maximum speaker-listener distance when proximity voice is enabled.
+    proximity_range: Option<f32>,
+    /// Last known player positions.
+    positions: HashMap<SteamId, Vec3>,
+}
+
+impl VoiceManager {
+    /// Create a voice manager with proximity voice disabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable proximity voice with the given range, or disable it with `None`.
+    pub fn set_proximity_range(&mut self, range: Option<f32>) {
+        self.proximity_range = range;
+    }
+
+    /// Get the proximity range, if enabled.
+    pub fn proximity_range(&self) -> Option<f32> {
+        self.proximity_range
+    }
+
+    /// Update a player's position (typically from their ECS `Position`).
+    pub fn set_position(&mut self, player: SteamId, position: Vec3) {
+        self.positions.insert(player, position);
+    }
+
+    /// Forget a player's position.
+    pub fn remove_player(&mut self, player: SteamId) {
+        self.positions.remove(&player);
+    }
+
+    /// Determine which listeners should receive the speaker's voice.
+    ///
+    /// Listeners who muted the speaker in `chat` are skipped. Under
+    /// proximity voice, listeners out of range or without a known position
+    /// are skipped too.
+    pub fn route(
+        &self,
+        speaker: SteamId,
+        listeners: &[SteamId],
+        chat: &ChatManager,
+    ) -> Result<Vec<SteamId>, VoiceResult> {
+        if chat
+            .get_player(speaker)
+            .is_some_and(|s| s.is_server_muted())
+        {
+            return Err(VoiceResult::Restricted);
+        }
+
+        let speaker_pos = self.positions.get(&speaker);
+        let in_range = |listener: &SteamId| match self.proximity_range {
+            None => true,
+            Some(range) => match (speaker_pos, self.positions.get(listener)) {
+                (Some(a), Some(b)) => a.distance_sq(*b) <= range * range,
+                _ => false,
+            },
+        };
+
+        Ok(listeners
+            .iter()
+            .filter(|&&listener| listener != speaker)
+            .filter(|&&listener| {
+                chat.get_player(listener)
+                    .is_none_or(|state| state.can_receive_from(speaker))
+            })
+            .filter(|listener| in_range(listener))
+            .copied()
+            .collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -551,6 +895,53 @@ mod tests {
         assert!(!recorder.is_vad_enabled());
     }
 
+    #[test]
+    fn vox_009_is_active_uses_rms() {
+        assert!(!is_active(&[], 1));
+        assert!(is_active(&[2000, -2000, 2000, -2000], 1500));
+        // A lone click has a high peak but low RMS.
+        let mut click = [0i16; 4000];
+        click[0] = i16::MAX;
+        assert!(!is_active(&click, 1000));
+    }
+
+    #[test]
+    fn vox_009_gate_holds_speech_tail() {
+        let loud = [3000i16; 160];
+        let murmur = [700i16; 160];
+        let quiet = [10i16; 160];
+        let mut gate = VoiceGate::new(1000, 500, 3);
+
+        assert!(!gate.process(&quiet));
+        assert!(gate.process(&loud));
+        // Between thresholds keeps the gate open without using the hold.
+        assert!(gate.process(&murmur));
+        for _ in 0..3 {
+            assert!(gate.process(&quiet));
+        }
+        assert!(!gate.process(&quiet));
+        assert!(!gate.is_open());
+
+        // Murmur alone doesn't reopen it.
+        assert!(!gate.process(&murmur));
+    }
+
+    #[test]
+    fn vox_009_recorder_skips_silent_frames() {
+        let mut recorder = VoiceRecorder::new();
+        recorder.set_voice_gate(VoiceGate::new(1000, 500, 1));
+        recorder.start_recording();
+
+        assert!(!recorder.add_pcm_frame(&[0; 4]));
+        assert!(recorder.add_pcm_frame(&[2000; 4]));
+        assert!(recorder.add_pcm_frame(&[0; 4]));
+        assert!(!recorder.add_pcm_frame(&[0; 4]));
+        assert_eq!(recorder.get_available_voice(), (VoiceResult::Ok, 16));
+
+        recorder.set_vad_enabled(false);
+        assert!(recorder.add_pcm_frame(&[0; 4]));
+    }
+
     // =============================================================================
     // VOX-010: Mute Self
     // =============================================================================
@@ -580,6 +971,65 @@ mod tests {
         assert_eq!(recorder.state(), VoiceRecordingState::Paused);
     }
 
+    // =============================================================================
+    // VOX-011: Voice Routing
+    // =============================================================================
+
+    fn players() -> (SteamId, SteamId, SteamId, ChatManager) {
+        let speaker = SteamId::from_account_id(1);
+        let near = SteamId::from_account_id(2);
+        let far = SteamId::from_account_id(3);
+        let mut chat = ChatManager::new(10);
+        for id in [speaker, near, far] {
+            chat.add_player(id);
+        }
+        (speaker, near, far, chat)
+    }
+
+    #[test]
+    fn vox_011_muted_listener_excluded() {
+        let (speaker, near, far, mut chat) = players();
+        chat.get_player_mut(far).unwrap().mute_player(speaker);
+        let voice = VoiceManager::new();
+
+        let recipients = voice.route(speaker, &[speaker, near, far], &chat).unwrap();
+        assert_eq!(recipients, vec![near]);
+    }
+
+    #[test]
+    fn vox_011_proximity_excludes_far_listener() {
+        let (speaker, near, far, chat) = players();
+        let mut voice = VoiceManager::new();
+        voice.set_position(speaker, Vec3::ZERO);
+        voice.set_position(near, Vec3::new(300.0, 400.0, 0.0));
+        voice.set_position(far, Vec3::new(2000.0, 0.0, 0.0));
+
+        let listeners = [near, far];
+        assert_eq!(
+            voice.route(speaker, &listeners, &chat).unwrap(),
+            vec![near, far]
+        );
+
+        voice.set_proximity_range(Some(500.0));
+        assert_eq!(voice.route(speaker, &listeners, &chat).unwrap(), vec![near]);
+
+        // Listeners without a known position can't be in range.
+        voice.remove_player(near);
+        assert!(voice.route(speaker, &listeners, &chat).unwrap().is_empty());
+    }
+
+    #[test]
+    fn vox_011_server_muted_speaker_restricted() {
+        let (speaker, near, _, mut chat) = players();
+        chat.admin_mute(speaker, None);
+
+        let voice = VoiceManager::new();
+        assert_eq!(
+            voice.route(speaker, &[near], &chat),
+            Err(VoiceResult::Restricted)
+        );
+    }
+
     // =============================================================================
     // Additional Tests
     // =============================================================================
@@ -646,4 +1096,124 @@ mod tests {
         let (result, _) = recorder.get_available_voice();
         assert_eq!(result, VoiceResult::NoData);
     }
+
+    // =============================================================================
+    // VOX-CODEC: Voice codecs
+    // =============================================================================
+
+    /// 20 ms of a 440 Hz tone at 16 kHz.
+    fn tone(amplitude: f32) -> Vec<i16> {
+        (0..320)
+            .map(|i| {
+                let t = i as f32 / 16000.0;
+                (amplitude * (2.0 * std::f32::consts::PI * 440.0 * t).sin()) as i16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn vox_codec_pcm_passthrough_roundtrip() {
+        let mut codec = PcmPassthrough;
+        let pcm = vec![0, 1, -1, i16::MAX, i16::MIN, 1234, -4321];
+        let data = codec.encode(&pcm);
+        assert_eq!(data.len(), pcm.len() * 2);
+        assert_eq!(&data[6..8], &i16::MAX.to_le_bytes());
+        assert_eq!(codec.decode(&data), pcm);
+    }
+
+    #[test]
+    fn vox_codec_adpcm_roundtrip_is_close() {
+        let mut encoder = AdpcmCodec::new();
+        let mut decoder = AdpcmCodec::new();
+        let pcm = tone(8000.0);
+
+        let data = encoder.encode(&pcm);
+        assert_eq!(data.len(), ADPCM_HEADER_LEN + pcm.len() / 2);
+
+        let decoded = decoder.decode(&data);
+        assert_eq!(decoded.len(), pcm.len());
+        let error: Vec<i16> = pcm
+            .iter()
+            .zip(&decoded)
+            .map(|(&a, &b)| a.saturating_sub(b))
+            .collect();
+        // The quantizer needs a few samples to adapt from its smallest step.
+        assert!(
+            rms(&error[32..]) < 0.05 * rms(&pcm),
+            "error rms {}",
+            rms(&error[32..])
+        );
+    }
+
+    #[test]
+    fn vox_codec_adpcm_frames_decode_independently() {
+        let mut encoder = AdpcmCodec::new();
+        let first = encoder.encode(&tone(8000.0));
+        let second = encoder.encode(&tone(8000.0)[..101]);
+
+        // Decoding only the second frame, as if the first was lost, matches
+        // decoding both in order.
+        let mut in_order = AdpcmCodec::new();
+        in_order.decode(&first);
+        let expected = in_order.decode(&second);
+        assert_eq!(expected.len(), 101);
+        assert_eq!(AdpcmCodec::new().decode(&second), expected);
+
+        // Truncated data yields what it can.
+        assert_eq!(
+            AdpcmCodec::new()
+                .decode(&second[..ADPCM_HEADER_LEN + 3])
+                .len(),
+            6
+        );
+        assert!(AdpcmCodec::new().decode(&second[..2]).is_empty());
+    }
+
+    #[test]
+    fn vox_codec_recorder_uses_codec() {
+        let mut recorder = VoiceRecorder::new();
+        recorder.set_vad_enabled(false);
+        recorder.set_codec(Box::new(AdpcmCodec::new()));
+        recorder.start_recording();
+
+        let pcm = tone(8000.0);
+        assert!(recorder.add_pcm_frame(&pcm));
+        let (result, data) = recorder.get_voice(4096);
+        assert_eq!(result, VoiceResult::Ok);
+        assert_eq!(data.len(), ADPCM_HEADER_LEN + pcm.len() / 2);
+
+        let packet = recorder.create_packet(test_steam_id(), data);
+        assert_eq!(AdpcmCodec::new().decode(&packet.data).len(), pcm.len());
+    }
+
+    #[test]
+    fn vox_codec_adpcm_splits_long_input_into_frames() {
+        let pcm: Vec<i16> = tone(8000.0)
+            .into_iter()
+            .cycle()
+            .take(usize::from(u16::MAX) * 2 + 7)
+            .collect();
+        let data = AdpcmCodec::new().encode(&pcm);
+        // Two full frames, each with an odd sample count, then 7 samples.
+        let full = ADPCM_HEADER_LEN + usize::from(u16::MAX).div_ceil(2);
+        assert_eq!(data.len(), 2 * full + ADPCM_HEADER_LEN + 4);
+        assert_eq!(AdpcmCodec::new().decode(&data).len(), pcm.len());
+    }
+
+    #[test]
+    fn vox_codec_decompressor_decodes_with_its_codec() {
+        let mut recorder = VoiceRecorder::new();
+        recorder.set_vad_enabled(false);
+        recorder.set_codec(Box::new(AdpcmCodec::new()));
+        recorder.start_recording();
+        let pcm = tone(8000.0);
+        recorder.add_pcm_frame(&pcm);
+        let (_, data) = recorder.get_voice(4096);
+
+        let mut decompressor = VoiceDecompressor::default();
+        decompressor.set_codec(Box::new(AdpcmCodec::new()));
+        decompressor.add_packet(recorder.create_packet(test_steam_id(), data));
+        assert_eq!(decompressor.decode_next().map(|d| d.len()), Some(pcm.len()));
+        assert_eq!(decompressor.decode_next(), None);
+    }
 }
@@ -9,11 +9,14 @@
 //! - Item subscription and download management
 //! - Query and discovery APIs
 //! - Voting and engagement
+//! - DLC-gated items, which only owners of the DLC can subscribe to
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 
+use crate::dlc::{AppId, DlcManager};
+
 /// Workshop item published file ID.
 pub type PublishedFileId = u64;
 
@@ -103,6 +106,23 @@ pub enum WorkshopFileType {
     Merch,
 }
 
+/// Largest preview image Steam accepts, in bytes.
+pub const MAX_PREVIEW_SIZE: u64 = 1024 * 1024;
+
+/// MIME types accepted for preview images.
+pub const PREVIEW_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/jpg", "image/gif"];
+
+/// A validated preview image.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreviewImage {
+    /// Local path or URL of the image.
+    pub source: String,
+    /// Image size in bytes.
+    pub size_bytes: u64,
+    /// MIME type, lowercased.
+    pub mime: String,
+}
+
 /// Workshop item details.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkshopItem {
@@ -120,6 +140,9 @@ pub struct WorkshopItem {
     pub file_size: u64,
     /// Preview image URL.
     pub preview_url: String,
+    /// Preview image set through `set_item_preview`.
+    #[serde(default)]
+    pub preview: Option<PreviewImage>,
     /// Tags.
     pub tags: Vec<String>,
     /// Vote score (up - down).
@@ -138,6 +161,9 @@ pub struct WorkshopItem {
     pub visibility: ItemVisibility,
     /// Content hash for verification.
     pub content_hash: String,
+    /// DLC that must be owned to subscribe, if any.
+    #[serde(default)]
+    pub required_dlc: Option<AppId>,
 }
 
 impl WorkshopItem {
@@ -151,6 +177,7 @@ impl WorkshopItem {
             app_id,
             file_size: 0,
             preview_url: String::new(),
+            preview: None,
             tags: Vec::new(),
             vote_score: 0.0,
             votes_up: 0,
@@ -160,10 +187,72 @@ impl WorkshopItem {
             updated: 0,
             visibility: ItemVisibility::Public,
             content_hash: String::new(),
+            required_dlc: None,
         }
     }
 }
 
+/// Sort order for [`WorkshopQuery`] results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WorkshopSortMode {
+    /// Highest subscription count first.
+    #[default]
+    MostSubscribed,
+    /// Most recently created first.
+    Newest,
+    /// Highest vote score first.
+    VoteScore,
+}
+
+/// Item discovery query.
+///
+/// An item matches when it carries every required tag and, if set, its title
+/// contains `search_text` (case-insensitive).
+#[derive(Debug, Clone, Default)]
+pub struct WorkshopQuery {
+    /// Tags an item must all have.
+    pub required_tags: Vec<String>,
+    /// Case-insensitive substring to match against the title.
+    pub search_text: Option<String>,
+    /// Result ordering.
+    pub sort: WorkshopSortMode,
+}
+
+impl WorkshopQuery {
+    /// Create an unfiltered query.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require a tag.
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.required_tags.push(tag.to_string());
+        self
+    }
+
+    /// Match titles containing `text`.
+    pub fn with_search_text(mut self, text: &str) -> Self {
+        self.search_text = Some(text.to_string());
+        self
+    }
+
+    /// Set the sort order.
+    pub fn sort_by(mut self, sort: WorkshopSortMode) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Check whether an item matches the tag and text filters.
+    pub fn matches(&self, item: &WorkshopItem) -> bool {
+        let has_tags = self.required_tags.iter().all(|tag| item.tags.contains(tag));
+        let has_text = match &self.search_text {
+            Some(text) => item.title.to_lowercase().contains(&text.to_lowercase()),
+            None => true,
+        };
+        has_tags && has_text
+    }
+}
+
 /// Workshop item installation info.
 #[derive(Debug, Clone)]
 pub struct InstallInfo {
@@ -217,7 +306,10 @@ pub enum WorkshopResult {
     Banned,
     NotLoggedIn,
     InsufficientPrivilege,
-    LimitExceeded,
+    /// Subscription cap reached.
+    LimitExceeded {
+        max: usize,
+    },
 }
 
 /// Mock Workshop manager for testing.
@@ -246,6 +338,10 @@ pub struct WorkshopManager {
     max_subscriptions: usize,
     /// Item dependencies.
     dependencies: HashMap<PublishedFileId, Vec<PublishedFileId>>,
+    /// Whether subscribing also subscribes to an item's dependencies.
+    auto_subscribe_dependencies: bool,
+    /// The user's DLC entitlements, checked for DLC-gated items.
+    dlc: Option<DlcManager>,
 }
 
 impl WorkshopManager {
@@ -263,6 +359,8 @@ impl WorkshopManager {
             next_file_id: 1000,
             max_subscriptions: 1000,
             dependencies: HashMap::new(),
+            auto_subscribe_dependencies: false,
+            dlc: None,
         }
     }
 
@@ -275,13 +373,116 @@ impl WorkshopManager {
         let file_id = self.next_file_id;
         self.next_file_id += 1;
 
-        let item = WorkshopItem::new(file_id, title, self.app_id);
+        let mut item = WorkshopItem::new(file_id, title, self.app_id);
+        item.creator_id = self.local_user;
+        item.created = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
         self.items.insert(file_id, item);
         self.states.insert(file_id, ItemState::default());
 
         Ok(file_id)
     }
 
+    /// Set an item's visibility.
+    pub fn set_item_visibility(
+        &mut self,
+        file_id: PublishedFileId,
+        visibility: ItemVisibility,
+    ) -> Result<(), WorkshopResult> {
+        let item = self
+            .items
+            .get_mut(&file_id)
+            .ok_or(WorkshopResult::FileNotFound)?;
+        if item.creator_id != self.local_user {
+            return Err(WorkshopResult::AccessDenied);
+        }
+        item.visibility = visibility;
+        Ok(())
+    }
+
+    /// Require owning `dlc` to subscribe to an item, or lift the
+    /// requirement with `None`.
+    pub fn set_item_required_dlc(
+        &mut self,
+        file_id: PublishedFileId,
+        dlc: Option<AppId>,
+    ) -> Result<(), WorkshopResult> {
+        let item = self
+            .items
+            .get_mut(&file_id)
+            .ok_or(WorkshopResult::FileNotFound)?;
+        if item.creator_id != self.local_user {
+            return Err(WorkshopResult::AccessDenied);
+        }
+        item.required_dlc = dlc;
+        Ok(())
+    }
+
+    /// Set an item's preview image.
+    ///
+    /// The image must be at most [`MAX_PREVIEW_SIZE`] bytes and one of
+    /// [`PREVIEW_MIME_TYPES`]. Also updates `preview_url`.
+    pub fn set_item_preview(
+        &mut self,
+        file_id: PublishedFileId,
+        path_or_url: &str,
+        size_bytes: u64,
+        mime: &str,
+    ) -> Result<(), WorkshopResult> {
+        let item = self
+            .items
+            .get_mut(&file_id)
+            .ok_or(WorkshopResult::FileNotFound)?;
+        if item.creator_id != self.local_user {
+            return Err(WorkshopResult::AccessDenied);
+        }
+        let mime = mime.trim().to_ascii_lowercase();
+        if path_or_url.trim().is_empty()
+            || size_bytes == 0
+            || size_bytes > MAX_PREVIEW_SIZE
+            || !PREVIEW_MIME_TYPES.contains(&mime.as_str())
+        {
+            return Err(WorkshopResult::InvalidParam);
+        }
+        item.preview_url = path_or_url.to_string();
+        item.preview = Some(PreviewImage {
+            source: path_or_url.to_string(),
+            size_bytes,
+            mime,
+        });
+        Ok(())
+    }
+
+    /// Find items matching a query.
+    ///
+    /// Private and unlisted items are only returned to their creator. Ties in
+    /// the sort order are broken by file ID.
+    pub fn query(&self, query: &WorkshopQuery) -> Vec<&WorkshopItem> {
+        let mut results: Vec<&WorkshopItem> = self
+            .items
+            .values()
+            .filter(|item| match item.visibility {
+                ItemVisibility::Public | ItemVisibility::FriendsOnly => true,
+                ItemVisibility::Private | ItemVisibility::Unlisted => {
+                    item.creator_id == self.local_user
+                }
+            })
+            .filter(|item| query.matches(item))
+            .collect();
+
+        results.sort_by(|a, b| {
+            let order = match query.sort {
+                WorkshopSortMode::MostSubscribed => b.subscriptions.cmp(&a.subscriptions),
+                WorkshopSortMode::Newest => b.created.cmp(&a.created),
+                WorkshopSortMode::VoteScore => b.vote_score.total_cmp(&a.vote_score),
+            };
+            order.then(a.file_id.cmp(&b.file_id))
+        });
+        results
+    }
+
     /// Update a workshop item.
     pub fn submit_item_update(
         &mut self,
@@ -312,16 +513,84 @@ impl WorkshopManager {
         Ok(())
     }
 
+    /// Set the maximum number of subscriptions.
+    ///
+    /// Lowering it below the current count keeps existing subscriptions but
+    /// blocks new ones.
+    pub fn set_max_subscriptions(&mut self, max: usize) {
+        self.max_subscriptions = max;
+    }
+
+    /// Subscriptions used and the maximum allowed, as `(used, max)`.
+    pub fn subscription_quota(&self) -> (usize, usize) {
+        (self.subscriptions.len(), self.max_subscriptions)
+    }
+
+    /// Number of subscriptions still available.
+    pub fn remaining_subscriptions(&self) -> usize {
+        self.max_subscriptions
+            .saturating_sub(self.subscriptions.len())
+    }
+
+    /// Enable or disable subscribing to dependencies in
+    /// [`subscribe_item`](Self::subscribe_item).
+    pub fn set_auto_subscribe_dependencies(&mut self, enabled: bool) {
+        self.auto_subscribe_dependencies = enabled;
+    }
+
+    /// Set the user's DLC entitlements. Until this is called, DLC-gated
+    /// items can't be subscribed to.
+    pub fn set_dlc_manager(&mut self, dlc: DlcManager) {
+        self.dlc = Some(dlc);
+    }
+
     /// Subscribe to an item.
+    ///
+    /// With auto-subscribe enabled, its unsubscribed dependencies are
+    /// subscribed first. Nothing is subscribed if the dependency graph has a
+    /// cycle, the whole set would exceed the subscription limit, or any of
+    /// it requires DLC the user doesn't own (`AccessDenied`; see
+    /// [`set_dlc_manager`](Self::set_dlc_manager)).
     pub fn subscribe_item(&mut self, file_id: PublishedFileId) -> Result<(), WorkshopResult> {
         if self.subscriptions.contains(&file_id) {
             return Err(WorkshopResult::AlreadySubscribed);
         }
 
-        if self.subscriptions.len() >= self.max_subscriptions {
-            return Err(WorkshopResult::LimitExceeded);
+        let mut to_subscribe = Vec::new();
+        if self.auto_subscribe_dependencies {
+            to_subscribe = self.resolve_dependencies(file_id)?;
+            to_subscribe.retain(|id| !self.subscriptions.contains(id));
+        }
+        to_subscribe.push(file_id);
+
+        let locked = to_subscribe.iter().any(|id| {
+            self.items
+                .get(id)
+                .and_then(|item| item.required_dlc)
+                .is_some_and(|app_id| {
+                    !self
+                        .dlc
+                        .as_ref()
+                        .is_some_and(|dlc| dlc.is_subscribed_app(app_id))
+                })
+        });
+        if locked {
+            return Err(WorkshopResult::AccessDenied);
+        }
+
+        if self.subscriptions.len() + to_subscribe.len() > self.max_subscriptions {
+            return Err(WorkshopResult::LimitExceeded {
+                max: self.max_subscriptions,
+            });
+        }
+
+        for id in to_subscribe {
+            self.add_subscription(id);
         }
+        Ok(())
+    }
 
+    fn add_subscription(&mut self, file_id: PublishedFileId) {
         // Create a mock item if it doesn't exist (simulating remote item).
         if !self.items.contains_key(&file_id) {
             let item = WorkshopItem::new(file_id, "Remote Item", self.app_id);
@@ -334,8 +603,6 @@ impl WorkshopManager {
         let state = self.states.entry(file_id).or_default();
         state.insert(ItemState::SUBSCRIBED);
         state.insert(ItemState::DOWNLOAD_PENDING);
-
-        Ok(())
     }
 
     /// Unsubscribe from an item.
@@ -421,6 +688,34 @@ impl WorkshopManager {
         });
     }
 
+    /// Advance every in-progress download by `bytes_per_tick`.
+    ///
+    /// Downloads that reach their total are completed as if by
+    /// [`complete_download`](Self::complete_download).
+    pub fn tick_downloads(&mut self, bytes_per_tick: u64) {
+        let mut finished = Vec::new();
+        for (&file_id, progress) in &mut self.downloads {
+            let downloading = self
+                .states
+                .get(&file_id)
+                .is_some_and(|state| state.contains(ItemState::DOWNLOADING));
+            if !downloading {
+                continue;
+            }
+            progress.bytes_downloaded = progress
+                .bytes_downloaded
+                .saturating_add(bytes_per_tick)
+                .min(progress.bytes_total);
+            if progress.bytes_downloaded == progress.bytes_total {
+                finished.push(file_id);
+            }
+        }
+
+        for file_id in finished {
+            self.complete_download(file_id);
+        }
+    }
+
     /// Get download progress.
     pub fn get_item_download_info(&self, file_id: PublishedFileId) -> Option<&DownloadProgress> {
         self.downloads.get(&file_id)
@@ -437,22 +732,44 @@ impl WorkshopManager {
     }
 
     /// Set user vote on item.
-    pub fn set_user_item_vote(&mut self, file_id: PublishedFileId, vote_up: bool) -> Result<(), WorkshopResult> {
+    ///
+    /// Changing an existing vote moves it between the up and down tallies;
+    /// repeating the same vote changes nothing.
+    pub fn set_user_item_vote(
+        &mut self,
+        file_id: PublishedFileId,
+        vote_up: bool,
+    ) -> Result<(), WorkshopResult> {
         if !self.items.contains_key(&file_id) {
             return Err(WorkshopResult::FileNotFound);
         }
 
-        let vote = if vote_up { UserVote::VotedUp } else { UserVote::VotedDown };
-        self.votes.insert(file_id, vote);
+        let vote = if vote_up {
+            UserVote::VotedUp
+        } else {
+            UserVote::VotedDown
+        };
+        let previous = self
+            .votes
+            .insert(file_id, vote)
+            .unwrap_or(UserVote::NotVoted);
+        if previous == vote {
+            return Ok(());
+        }
 
         // Update vote counts.
         if let Some(item) = self.items.get_mut(&file_id) {
+            match previous {
+                UserVote::VotedUp => item.votes_up = item.votes_up.saturating_sub(1),
+                UserVote::VotedDown => item.votes_down = item.votes_down.saturating_sub(1),
+                UserVote::NotVoted => {}
+            }
             if vote_up {
                 item.votes_up += 1;
             } else {
                 item.votes_down += 1;
             }
-            item.vote_score = (item.votes_up as f32) / (item.votes_up + item.votes_down).max(1) as f32;
+            item.vote_score = item.votes_up as f32 - item.votes_down as f32;
         }
 
         Ok(())
@@ -476,6 +793,49 @@ impl WorkshopManager {
         self.dependencies.get(&file_id)
     }
 
+    /// Resolve an item's dependencies transitively.
+    ///
+    /// Returns them in install order: every item comes after its own
+    /// dependencies. The item itself is not included. Fails with
+    /// [`WorkshopResult::Fail`] if the dependency graph contains a cycle.
+    pub fn resolve_dependencies(
+        &self,
+        file_id: PublishedFileId,
+    ) -> Result<Vec<PublishedFileId>, WorkshopResult> {
+        fn visit(
+            deps: &HashMap<PublishedFileId, Vec<PublishedFileId>>,
+            id: PublishedFileId,
+            in_progress: &mut HashSet<PublishedFileId>,
+            done: &mut HashSet<PublishedFileId>,
+            order: &mut Vec<PublishedFileId>,
+        ) -> Result<(), WorkshopResult> {
+            if done.contains(&id) {
+                return Ok(());
+            }
+            if !in_progress.insert(id) {
+                return Err(WorkshopResult::Fail);
+            }
+            for &dep in deps.get(&id).into_iter().flatten() {
+                visit(deps, dep, in_progress, done, order)?;
+            }
+            in_progress.remove(&id);
+            done.insert(id);
+            order.push(id);
+            Ok(())
+        }
+
+        let mut order = Vec::new();
+        visit(
+            &self.dependencies,
+            file_id,
+            &mut HashSet::new(),
+            &mut HashSet::new(),
+            &mut order,
+        )?;
+        order.pop();
+        Ok(order)
+    }
+
     /// Mark item as needing update.
     pub fn mark_needs_update(&mut self, file_id: PublishedFileId) {
         if let Some(state) = self.states.get_mut(&file_id) {
@@ -492,12 +852,45 @@ impl WorkshopManager {
         }
     }
 
+    /// Verify content integrity by hashing raw item bytes.
+    pub fn verify_content_bytes(&self, file_id: PublishedFileId, data: &[u8]) -> bool {
+        self.verify_content(file_id, &content_hash(data))
+    }
+
     /// Set content hash for an item.
     pub fn set_content_hash(&mut self, file_id: PublishedFileId, hash: &str) {
         if let Some(item) = self.items.get_mut(&file_id) {
             item.content_hash = hash.to_string();
         }
     }
+
+    /// Hash item bytes and store the result as the item's content hash.
+    pub fn compute_and_store_hash(
+        &mut self,
+        file_id: PublishedFileId,
+        data: &[u8],
+    ) -> Result<(), WorkshopResult> {
+        let item = self
+            .items
+            .get_mut(&file_id)
+            .ok_or(WorkshopResult::FileNotFound)?;
+        item.content_hash = content_hash(data);
+        Ok(())
+    }
+}
+
+/// FNV-1a 64-bit hash of `data` as 16 lowercase hex digits.
+///
+/// Deterministic across platforms, but not collision resistant against a
+/// deliberate attacker.
+pub fn content_hash(data: &[u8]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let hash = data.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    });
+    format!("{:016x}", hash)
 }
 
 #[cfg(test)]
@@ -578,11 +971,92 @@ mod tests {
         workshop.create_item("Map 2").unwrap();
         workshop.create_item("Map 3").unwrap();
 
-        // Query by iterating items (simplified).
-        let count: usize = (1000..1003)
-            .filter(|&id| workshop.get_item_details(id).is_some())
-            .count();
-        assert_eq!(count, 3);
+        let results = workshop.query(&WorkshopQuery::new());
+        assert_eq!(results.len(), 3);
+
+        let results = workshop.query(&WorkshopQuery::new().with_search_text("map 2"));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Map 2");
+    }
+
+    #[test]
+    fn wks_003_query_by_tags() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+
+        let dust = workshop.create_item("Dust").unwrap();
+        let office = workshop.create_item("Office").unwrap();
+        let aim = workshop.create_item("Aim Arena").unwrap();
+        let tags = |t: &[&str]| Some(t.iter().map(|s| s.to_string()).collect());
+        workshop
+            .submit_item_update(dust, None, None, tags(&["map", "defuse"]))
+            .unwrap();
+        workshop
+            .submit_item_update(office, None, None, tags(&["map", "hostage"]))
+            .unwrap();
+        workshop
+            .submit_item_update(aim, None, None, tags(&["training"]))
+            .unwrap();
+
+        let ids = |q: &WorkshopQuery| -> Vec<PublishedFileId> {
+            workshop.query(q).iter().map(|i| i.file_id).collect()
+        };
+        assert_eq!(
+            ids(&WorkshopQuery::new().with_tag("map")),
+            vec![dust, office]
+        );
+        assert_eq!(
+            ids(&WorkshopQuery::new().with_tag("map").with_tag("hostage")),
+            vec![office]
+        );
+        assert!(ids(&WorkshopQuery::new().with_tag("weapon")).is_empty());
+    }
+
+    #[test]
+    fn wks_003_query_sort_order() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+
+        let a = workshop.create_item("A").unwrap();
+        let b = workshop.create_item("B").unwrap();
+        let c = workshop.create_item("C").unwrap();
+        for (id, subs, created, score) in [(a, 10, 300, 1.0), (b, 50, 100, 5.0), (c, 20, 200, -2.0)]
+        {
+            let item = workshop.items.get_mut(&id).unwrap();
+            item.subscriptions = subs;
+            item.created = created;
+            item.vote_score = score;
+        }
+
+        let ids = |sort: WorkshopSortMode| -> Vec<PublishedFileId> {
+            let query = WorkshopQuery::new().sort_by(sort);
+            workshop.query(&query).iter().map(|i| i.file_id).collect()
+        };
+        assert_eq!(ids(WorkshopSortMode::MostSubscribed), vec![b, c, a]);
+        assert_eq!(ids(WorkshopSortMode::Newest), vec![a, c, b]);
+        assert_eq!(ids(WorkshopSortMode::VoteScore), vec![b, a, c]);
+    }
+
+    #[test]
+    fn wks_003_query_respects_visibility() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+
+        let mine = workshop.create_item("My Private Map").unwrap();
+        workshop
+            .set_item_visibility(mine, ItemVisibility::Private)
+            .unwrap();
+
+        // An item published by someone else.
+        let mut theirs = WorkshopItem::new(5000, "Their Private Map", 730);
+        theirs.creator_id = 67890;
+        theirs.visibility = ItemVisibility::Private;
+        workshop.items.insert(theirs.file_id, theirs);
+        assert_eq!(
+            workshop.set_item_visibility(5000, ItemVisibility::Public),
+            Err(WorkshopResult::AccessDenied)
+        );
+
+        let results = workshop.query(&WorkshopQuery::new().with_search_text("private"));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_id, mine);
     }
 
     // =============================================================================
@@ -616,6 +1090,34 @@ mod tests {
         assert_eq!(result, Err(WorkshopResult::AlreadySubscribed));
     }
 
+    #[test]
+    fn wks_004_subscription_quota() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+        workshop.set_max_subscriptions(3);
+        assert_eq!(workshop.subscription_quota(), (0, 3));
+
+        for id in 1..=3 {
+            workshop.subscribe_item(id).unwrap();
+        }
+        assert_eq!(workshop.subscription_quota(), (3, 3));
+        assert_eq!(workshop.remaining_subscriptions(), 0);
+
+        assert_eq!(
+            workshop.subscribe_item(4),
+            Err(WorkshopResult::LimitExceeded { max: 3 })
+        );
+        assert_eq!(workshop.subscription_quota(), (3, 3));
+
+        workshop.unsubscribe_item(2).unwrap();
+        assert_eq!(workshop.remaining_subscriptions(), 1);
+        workshop.subscribe_item(4).unwrap();
+
+        // Lowering the cap below usage leaves nothing remaining.
+        workshop.set_max_subscriptions(1);
+        assert_eq!(workshop.subscription_quota(), (3, 1));
+        assert_eq!(workshop.remaining_subscriptions(), 0);
+    }
+
     // =============================================================================
     // WKS-005: Unsubscribe Item
     // Reference: https://partner.steamgames.com/doc/api/ISteamUGC#UnsubscribeItem
@@ -804,6 +1306,39 @@ mod tests {
         assert_eq!(vote, UserVote::VotedDown);
     }
 
+    #[test]
+    fn wks_010_switch_vote_moves_tally() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+
+        let file_id = workshop.create_item("Test Item").unwrap();
+        workshop.items.get_mut(&file_id).unwrap().votes_down = 2;
+
+        workshop.set_user_item_vote(file_id, true).unwrap();
+        let item = workshop.get_item_details(file_id).unwrap();
+        assert_eq!((item.votes_up, item.votes_down), (1, 2));
+        assert_eq!(item.vote_score, -1.0);
+
+        workshop.set_user_item_vote(file_id, false).unwrap();
+        assert_eq!(workshop.get_user_item_vote(file_id), UserVote::VotedDown);
+        let item = workshop.get_item_details(file_id).unwrap();
+        assert_eq!((item.votes_up, item.votes_down), (0, 3));
+        assert_eq!(item.vote_score, -3.0);
+    }
+
+    #[test]
+    fn wks_010_repeated_vote_is_noop() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+
+        let file_id = workshop.create_item("Test Item").unwrap();
+
+        workshop.set_user_item_vote(file_id, true).unwrap();
+        workshop.set_user_item_vote(file_id, true).unwrap();
+
+        let item = workshop.get_item_details(file_id).unwrap();
+        assert_eq!((item.votes_up, item.votes_down), (1, 0));
+        assert_eq!(item.vote_score, 1.0);
+    }
+
     // =============================================================================
     // WKS-VER-001: Content Hash Verification
     // =============================================================================
@@ -819,6 +1354,33 @@ mod tests {
         assert!(!workshop.verify_content(file_id, "wronghash"));
     }
 
+    #[test]
+    fn wks_ver_001_computed_content_hash() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+
+        let file_id = workshop.create_item("Test Item").unwrap();
+        let data = b"VBSP map lump data".to_vec();
+        workshop.compute_and_store_hash(file_id, &data).unwrap();
+
+        assert!(workshop.verify_content_bytes(file_id, &data));
+
+        let mut tampered = data.clone();
+        tampered[5] ^= 0x01;
+        assert!(!workshop.verify_content_bytes(file_id, &tampered));
+
+        assert_eq!(
+            workshop.compute_and_store_hash(99999, &data),
+            Err(WorkshopResult::FileNotFound)
+        );
+    }
+
+    #[test]
+    fn content_hash_known_values() {
+        // Reference FNV-1a 64 test vectors.
+        assert_eq!(content_hash(b""), "cbf29ce484222325");
+        assert_eq!(content_hash(b"a"), "af63dc4c8601ec8c");
+    }
+
     // =============================================================================
     // WKS-VER-002: File Size Match
     // =============================================================================
@@ -861,6 +1423,66 @@ mod tests {
         assert!(deps.unwrap().contains(&base_id));
     }
 
+    #[test]
+    fn wks_ver_004_resolve_dependency_chain() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+
+        let core = workshop.create_item("Core").unwrap();
+        let lib = workshop.create_item("Library").unwrap();
+        let textures = workshop.create_item("Textures").unwrap();
+        let addon = workshop.create_item("Addon").unwrap();
+
+        // addon -> lib -> core, addon -> textures -> core
+        workshop.add_dependency(addon, lib);
+        workshop.add_dependency(addon, textures);
+        workshop.add_dependency(lib, core);
+        workshop.add_dependency(textures, core);
+
+        let order = workshop.resolve_dependencies(addon).unwrap();
+        assert_eq!(order, vec![core, lib, textures]);
+        assert_eq!(workshop.resolve_dependencies(core), Ok(vec![]));
+    }
+
+    #[test]
+    fn wks_ver_004_dependency_cycle_fails() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+
+        let a = workshop.create_item("A").unwrap();
+        let b = workshop.create_item("B").unwrap();
+        let c = workshop.create_item("C").unwrap();
+        workshop.add_dependency(a, b);
+        workshop.add_dependency(b, c);
+        workshop.add_dependency(c, a);
+
+        assert_eq!(workshop.resolve_dependencies(a), Err(WorkshopResult::Fail));
+
+        workshop.set_auto_subscribe_dependencies(true);
+        assert_eq!(workshop.subscribe_item(a), Err(WorkshopResult::Fail));
+        assert!(workshop.get_subscribed_items().is_empty());
+    }
+
+    #[test]
+    fn wks_ver_004_auto_subscribe_dependencies() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+
+        let core = workshop.create_item("Core").unwrap();
+        let lib = workshop.create_item("Library").unwrap();
+        let addon = workshop.create_item("Addon").unwrap();
+        workshop.add_dependency(addon, lib);
+        workshop.add_dependency(lib, core);
+
+        // Off by default.
+        workshop.subscribe_item(lib).unwrap();
+        assert_eq!(workshop.get_subscribed_items(), &[lib]);
+
+        workshop.set_auto_subscribe_dependencies(true);
+        workshop.subscribe_item(addon).unwrap();
+        assert_eq!(workshop.get_subscribed_items(), &[lib, core, addon]);
+        assert!(workshop
+            .get_item_state(core)
+            .contains(ItemState::SUBSCRIBED));
+    }
+
     // =============================================================================
     // WKS-VER-005: Version Matching
     // =============================================================================
@@ -906,6 +1528,57 @@ mod tests {
         assert_eq!(progress.unwrap().bytes_downloaded, 0);
     }
 
+    #[test]
+    fn tick_downloads_progresses_to_install() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+
+        let file_id = workshop.create_item("Test Item").unwrap();
+        workshop.items.get_mut(&file_id).unwrap().file_size = 4000;
+        workshop.subscribe_item(file_id).unwrap();
+        workshop.download_item(file_id, false).unwrap();
+
+        workshop.tick_downloads(1000);
+        let progress = workshop.get_item_download_info(file_id).unwrap();
+        assert_eq!(progress.bytes_downloaded, 1000);
+        assert_eq!(progress.percent(), 25.0);
+        let state = workshop.get_item_state(file_id);
+        assert!(state.contains(ItemState::DOWNLOADING));
+        assert!(!state.contains(ItemState::INSTALLED));
+        assert!(workshop.get_item_install_info(file_id).is_none());
+
+        // Overshooting the total clamps to it and completes the download.
+        workshop.tick_downloads(5000);
+        let progress = workshop.get_item_download_info(file_id).unwrap();
+        assert_eq!(progress.bytes_downloaded, 4000);
+        assert_eq!(progress.percent(), 100.0);
+        let state = workshop.get_item_state(file_id);
+        assert!(!state.contains(ItemState::DOWNLOADING));
+        assert!(state.contains(ItemState::INSTALLED));
+        assert_eq!(
+            workshop
+                .get_item_install_info(file_id)
+                .unwrap()
+                .size_on_disk,
+            4000
+        );
+    }
+
+    #[test]
+    fn tick_downloads_ignores_finished_items() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+
+        let file_id = workshop.create_item("Test Item").unwrap();
+        workshop.subscribe_item(file_id).unwrap();
+        workshop.download_item(file_id, true).unwrap();
+        let installed_at = workshop.get_item_install_info(file_id).unwrap().timestamp;
+
+        workshop.tick_downloads(1000);
+        assert_eq!(
+            workshop.get_item_install_info(file_id).unwrap().timestamp,
+            installed_at
+        );
+    }
+
     #[test]
     fn cleanup_on_unsubscribe() {
         let mut workshop = WorkshopManager::new(730, 12345);
@@ -920,4 +1593,167 @@ mod tests {
 
         assert!(workshop.get_item_install_info(file_id).is_none());
     }
+
+    // =============================================================================
+    // WKS-DLC: DLC-gated items
+    // Reference: https://partner.steamgames.com/doc/api/ISteamApps#BIsSubscribedApp
+    // =============================================================================
+
+    const MAP_PACK_DLC: AppId = 1001;
+
+    fn gated_item(workshop: &mut WorkshopManager) -> PublishedFileId {
+        let file_id = workshop.create_item("Map Pack Remix").unwrap();
+        workshop
+            .set_item_required_dlc(file_id, Some(MAP_PACK_DLC))
+            .unwrap();
+        file_id
+    }
+
+    #[test]
+    fn wks_dlc_subscribe_with_dlc_owned() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+        let file_id = gated_item(&mut workshop);
+        let mut dlc = DlcManager::new(730);
+        dlc.add_dlc(MAP_PACK_DLC, "Map Pack", true);
+        workshop.set_dlc_manager(dlc);
+
+        assert_eq!(workshop.subscribe_item(file_id), Ok(()));
+        assert_eq!(workshop.get_subscribed_items(), &[file_id]);
+        assert_eq!(
+            workshop.get_item_details(file_id).unwrap().required_dlc,
+            Some(MAP_PACK_DLC)
+        );
+    }
+
+    #[test]
+    fn wks_dlc_subscribe_without_dlc_denied() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+        let file_id = gated_item(&mut workshop);
+        let mut dlc = DlcManager::new(730);
+        dlc.add_dlc(MAP_PACK_DLC, "Map Pack", false);
+        workshop.set_dlc_manager(dlc);
+
+        assert_eq!(
+            workshop.subscribe_item(file_id),
+            Err(WorkshopResult::AccessDenied)
+        );
+        assert!(workshop.get_subscribed_items().is_empty());
+
+        // Ungated items are unaffected.
+        let free = workshop.create_item("Free Map").unwrap();
+        assert_eq!(workshop.subscribe_item(free), Ok(()));
+    }
+
+    #[test]
+    fn wks_dlc_gated_dependency_blocks_subscription() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+        workshop.set_auto_subscribe_dependencies(true);
+        let textures = gated_item(&mut workshop);
+        let map = workshop.create_item("Map Using Textures").unwrap();
+        workshop.add_dependency(map, textures);
+
+        workshop.set_dlc_manager(DlcManager::new(730));
+
+        assert_eq!(
+            workshop.subscribe_item(map),
+            Err(WorkshopResult::AccessDenied)
+        );
+        assert!(workshop.get_subscribed_items().is_empty());
+    }
+
+    // =============================================================================
+    // WKS-PREVIEW: Preview image validation
+    // Reference: https://partner.steamgames.com/doc/api/ISteamUGC#SetItemPreview
+    // =============================================================================
+
+    #[test]
+    fn wks_preview_valid_png_accepted() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+        let file_id = workshop.create_item("Preview Map").unwrap();
+
+        assert_eq!(
+            workshop.set_item_preview(file_id, "previews/map.png", 512 * 1024, "image/PNG"),
+            Ok(())
+        );
+        let item = workshop.get_item_details(file_id).unwrap();
+        assert_eq!(item.preview_url, "previews/map.png");
+        assert_eq!(
+            item.preview,
+            Some(PreviewImage {
+                source: "previews/map.png".to_string(),
+                size_bytes: 512 * 1024,
+                mime: "image/png".to_string(),
+            })
+        );
+
+        // Exactly at the limit is fine.
+        assert_eq!(
+            workshop.set_item_preview(
+                file_id,
+                "https://example.com/map.jpg",
+                MAX_PREVIEW_SIZE,
+                "image/jpeg"
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn wks_preview_oversized_rejected() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+        let file_id = workshop.create_item("Preview Map").unwrap();
+        workshop
+            .set_item_preview(file_id, "previews/map.png", 1024, "image/png")
+            .unwrap();
+
+        assert_eq!(
+            workshop.set_item_preview(
+                file_id,
+                "previews/huge.png",
+                MAX_PREVIEW_SIZE + 1,
+                "image/png"
+            ),
+            Err(WorkshopResult::InvalidParam)
+        );
+        // The previous preview is kept.
+        let item = workshop.get_item_details(file_id).unwrap();
+        assert_eq!(item.preview_url, "previews/map.png");
+        assert_eq!(item.preview.as_ref().unwrap().size_bytes, 1024);
+    }
+
+    #[test]
+    fn wks_preview_bad_type_or_owner_rejected() {
+        let mut workshop = WorkshopManager::new(730, 12345);
+        let file_id = workshop.create_item("Preview Map").unwrap();
+
+        for (path, size, mime) in [
+            ("map.bmp", 1024, "image/bmp"),
+            ("map.webm", 1024, "video/webm"),
+            ("", 1024, "image/png"),
+            ("map.png", 0, "image/png"),
+        ] {
+            assert_eq!(
+                workshop.set_item_preview(file_id, path, size, mime),
+                Err(WorkshopResult::InvalidParam),
+                "{path} {size} {mime}"
+            );
+        }
+        assert!(workshop
+            .get_item_details(file_id)
+            .unwrap()
+            .preview
+            .is_none());
+
+        assert_eq!(
+            workshop.set_item_preview(9999, "map.png", 1024, "image/png"),
+            Err(WorkshopResult::FileNotFound)
+        );
+        let mut theirs = WorkshopItem::new(5000, "Their Map", 730);
+        theirs.creator_id = 67890;
+        workshop.items.insert(theirs.file_id, theirs);
+        assert_eq!(
+            workshop.set_item_preview(5000, "map.png", 1024, "image/png"),
+            Err(WorkshopResult::AccessDenied)
+        );
+    }
 }
@@ -76,6 +76,7 @@ fn main() {
     // Save reports
     let html_path = output_dir.join("parity-tests.html");
     let json_path = output_dir.join("parity-tests.json");
+    let junit_path = output_dir.join("parity-tests.xml");
 
     report
         .save_html(&html_path)
@@ -83,10 +84,14 @@ fn main() {
     report
         .save_json(&json_path)
         .expect("Failed to save JSON report");
+    report
+        .save_junit(&junit_path)
+        .expect("Failed to save JUnit report");
 
     println!("\n📄 Reports saved to:");
     println!("   HTML: {}", html_path.display());
     println!("   JSON: {}", json_path.display());
+    println!("   JUnit: {}", junit_path.display());
 
     // Exit with appropriate code
     if stats.failed > 0 {
@@ -757,6 +762,9 @@ fn run_network_tests(mut builder: ReportBuilder) -> ReportBuilder {
             || {
                 let hello = NetMsg::Hello {
                     protocol: PROTOCOL_VERSION,
+                    steam_id: engine_shared::steam_id::SteamId::from_account_id(12345),
+                    owned_dlc: Vec::new(),
+                    player_name: String::new(),
                 };
                 let bytes = encode_to_bytes(&hello).map_err(|e| e.to_string())?;
                 let decoded: NetMsg = decode_from_bytes(&bytes).map_err(|e| e.to_string())?;
@@ -810,6 +818,7 @@ fn run_network_tests(mut builder: ReportBuilder) -> ReportBuilder {
                 let snapshot = NetMsg::Snapshot(Snapshot {
                     tick: 1000,
                     entities: vec![],
+                    ack: None,
                 });
                 let bytes = encode_to_bytes(&snapshot).map_err(|e| e.to_string())?;
                 let decoded: NetMsg = decode_from_bytes(&bytes).map_err(|e| e.to_string())?;
@@ -0,0 +1,144 @@
+//! Per-client snapshot rate capping (`sv_maxrate`) and quantization
+//! (`sv_quantize`).
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use engine_server::server::{bind_ephemeral, GameServer};
+use engine_shared::bsp::BspMap;
+use engine_shared::ecs::EntityId;
+use engine_shared::math::Vec3;
+use engine_shared::net::{
+    decode_from_bytes, ClientId, NetMsg, ReliableConn, Snapshot, PROTOCOL_VERSION,
+};
+use engine_shared::steam_id::SteamId;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::Instant;
+
+/// Waits briefly for a snapshot, returning its datagram size.
+async fn recv_snapshot(udp: &UdpSocket) -> anyhow::Result<Option<usize>> {
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let Ok(recv) =
+            tokio::time::timeout(Duration::from_millis(200), udp.recv_from(&mut buf)).await
+        else {
+            return Ok(None);
+        };
+        let (n, _) = recv?;
+        if let NetMsg::Snapshot(_) = decode_from_bytes(&buf[..n])? {
+            return Ok(Some(n));
+        }
+    }
+}
+
+/// Connects a raw client over TCP and UDP and marks it ready.
+async fn join(
+    server: &mut GameServer,
+    addr: String,
+) -> anyhow::Result<(ClientId, EntityId, ReliableConn, UdpSocket)> {
+    let udp = UdpSocket::bind("127.0.0.1:0").await?;
+    let udp_port = udp.local_addr()?.port();
+    let client = tokio::spawn(async move {
+        let mut conn = ReliableConn::new(TcpStream::connect(addr).await?);
+        conn.send(&NetMsg::Hello {
+            protocol: PROTOCOL_VERSION,
+            steam_id: SteamId::from_account_id(1000),
+            owned_dlc: Vec::new(),
+            player_name: String::new(),
+        })
+        .await?;
+        conn.send(&NetMsg::UdpHello {
+            client_udp_port: udp_port,
+        })
+        .await?;
+        anyhow::Ok(conn)
+    });
+    let client_id = server.accept_one().await?;
+    let conn = client.await??;
+    let player = server.client_ready(client_id)?;
+    Ok((client_id, player, conn, udp))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn snapshots_over_maxrate_wait_for_the_window() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    server.load_bsp(BspMap {
+        name: "de_rate".to_string(),
+        ..Default::default()
+    });
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let clock = Arc::clone(&now);
+    server.set_clock(Arc::new(move || *clock.lock().unwrap()));
+    let (client_id, _, _conn, udp) = join(&mut server, cfg.server_addr.clone()).await?;
+
+    // Unlimited: every snapshot goes out, and is counted.
+    server.exec_console("sv_maxrate 0")?;
+    server.step(1.0 / 64.0).await?;
+    let size = recv_snapshot(&udp)
+        .await?
+        .expect("snapshot while unlimited");
+    assert_eq!(server.client_bytes_per_sec(client_id) as usize, size);
+
+    // Room for about one more snapshot this second.
+    server.exec_console(&format!("sv_maxrate {}", 2 * size + size / 2))?;
+    server.step(1.0 / 64.0).await?;
+    assert!(recv_snapshot(&udp).await?.is_some());
+    server.step(1.0 / 64.0).await?;
+    assert_eq!(recv_snapshot(&udp).await?, None, "over the cap");
+
+    // Once the earlier sends age out, snapshots flow again.
+    *now.lock().unwrap() += Duration::from_secs(1);
+    server.step(1.0 / 64.0).await?;
+    assert!(recv_snapshot(&udp).await?.is_some());
+    Ok(())
+}
+
+/// Waits briefly for a full or quantized snapshot.
+async fn recv_any_snapshot(udp: &UdpSocket) -> anyhow::Result<Option<NetMsg>> {
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let Ok(recv) =
+            tokio::time::timeout(Duration::from_millis(200), udp.recv_from(&mut buf)).await
+        else {
+            return Ok(None);
+        };
+        let (n, _) = recv?;
+        let msg = decode_from_bytes(&buf[..n])?;
+        if matches!(msg, NetMsg::Snapshot(_) | NetMsg::QuantizedSnapshot(_)) {
+            return Ok(Some(msg));
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sv_quantize_switches_snapshot_encoding() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    server.load_bsp(BspMap {
+        name: "de_quant".to_string(),
+        ..Default::default()
+    });
+    let (_, player, _conn, udp) = join(&mut server, cfg.server_addr.clone()).await?;
+    server.exec_console("sv_maxrate 0")?;
+
+    server.step(1.0 / 64.0).await?;
+    let full = match recv_any_snapshot(&udp).await? {
+        Some(NetMsg::Snapshot(snap)) => snap,
+        other => panic!("expected a full snapshot, got {other:?}"),
+    };
+
+    server.exec_console("sv_quantize 1")?;
+    server.step(1.0 / 64.0).await?;
+    let quantized: Snapshot = match recv_any_snapshot(&udp).await? {
+        Some(NetMsg::QuantizedSnapshot(q)) => q.dequantize(),
+        other => panic!("expected a quantized snapshot, got {other:?}"),
+    };
+    assert_eq!(quantized.tick, full.tick + 1);
+    assert_eq!(quantized.entities.len(), full.entities.len());
+    let state = quantized
+        .entities
+        .iter()
+        .find(|e| e.id == player)
+        .expect("player in snapshot");
+    assert_eq!(state.position, Vec3::ZERO);
+    Ok(())
+}
@@ -0,0 +1,156 @@
+//! Chat relayed by the server between connected clients.
+
+use std::time::Duration;
+
+use engine_client::GameClient;
+use engine_server::server::GameServer;
+use engine_shared::chat::{ChatChannel, ChatMessage};
+use engine_shared::config::EngineConfig;
+use engine_shared::net::{NetMsg, PROTOCOL_VERSION};
+use engine_shared::steam_id::SteamId;
+
+fn player_cfg(account: u32, name: &str) -> EngineConfig {
+    EngineConfig {
+        server_addr: "127.0.0.1:0".to_string(),
+        player_name: name.to_string(),
+        steam_id: SteamId::from_account_id(account),
+        ..Default::default()
+    }
+}
+
+async fn join(server: &mut GameServer, cfg: &EngineConfig) -> anyhow::Result<GameClient> {
+    let (client, id) = tokio::join!(
+        GameClient::connect_loopback(cfg, server.loopback_connector()),
+        server.accept_local(),
+    );
+    id?;
+    client
+}
+
+/// Steps the server and polls each client until `done` holds or two
+/// seconds pass.
+async fn pump(
+    server: &mut GameServer,
+    clients: &mut [&mut GameClient],
+    done: impl Fn(&[&mut GameClient]) -> bool,
+) -> anyhow::Result<()> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while !done(clients) && tokio::time::Instant::now() < deadline {
+        server.step(1.0 / 64.0).await?;
+        for client in clients.iter_mut() {
+            client.poll_reliable().await?;
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn global_chat_reaches_every_client() -> anyhow::Result<()> {
+    let cfg = player_cfg(1, "alice");
+    let mut server = GameServer::new(cfg.clone(), std::env::temp_dir()).await?;
+    let mut alice = join(&mut server, &cfg).await?;
+    let mut bob = join(&mut server, &player_cfg(2, "bob")).await?;
+
+    alice.exec_console("say gl hf").await?;
+    pump(&mut server, &mut [&mut alice, &mut bob], |c| {
+        c.iter().all(|c| !c.chat_messages.is_empty())
+    })
+    .await?;
+
+    for client in [&alice, &bob] {
+        assert_eq!(client.chat_messages.len(), 1);
+        let msg = &client.chat_messages[0];
+        assert_eq!(msg.sender, SteamId::from_account_id(1));
+        assert_eq!(msg.sender_name, "alice");
+        assert_eq!(msg.channel, ChatChannel::Global);
+        assert_eq!(msg.content, "gl hf");
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn private_chat_reaches_only_target_and_sender() -> anyhow::Result<()> {
+    let cfg = player_cfg(1, "alice");
+    let mut server = GameServer::new(cfg.clone(), std::env::temp_dir()).await?;
+    let mut alice = join(&mut server, &cfg).await?;
+    let mut bob = join(&mut server, &player_cfg(2, "bob")).await?;
+    let mut carol = join(&mut server, &player_cfg(3, "carol")).await?;
+
+    let to_bob = ChatChannel::Private(SteamId::from_account_id(2));
+    alice.say(to_bob, "rematch?").await?;
+    pump(&mut server, &mut [&mut alice, &mut bob, &mut carol], |c| {
+        !c[0].chat_messages.is_empty() && !c[1].chat_messages.is_empty()
+    })
+    .await?;
+
+    assert_eq!(alice.chat_messages.len(), 1);
+    assert_eq!(bob.chat_messages.len(), 1);
+    assert_eq!(bob.chat_messages[0].channel, to_bob);
+    assert_eq!(bob.chat_messages[0].content, "rematch?");
+    assert!(carol.chat_messages.is_empty());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn chat_sender_comes_from_the_connection() -> anyhow::Result<()> {
+    let cfg = player_cfg(1, "alice");
+    let mut server = GameServer::new(cfg.clone(), std::env::temp_dir()).await?;
+    let mut alice = join(&mut server, &cfg).await?;
+
+    // A hand-rolled client claiming to be alice, under an admin's name.
+    let (mut conn, _transport) = server.loopback_connector().connect()?;
+    let handshake = async {
+        conn.send(&NetMsg::Hello {
+            protocol: PROTOCOL_VERSION,
+            steam_id: SteamId::from_account_id(2),
+            owned_dlc: Vec::new(),
+            player_name: "mallory".to_string(),
+        })
+        .await?;
+        conn.send(&NetMsg::UdpHello { client_udp_port: 0 }).await?;
+        conn.recv().await
+    };
+    let (welcome, id) = tokio::join!(handshake, server.accept_local());
+    id?;
+    assert!(matches!(welcome?, NetMsg::Welcome { .. }));
+
+    let forged = ChatMessage::new(
+        SteamId::from_account_id(1),
+        "admin",
+        ChatChannel::Global,
+        "free skins",
+        0,
+    );
+    conn.send(&NetMsg::Chat(forged)).await?;
+    pump(&mut server, &mut [&mut alice], |c| {
+        !c[0].chat_messages.is_empty()
+    })
+    .await?;
+
+    let msg = &alice.chat_messages[0];
+    assert_eq!(msg.sender, SteamId::from_account_id(2));
+    assert_eq!(msg.sender_name, "mallory");
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn server_only_chat_channels_are_refused() -> anyhow::Result<()> {
+    let cfg = player_cfg(1, "alice");
+    let mut server = GameServer::new(cfg.clone(), std::env::temp_dir()).await?;
+    let mut alice = join(&mut server, &cfg).await?;
+    let mut bob = join(&mut server, &player_cfg(2, "bob")).await?;
+
+    alice.say(ChatChannel::Server, "server restarting").await?;
+    alice.say(ChatChannel::Console, "rcon_password x").await?;
+    alice.say(ChatChannel::Global, "done").await?;
+    pump(&mut server, &mut [&mut alice, &mut bob], |c| {
+        c.iter().all(|c| !c.chat_messages.is_empty())
+    })
+    .await?;
+
+    for client in [&alice, &bob] {
+        assert_eq!(client.chat_messages.len(), 1);
+        assert_eq!(client.chat_messages[0].content, "done");
+    }
+    Ok(())
+}
@@ -7,12 +7,16 @@ use engine_client::GameClient;
 use engine_server::server::bind_ephemeral;
 use engine_shared::config::EngineConfig;
 use engine_shared::net::{decode_from_bytes, encode_to_bytes, ClientId, NetMsg, PROTOCOL_VERSION};
+use engine_shared::steam_id::SteamId;
 
 /// Unit-style test: protocol messages roundtrip correctly.
 #[test]
 fn protocol_messages_roundtrip() -> anyhow::Result<()> {
     let hello = NetMsg::Hello {
         protocol: PROTOCOL_VERSION,
+        steam_id: SteamId::from_account_id(12345),
+        owned_dlc: vec![1001],
+        player_name: String::new(),
     };
     assert_eq!(decode_from_bytes(&encode_to_bytes(&hello)?)?, hello);
 
@@ -62,6 +66,7 @@ async fn client_server_full_roundtrip() -> anyhow::Result<()> {
         tick_hz: 64,
         maps_dir: "./maps".into(),
         player_name: "TestPlayer".to_string(),
+        ..Default::default()
     })
     .await?;
 
@@ -72,6 +77,7 @@ async fn client_server_full_roundtrip() -> anyhow::Result<()> {
                 forward: 1.0,
                 right: 0.0,
                 up: 0.0,
+                ..Default::default()
             })
             .await?;
         client.recv_snapshot().await?;
@@ -89,3 +95,79 @@ async fn client_server_full_roundtrip() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// The server rejects handshakes whose Steam ID is not an individual/public account.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn server_rejects_game_server_steam_id() -> anyhow::Result<()> {
+    use engine_shared::steam_id::{AccountType, Universe};
+
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    let server_handle = tokio::spawn(async move { server.accept_one().await });
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let result = GameClient::connect(&EngineConfig {
+        steam_id: SteamId::from_parts(12345, 1, AccountType::GameServer, Universe::Public),
+        ..cfg
+    })
+    .await;
+
+    assert!(
+        result.is_err(),
+        "client with game server ID should not connect"
+    );
+    assert!(server_handle.await?.is_err());
+
+    Ok(())
+}
+
+/// The server rejects clients that don't own the DLC the current map ships with.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn server_rejects_client_without_map_dlc() -> anyhow::Result<()> {
+    use engine_shared::bsp::BspMap;
+    use engine_shared::net::ReliableConn;
+    use tokio::net::TcpStream;
+
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    server.load_bsp(BspMap {
+        name: "de_dlc".to_string(),
+        ..Default::default()
+    });
+    server.set_map_dlc("de_dlc", 1001);
+
+    let server_handle = tokio::spawn(async move {
+        let rejected = server.accept_one().await;
+        let accepted = server.accept_one().await;
+        (rejected.is_err(), accepted.is_ok())
+    });
+
+    // Performs a raw handshake and returns the server's first reply.
+    let handshake = |owned_dlc: Vec<u32>| {
+        let addr = cfg.server_addr.clone();
+        async move {
+            let mut conn = ReliableConn::new(TcpStream::connect(addr).await?);
+            conn.send(&NetMsg::Hello {
+                protocol: PROTOCOL_VERSION,
+                steam_id: SteamId::from_account_id(12345),
+                owned_dlc,
+                player_name: String::new(),
+            })
+            .await?;
+            conn.send(&NetMsg::UdpHello {
+                client_udp_port: 50000,
+            })
+            .await?;
+            conn.recv().await
+        }
+    };
+
+    let reply = handshake(vec![1002]).await?;
+    assert!(matches!(reply, NetMsg::Disconnect { .. }), "got {reply:?}");
+
+    let reply = handshake(vec![1001]).await?;
+    assert!(matches!(reply, NetMsg::Welcome { .. }), "got {reply:?}");
+
+    assert_eq!(server_handle.await?, (true, true));
+
+    Ok(())
+}
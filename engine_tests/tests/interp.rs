@@ -0,0 +1,78 @@
+//! Entity interpolation buffer behavior.
+
+use std::time::Duration;
+
+use engine_client::interp::{InterpBuffer, DEFAULT_INTERP_DELAY};
+use engine_shared::ecs::EntityId;
+use engine_shared::math::Vec3;
+use engine_shared::net::EntityState;
+
+fn state(x: f32, yaw: f32) -> EntityState {
+    EntityState {
+        id: EntityId(1),
+        position: Vec3::new(x, 0.0, 0.0),
+        angles: Vec3::new(0.0, yaw, 0.0),
+        health: None,
+    }
+}
+
+fn approx(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn interpolates_midpoint_between_bracketing_samples() {
+    // 10 Hz ticks: tick n is at n / 10 seconds.
+    let mut buf = InterpBuffer::new(10, 16);
+    buf.push(1, state(0.0, 350.0));
+    buf.push(2, state(10.0, 10.0));
+    buf.push(3, state(30.0, 20.0));
+
+    assert_eq!(buf.interp_delay(), DEFAULT_INTERP_DELAY);
+    // Server time 0.25 renders at 0.15, halfway between ticks 1 and 2.
+    let s = buf.sample(0.25).unwrap();
+    assert!(!s.extrapolated);
+    assert!(approx(s.position.x, 5.0));
+    // Yaw takes the short way round through 0.
+    assert!(approx(s.angles.y.rem_euclid(360.0), 0.0));
+
+    let s = buf.sample_at(0.275).unwrap();
+    assert!(approx(s.position.x, 25.0));
+}
+
+#[test]
+fn extrapolates_past_newest_sample_up_to_cap() {
+    let mut buf = InterpBuffer::new(10, 16)
+        .with_interp_delay(Duration::ZERO)
+        .with_max_extrapolation(Duration::from_millis(100));
+    buf.push(1, state(0.0, 0.0));
+    buf.push(2, state(10.0, 0.0));
+
+    // 50ms past tick 2 continues at the last velocity.
+    let s = buf.sample(0.25).unwrap();
+    assert!(s.extrapolated);
+    assert!(approx(s.position.x, 15.0));
+
+    // Far past the newest sample holds at the cap.
+    let s = buf.sample(5.0).unwrap();
+    assert!(s.extrapolated);
+    assert!(approx(s.position.x, 20.0));
+}
+
+#[test]
+fn clamps_before_oldest_and_ignores_stale_samples() {
+    let mut buf = InterpBuffer::new(10, 2).with_max_extrapolation(Duration::ZERO);
+    assert!(buf.sample(1.0).is_none());
+
+    buf.push(5, state(50.0, 0.0));
+    buf.push(4, state(40.0, 0.0));
+    assert_eq!(buf.len(), 1);
+    assert_eq!(buf.sample_at(0.0).unwrap().position.x, 50.0);
+
+    buf.push(6, state(60.0, 0.0));
+    buf.push(7, state(70.0, 0.0));
+    assert_eq!(buf.len(), 2);
+    assert_eq!(buf.latest_tick(), Some(7));
+    assert_eq!(buf.sample_at(0.0).unwrap().position.x, 60.0);
+    assert_eq!(buf.sample_at(2.0).unwrap().position.x, 70.0);
+}
@@ -0,0 +1,94 @@
+//! Listen server: an in-process server and client talking over loopback.
+
+use std::path::Path;
+use std::time::Duration;
+
+use engine_client::client::ClientState;
+use engine_client::input::InputState;
+use engine_client::GameClient;
+use engine_server::server::GameServer;
+use engine_shared::bsp::{LumpType, HEADER_LUMPS, HEADER_SIZE};
+use engine_shared::config::EngineConfig;
+
+/// Writes a BSP with only an entity lump to `dir/name.bsp`.
+fn write_map(dir: &Path, name: &str, entities: &str) {
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + entities.len());
+    bytes.extend_from_slice(b"VBSP");
+    bytes.extend_from_slice(&20u32.to_le_bytes());
+    for index in 0..HEADER_LUMPS {
+        let (offset, length) = if index == LumpType::Entities as usize {
+            (HEADER_SIZE as u32, entities.len() as u32)
+        } else {
+            (0, 0)
+        };
+        bytes.extend_from_slice(&offset.to_le_bytes());
+        bytes.extend_from_slice(&length.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+    }
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(entities.as_bytes());
+    std::fs::write(dir.join(format!("{name}.bsp")), bytes).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn loopback_client_loads_map_and_receives_snapshots() -> anyhow::Result<()> {
+    let maps_dir = std::env::temp_dir().join(format!("listen_server_{}", std::process::id()));
+    std::fs::create_dir_all(&maps_dir)?;
+    write_map(
+        &maps_dir,
+        "lp_test",
+        "{\n\"classname\" \"worldspawn\"\n}\n{\n\"classname\" \"info_player_start\"\n\"origin\" \"64 0 0\"\n}\n",
+    );
+
+    let cfg = EngineConfig {
+        server_addr: "127.0.0.1:0".to_string(),
+        maps_dir: maps_dir.to_string_lossy().into_owned(),
+        ..Default::default()
+    };
+    let mut server = GameServer::new(cfg.clone(), maps_dir.clone()).await?;
+    server.load_map("lp_test")?;
+
+    let (client, client_id) = tokio::join!(
+        GameClient::connect_loopback(&cfg, server.loopback_connector()),
+        server.accept_local(),
+    );
+    let (mut client, client_id) = (client?, client_id?);
+    assert_eq!(client.client_id, client_id);
+    assert!(client.server_peer().is_err(), "loopback has no socket peer");
+
+    // The handshake's MapInfo was loaded from the shared maps dir.
+    assert_eq!(client.state, ClientState::Ready);
+    assert_eq!(
+        client.current_map.as_ref().map(|m| m.name.as_str()),
+        Some("lp_test")
+    );
+    assert!(client.needs_ready());
+    client.send_ready().await?;
+    assert!(!client.needs_ready());
+
+    let forward = InputState {
+        forward: 1.0,
+        ..Default::default()
+    };
+    client.tick(forward).await?;
+    server.step(1.0 / 64.0).await?;
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while client.snaps.last_snapshot().is_none() && tokio::time::Instant::now() < deadline {
+        client.recv_snapshot().await?;
+    }
+    std::fs::remove_dir_all(&maps_dir).ok();
+
+    let snap = client
+        .snaps
+        .last_snapshot()
+        .expect("snapshot over loopback");
+    let ack = snap.ack.expect("command ack for the local player");
+    assert_eq!(ack.sequence, 1);
+    // Spawned at the map's player start and moved by the command.
+    assert!(ack.position.x > 64.0);
+    assert!(snap.entities.iter().any(|e| e.position == ack.position));
+    assert_eq!(client.prediction.state().position, ack.position);
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! Client-side prediction and reconciliation against server acks.
+
+use std::time::Duration;
+
+use engine_client::prediction::{Correction, Prediction, DEFAULT_SNAP_DISTANCE};
+use engine_server::server::bind_ephemeral;
+use engine_shared::bsp::BspMap;
+use engine_shared::input::InputState;
+use engine_shared::math::Vec3;
+use engine_shared::net::{
+    decode_from_bytes, encode_to_bytes, CommandAck, NetMsg, PlayerCommand, ReliableConn,
+    PROTOCOL_VERSION,
+};
+use engine_shared::physics::{move_player, GroundInfo, PlayerPhysics};
+use engine_shared::steam_id::SteamId;
+use tokio::net::{TcpStream, UdpSocket};
+
+const DT: f32 = 1.0 / 64.0;
+
+fn forward() -> InputState {
+    InputState {
+        forward: 1.0,
+        ..Default::default()
+    }
+}
+
+fn right() -> InputState {
+    InputState {
+        right: 1.0,
+        ..Default::default()
+    }
+}
+
+/// Runs `inputs` through the movement code the way the server does.
+fn server_state(start: PlayerPhysics, inputs: &[InputState]) -> PlayerPhysics {
+    let mut state = start;
+    for input in inputs {
+        move_player(&mut state, input, DT, GroundInfo::FLAT);
+    }
+    state
+}
+
+fn ack(sequence: u32, state: PlayerPhysics) -> CommandAck {
+    CommandAck {
+        sequence,
+        position: state.position,
+        velocity: state.velocity,
+    }
+}
+
+fn approx(a: Vec3, b: Vec3) -> bool {
+    a.distance_sq(b) < 1e-8
+}
+
+#[test]
+fn replaying_unacked_commands_reproduces_prediction() {
+    let mut pred = Prediction::new(DT);
+    let inputs: Vec<InputState> = (0..10)
+        .map(|i| if i % 3 == 0 { right() } else { forward() })
+        .collect();
+    for (tick, input) in inputs.iter().enumerate() {
+        pred.predict(tick as u32, *input, (0.0, 0.0));
+    }
+    let predicted = pred.state();
+    assert!(predicted.position.x > 0.0);
+
+    // The server has run the first four commands, exactly as predicted.
+    let authoritative = server_state(PlayerPhysics::default(), &inputs[..4]);
+    assert_eq!(pred.reconcile(&ack(4, authoritative)), Correction::None);
+
+    assert_eq!(pred.commands().last_acked(), 4);
+    assert_eq!(pred.commands().len(), 6);
+    assert!(approx(pred.state().position, predicted.position));
+    assert!(approx(pred.state().velocity, predicted.velocity));
+    assert_eq!(pred.render_position(), pred.state().position);
+}
+
+#[test]
+fn small_correction_is_applied_and_smoothed() {
+    let mut pred = Prediction::new(DT);
+    for tick in 0..6 {
+        pred.predict(tick, forward(), (0.0, 0.0));
+    }
+    let predicted = pred.state().position;
+
+    // The server had the player slightly off to the side after command 2.
+    let mut authoritative = server_state(PlayerPhysics::default(), &[forward(), forward()]);
+    authoritative.position.y += 0.5;
+    let correction = pred.reconcile(&ack(2, authoritative));
+
+    let Correction::Smoothed { error } = correction else {
+        panic!("expected a smoothed correction, got {correction:?}");
+    };
+    assert!((error - 0.5).abs() < 1e-4);
+    // The corrected state carries the server's offset through the replay...
+    let replayed = server_state(authoritative, &[forward(); 4]);
+    assert!(approx(pred.state().position, replayed.position));
+    // ...but the player is still drawn where they were predicted.
+    assert!(approx(pred.render_position(), predicted));
+
+    // The leftover offset shrinks with each new command.
+    let before = pred.render_position().distance_sq(pred.state().position);
+    pred.predict(6, forward(), (0.0, 0.0));
+    let after = pred.render_position().distance_sq(pred.state().position);
+    assert!(after < before);
+}
+
+#[test]
+fn large_correction_snaps() {
+    let mut pred = Prediction::new(DT);
+    for tick in 0..3 {
+        pred.predict(tick, forward(), (0.0, 0.0));
+    }
+
+    // The server teleported the player.
+    let authoritative = PlayerPhysics {
+        position: Vec3::new(100.0, 0.0, 0.0),
+        velocity: Vec3::ZERO,
+    };
+    let correction = pred.reconcile(&ack(1, authoritative));
+    let Correction::Snapped { error } = correction else {
+        panic!("expected a snap, got {correction:?}");
+    };
+    assert!(error >= DEFAULT_SNAP_DISTANCE);
+
+    let replayed = server_state(authoritative, &[forward(), forward()]);
+    assert!(approx(pred.state().position, replayed.position));
+    assert_eq!(pred.render_position(), pred.state().position);
+}
+
+#[test]
+fn stale_ack_is_ignored() {
+    let mut pred = Prediction::new(DT);
+    let inputs = [forward(); 4];
+    for (tick, input) in inputs.iter().enumerate() {
+        pred.predict(tick as u32, *input, (0.0, 0.0));
+    }
+    let predicted = pred.state();
+    pred.reconcile(&ack(
+        3,
+        server_state(PlayerPhysics::default(), &inputs[..3]),
+    ));
+
+    // A reordered snapshot with an older ack must not rewind further.
+    let stale = PlayerPhysics {
+        position: Vec3::new(-50.0, 0.0, 0.0),
+        velocity: Vec3::ZERO,
+    };
+    assert_eq!(pred.reconcile(&ack(2, stale)), Correction::None);
+    assert!(approx(pred.state().position, predicted.position));
+    assert_eq!(pred.commands().last_acked(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn server_acks_match_client_prediction() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    server.load_bsp(BspMap {
+        name: "de_predict".to_string(),
+        ..Default::default()
+    });
+
+    // Commands come from the announced socket, which snapshots go to.
+    let udp = UdpSocket::bind("127.0.0.1:0").await?;
+    let udp_port = udp.local_addr()?.port();
+    let addr = cfg.server_addr.clone();
+    let client = tokio::spawn(async move {
+        let mut conn = ReliableConn::new(TcpStream::connect(addr).await?);
+        conn.send(&NetMsg::Hello {
+            protocol: PROTOCOL_VERSION,
+            steam_id: SteamId::from_account_id(1000),
+            owned_dlc: Vec::new(),
+            player_name: String::new(),
+        })
+        .await?;
+        conn.send(&NetMsg::UdpHello {
+            client_udp_port: udp_port,
+        })
+        .await?;
+        anyhow::Ok(conn)
+    });
+    let client_id = server.accept_one().await?;
+    let _conn = client.await??;
+    server.client_ready(client_id)?;
+
+    let mut pred = Prediction::new(DT);
+    for tick in 0..3 {
+        let cmd = pred.predict(tick, forward(), (0.0, 0.0));
+        let msg = NetMsg::PlayerCommand(PlayerCommand {
+            client_id,
+            tick,
+            wish: forward().wish_vector(),
+            sequence: cmd.sequence,
+        });
+        udp.send_to(&encode_to_bytes(&msg)?, &cfg.server_addr)
+            .await?;
+    }
+    // Two more the server hasn't seen yet.
+    pred.predict(3, forward(), (0.0, 0.0));
+    pred.predict(4, forward(), (0.0, 0.0));
+    let predicted = pred.state();
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    server.step(DT).await?;
+
+    let mut buf = vec![0u8; 64 * 1024];
+    let ack = loop {
+        let (n, _) =
+            tokio::time::timeout(Duration::from_secs(2), udp.recv_from(&mut buf)).await??;
+        if let NetMsg::Snapshot(snap) = decode_from_bytes(&buf[..n])? {
+            break snap.ack.expect("snapshot carries the command ack");
+        }
+    };
+    assert_eq!(ack.sequence, 3);
+
+    assert_eq!(pred.reconcile(&ack), Correction::None);
+    assert_eq!(pred.commands().len(), 2);
+    assert!(approx(pred.state().position, predicted.position));
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Client reconnect backoff, with mocked connect attempts and sleeps.
+
+use std::cell::RefCell;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use engine_client::client::ClientState;
+use engine_client::reconnect::{retry_with_backoff, ReconnectPolicy};
+use engine_client::GameClient;
+use engine_server::server::bind_ephemeral;
+
+fn policy(max_attempts: u32) -> ReconnectPolicy {
+    ReconnectPolicy {
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(1),
+        max_attempts,
+    }
+}
+
+fn ms(values: &[u64]) -> Vec<Duration> {
+    values.iter().map(|&v| Duration::from_millis(v)).collect()
+}
+
+#[tokio::test]
+async fn retries_with_doubling_delays_until_connected() {
+    let delays = RefCell::new(Vec::new());
+    let result = retry_with_backoff(
+        &policy(5),
+        |d| {
+            delays.borrow_mut().push(d);
+            std::future::ready(())
+        },
+        |attempt| {
+            std::future::ready(if attempt < 4 {
+                Err(anyhow!("connection refused"))
+            } else {
+                Ok(attempt)
+            })
+        },
+    )
+    .await;
+
+    assert_eq!(result.unwrap(), 4);
+    assert_eq!(delays.into_inner(), ms(&[100, 200, 400, 800]));
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let delays = RefCell::new(Vec::new());
+    let result: anyhow::Result<()> = retry_with_backoff(
+        &policy(6),
+        |d| {
+            delays.borrow_mut().push(d);
+            std::future::ready(())
+        },
+        |_| std::future::ready(Err(anyhow!("connection refused"))),
+    )
+    .await;
+
+    let err = format!("{:#}", result.unwrap_err());
+    assert!(err.contains("after 6 attempts"), "{err}");
+    assert!(err.contains("connection refused"), "{err}");
+    // Capped at max_delay.
+    assert_eq!(delays.into_inner(), ms(&[100, 200, 400, 800, 1000, 1000]));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn reconnect_redoes_handshake_with_server() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    let server_handle = tokio::spawn(async move {
+        server.accept_one().await?;
+        server.accept_one().await?;
+        Ok::<_, anyhow::Error>(server.connected_client_count())
+    });
+
+    let mut client = GameClient::connect(&cfg).await?;
+    let first_id = client.client_id;
+    client.reconnect_policy = ReconnectPolicy {
+        base_delay: Duration::from_millis(10),
+        ..policy(3)
+    };
+
+    client.reconnect().await?;
+    assert_ne!(client.client_id, first_id);
+    assert_eq!(client.state, ClientState::Connected);
+    assert!(!client.connection_lost());
+    // The old slot lingers until the server times it out.
+    assert_eq!(server_handle.await??, 2);
+    Ok(())
+}
@@ -0,0 +1,167 @@
+//! Server address parsing, resolution and multi-address fallback.
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use engine_client::resolve::{connect_any, resolve, ResolveError, Resolver};
+use engine_client::GameClient;
+use engine_server::server::bind_ephemeral;
+use engine_shared::net::{AddrError, HostPort};
+
+/// Answers every lookup with a fixed list, or fails if there is none.
+struct MockResolver {
+    addrs: Option<Vec<SocketAddr>>,
+    lookups: AtomicUsize,
+}
+
+impl MockResolver {
+    fn new(addrs: Option<Vec<SocketAddr>>) -> Self {
+        Self {
+            addrs,
+            lookups: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Resolver for MockResolver {
+    fn lookup(
+        &self,
+        _target: &HostPort,
+    ) -> impl Future<Output = io::Result<Vec<SocketAddr>>> + Send {
+        self.lookups.fetch_add(1, Ordering::SeqCst);
+        let result = self
+            .addrs
+            .clone()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such host"));
+        async move { result }
+    }
+}
+
+fn addr(s: &str) -> SocketAddr {
+    s.parse().unwrap()
+}
+
+#[tokio::test]
+async fn ip_literals_skip_the_resolver() -> anyhow::Result<()> {
+    let resolver = MockResolver::new(None);
+    assert_eq!(
+        resolve(&resolver, "10.0.0.5:27015").await?,
+        vec![addr("10.0.0.5:27015")]
+    );
+    assert_eq!(
+        resolve(&resolver, "[::1]:27015").await?,
+        vec![addr("[::1]:27015")]
+    );
+    assert_eq!(resolver.lookups.load(Ordering::SeqCst), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn hostnames_resolve_to_every_address_in_order() -> anyhow::Result<()> {
+    let addrs = vec![addr("[2001:db8::1]:27015"), addr("192.0.2.1:27015")];
+    let resolver = MockResolver::new(Some(addrs.clone()));
+    assert_eq!(resolve(&resolver, "play.example.com:27015").await?, addrs);
+    assert_eq!(resolver.lookups.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn resolution_failures_are_reported_clearly() {
+    let err = resolve(&MockResolver::new(None), "nowhere.invalid:27015")
+        .await
+        .unwrap_err();
+    assert!(matches!(&err, ResolveError::Lookup { host, .. } if host == "nowhere.invalid"));
+    assert_eq!(
+        err.to_string(),
+        "could not resolve 'nowhere.invalid': no such host"
+    );
+
+    let err = resolve(&MockResolver::new(Some(Vec::new())), "empty.example.com:1")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ResolveError::NoAddresses { .. }));
+
+    let err = resolve(&MockResolver::new(None), "localhost")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ResolveError::Invalid(AddrError::MissingPort(_))
+    ));
+}
+
+#[tokio::test]
+async fn connect_any_falls_back_to_later_addresses() -> anyhow::Result<()> {
+    let resolver = MockResolver::new(Some(vec![
+        addr("[2001:db8::1]:27015"),
+        addr("192.0.2.1:27015"),
+        addr("192.0.2.2:27015"),
+    ]));
+    let addrs = resolve(&resolver, "play.example.com:27015").await?;
+
+    // Only the second address answers.
+    let tried = Mutex::new(Vec::new());
+    let connected = connect_any(&addrs, |a| {
+        tried.lock().unwrap().push(a);
+        async move {
+            if a == addr("192.0.2.1:27015") {
+                Ok(a)
+            } else {
+                anyhow::bail!("connection refused")
+            }
+        }
+    })
+    .await?;
+    assert_eq!(connected, addr("192.0.2.1:27015"));
+    assert_eq!(*tried.lock().unwrap(), addrs[..2]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn connect_any_fails_when_every_address_does() {
+    let addrs = [addr("192.0.2.1:1"), addr("192.0.2.2:1")];
+    let attempts = AtomicUsize::new(0);
+    let err = connect_any(&addrs, |_| {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        async { anyhow::bail!("connection refused") as anyhow::Result<()> }
+    })
+    .await
+    .unwrap_err();
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        format!("{err:#}"),
+        "all 2 resolved addresses failed: connection refused"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn console_connect_switches_servers() -> anyhow::Result<()> {
+    let (mut first, cfg) = bind_ephemeral(64).await?;
+    let (mut second, _) = bind_ephemeral(64).await?;
+    let second_addr = second.local_addr()?;
+
+    let accept = tokio::spawn(async move { first.accept_one().await.map(|_| first) });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let mut client = GameClient::connect(&cfg).await?;
+    let _first = accept.await??;
+
+    // A bad address reports the problem and keeps the current server.
+    let out = client.exec_console("connect nowhere").await?;
+    assert!(out[0].starts_with("Failed to connect"), "{out:?}");
+    assert_eq!(client.server_peer()?.to_string(), cfg.server_addr);
+
+    // By name, so the lookup (and any fallback across its addresses) runs.
+    let accept = tokio::spawn(async move { second.accept_one().await });
+    let target = format!("localhost:{}", second_addr.port());
+    let out = client.exec_console(&format!("connect {target}")).await?;
+    assert_eq!(out, vec![format!("Connected to {target}")]);
+    let second_id = accept.await??;
+
+    assert_eq!(client.server_peer()?, second_addr);
+    assert_eq!(client.client_id, second_id);
+    Ok(())
+}
@@ -0,0 +1,457 @@
+//! Server lifecycle: shutdown, timeouts, map changes and slot limits.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use engine_server::server::{
+    bind_ephemeral, GameServer, ServerState, MAP_LOAD_TIMED_OUT_REASON, MAP_LOAD_TIMEOUT,
+    SHUTDOWN_REASON, TIMED_OUT_REASON,
+};
+use engine_shared::bsp::{BspEntity, BspMap};
+use engine_shared::math::Vec3;
+use engine_shared::net::{
+    decode_from_bytes, encode_to_bytes, ClientId, NetMsg, PlayerCommand, RejectReason,
+    ReliableConn, PROTOCOL_VERSION,
+};
+use engine_shared::steam_id::SteamId;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::Instant;
+
+/// A handshaken raw connection and the UDP socket it announced.
+type RawClient = (ReliableConn, ClientId, UdpSocket);
+
+/// Handshakes a raw connection with the server, returning it after the
+/// `Welcome`.
+async fn raw_client(addr: String, account: u32) -> anyhow::Result<RawClient> {
+    raw_client_with_dlc(addr, account, Vec::new()).await
+}
+
+/// Like [`raw_client`], claiming to own `owned_dlc`.
+async fn raw_client_with_dlc(
+    addr: String,
+    account: u32,
+    owned_dlc: Vec<u32>,
+) -> anyhow::Result<RawClient> {
+    let udp = UdpSocket::bind("127.0.0.1:0").await?;
+    let mut conn = ReliableConn::new(TcpStream::connect(addr).await?);
+    conn.send(&NetMsg::Hello {
+        protocol: PROTOCOL_VERSION,
+        steam_id: SteamId::from_account_id(account),
+        owned_dlc,
+        player_name: String::new(),
+    })
+    .await?;
+    conn.send(&NetMsg::UdpHello {
+        client_udp_port: udp.local_addr()?.port(),
+    })
+    .await?;
+    match conn.recv().await? {
+        NetMsg::Welcome { client_id } => Ok((conn, client_id, udp)),
+        NetMsg::Reject { reason } => anyhow::bail!("rejected: {reason}"),
+        other => anyhow::bail!("expected Welcome, got {other:?}"),
+    }
+}
+
+/// Connects `n` raw clients, accepting each on the server.
+async fn connect_clients(
+    server: &mut GameServer,
+    addr: &str,
+    n: u32,
+) -> anyhow::Result<Vec<RawClient>> {
+    let mut conns = Vec::new();
+    for account in 0..n {
+        let client = tokio::spawn(raw_client(addr.to_string(), 1000 + account));
+        server.accept_one().await?;
+        conns.push(client.await??);
+    }
+    Ok(conns)
+}
+
+/// Reads until a `MapInfo`, returning the map name.
+async fn recv_map_info(conn: &mut ReliableConn) -> anyhow::Result<String> {
+    loop {
+        let msg = tokio::time::timeout(Duration::from_secs(2), conn.recv()).await??;
+        if let NetMsg::MapInfo(info) = msg {
+            return Ok(info.name);
+        }
+    }
+}
+
+/// Sends a datagram to the server from `udp`.
+async fn send_udp(udp: &UdpSocket, addr: &str, msg: &NetMsg) -> anyhow::Result<()> {
+    udp.send_to(&encode_to_bytes(msg)?, addr).await?;
+    Ok(())
+}
+
+/// Reads until a `Disconnect`, skipping the handshake's trailing messages.
+async fn recv_disconnect(conn: &mut ReliableConn) -> anyhow::Result<String> {
+    loop {
+        let msg = tokio::time::timeout(Duration::from_secs(2), conn.recv()).await??;
+        if let NetMsg::Disconnect { reason } = msg {
+            return Ok(reason);
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn shutdown_disconnects_every_client_and_stops() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    let mut conns = connect_clients(&mut server, &cfg.server_addr, 2).await?;
+
+    server.shutdown().await;
+    assert_eq!(*server.state(), ServerState::Stopped);
+
+    for (conn, _, _) in &mut conns {
+        assert_eq!(recv_disconnect(conn).await?, SHUTDOWN_REASON);
+        // The connection was closed after the notice.
+        assert!(conn.recv().await.is_err());
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn quit_command_shuts_down_on_next_step() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    let mut conns = connect_clients(&mut server, &cfg.server_addr, 1).await?;
+
+    server.exec_console("quit")?;
+    assert_eq!(*server.state(), ServerState::ShuttingDown);
+    server.step(1.0 / 64.0).await?;
+    assert_eq!(*server.state(), ServerState::Stopped);
+    assert_eq!(recv_disconnect(&mut conns[0].0).await?, SHUTDOWN_REASON);
+
+    // Further steps are no-ops.
+    server.step(1.0 / 64.0).await?;
+    assert_eq!(*server.state(), ServerState::Stopped);
+    Ok(())
+}
+
+/// A clock the test moves by hand.
+fn manual_clock(server: &mut GameServer) -> Arc<Mutex<Instant>> {
+    let now = Arc::new(Mutex::new(Instant::now()));
+    let shared = Arc::clone(&now);
+    server.set_clock(Arc::new(move || *shared.lock().unwrap()));
+    now
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn silent_client_times_out_and_active_one_survives() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    let clock = manual_clock(&mut server);
+    let start = *clock.lock().unwrap();
+    let mut conns = connect_clients(&mut server, &cfg.server_addr, 2).await?;
+    let active = conns[1].1;
+    assert_eq!(server.connected_client_count(), 2);
+
+    // The active client answers a ping 20s in. A pong for the silent one
+    // from some other address doesn't count.
+    *clock.lock().unwrap() = start + Duration::from_secs(20);
+    let pong = NetMsg::Pong {
+        client_id: active,
+        seq: 0,
+    };
+    send_udp(&conns[1].2, &cfg.server_addr, &pong).await?;
+    let spoofed = NetMsg::Pong {
+        client_id: conns[0].1,
+        seq: 0,
+    };
+    let stranger = UdpSocket::bind("127.0.0.1:0").await?;
+    send_udp(&stranger, &cfg.server_addr, &spoofed).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    server.step(1.0 / 64.0).await?;
+    assert_eq!(server.connected_client_count(), 2);
+
+    // Past the 30s default for the silent client only.
+    *clock.lock().unwrap() = start + Duration::from_secs(31);
+    server.step(1.0 / 64.0).await?;
+    assert_eq!(server.connected_client_count(), 1);
+    assert_eq!(recv_disconnect(&mut conns[0].0).await?, TIMED_OUT_REASON);
+
+    *clock.lock().unwrap() = start + Duration::from_secs(51);
+    server.step(1.0 / 64.0).await?;
+    assert_eq!(server.connected_client_count(), 0);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn commands_from_a_foreign_address_are_ignored() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    let conns = connect_clients(&mut server, &cfg.server_addr, 1).await?;
+    let (_, id, udp) = &conns[0];
+    let player = server.client_ready(*id)?;
+    let command = |sequence| {
+        NetMsg::PlayerCommand(PlayerCommand {
+            client_id: *id,
+            tick: sequence,
+            wish: Vec3::new(1.0, 0.0, 0.0),
+            sequence,
+        })
+    };
+
+    // A stranger using the client's ID neither moves the player nor takes
+    // over where its snapshots go.
+    let stranger = UdpSocket::bind("127.0.0.1:0").await?;
+    send_udp(&stranger, &cfg.server_addr, &command(1)).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    server.step(1.0 / 64.0).await?;
+    assert_eq!(
+        server.entity_state_at(player, 0).unwrap().position,
+        Vec3::ZERO
+    );
+    let mut buf = vec![0u8; 64 * 1024];
+    let stolen = tokio::time::timeout(Duration::from_millis(200), stranger.recv_from(&mut buf));
+    assert!(stolen.await.is_err());
+
+    // The same command from the client's own socket is applied.
+    send_udp(udp, &cfg.server_addr, &command(1)).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    server.step(1.0 / 64.0).await?;
+    assert_ne!(
+        server.entity_state_at(player, 1).unwrap().position,
+        Vec3::ZERO
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn repeated_client_ready_keeps_one_player() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    let conns = connect_clients(&mut server, &cfg.server_addr, 1).await?;
+    let (_, id, udp) = &conns[0];
+
+    let player = server.client_ready(*id)?;
+    assert_eq!(server.client_ready(*id)?, player);
+
+    // A retransmitted ClientReady doesn't spawn another either.
+    let ready = NetMsg::ClientReady { client_id: *id };
+    send_udp(udp, &cfg.server_addr, &ready).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    server.step(1.0 / 64.0).await?;
+
+    let mut buf = vec![0u8; 64 * 1024];
+    let snapshot = loop {
+        let (n, _) =
+            tokio::time::timeout(Duration::from_secs(2), udp.recv_from(&mut buf)).await??;
+        if let NetMsg::Snapshot(snap) = decode_from_bytes(&buf[..n])? {
+            break snap;
+        }
+    };
+    assert_eq!(snapshot.entities.len(), 1);
+    Ok(())
+}
+
+fn map(name: &str) -> BspMap {
+    BspMap {
+        name: name.to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn change_map_resyncs_clients_and_waits_for_ready() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    server.load_bsp(map("de_old"));
+    let mut conns = connect_clients(&mut server, &cfg.server_addr, 2).await?;
+
+    server.change_map_bsp(map("de_new")).await;
+    assert_eq!(*server.state(), ServerState::ChangingMap);
+    for (conn, _, _) in &mut conns {
+        // The handshake's MapInfo for the old map comes first.
+        assert_eq!(recv_map_info(conn).await?, "de_old");
+        assert_eq!(recv_map_info(conn).await?, "de_new");
+    }
+
+    let tick = 1.0 / 64.0;
+    server.step(tick).await?;
+    assert_eq!(*server.state(), ServerState::ChangingMap);
+
+    for (i, (_, id, udp)) in conns.iter().enumerate() {
+        let ready = NetMsg::ClientReady { client_id: *id };
+        send_udp(udp, &cfg.server_addr, &ready).await?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        server.step(tick).await?;
+        let expected = if i + 1 < conns.len() {
+            ServerState::ChangingMap
+        } else {
+            ServerState::Running
+        };
+        assert_eq!(*server.state(), expected);
+    }
+    assert_eq!(server.connected_client_count(), 2);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn change_map_forgets_old_map_snapshots() -> anyhow::Result<()> {
+    let (mut server, _) = bind_ephemeral(64).await?;
+    let old_spawn = Vec3::new(100.0, 0.0, 0.0);
+    server.load_bsp(BspMap {
+        entities: vec![BspEntity {
+            classname: "info_player_start".to_string(),
+            properties: [("origin".to_string(), "100 0 0".to_string())].into(),
+        }],
+        ..map("de_old")
+    });
+    let old = server.client_ready(ClientId(1))?;
+    for _ in 0..4 {
+        server.step(1.0 / 64.0).await?;
+    }
+    assert_eq!(server.entity_state_at(old, 2).unwrap().position, old_spawn);
+
+    server.change_map_bsp(map("de_new")).await;
+    for tick in 0..4 {
+        assert!(server.entity_state_at(old, tick).is_none());
+    }
+
+    let player = server.client_ready(ClientId(1))?;
+    for _ in 0..2 {
+        server.step(1.0 / 64.0).await?;
+    }
+    for tick in 0..4 {
+        if let Some(state) = server.entity_state_at(player, tick) {
+            assert_eq!(state.position, Vec3::ZERO, "tick {tick}");
+        }
+    }
+    assert!(server.entity_state_at(player, 1).is_some());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn change_map_drops_clients_without_the_new_maps_dlc() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    server.load_bsp(map("de_free"));
+    server.set_map_dlc("de_dlc", 1001);
+
+    let mut conns = Vec::new();
+    for (account, owned_dlc) in [(1000, vec![1001]), (1001, Vec::new())] {
+        let client = tokio::spawn(raw_client_with_dlc(
+            cfg.server_addr.clone(),
+            account,
+            owned_dlc,
+        ));
+        server.accept_one().await?;
+        conns.push(client.await??);
+    }
+
+    server.change_map_bsp(map("de_dlc")).await;
+    assert_eq!(server.connected_client_count(), 1);
+    assert_eq!(
+        recv_disconnect(&mut conns[1].0).await?,
+        "Map de_dlc requires DLC 1001"
+    );
+
+    let (owner, id, udp) = &mut conns[0];
+    assert_eq!(recv_map_info(owner).await?, "de_free");
+    assert_eq!(recv_map_info(owner).await?, "de_dlc");
+    let ready = NetMsg::ClientReady { client_id: *id };
+    send_udp(udp, &cfg.server_addr, &ready).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    server.step(1.0 / 64.0).await?;
+    assert_eq!(*server.state(), ServerState::Running);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn change_map_drops_clients_that_never_load() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    let clock = manual_clock(&mut server);
+    let start = *clock.lock().unwrap();
+    let mut conns = connect_clients(&mut server, &cfg.server_addr, 2).await?;
+
+    server.change_map_bsp(map("de_new")).await;
+    let ready = NetMsg::ClientReady {
+        client_id: conns[0].1,
+    };
+    send_udp(&conns[0].2, &cfg.server_addr, &ready).await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    server.step(1.0 / 64.0).await?;
+    assert_eq!(*server.state(), ServerState::ChangingMap);
+
+    // Both keep answering pings, so only the map load times out.
+    *clock.lock().unwrap() = start + MAP_LOAD_TIMEOUT / 2;
+    for (_, id, udp) in &conns {
+        let pong = NetMsg::Pong {
+            client_id: *id,
+            seq: 0,
+        };
+        send_udp(udp, &cfg.server_addr, &pong).await?;
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    server.step(1.0 / 64.0).await?;
+    assert_eq!(server.connected_client_count(), 2);
+
+    *clock.lock().unwrap() = start + MAP_LOAD_TIMEOUT;
+    server.step(1.0 / 64.0).await?;
+    assert_eq!(*server.state(), ServerState::Running);
+    assert_eq!(server.connected_client_count(), 1);
+    assert_eq!(
+        recv_disconnect(&mut conns[1].0).await?,
+        MAP_LOAD_TIMED_OUT_REASON
+    );
+    Ok(())
+}
+
+/// Handshakes as `account` while the server accepts, returning the reply
+/// to the handshake.
+async fn try_join(server: &mut GameServer, addr: &str, account: u32) -> anyhow::Result<NetMsg> {
+    let addr = addr.to_string();
+    let client = tokio::spawn(async move {
+        let mut conn = ReliableConn::new(TcpStream::connect(addr).await?);
+        conn.send(&NetMsg::Hello {
+            protocol: PROTOCOL_VERSION,
+            steam_id: SteamId::from_account_id(account),
+            owned_dlc: Vec::new(),
+            player_name: String::new(),
+        })
+        .await?;
+        conn.send(&NetMsg::UdpHello {
+            client_udp_port: 50000,
+        })
+        .await?;
+        conn.recv().await
+    });
+    let _ = server.accept_one().await;
+    client.await?
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn full_server_rejects_until_a_slot_frees() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    server.exec_console("sv_maxclients 2")?;
+    assert_eq!(server.max_clients(), 2);
+    let conns = connect_clients(&mut server, &cfg.server_addr, 2).await?;
+
+    let reply = try_join(&mut server, &cfg.server_addr, 2000).await?;
+    assert_eq!(
+        reply,
+        NetMsg::Reject {
+            reason: RejectReason::ServerFull
+        }
+    );
+    assert_eq!(server.connected_client_count(), 2);
+
+    assert!(server.disconnect_client(conns[0].1, "kicked").await);
+    let reply = try_join(&mut server, &cfg.server_addr, 2000).await?;
+    assert!(matches!(reply, NetMsg::Welcome { .. }), "got {reply:?}");
+    assert_eq!(server.connected_client_count(), 2);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn reserved_slot_is_kept_for_its_player() -> anyhow::Result<()> {
+    let (mut server, cfg) = bind_ephemeral(64).await?;
+    server.exec_console("sv_maxclients 2")?;
+    let party_member = SteamId::from_account_id(3000);
+    server.reserve_slot(party_member);
+    connect_clients(&mut server, &cfg.server_addr, 1).await?;
+
+    // The last slot is held, so a stranger is turned away...
+    let reply = try_join(&mut server, &cfg.server_addr, 2000).await?;
+    assert!(matches!(reply, NetMsg::Reject { .. }), "got {reply:?}");
+
+    // ...but the party member gets in.
+    let reply = try_join(&mut server, &cfg.server_addr, 3000).await?;
+    assert!(matches!(reply, NetMsg::Welcome { .. }), "got {reply:?}");
+    assert!(!server.cancel_reservation(party_member));
+    Ok(())
+}